@@ -0,0 +1,283 @@
+//! A sequence with gaps that is one row of an alignment.
+
+use std::fmt;
+use std::ops::{Bound, RangeBounds};
+use crate::alphabet::{Alphabet, Gapped};
+use crate::alphabet::encoding;
+use crate::sequence::Sequence;
+
+/// A row of an alignment: a sequence of residues from an alphabet interleaved with gaps.
+///
+/// Positions in an aligned sequence are called columns and the positions of the residues in the
+/// ungapped sequence are called residues. Both are counted from 0.
+///
+/// # Example
+/// ```
+/// use biors::align::AlignedSequence;
+/// use biors::alphabet::{Gapped, UnambiguousDnaAlphabet};
+///
+/// let dna = UnambiguousDnaAlphabet;
+/// let gapped = Gapped::new(&dna);
+/// let mut aligned = AlignedSequence::new(&gapped);
+/// aligned.push("AC--GT").unwrap();
+///
+/// assert_eq!(aligned.column_to_residue(4), Some(2));
+/// assert_eq!(aligned.column_to_residue(2), None);
+/// assert_eq!(aligned.residue_to_column(2), Some(4));
+///
+/// aligned.insert_gaps(1, 2);
+/// assert_eq!(aligned.to_string(), "A--C--GT");
+/// assert_eq!(aligned.ungap().as_bytes().len(), 4);
+/// ```
+pub struct AlignedSequence<'a, A: Alphabet> {
+    sequence: Sequence<'a, Gapped<'a, A>>,
+    alphabet: &'a Gapped<'a, A>,
+}
+
+impl<'a, A: Alphabet> AlignedSequence<'a, A> {
+    /// Constructs an empty aligned sequence.
+    pub fn new(alphabet: &'a Gapped<'a, A>) -> Self {
+        AlignedSequence { sequence: Sequence::new(alphabet), alphabet }
+    }
+
+    /// Constructs an aligned sequence with no gaps from a sequence of the alphabet that
+    /// gapped wraps.
+    pub fn from_ungapped(alphabet: &'a Gapped<'a, A>, sequence: &Sequence<A>) -> Self {
+        // The wrapped symbols have the same index in the gapped alphabet
+        AlignedSequence::from_indices(alphabet, sequence.as_bytes().to_vec())
+    }
+
+    /// Constructs an aligned sequence from indices in the gapped alphabet
+    pub(crate) fn from_indices(alphabet: &'a Gapped<'a, A>, indices: Vec<u8>) -> Self {
+        AlignedSequence { sequence: Sequence::from_indices(alphabet, indices), alphabet }
+    }
+
+    /// Appends residues and gaps to the end of the sequence.
+    ///
+    /// # Errors
+    /// If the string contains symbols that are not in the gapped alphabet.
+    pub fn push<S: AsRef<str>>(&mut self, symbols: S) -> encoding::Result<()> {
+        self.sequence.push(symbols)
+    }
+
+    /// Appends a gap column to the end of the sequence.
+    pub fn push_gap(&mut self) {
+        let gap = self.alphabet.gap_index();
+        self.sequence.indices_mut().push(gap);
+    }
+
+    /// The gapped alphabet of the sequence.
+    pub fn alphabet(&self) -> &'a Gapped<'a, A> {
+        self.alphabet
+    }
+
+    /// The aligned sequence as a sequence of the gapped alphabet.
+    pub fn as_sequence(&self) -> &Sequence<'a, Gapped<'a, A>> {
+        &self.sequence
+    }
+
+    /// The index of the symbol in each column in the gapped alphabet.
+    pub fn as_bytes(&self) -> &[u8] {
+        self.sequence.as_bytes()
+    }
+
+    /// The number of columns, including gaps.
+    pub fn len(&self) -> usize {
+        self.sequence.len()
+    }
+
+    /// Returns true if there are no columns.
+    pub fn is_empty(&self) -> bool {
+        self.sequence.is_empty()
+    }
+
+    /// Whether the column is a gap.
+    ///
+    /// # Panics
+    /// If the column is out of bounds.
+    pub fn is_gap(&self, column: usize) -> bool {
+        self.alphabet.is_gap(self.as_bytes()[column])
+    }
+
+    /// The number of residues, that is the number of columns that are not gaps.
+    pub fn residue_count(&self) -> usize {
+        self.as_bytes().iter().filter(|&&index| !self.alphabet.is_gap(index)).count()
+    }
+
+    /// The sequence with every gap removed.
+    pub fn ungap(&self) -> Sequence<'a, A> {
+        let residues = self.as_bytes().iter()
+            .copied()
+            .filter(|&index| !self.alphabet.is_gap(index))
+            .collect();
+
+        Sequence::from_indices(self.alphabet.ungapped(), residues)
+    }
+
+    /// The residue in a column or None if the column is a gap.
+    ///
+    /// # Panics
+    /// If the column is out of bounds.
+    pub fn column_to_residue(&self, column: usize) -> Option<usize> {
+        if self.is_gap(column) {
+            None
+        } else {
+            let before = &self.as_bytes()[..column];
+            Some(before.iter().filter(|&&index| !self.alphabet.is_gap(index)).count())
+        }
+    }
+
+    /// The column a residue is in or None if there are not that many residues.
+    pub fn residue_to_column(&self, residue: usize) -> Option<usize> {
+        self.as_bytes().iter()
+            .enumerate()
+            .filter(|&(_, &index)| !self.alphabet.is_gap(index))
+            .nth(residue)
+            .map(|(column, _)| column)
+    }
+
+    /// The column of every residue in order. Useful for mapping many positions at once without
+    /// scanning the sequence each time.
+    pub fn residue_columns(&self) -> Vec<usize> {
+        self.as_bytes().iter()
+            .enumerate()
+            .filter(|&(_, &index)| !self.alphabet.is_gap(index))
+            .map(|(column, _)| column)
+            .collect()
+    }
+
+    /// Inserts count gap columns before column. A column equal to the length appends the gaps.
+    ///
+    /// # Panics
+    /// If the column is greater than the length.
+    pub fn insert_gaps(&mut self, column: usize, count: usize) {
+        assert!(
+            column <= self.len(),
+            "Column {} is out of bounds for length {}.", column, self.len()
+        );
+
+        let gap = self.alphabet.gap_index();
+        self.sequence.indices_mut().splice(column..column, std::iter::repeat_n(gap, count));
+    }
+
+    /// Removes a range of columns that are all gaps.
+    ///
+    /// # Panics
+    /// If the range is out of bounds or contains a residue.
+    pub fn remove_gaps<R: RangeBounds<usize>>(&mut self, columns: R) {
+        let start = match columns.start_bound() {
+            Bound::Included(&start) => start,
+            Bound::Excluded(&start) => start + 1,
+            Bound::Unbounded => 0,
+        };
+        let end = match columns.end_bound() {
+            Bound::Included(&end) => end + 1,
+            Bound::Excluded(&end) => end,
+            Bound::Unbounded => self.len(),
+        };
+
+        if let Some(column) = (start..end).find(|&column| !self.is_gap(column)) {
+            panic!("Can't remove column {} as it is not a gap.", column);
+        }
+        self.sequence.indices_mut().drain(start..end);
+    }
+}
+
+impl<'a, A: Alphabet> Clone for AlignedSequence<'a, A> {
+    fn clone(&self) -> Self {
+        AlignedSequence { sequence: self.sequence.clone(), alphabet: self.alphabet }
+    }
+}
+
+impl<'a, A: Alphabet> PartialEq for AlignedSequence<'a, A> {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_bytes() == other.as_bytes()
+    }
+}
+
+impl<'a, A: Alphabet> fmt::Debug for AlignedSequence<'a, A> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "AlignedSequence({})", self)
+    }
+}
+
+/// Writes every column, unlike [Sequence] which only shows the start.
+impl<'a, A: Alphabet> fmt::Display for AlignedSequence<'a, A> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let symbols = self.alphabet.symbols();
+        for &index in self.as_bytes() {
+            f.write_str(symbols[index as usize])?;
+        }
+        Ok(())
+    }
+}
+
+//================================================================================
+// Tests
+//================================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alphabet::UnambiguousDnaAlphabet;
+
+    /// Tests converting between columns and residues
+    #[test]
+    fn coordinates() {
+        let dna = UnambiguousDnaAlphabet;
+        let gapped = Gapped::new(&dna);
+        let mut aligned = AlignedSequence::new(&gapped);
+        aligned.push("-A.CG--T").unwrap();
+
+        assert_eq!(aligned.len(), 8);
+        assert_eq!(aligned.residue_count(), 4);
+        assert_eq!(aligned.residue_columns(), vec![1, 3, 4, 7]);
+
+        let residues: Vec<_> = (0..8).map(|column| aligned.column_to_residue(column)).collect();
+        assert_eq!(residues, vec![None, Some(0), None, Some(1), Some(2), None, None, Some(3)]);
+
+        for (residue, &column) in aligned.residue_columns().iter().enumerate() {
+            assert_eq!(aligned.residue_to_column(residue), Some(column));
+        }
+        assert_eq!(aligned.residue_to_column(4), None);
+
+        let mut expected = Sequence::new(&dna);
+        expected.push("ACGT").unwrap();
+        assert_eq!(aligned.ungap().as_bytes(), expected.as_bytes());
+    }
+
+    /// Tests inserting and removing gap columns
+    #[test]
+    fn edit_gaps() {
+        let dna = UnambiguousDnaAlphabet;
+        let gapped = Gapped::new(&dna);
+        let mut seq = Sequence::new(&dna);
+        seq.push("ACGT").unwrap();
+
+        let mut aligned = AlignedSequence::from_ungapped(&gapped, &seq);
+        assert_eq!(aligned.to_string(), "ACGT");
+
+        aligned.insert_gaps(0, 1);
+        aligned.insert_gaps(3, 2);
+        aligned.insert_gaps(7, 1);
+        aligned.push_gap();
+        assert_eq!(aligned.to_string(), "-AC--GT--");
+
+        aligned.remove_gaps(3..5);
+        aligned.remove_gaps(..1);
+        aligned.remove_gaps(4..);
+        assert_eq!(aligned.to_string(), "ACGT");
+    }
+
+    /// Tests that removing a residue panics
+    #[test]
+    #[should_panic]
+    fn remove_residue() {
+        let dna = UnambiguousDnaAlphabet;
+        let gapped = Gapped::new(&dna);
+        let mut aligned = AlignedSequence::new(&gapped);
+        aligned.push("A--C").unwrap();
+
+        aligned.remove_gaps(1..4);
+    }
+}
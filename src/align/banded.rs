@@ -0,0 +1,663 @@
+//! Alignments that only fill in the part of the dynamic programming matrix near the best path.
+//!
+//! Banded alignment restricts the global alignment to a fixed band of diagonals and X-drop
+//! extension grows a local alignment out from a seed until its score falls too far below the
+//! best seen. Both keep two rows of scores and a single byte of traceback per visited cell so
+//! memory grows with the area of the band rather than the product of the lengths.
+
+use crate::alphabet::Alphabet;
+use crate::alphabet::encoding::AlphabetEncoder;
+use crate::sequence::Sequence;
+use super::{sequence_indices, Aligner, Alignment, AlignmentMode, AlignmentOperation, Scoring};
+use super::SubstitutionScore;
+use super::pairwise::MIN_SCORE;
+
+/// Traceback bits saying which matrix the best score of a cell came from
+const FROM_DIAGONAL: u8 = 0;
+const FROM_INSERTION: u8 = 1;
+const FROM_DELETION: u8 = 2;
+const FROM_MASK: u8 = 0b11;
+/// Traceback bit set if the insertion score of a cell extends the insertion above it
+const INSERTION_EXTENDS: u8 = 0b100;
+/// Traceback bit set if the deletion score of a cell extends the deletion to its left
+const DELETION_EXTENDS: u8 = 0b1000;
+
+/// An exact or near exact match between x and y that an X-drop alignment is extended from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Seed {
+    /// The position in x the seed starts at
+    pub x_start: usize,
+    /// The position in y the seed starts at
+    pub y_start: usize,
+    /// The number of aligned symbols in the seed
+    pub len: usize,
+}
+
+impl<S: SubstitutionScore> Aligner<S> {
+    /// Globally aligns x to y only considering alignments that stay within bandwidth diagonals
+    /// of the main diagonal. The band is widened by the difference in length of the sequences
+    /// so that the end of both can always be reached.
+    ///
+    /// Takes O((n + m)w) time and memory. The alignment is optimal if the optimal alignment
+    /// stays within the band.
+    ///
+    /// # Example
+    /// ```
+    /// use biors::align::{Aligner, Scoring};
+    /// use biors::alphabet::UnambiguousDnaAlphabet;
+    /// use biors::sequence::Sequence;
+    ///
+    /// let alphabet = UnambiguousDnaAlphabet;
+    /// let mut x = Sequence::new(&alphabet);
+    /// let mut y = Sequence::new(&alphabet);
+    /// x.push("ACGTTGCAACGTAGCTAGCT").unwrap();
+    /// y.push("ACGTTGCACGTAGCTTAGCT").unwrap();
+    ///
+    /// let aligner = Aligner::new(Scoring::match_mismatch(1, -1, -2, -1));
+    /// let banded = aligner.banded_global(&x, &y, 2);
+    ///
+    /// assert_eq!(banded, aligner.global(&x, &y));
+    /// ```
+    pub fn banded_global<A, E>(
+        &self,
+        x: &Sequence<A, E>,
+        y: &Sequence<A, E>,
+        bandwidth: usize
+    ) -> Alignment
+    where
+        A: Alphabet,
+        E: AlphabetEncoder<A>
+    {
+        self.banded_global_indices(&sequence_indices(x), &sequence_indices(y), bandwidth)
+    }
+
+    /// Banded global alignment of two sequences given as the indices of their symbols in the
+    /// alphabet. See [banded_global()](Aligner::banded_global).
+    pub fn banded_global_indices(&self, x: &[usize], y: &[usize], bandwidth: usize) -> Alignment {
+        let scoring = self.scoring();
+        let (n, m) = (x.len(), y.len());
+
+        // Diagonals are numbered j - i
+        let low_diagonal = (m as isize - n as isize).min(0) - bandwidth as isize;
+        let high_diagonal = (m as isize - n as isize).max(0) + bandwidth as isize;
+        let band = |i: usize| {
+            let lo = (i as isize + low_diagonal).max(0) as usize;
+            let hi = (i as isize + high_diagonal).min(m as isize) as usize;
+            (lo, hi)
+        };
+
+        let mut previous = Row::new(m);
+        let mut current = Row::new(m);
+        let mut traceback = Traceback::default();
+
+        // The first row is a single deletion
+        let (_, hi) = band(0);
+        for j in 0..=hi {
+            previous.best[j] = scoring.gap(j);
+        }
+        previous.clear(hi + 1);
+        traceback.push_row(0, vec![FROM_DELETION; hi + 1]);
+
+        for (i, &a) in x.iter().enumerate().map(|(i, a)| (i + 1, a)) {
+            let (lo, hi) = band(i);
+            let mut bytes = Vec::with_capacity(hi - lo + 1);
+            let mut start = lo;
+
+            if lo == 0 {
+                // The first column is a single insertion
+                current.best[0] = scoring.gap(i);
+                current.insertion[0] = MIN_SCORE;
+                current.deletion[0] = MIN_SCORE;
+                bytes.push(FROM_INSERTION);
+                start = 1;
+            } else {
+                current.clear(lo - 1);
+            }
+
+            for j in start..=hi {
+                let byte = compute_cell(scoring, &previous, &mut current, j, a, y[j - 1]);
+                bytes.push(byte);
+            }
+
+            current.clear(hi + 1);
+            traceback.push_row(lo, bytes);
+            std::mem::swap(&mut previous, &mut current);
+        }
+
+        let score = previous.best[m];
+        let operations = traceback.trace(x, y, n, m);
+
+        Alignment {
+            score,
+            x_start: 0,
+            x_end: n,
+            y_start: 0,
+            y_end: m,
+            x_len: n,
+            y_len: m,
+            mode: AlignmentMode::Global,
+            operations,
+        }
+    }
+
+    /// Extends a seed in both directions with gapped alignment, stopping in each direction once
+    /// every alignment being extended scores more than x_drop below the best score seen. This is
+    /// the gapped extension used by BLAST.
+    ///
+    /// The result is a local alignment containing the seed. Only cells close to the best
+    /// alignment are visited so memory grows with its length rather than the lengths of x and y.
+    ///
+    /// # Panics
+    /// If the seed runs past the end of either sequence or x_drop is negative.
+    ///
+    /// # Example
+    /// ```
+    /// use biors::align::{Aligner, Scoring};
+    /// use biors::align::banded::Seed;
+    /// use biors::alphabet::UnambiguousDnaAlphabet;
+    /// use biors::sequence::Sequence;
+    ///
+    /// let alphabet = UnambiguousDnaAlphabet;
+    /// let mut x = Sequence::new(&alphabet);
+    /// let mut y = Sequence::new(&alphabet);
+    /// x.push("CCCCCCACGTAGGATTACAGATTCCCCCC").unwrap();
+    /// y.push("GGGACGTAGGATTTACAGATTGGGGG").unwrap();
+    ///
+    /// let aligner = Aligner::new(Scoring::match_mismatch(1, -2, -2, -1));
+    /// let seed = Seed { x_start: 9, y_start: 6, len: 4 };
+    /// let alignment = aligner.xdrop(&x, &y, seed, 5);
+    ///
+    /// assert_eq!((alignment.x_start, alignment.x_end), (6, 23));
+    /// assert_eq!(alignment.cigar(), "8=1D9=");
+    /// ```
+    pub fn xdrop<A, E>(
+        &self,
+        x: &Sequence<A, E>,
+        y: &Sequence<A, E>,
+        seed: Seed,
+        x_drop: i32
+    ) -> Alignment
+    where
+        A: Alphabet,
+        E: AlphabetEncoder<A>
+    {
+        self.xdrop_indices(&sequence_indices(x), &sequence_indices(y), seed, x_drop)
+    }
+
+    /// X-drop extension of a seed between two sequences given as the indices of their symbols
+    /// in the alphabet. See [xdrop()](Aligner::xdrop).
+    pub fn xdrop_indices(&self, x: &[usize], y: &[usize], seed: Seed, x_drop: i32) -> Alignment {
+        assert!(x_drop >= 0, "The X-drop must not be negative but is {}", x_drop);
+        assert!(
+            seed.x_start + seed.len <= x.len() && seed.y_start + seed.len <= y.len(),
+            "The seed {:?} runs past the end of the sequences", seed
+        );
+
+        let scoring = self.scoring();
+        let x_end = seed.x_start + seed.len;
+        let y_end = seed.y_start + seed.len;
+
+        let mut seed_score = 0;
+        let mut seed_operations = Vec::with_capacity(seed.len);
+        for (&a, &b) in x[seed.x_start..x_end].iter().zip(&y[seed.y_start..y_end]) {
+            seed_score += scoring.substitution.score(a, b);
+            seed_operations.push(if a == b {
+                AlignmentOperation::Match
+            } else {
+                AlignmentOperation::Substitution
+            });
+        }
+
+        // Extending to the left is extending the reversed prefixes to the right
+        let x_left: Vec<usize> = x[..seed.x_start].iter().rev().copied().collect();
+        let y_left: Vec<usize> = y[..seed.y_start].iter().rev().copied().collect();
+        let left = extend(scoring, &x_left, &y_left, x_drop);
+        let right = extend(scoring, &x[x_end..], &y[y_end..], x_drop);
+
+        let mut operations = left.operations;
+        operations.reverse();
+        operations.extend(seed_operations);
+        operations.extend(right.operations);
+
+        Alignment {
+            score: left.score + seed_score + right.score,
+            x_start: seed.x_start - left.x_len,
+            x_end: x_end + right.x_len,
+            y_start: seed.y_start - left.y_len,
+            y_end: y_end + right.y_len,
+            x_len: x.len(),
+            y_len: y.len(),
+            mode: AlignmentMode::Local,
+            operations,
+        }
+    }
+}
+
+//================================================================================
+// X-drop Extension
+//================================================================================
+
+/// The best extension of an alignment from the start of two sequences
+struct Extension {
+    score: i32,
+    x_len: usize,
+    y_len: usize,
+    operations: Vec<AlignmentOperation>,
+}
+
+/// Finds the best scoring alignment of a prefix of x to a prefix of y pruning cells that score
+/// more than x_drop below the best score found so far
+fn extend<S: SubstitutionScore>(
+    scoring: &Scoring<S>,
+    x: &[usize],
+    y: &[usize],
+    x_drop: i32
+) -> Extension {
+    let m = y.len();
+    let mut previous = Row::new(m);
+    let mut current = Row::new(m);
+    let mut traceback = Traceback::default();
+
+    let mut best = (0, 0, 0);
+
+    // The first row is a single deletion as long as it stays above the drop off
+    let mut hi = 0;
+    previous.best[0] = 0;
+    while hi < m && scoring.gap(hi + 1) >= -x_drop {
+        hi += 1;
+        previous.best[hi] = scoring.gap(hi);
+    }
+    previous.clear(hi + 1);
+    traceback.push_row(0, vec![FROM_DELETION; hi + 1]);
+    let mut lo = 0;
+
+    // The last column of the row two rows back which may still be in the current buffer
+    let mut stale_hi = 0;
+
+    for (i, &a) in x.iter().enumerate().map(|(i, a)| (i + 1, a)) {
+        let previous_hi = hi;
+        let mut bytes = vec![];
+        let mut first_alive = None;
+        let mut last_alive = lo;
+
+        if lo > 0 {
+            current.clear(lo - 1);
+        }
+
+        let mut j = lo;
+        while j <= m {
+            let byte = if j == 0 {
+                current.best[0] = scoring.gap(i);
+                current.insertion[0] = MIN_SCORE;
+                current.deletion[0] = MIN_SCORE;
+                FROM_INSERTION
+            } else {
+                compute_cell(scoring, &previous, &mut current, j, a, y[j - 1])
+            };
+
+            if current.best[j] < best.0 - x_drop {
+                current.clear(j);
+            } else {
+                first_alive.get_or_insert(j);
+                last_alive = j;
+
+                if current.best[j] > best.0 {
+                    best = (current.best[j], i, j);
+                }
+            }
+            bytes.push(byte);
+
+            // Past the end of the previous row cells can only be reached from the left
+            if j > previous_hi && current.best[j] == MIN_SCORE {
+                break;
+            }
+            j += 1;
+        }
+
+        let first_alive = match first_alive {
+            Some(first) => first,
+            None => break,
+        };
+
+        for k in j.min(m) + 1..=stale_hi {
+            current.clear(k);
+        }
+        current.clear(last_alive + 1);
+        bytes.truncate(last_alive + 1 - lo);
+        traceback.push_row(lo, bytes);
+        std::mem::swap(&mut previous, &mut current);
+
+        // Everything before the first live cell is already dead in this row so it can't be
+        // reached again
+        stale_hi = previous_hi;
+        hi = last_alive;
+        lo = first_alive;
+        for k in traceback.rows.last().unwrap().0..lo {
+            previous.clear(k);
+        }
+    }
+
+    let (score, x_len, y_len) = best;
+    let operations = traceback.trace(x, y, x_len, y_len);
+
+    Extension { score, x_len, y_len, operations }
+}
+
+//================================================================================
+// Utility Functions
+//================================================================================
+
+/// One row of each of the dynamic programming matrices
+struct Row {
+    best: Vec<i32>,
+    insertion: Vec<i32>,
+    deletion: Vec<i32>,
+}
+
+impl Row {
+    /// A row for aligning against a sequence of length m with every cell unreachable
+    fn new(m: usize) -> Self {
+        Row {
+            best: vec![MIN_SCORE; m + 1],
+            insertion: vec![MIN_SCORE; m + 1],
+            deletion: vec![MIN_SCORE; m + 1],
+        }
+    }
+
+    /// Marks the cell as unreachable if it is in the row
+    #[inline]
+    fn clear(&mut self, j: usize) {
+        if j < self.best.len() {
+            self.best[j] = MIN_SCORE;
+            self.insertion[j] = MIN_SCORE;
+            self.deletion[j] = MIN_SCORE;
+        }
+    }
+}
+
+/// Fills in cell j of the current row for aligning symbol a of x to symbol b of y returning
+/// its traceback byte. Ties prefer the diagonal, then insertions, then deletions.
+#[inline]
+fn compute_cell<S: SubstitutionScore>(
+    scoring: &Scoring<S>,
+    previous: &Row,
+    current: &mut Row,
+    j: usize,
+    a: usize,
+    b: usize
+) -> u8 {
+    let open = scoring.gap_open + scoring.gap_extend;
+    let extend = scoring.gap_extend;
+    let mut byte = 0;
+
+    let diagonal = previous.best[j - 1].saturating_add(scoring.substitution.score(a, b));
+
+    let insertion_open = previous.best[j] + open;
+    let insertion_extend = previous.insertion[j] + extend;
+    let insertion = if insertion_extend >= insertion_open {
+        byte |= INSERTION_EXTENDS;
+        insertion_extend
+    } else {
+        insertion_open
+    };
+
+    let deletion_open = current.best[j - 1] + open;
+    let deletion_extend = current.deletion[j - 1] + extend;
+    let deletion = if deletion_extend >= deletion_open {
+        byte |= DELETION_EXTENDS;
+        deletion_extend
+    } else {
+        deletion_open
+    };
+
+    let best = if diagonal >= insertion && diagonal >= deletion {
+        byte |= FROM_DIAGONAL;
+        diagonal
+    } else if insertion >= deletion {
+        byte |= FROM_INSERTION;
+        insertion
+    } else {
+        byte |= FROM_DELETION;
+        deletion
+    };
+
+    // Keep unreachable cells at the same floor so they never look reachable
+    current.best[j] = best.max(MIN_SCORE);
+    current.insertion[j] = insertion.max(MIN_SCORE);
+    current.deletion[j] = deletion.max(MIN_SCORE);
+
+    byte
+}
+
+/// The traceback bytes of the visited cells of each row along with the column of the first one
+#[derive(Default)]
+struct Traceback {
+    rows: Vec<(usize, Vec<u8>)>,
+}
+
+impl Traceback {
+    /// Adds the next row
+    fn push_row(&mut self, start: usize, bytes: Vec<u8>) {
+        self.rows.push((start, bytes));
+    }
+
+    /// The traceback byte of a cell
+    #[inline]
+    fn get(&self, i: usize, j: usize) -> u8 {
+        let (start, ref bytes) = self.rows[i];
+        bytes[j - start]
+    }
+
+    /// Follows the traceback from (i, j) back to (0, 0)
+    fn trace(
+        &self,
+        x: &[usize],
+        y: &[usize],
+        mut i: usize,
+        mut j: usize
+    ) -> Vec<AlignmentOperation> {
+        #[derive(PartialEq)]
+        enum State { Best, Insertion, Deletion }
+
+        let mut operations = Vec::with_capacity(i.max(j));
+        let mut state = State::Best;
+
+        while i > 0 || j > 0 {
+            let byte = self.get(i, j);
+
+            if i == 0 || j == 0 {
+                if i > 0 {
+                    operations.extend(std::iter::repeat_n(AlignmentOperation::Insertion, i));
+                } else {
+                    operations.extend(std::iter::repeat_n(AlignmentOperation::Deletion, j));
+                }
+                break;
+            }
+
+            if state == State::Best {
+                state = match byte & FROM_MASK {
+                    FROM_INSERTION => State::Insertion,
+                    FROM_DELETION => State::Deletion,
+                    _ => {
+                        operations.push(if x[i - 1] == y[j - 1] {
+                            AlignmentOperation::Match
+                        } else {
+                            AlignmentOperation::Substitution
+                        });
+                        i -= 1;
+                        j -= 1;
+                        continue;
+                    }
+                };
+            }
+
+            if state == State::Insertion {
+                operations.push(AlignmentOperation::Insertion);
+                if byte & INSERTION_EXTENDS == 0 {
+                    state = State::Best;
+                }
+                i -= 1;
+            } else {
+                operations.push(AlignmentOperation::Deletion);
+                if byte & DELETION_EXTENDS == 0 {
+                    state = State::Best;
+                }
+                j -= 1;
+            }
+        }
+
+        operations.reverse();
+        operations
+    }
+}
+
+//================================================================================
+// Tests
+//================================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::align::MatchMismatch;
+    use crate::sequence::test_util::random_indices;
+
+    /// Copies a sequence with a few substitutions, insertions and deletions
+    fn mutate(indices: &[usize], seed: u64) -> Vec<usize> {
+        let noise = random_indices(seed, indices.len(), 4);
+        let mut mutated = vec![];
+
+        for (i, (&symbol, &r)) in indices.iter().zip(&noise).enumerate() {
+            match (i % 17, r) {
+                (3, _) => mutated.push((symbol + 1) % 4),
+                (9, 0) => {}
+                (12, 1) => {
+                    mutated.push(symbol);
+                    mutated.push(r);
+                }
+                _ => mutated.push(symbol),
+            }
+        }
+
+        mutated
+    }
+
+    /// The aligner used by the tests
+    fn aligner() -> Aligner<MatchMismatch> {
+        Aligner::new(Scoring::match_mismatch(2, -3, -4, -1))
+    }
+
+    /// Tests that a band covering the whole matrix gives the same alignment as the full DP
+    #[test]
+    fn wide_band_matches_full() {
+        let aligner = aligner();
+
+        for seed in 1..15 {
+            let x = random_indices(seed, 20 + seed as usize, 4);
+            let y = random_indices(seed * 31, 35 - seed as usize, 4);
+
+            let full = aligner.align_indices(AlignmentMode::Global, &x, &y);
+            let banded = aligner.banded_global_indices(&x, &y, 40);
+            assert_eq!(banded, full);
+        }
+    }
+
+    /// Tests that a narrow band finds the optimal alignment of similar sequences
+    #[test]
+    fn narrow_band_similar() {
+        let aligner = aligner();
+
+        for seed in 1..10 {
+            let x = random_indices(seed, 500, 4);
+            let y = mutate(&x, seed + 100);
+
+            let full = aligner.align_indices(AlignmentMode::Global, &x, &y);
+            let banded = aligner.banded_global_indices(&x, &y, 8);
+
+            assert_eq!(banded.score, full.score);
+            let score = aligner.scoring().score_operations(&x, &y, 0, 0, &banded.operations);
+            assert_eq!(score, banded.score);
+        }
+    }
+
+    /// Tests that a band of zero still reaches the end of sequences of different lengths
+    #[test]
+    fn zero_band() {
+        let aligner = aligner();
+        let x = [0, 1, 2, 3, 0, 1];
+        let y = [0, 1, 2, 3];
+
+        let alignment = aligner.banded_global_indices(&x, &y, 0);
+        assert_eq!(alignment.cigar(), "4=2I");
+
+        let alignment = aligner.banded_global_indices(&[], &y, 0);
+        assert_eq!(alignment.cigar(), "4D");
+    }
+
+    /// Tests that X-drop extension finds the similar region around a seed and stops at the
+    /// unrelated sequence on either side
+    #[test]
+    fn xdrop_extends_similar_region() {
+        let aligner = aligner();
+        let core = random_indices(7, 300, 4);
+        let similar = mutate(&core, 8);
+
+        // Flanks that never match each other
+        let mut x = vec![0; 100];
+        let x_core_start = x.len();
+        x.extend(&core);
+        x.extend(vec![0; 100]);
+
+        let mut y = vec![1; 50];
+        let y_core_start = y.len();
+        y.extend(&similar);
+        y.extend(vec![1; 80]);
+
+        // The first mutation is at position 3 of the core
+        let seed = Seed { x_start: x_core_start + 4, y_start: y_core_start + 4, len: 5 };
+        let alignment = aligner.xdrop_indices(&x, &y, seed, 20);
+
+        assert!(alignment.x_start.abs_diff(x_core_start) < 10);
+        assert!(alignment.x_end.abs_diff(x_core_start + core.len()) < 10);
+        assert!(alignment.y_start.abs_diff(y_core_start) < 10);
+
+        let score = aligner.scoring().score_operations(
+            &x, &y, alignment.x_start, alignment.y_start, &alignment.operations
+        );
+        assert_eq!(score, alignment.score);
+
+        // A smaller drop off stops earlier
+        let short = aligner.xdrop_indices(&x, &y, seed, 0);
+        assert!(short.score <= alignment.score);
+        assert!(short.len() < alignment.len());
+    }
+
+    /// Tests that X-drop extension with an unlimited drop off finds the best extension
+    #[test]
+    fn xdrop_unlimited() {
+        let aligner = aligner();
+        let x = random_indices(11, 25, 4);
+        let y = mutate(&x, 12);
+        let seed = Seed { x_start: 0, y_start: 0, len: 0 };
+
+        let alignment = aligner.xdrop_indices(&x, &y, seed, i32::MAX / 8);
+
+        // Without a drop off the extension is the best global alignment of any pair of prefixes
+        let best = (0..=x.len())
+            .flat_map(|i| (0..=y.len()).map(move |j| (i, j)))
+            .map(|(i, j)| aligner.align_indices(AlignmentMode::Global, &x[..i], &y[..j]).score)
+            .max()
+            .unwrap();
+
+        assert_eq!(alignment.score, best);
+        assert_eq!((alignment.x_start, alignment.y_start), (0, 0));
+        let score = aligner.scoring().score_operations(&x, &y, 0, 0, &alignment.operations);
+        assert_eq!(score, alignment.score);
+    }
+
+    /// Tests that seeds running past the sequences are rejected
+    #[test]
+    #[should_panic]
+    fn xdrop_bad_seed() {
+        aligner().xdrop_indices(&[0, 1], &[0, 1], Seed { x_start: 1, y_start: 0, len: 2 }, 5);
+    }
+}
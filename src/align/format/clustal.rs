@@ -0,0 +1,212 @@
+//! The Clustal alignment format written by Clustal, MUSCLE, T-Coffee and others.
+//!
+//! ```text
+//! CLUSTAL W (1.83) multiple sequence alignment
+//!
+//! seq1      ACGT-ACGTA 9
+//! seq2      ACGTTACG-A 9
+//!           ****.*** *
+//!
+//! seq1      CC 11
+//! seq2      CC 11
+//!           **
+//! ```
+//!
+//! The rows are split into blocks of columns. Each line of a block has an ID followed by the
+//! columns and optionally the number of residues so far. The line under each block marks the
+//! conserved columns.
+
+use crate::alphabet::{Alphabet, Gapped};
+use super::super::msa::Msa;
+use super::{push_columns, check_id, id_width, ErrorKind, FormatError, Result, Rows};
+
+/// The number of columns written in each block
+const BLOCK_WIDTH: usize = 60;
+
+/// Reads a Clustal file. The header must start with CLUSTAL or, like MUSCLE's, end with
+/// "multiple sequence alignment". The residue counts and conservation lines are ignored.
+///
+/// # Errors
+/// If the file doesn't start with a Clustal header, a line has no columns, the rows have
+/// different lengths or they contain symbols not in the alphabet.
+///
+/// # Example
+/// ```
+/// use biors::align::format::clustal;
+/// use biors::alphabet::{Gapped, UnambiguousDnaAlphabet};
+///
+/// let dna = UnambiguousDnaAlphabet;
+/// let gapped = Gapped::new(&dna);
+/// let text = "CLUSTAL W multiple sequence alignment\n\n\
+///             a    ACGT 4\n\
+///             b    AC-T 3\n     \
+///                  ** *\n\n\
+///             a    TA 6\n\
+///             b    TA 5\n     \
+///                  **\n";
+/// let msa = clustal::read(text, &gapped).unwrap();
+///
+/// assert_eq!(msa.rows()[1].to_string(), "AC-TTA");
+/// ```
+pub fn read<'a, A: Alphabet>(text: &str, alphabet: &'a Gapped<'a, A>) -> Result<Msa<'a, A>> {
+    let mut lines = text.lines()
+        .enumerate()
+        .map(|(line_number, line)| (line_number + 1, line.trim_end()))
+        .filter(|(_, line)| !line.is_empty());
+
+    match lines.next() {
+        Some((_, line))
+            if line.starts_with("CLUSTAL") || line.ends_with("multiple sequence alignment") => {}
+        Some((line_number, _)) => {
+            let description = "The file must start with a Clustal header".to_owned();
+            return Err(FormatError::new(ErrorKind::Syntax, Some(line_number), description));
+        }
+        None => {
+            let description = "The file is empty".to_owned();
+            return Err(FormatError::new(ErrorKind::UnexpectedEnd, None, description));
+        }
+    }
+
+    let mut rows = Rows::new(alphabet);
+    for (line_number, line) in lines {
+        // Conservation lines are indented under the columns
+        if line.starts_with(char::is_whitespace) {
+            continue;
+        }
+
+        let mut fields: Vec<&str> = line.split_whitespace().collect();
+        if fields.len() > 2 && fields[fields.len() - 1].chars().all(|c| c.is_ascii_digit()) {
+            fields.pop();
+        }
+        if fields.len() < 2 {
+            let description = format!("The line for {} has no columns", fields[0]);
+            return Err(FormatError::new(ErrorKind::Syntax, Some(line_number), description));
+        }
+
+        let row = rows.get_or_add(fields[0], line_number)?;
+        for symbols in &fields[1..] {
+            rows.extend(row, symbols, line_number)?;
+        }
+    }
+
+    rows.finish()
+}
+
+/// Writes an alignment in Clustal format in blocks of 60 columns. Columns where every row has
+/// the same residue are marked with a *.
+///
+/// # Errors
+/// If an ID is empty or contains whitespace.
+pub fn write<A: Alphabet>(msa: &Msa<A>) -> Result<String> {
+    for id in msa.ids() {
+        check_id(id)?;
+    }
+
+    let width = id_width(msa) + 4;
+    let gap = msa.alphabet().gap_index();
+    let mut out = "CLUSTAL multiple sequence alignment\n".to_owned();
+
+    for start in (0..msa.num_columns()).step_by(BLOCK_WIDTH) {
+        let end = (start + BLOCK_WIDTH).min(msa.num_columns());
+        out.push('\n');
+
+        for (id, row) in msa.ids().iter().zip(msa.rows()) {
+            out.push_str(&format!("{:width$}", id, width = width));
+            push_columns(&mut out, row, start..end);
+            out.push('\n');
+        }
+
+        out.push_str(&" ".repeat(width));
+        for column in start..end {
+            let symbols = msa.column(column);
+            let conserved = symbols[0] < gap && symbols.iter().all(|&symbol| symbol == symbols[0]);
+            let mark = if conserved { "*" } else { " " };
+            out.push_str(&mark.repeat(msa.alphabet().symbol_size()));
+        }
+        out.push('\n');
+    }
+
+    Ok(out)
+}
+
+//================================================================================
+// Tests
+//================================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alphabet::{ProteinAlphabet, UnambiguousDnaAlphabet};
+
+    /// Tests reading a file written by Clustal and writing it back
+    #[test]
+    fn round_trip() {
+        let protein = ProteinAlphabet;
+        let gapped = Gapped::new(&protein);
+        let text = "CLUSTAL W (1.83) multiple sequence alignment
+
+
+sp|P1|A         MKTAYIAKQR-QISFVKSHF 19
+sp|P2|B         MKTAYIAKQRQQISFVKSHF 20
+sp|P3|C         MKSAYIAK--QQISFVRSHF 18
+                **:*****  .*****:***
+
+sp|P1|A         SRQ 22
+sp|P2|B         SRQ 23
+sp|P3|C         SR- 20
+                **
+";
+        let msa = read(text, &gapped).unwrap();
+        assert_eq!(msa.ids(), &["sp|P1|A", "sp|P2|B", "sp|P3|C"]);
+        assert_eq!(msa.num_columns(), 23);
+        assert_eq!(msa.rows()[2].to_string(), "MKSAYIAK--QQISFVRSHFSR-");
+
+        let written = write(&msa).unwrap();
+        assert_eq!(written.lines().nth(2).unwrap(), "sp|P1|A    MKTAYIAKQR-QISFVKSHFSRQ");
+        assert_eq!(written.lines().nth(5).unwrap(), "           ** *****   ***** ***** ");
+
+        let read_back = read(&written, &gapped).unwrap();
+        assert_eq!(read_back.ids(), msa.ids());
+        assert_eq!(read_back.rows(), msa.rows());
+    }
+
+    /// Tests that an alignment longer than a block is split into several blocks
+    #[test]
+    fn blocks() {
+        let dna = UnambiguousDnaAlphabet;
+        let gapped = Gapped::new(&dna);
+        let mut msa = Msa::new(&gapped);
+        msa.push_str("x", &"ACGT".repeat(40)).unwrap();
+        msa.push_str("y", &"AC-T".repeat(40)).unwrap();
+
+        let written = write(&msa).unwrap();
+        assert_eq!(written.matches("\nx ").count(), 3);
+
+        let read_back = read(&written, &gapped).unwrap();
+        assert_eq!(read_back.rows(), msa.rows());
+    }
+
+    /// Tests that errors report the line they were found on
+    #[test]
+    fn errors() {
+        let dna = UnambiguousDnaAlphabet;
+        let gapped = Gapped::new(&dna);
+
+        let err = read("\nMUSCLE\na ACGT\n", &gapped).unwrap_err();
+        assert_eq!((err.kind(), err.line()), (&ErrorKind::Syntax, Some(2)));
+        let text = "MUSCLE (3.8) multiple sequence alignment\n\na ACGT\nb AC-T\n";
+        let muscle = read(text, &gapped).unwrap();
+        assert_eq!(muscle.rows()[1].to_string(), "AC-T");
+
+        let err = read("CLUSTAL\n\na ACGT\nb\n", &gapped).unwrap_err();
+        assert_eq!((err.kind(), err.line()), (&ErrorKind::Syntax, Some(4)));
+
+        let err = read("CLUSTAL\n\na ACGT\nb ACGT\n\na AC\nb ACG\n", &gapped).unwrap_err();
+        assert_eq!((err.kind(), err.line()), (&ErrorKind::LengthMismatch, Some(7)));
+
+        let err = read("CLUSTAL\n\na ACGT\nb ACXT\n", &gapped).unwrap_err();
+        assert_eq!((err.kind(), err.line()), (&ErrorKind::InvalidSymbol, Some(4)));
+
+        assert_eq!(read("", &gapped).unwrap_err().kind(), &ErrorKind::UnexpectedEnd);
+    }
+}
@@ -0,0 +1,138 @@
+//! Aligned FASTA: a FASTA file where every record is a row of the alignment including its gaps.
+//!
+//! ```text
+//! >seq1 description
+//! ACGT-A
+//! >seq2
+//! AC-TTA
+//! ```
+
+use crate::alphabet::{Alphabet, Gapped};
+use super::super::msa::Msa;
+use super::{push_columns, check_id, ErrorKind, FormatError, Result, Rows};
+
+/// The number of columns written on each sequence line
+const LINE_WIDTH: usize = 60;
+
+/// Reads an aligned FASTA file. The ID of each row is the first word of its header and the
+/// rest of the header is ignored. Lines starting with ; are comments.
+///
+/// # Errors
+/// If there is a sequence line before the first header, a header has no ID, two rows have the
+/// same ID, the rows have different lengths or they contain symbols not in the alphabet.
+///
+/// # Example
+/// ```
+/// use biors::align::format::fasta;
+/// use biors::alphabet::{Gapped, UnambiguousDnaAlphabet};
+///
+/// let dna = UnambiguousDnaAlphabet;
+/// let gapped = Gapped::new(&dna);
+/// let msa = fasta::read(">a first\nACGT\n-A\n>b\nAC-TTA\n", &gapped).unwrap();
+///
+/// assert_eq!(msa.ids(), &["a", "b"]);
+/// assert_eq!(msa.rows()[0].to_string(), "ACGT-A");
+/// assert_eq!(fasta::write(&msa).unwrap(), ">a\nACGT-A\n>b\nAC-TTA\n");
+/// ```
+pub fn read<'a, A: Alphabet>(text: &str, alphabet: &'a Gapped<'a, A>) -> Result<Msa<'a, A>> {
+    let mut rows = Rows::new(alphabet);
+    let mut current = None;
+
+    for (line_number, line) in text.lines().enumerate() {
+        let line_number = line_number + 1;
+        let line = line.trim_end();
+
+        if line.is_empty() || line.starts_with(';') {
+            continue;
+        }
+
+        if let Some(header) = line.strip_prefix('>') {
+            let id = header.split_whitespace().next().ok_or_else(|| {
+                let description = "The header has no ID".to_owned();
+                FormatError::new(ErrorKind::Syntax, Some(line_number), description)
+            })?;
+            current = Some(rows.add(id, line_number)?);
+        } else {
+            let row = current.ok_or_else(|| {
+                let description = "The file must start with a header line".to_owned();
+                FormatError::new(ErrorKind::Syntax, Some(line_number), description)
+            })?;
+            rows.extend(row, line, line_number)?;
+        }
+    }
+
+    rows.finish()
+}
+
+/// Writes an alignment as aligned FASTA with 60 columns on each line.
+///
+/// # Errors
+/// If an ID is empty or contains whitespace.
+pub fn write<A: Alphabet>(msa: &Msa<A>) -> Result<String> {
+    let mut out = String::new();
+
+    for (id, row) in msa.ids().iter().zip(msa.rows()) {
+        check_id(id)?;
+        out.push('>');
+        out.push_str(id);
+        out.push('\n');
+
+        for start in (0..row.len()).step_by(LINE_WIDTH) {
+            push_columns(&mut out, row, start..(start + LINE_WIDTH).min(row.len()));
+            out.push('\n');
+        }
+    }
+
+    Ok(out)
+}
+
+//================================================================================
+// Tests
+//================================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alphabet::ProteinAlphabet;
+
+    /// Tests that long rows are wrapped and read back the same
+    #[test]
+    fn round_trip() {
+        let protein = ProteinAlphabet;
+        let gapped = Gapped::new(&protein);
+        let mut msa = Msa::new(&gapped);
+        msa.push_str("long", &"ACDEFGHIK-".repeat(13)).unwrap();
+        msa.push_str("gappy", &"-C-E-G-I-L".repeat(13)).unwrap();
+
+        let text = write(&msa).unwrap();
+        assert_eq!(text.lines().count(), 8);
+        assert_eq!(text.lines().nth(1).unwrap().len(), 60);
+        assert_eq!(text.lines().nth(3).unwrap().len(), 10);
+
+        let read_back = read(&text, &gapped).unwrap();
+        assert_eq!(read_back.ids(), msa.ids());
+        assert_eq!(read_back.rows(), msa.rows());
+    }
+
+    /// Tests that errors report the line they were found on
+    #[test]
+    fn errors() {
+        let protein = ProteinAlphabet;
+        let gapped = Gapped::new(&protein);
+
+        let err = read("ACDE\n>a\nACDE\n", &gapped).unwrap_err();
+        assert_eq!((err.kind(), err.line()), (&ErrorKind::Syntax, Some(1)));
+
+        let err = read(">a\nACDE\n\n>b\nAC\nDEF\n", &gapped).unwrap_err();
+        assert_eq!((err.kind(), err.line()), (&ErrorKind::LengthMismatch, Some(6)));
+
+        let err = read(">a\nACDE\n>b\nAC#E\n", &gapped).unwrap_err();
+        assert_eq!((err.kind(), err.line()), (&ErrorKind::InvalidSymbol, Some(4)));
+
+        let err = read(">a\nACDE\n>a\nACDE\n", &gapped).unwrap_err();
+        assert_eq!((err.kind(), err.line()), (&ErrorKind::DuplicateId("a".to_owned()), Some(3)));
+
+        let err = read(">\nACDE\n", &gapped).unwrap_err();
+        assert_eq!((err.kind(), err.line()), (&ErrorKind::Syntax, Some(1)));
+    }
+}
@@ -0,0 +1,238 @@
+//! Reading and writing [Msa]s in common multiple alignment file formats.
+//!
+//! Each format has its own module with a `read` function that parses the whole file from a
+//! string and a `write` function that formats an alignment as a string:
+//!
+//! - [fasta]: aligned FASTA, where every record has the same number of columns
+//! - [clustal]: the output of Clustal and many other aligners
+//! - [stockholm]: used by Pfam and Rfam, including its `#=G` annotation lines
+//! - [phylip]: in both its strict and relaxed forms
+//! - [nexus]: the DATA or CHARACTERS block of a NEXUS file
+//!
+//! Symbols are read exactly as they are written so they must match the case of the alphabet's
+//! symbols. Both - and . are read as gaps. Stockholm writes each gap as it was read, where .
+//! marks a gap in a column that is mostly gaps, and the other formats write every gap as - so
+//! that a . isn't taken as the NEXUS match character, for example.
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::ops::Range;
+use crate::alphabet::{Alphabet, Gapped};
+use super::AlignedSequence;
+use super::msa::{self, Msa, MsaError};
+
+pub mod clustal;
+pub mod fasta;
+pub mod nexus;
+pub mod phylip;
+pub mod stockholm;
+
+/// Result type for reading and writing alignment files
+pub type Result<T> = std::result::Result<T, FormatError>;
+
+/// Collects the rows of an alignment while a file is read so that formats which split rows
+/// over several lines or blocks can add to them by ID
+struct Rows<'a, A: Alphabet> {
+    alphabet: &'a Gapped<'a, A>,
+    ids: Vec<String>,
+    rows: Vec<AlignedSequence<'a, A>>,
+    /// The last line each row was extended on, to report where a row went wrong
+    lines: Vec<usize>,
+    positions: HashMap<String, usize>,
+}
+
+impl<'a, A: Alphabet> Rows<'a, A> {
+    fn new(alphabet: &'a Gapped<'a, A>) -> Self {
+        Rows { alphabet, ids: vec![], rows: vec![], lines: vec![], positions: HashMap::new() }
+    }
+
+    fn len(&self) -> usize {
+        self.rows.len()
+    }
+
+    /// The index of the row with the ID
+    fn position(&self, id: &str) -> Option<usize> {
+        self.positions.get(id).copied()
+    }
+
+    /// Adds an empty row and returns its index
+    fn add(&mut self, id: &str, line: usize) -> Result<usize> {
+        if self.positions.contains_key(id) {
+            let description = format!("There is already a row with the ID {}", id);
+            let kind = ErrorKind::DuplicateId(id.to_owned());
+            return Err(FormatError::new(kind, Some(line), description));
+        }
+
+        self.positions.insert(id.to_owned(), self.rows.len());
+        self.ids.push(id.to_owned());
+        self.rows.push(AlignedSequence::new(self.alphabet));
+        self.lines.push(line);
+        Ok(self.rows.len() - 1)
+    }
+
+    /// The index of the row with the ID, adding it if it's new
+    fn get_or_add(&mut self, id: &str, line: usize) -> Result<usize> {
+        match self.position(id) {
+            Some(row) => Ok(row),
+            None => self.add(id, line),
+        }
+    }
+
+    /// The number of columns in a row so far
+    fn columns(&self, row: usize) -> usize {
+        self.rows[row].len()
+    }
+
+    /// Appends symbols to a row, ignoring any whitespace between them
+    fn extend(&mut self, row: usize, symbols: &str, line: usize) -> Result<()> {
+        for part in symbols.split_whitespace() {
+            if let Err(err) = self.rows[row].push(part) {
+                let description = format!("Row {} could not be encoded: {}", self.ids[row], err);
+                return Err(FormatError::new(ErrorKind::InvalidSymbol, Some(line), description));
+            }
+        }
+
+        self.lines[row] = line;
+        Ok(())
+    }
+
+    /// Builds the alignment, checking every row has the same length
+    fn finish(self) -> Result<Msa<'a, A>> {
+        let mut msa = Msa::new(self.alphabet);
+
+        for ((id, row), line) in self.ids.into_iter().zip(self.rows).zip(self.lines) {
+            msa.push(id, row).map_err(|err| FormatError::from_msa(err, line))?;
+        }
+
+        Ok(msa)
+    }
+}
+
+/// Writes the symbols in a range of columns of a row, with - for both kinds of gap
+fn push_columns<A: Alphabet>(out: &mut String, row: &AlignedSequence<A>, columns: Range<usize>) {
+    let alphabet = row.alphabet();
+    for &index in &row.as_bytes()[columns] {
+        if alphabet.is_gap(index) {
+            out.push_str(alphabet.gap_symbol());
+        } else {
+            out.push_str(alphabet.symbols()[index as usize]);
+        }
+    }
+}
+
+/// Writes the symbols in a range of columns of a row as they were read, keeping . gaps
+fn push_symbols<A: Alphabet>(out: &mut String, row: &AlignedSequence<A>, columns: Range<usize>) {
+    let symbols = row.alphabet().symbols();
+    for &index in &row.as_bytes()[columns] {
+        out.push_str(symbols[index as usize]);
+    }
+}
+
+/// Splits the first whitespace separated field from a line
+fn split_field(line: &str) -> Option<(&str, &str)> {
+    let line = line.trim_start();
+    let end = line.find(char::is_whitespace).unwrap_or(line.len());
+
+    if end == 0 {
+        None
+    } else {
+        Some((&line[..end], line[end..].trim()))
+    }
+}
+
+/// The width of the widest ID, used to line up the rows
+fn id_width<A: Alphabet>(msa: &Msa<A>) -> usize {
+    msa.ids().iter().map(|id| id.chars().count()).max().unwrap_or(0)
+}
+
+/// Checks that an ID can be written in formats that end IDs at whitespace
+fn check_id(id: &str) -> Result<()> {
+    if id.is_empty() || id.contains(char::is_whitespace) {
+        let description = format!("The ID {:?} is empty or contains whitespace", id);
+        Err(FormatError::new(ErrorKind::InvalidId, None, description))
+    } else {
+        Ok(())
+    }
+}
+
+//================================================================================
+// Errors
+//================================================================================
+
+/// Represents the kind of error that occurred while reading or writing an alignment file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ErrorKind {
+    /// A line could not be parsed
+    Syntax,
+
+    /// The file ended before the alignment was complete
+    UnexpectedEnd,
+
+    /// A row contains a symbol that isn't in the gapped alphabet
+    InvalidSymbol,
+
+    /// A row or annotation has a different number of columns to the alignment or to the
+    /// number given in the file's header
+    LengthMismatch,
+
+    /// Two rows have the same ID
+    DuplicateId(String),
+
+    /// An ID can't be written in the format
+    InvalidId,
+}
+
+/// The type of error returned when an alignment file can't be read or written. Errors reading a
+/// file carry the line they were found on.
+#[derive(Debug, Clone)]
+pub struct FormatError {
+    kind: ErrorKind,
+    line: Option<usize>,
+    description: String,
+}
+
+impl FormatError {
+    /// Construct a new FormatError from the given ErrorKind, line and description
+    pub fn new(kind: ErrorKind, line: Option<usize>, description: String) -> FormatError {
+        FormatError { kind, line, description }
+    }
+
+    /// Converts an error building the Msa to an error at a line of the file
+    fn from_msa(err: MsaError, line: usize) -> FormatError {
+        let kind = match err.kind() {
+            msa::ErrorKind::LengthMismatch => ErrorKind::LengthMismatch,
+            msa::ErrorKind::InvalidSymbol => ErrorKind::InvalidSymbol,
+            msa::ErrorKind::DuplicateId(id) => ErrorKind::DuplicateId(id.clone()),
+        };
+        FormatError::new(kind, Some(line), err.description().to_owned())
+    }
+
+    /// Get the associated ErrorKind for this error
+    pub fn kind(&self) -> &ErrorKind {
+        &self.kind
+    }
+
+    /// Get the line of the file the error was found on, counted from 1
+    pub fn line(&self) -> Option<usize> {
+        self.line
+    }
+
+    /// Get the associated description for this error
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+}
+
+impl Error for FormatError {}
+
+impl fmt::Display for FormatError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.line {
+            Some(line) => {
+                write!(f, "Format error: {:?} at line {}:\n\t{}", self.kind, line, self.description)
+            }
+            None => write!(f, "Format error: {:?}:\n\t{}", self.kind, self.description),
+        }
+    }
+}
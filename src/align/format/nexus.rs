@@ -0,0 +1,506 @@
+//! The alignment in the DATA or CHARACTERS block of a NEXUS file, used by PAUP*, MrBayes and
+//! other phylogenetics programs.
+//!
+//! ```text
+//! #NEXUS
+//! BEGIN DATA;
+//!     DIMENSIONS NTAX=2 NCHAR=6;
+//!     FORMAT DATATYPE=DNA MISSING=? GAP=-;
+//!     MATRIX
+//!     seq1         ACGT-A
+//!     'seq two'    AC-TTA
+//!     ;
+//! END;
+//! ```
+//!
+//! Keywords are case insensitive, comments are in square brackets and IDs with spaces or
+//! punctuation are quoted with '. Only the number of columns, the number of rows, whether the
+//! matrix is interleaved and the MATCHCHAR are read from the other commands in the block. The
+//! match character is replaced with the symbol in the same column of the first row. Missing
+//! data, ? unless MISSING says otherwise, isn't a gap so it can only be read if the alphabet has
+//! the symbol.
+
+use crate::alphabet::{Alphabet, Gapped};
+use super::super::msa::Msa;
+use super::{push_columns, ErrorKind, FormatError, Result, Rows};
+
+/// Characters that need an ID to be quoted
+const PUNCTUATION: &str = "()[]{}/\\,;:=*'\"`<>";
+
+/// Removes comments from each line, keeping track of comments over several lines. Brackets in
+/// quoted IDs aren't comments.
+fn strip_comments(text: &str) -> Vec<String> {
+    let mut depth = 0;
+
+    text.lines()
+        .map(|line| {
+            let mut stripped = String::with_capacity(line.len());
+            let mut quoted = false;
+            for c in line.chars() {
+                match c {
+                    '[' if !quoted => depth += 1,
+                    ']' if !quoted && depth > 0 => depth -= 1,
+                    _ if depth == 0 => {
+                        // A quote written twice inside a quoted ID toggles this back
+                        if c == '\'' {
+                            quoted = !quoted;
+                        }
+                        stripped.push(c);
+                    }
+                    _ => {}
+                }
+            }
+            stripped
+        })
+        .collect()
+}
+
+/// Finds the ; that ends a command, skipping any in quoted IDs
+fn find_terminator(line: &str) -> Option<usize> {
+    let mut quoted = false;
+
+    line.char_indices().find_map(|(i, c)| {
+        match c {
+            '\'' => quoted = !quoted,
+            ';' if !quoted => return Some(i),
+            _ => {}
+        }
+        None
+    })
+}
+
+/// Splits an ID that may be quoted from the start of a line
+fn split_id(line: &str) -> Option<(String, &str)> {
+    let line = line.trim_start();
+
+    if let Some(quoted) = line.strip_prefix('\'') {
+        // A quote inside a quoted ID is written twice
+        let mut id = String::new();
+        let mut chars = quoted.char_indices().peekable();
+        while let Some((i, c)) = chars.next() {
+            if c == '\'' {
+                if let Some(&(_, '\'')) = chars.peek() {
+                    chars.next();
+                } else {
+                    return Some((id, &quoted[i + 1..]));
+                }
+            }
+            id.push(c);
+        }
+        None
+    } else {
+        let end = line.find(char::is_whitespace).unwrap_or(line.len());
+        if end == 0 { None } else { Some((line[..end].to_owned(), &line[end..])) }
+    }
+}
+
+/// Finds the value of a KEY=value setting in a command
+fn setting<'l>(command: &'l str, key: &str) -> Option<&'l str> {
+    let lower = command.to_ascii_lowercase();
+    let mut start = 0;
+
+    while let Some(found) = lower[start..].find(key) {
+        let key_start = start + found;
+        let after = lower[key_start + key.len()..].trim_start();
+        let at_word_start =
+            key_start == 0 || !lower.as_bytes()[key_start - 1].is_ascii_alphanumeric();
+
+        if at_word_start && after.starts_with('=') {
+            let value = command[command.len() - after.len() + 1..].trim_start();
+            let end = value.find(|c: char| c.is_whitespace() || c == ';').unwrap_or(value.len());
+            return Some(&value[..end]);
+        }
+        start = key_start + key.len();
+    }
+
+    None
+}
+
+/// Replaces the match character in symbols being added to a row with the symbol in the same
+/// column of the first row
+fn replace_matches<A: Alphabet>(
+    rows: &Rows<A>,
+    row: usize,
+    symbols: &str,
+    matchchar: char,
+    line: usize
+) -> Result<String> {
+    let mut replaced = String::with_capacity(symbols.len());
+
+    for (column, c) in (rows.columns(row)..).zip(symbols.chars().filter(|c| !c.is_whitespace())) {
+        if c != matchchar {
+            replaced.push(c);
+        } else if row > 0 && column < rows.columns(0) {
+            push_columns(&mut replaced, &rows.rows[0], column..column + 1);
+        } else {
+            let description =
+                format!("The match character {} has no symbol in the first row to copy", c);
+            return Err(FormatError::new(ErrorKind::Syntax, Some(line), description));
+        }
+    }
+
+    Ok(replaced)
+}
+
+/// Reads the alignment in the first DATA or CHARACTERS block of a NEXUS file.
+///
+/// # Errors
+/// If the file doesn't start with #NEXUS, has no DATA or CHARACTERS block, the block has no
+/// NCHAR before its MATRIX, the matrix doesn't end with ;, the match character is used in the
+/// first row, the rows don't match the number of rows or columns in the header, two rows have
+/// the same ID or the rows contain symbols not in the alphabet.
+///
+/// # Example
+/// ```
+/// use biors::align::format::nexus;
+/// use biors::alphabet::{Gapped, UnambiguousDnaAlphabet};
+///
+/// let dna = UnambiguousDnaAlphabet;
+/// let gapped = Gapped::new(&dna);
+/// let text = "#NEXUS\n\
+///             begin data;\n\
+///             dimensions ntax=2 nchar=6;\n\
+///             format datatype=dna gap=-;\n\
+///             matrix\n\
+///             'seq one' ACGT-A [a comment]\n\
+///             seq_two   AC-TTA\n\
+///             ;\n\
+///             end;\n";
+/// let msa = nexus::read(text, &gapped).unwrap();
+///
+/// assert_eq!(msa.ids(), &["seq one", "seq_two"]);
+/// assert!(nexus::write(&msa).contains("\t'seq one' ACGT-A\n"));
+/// ```
+pub fn read<'a, A: Alphabet>(text: &str, alphabet: &'a Gapped<'a, A>) -> Result<Msa<'a, A>> {
+    let stripped = strip_comments(text);
+    let mut lines = stripped.iter()
+        .enumerate()
+        .map(|(line_number, line)| (line_number + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty());
+
+    match lines.next() {
+        Some((_, line)) if line.eq_ignore_ascii_case("#nexus") => {}
+        Some((line_number, _)) => {
+            let description = "The file must start with #NEXUS".to_owned();
+            return Err(FormatError::new(ErrorKind::Syntax, Some(line_number), description));
+        }
+        None => {
+            let description = "The file is empty".to_owned();
+            return Err(FormatError::new(ErrorKind::UnexpectedEnd, None, description));
+        }
+    }
+
+    let mut in_block = false;
+    let mut in_matrix = false;
+    let mut finished = false;
+    let mut num_rows = None;
+    let mut num_columns = None;
+    let mut dimensions_line = 0;
+    let mut interleaved = false;
+    let mut matchchar = None;
+    let mut rows = Rows::new(alphabet);
+    let mut current = None;
+
+    for (line_number, line) in lines {
+        let lower = line.to_ascii_lowercase();
+
+        if !in_block {
+            in_block = lower.starts_with("begin data") || lower.starts_with("begin characters");
+        } else if !in_matrix {
+            if lower.starts_with("dimensions") {
+                let parse = |key| setting(line, key).and_then(|value| value.parse::<usize>().ok());
+                num_rows = parse("ntax");
+                num_columns = parse("nchar");
+                dimensions_line = line_number;
+            } else if lower.starts_with("format") {
+                let interleave = setting(line, "interleave");
+                interleaved = interleave.map_or(lower.contains("interleave"), |value| {
+                    value.eq_ignore_ascii_case("yes")
+                });
+                matchchar = setting(line, "matchchar").and_then(|value| value.chars().next());
+            } else if lower.starts_with("matrix") {
+                if num_columns.is_none() {
+                    let description =
+                        "The matrix must follow a DIMENSIONS command with NCHAR".to_owned();
+                    return Err(FormatError::new(ErrorKind::Syntax, Some(line_number), description));
+                }
+                in_matrix = true;
+            } else if lower.starts_with("end") {
+                in_block = false;
+            }
+        } else if lower == "end;" || lower == "endblock;" {
+            let description = "The matrix must end with ; before the end of the block".to_owned();
+            return Err(FormatError::new(ErrorKind::Syntax, Some(line_number), description));
+        } else {
+            let (line, end) = match find_terminator(line) {
+                Some(end) => (&line[..end], true),
+                None => (line, false),
+            };
+            let num_columns = num_columns.unwrap_or(0);
+
+            let continues = match current {
+                Some(row) => !interleaved && rows.columns(row) < num_columns,
+                None => false,
+            };
+            let next = if continues {
+                Some((current.unwrap(), line))
+            } else if !line.trim().is_empty() {
+                let (id, symbols) = split_id(line).ok_or_else(|| {
+                    let description = "The quoted ID has no closing quote".to_owned();
+                    FormatError::new(ErrorKind::Syntax, Some(line_number), description)
+                })?;
+                let row = if interleaved {
+                    rows.get_or_add(&id, line_number)?
+                } else {
+                    rows.add(&id, line_number)?
+                };
+                current = Some(row);
+                Some((row, symbols))
+            } else {
+                None
+            };
+
+            if let Some((row, symbols)) = next {
+                match matchchar {
+                    Some(c) => {
+                        let replaced = replace_matches(&rows, row, symbols, c, line_number)?;
+                        rows.extend(row, &replaced, line_number)?;
+                    }
+                    None => rows.extend(row, symbols, line_number)?,
+                }
+            }
+
+            if end {
+                finished = true;
+                break;
+            }
+        }
+    }
+
+    if !finished {
+        let description = "The file ended before the end of a DATA or CHARACTERS matrix".to_owned();
+        return Err(FormatError::new(ErrorKind::UnexpectedEnd, None, description));
+    }
+
+    let msa = rows.finish()?;
+    let expected = (num_rows.unwrap_or(msa.num_rows()), num_columns.unwrap_or(0));
+    if (msa.num_rows(), msa.num_columns()) != expected {
+        let description = format!(
+            "The matrix has {} rows and {} columns but the dimensions are {} by {}",
+            msa.num_rows(), msa.num_columns(), expected.0, expected.1
+        );
+        return Err(FormatError::new(ErrorKind::LengthMismatch, Some(dimensions_line), description));
+    }
+
+    Ok(msa)
+}
+
+/// The NEXUS data type that matches the symbols of an alphabet
+fn datatype<A: Alphabet>(alphabet: &A) -> &'static str {
+    let within = |allowed: &str| alphabet.symbols().iter().all(|symbol| allowed.contains(symbol));
+
+    if within("ACGTRYSWKMBDHVN") {
+        "DNA"
+    } else if within("ACGURYSWKMBDHVN") {
+        "RNA"
+    } else {
+        "PROTEIN"
+    }
+}
+
+/// Quotes an ID if it has whitespace or punctuation
+fn quote_id(id: &str) -> String {
+    if id.is_empty() || id.contains(|c: char| c.is_whitespace() || PUNCTUATION.contains(c)) {
+        format!("'{}'", id.replace('\'', "''"))
+    } else {
+        id.to_owned()
+    }
+}
+
+/// Writes an alignment as a NEXUS file with a single DATA block and each row on one line. The
+/// data type is DNA, RNA or PROTEIN depending on the symbols of the alphabet.
+pub fn write<A: Alphabet>(msa: &Msa<A>) -> String {
+    let ids: Vec<String> = msa.ids().iter().map(|id| quote_id(id)).collect();
+    let width = ids.iter().map(|id| id.chars().count()).max().unwrap_or(0) + 1;
+
+    let mut out = "#NEXUS\n\nBEGIN DATA;\n".to_owned();
+    out.push_str(&format!("\tDIMENSIONS NTAX={} NCHAR={};\n", msa.num_rows(), msa.num_columns()));
+    let datatype = datatype(msa.alphabet().ungapped());
+    out.push_str(&format!("\tFORMAT DATATYPE={} MISSING=? GAP=-;\n", datatype));
+    out.push_str("\tMATRIX\n");
+
+    for (id, row) in ids.iter().zip(msa.rows()) {
+        out.push_str(&format!("\t{:width$}", id, width = width));
+        push_columns(&mut out, row, 0..row.len());
+        out.push('\n');
+    }
+    out.push_str("\t;\nEND;\n");
+
+    out
+}
+
+//================================================================================
+// Tests
+//================================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alphabet::{ProteinAlphabet, UnambiguousDnaAlphabet};
+
+    /// Tests reading an interleaved matrix with comments and other blocks around it
+    #[test]
+    fn interleaved() {
+        let dna = UnambiguousDnaAlphabet;
+        let gapped = Gapped::new(&dna);
+        let text = "#NEXUS
+[ A comment
+  over two lines ]
+BEGIN TAXA;
+    DIMENSIONS NTAX=3;
+END;
+
+BEGIN CHARACTERS;
+    DIMENSIONS NCHAR=10;
+    FORMAT DATATYPE=DNA GAP=- INTERLEAVE=YES;
+    MATRIX
+    'Homo sapiens'  ACGTA
+    'Pan''s'        ACG-A
+    Gorilla         ACGTT [ends in T]
+
+    'Homo sapiens'  CCGTA
+    'Pan''s'        CC-TA
+    Gorilla         CCGTA;
+END;
+";
+        let msa = read(text, &gapped).unwrap();
+        assert_eq!(msa.ids(), &["Homo sapiens", "Pan's", "Gorilla"]);
+        assert_eq!(msa.rows()[1].to_string(), "ACG-ACC-TA");
+
+        let written = write(&msa);
+        assert!(written.contains("NTAX=3 NCHAR=10;"));
+        assert!(written.contains("DATATYPE=DNA"));
+        assert!(written.contains("\t'Pan''s'       ACG-ACC-TA\n"));
+
+        let read_back = read(&written, &gapped).unwrap();
+        assert_eq!(read_back.ids(), msa.ids());
+        assert_eq!(read_back.rows(), msa.rows());
+    }
+
+    /// Tests reading a sequential matrix where rows continue over several lines
+    #[test]
+    fn sequential() {
+        let protein = ProteinAlphabet;
+        let gapped = Gapped::new(&protein);
+        let text = "#nexus
+begin data;
+    dimensions ntax=2 nchar=8;
+    format datatype=protein;
+    matrix
+    first  MKVL
+           A-IL
+    second MKVLAVIL
+    ;
+end;
+";
+        let msa = read(text, &gapped).unwrap();
+        assert_eq!(msa.rows()[0].to_string(), "MKVLA-IL");
+
+        let written = write(&msa);
+        assert!(written.contains("DATATYPE=PROTEIN"));
+        assert_eq!(read(&written, &gapped).unwrap().rows(), msa.rows());
+    }
+
+    /// Tests that . gaps are written as - rather than as the match character
+    #[test]
+    fn dot_gaps() {
+        let dna = UnambiguousDnaAlphabet;
+        let gapped = Gapped::new(&dna);
+        let text =
+            "#NEXUS\nBEGIN DATA;\nDIMENSIONS NTAX=2 NCHAR=4;\nMATRIX\na AC.T\nb A-GT\n;\nEND;\n";
+        let msa = read(text, &gapped).unwrap();
+
+        let written = write(&msa);
+        assert!(written.contains("\ta AC-T\n"));
+        assert!(!written.contains('.'));
+        assert_eq!(read(&written, &gapped).unwrap().rows()[0].to_string(), "AC-T");
+    }
+
+    /// Tests that the match character copies the first row and that missing data isn't a gap
+    #[test]
+    fn match_character() {
+        let dna = UnambiguousDnaAlphabet;
+        let gapped = Gapped::new(&dna);
+        let file = |matrix: &str| {
+            format!(
+                "#NEXUS\nBEGIN DATA;\nDIMENSIONS NTAX=3 NCHAR=6;\n\
+                 FORMAT DATATYPE=DNA MISSING=? GAP=- MATCHCHAR=. INTERLEAVE;\n\
+                 MATRIX\n{}\n;\nEND;\n",
+                matrix
+            )
+        };
+
+        let msa = read(&file("a ACG\nb .T.\nc -..\n\na T-A\nb ..C\nc .G."), &gapped).unwrap();
+        let rows: Vec<String> = msa.rows().iter().map(|row| row.to_string()).collect();
+        assert_eq!(rows, ["ACGT-A", "ATGT-C", "-CGTGA"]);
+
+        let err = read(&file("a AC.\nb ACG\nc ACG\n\na TTA\nb TTA\nc TTA"), &gapped).unwrap_err();
+        assert_eq!((err.kind(), err.line()), (&ErrorKind::Syntax, Some(6)));
+
+        let err = read(&file("a ACG\nb AC?\nc ACG\n\na TTA\nb TTA\nc TTA"), &gapped).unwrap_err();
+        assert_eq!((err.kind(), err.line()), (&ErrorKind::InvalidSymbol, Some(7)));
+    }
+
+    /// Tests that IDs with brackets and semicolons are quoted and read back
+    #[test]
+    fn quoted_punctuation() {
+        let dna = UnambiguousDnaAlphabet;
+        let gapped = Gapped::new(&dna);
+        let text = "#NEXUS\nBEGIN DATA;\nDIMENSIONS NTAX=2 NCHAR=4;\n\
+                    MATRIX\n'a;b [x]' ACGT\nc AC-T [c;d]\n;\nEND;\n";
+        let msa = read(text, &gapped).unwrap();
+        assert_eq!(msa.ids(), &["a;b [x]", "c"]);
+
+        let written = write(&msa);
+        assert!(written.contains("\t'a;b [x]' ACGT\n"));
+
+        let read_back = read(&written, &gapped).unwrap();
+        assert_eq!(read_back.ids(), msa.ids());
+        assert_eq!(read_back.rows(), msa.rows());
+    }
+
+    /// Tests that errors report the line they were found on
+    #[test]
+    fn errors() {
+        let dna = UnambiguousDnaAlphabet;
+        let gapped = Gapped::new(&dna);
+        let file = |matrix: &str| {
+            format!("#NEXUS\nBEGIN DATA;\nDIMENSIONS NTAX=2 NCHAR=4;\nMATRIX\n{}\nEND;\n", matrix)
+        };
+
+        let err = read("NEXUS\n", &gapped).unwrap_err();
+        assert_eq!((err.kind(), err.line()), (&ErrorKind::Syntax, Some(1)));
+
+        let err = read("#NEXUS\nBEGIN DATA;\nMATRIX\na ACGT;\nEND;\n", &gapped).unwrap_err();
+        assert_eq!((err.kind(), err.line()), (&ErrorKind::Syntax, Some(3)));
+
+        let err = read(&file("a ACGT\nb ACGT"), &gapped).unwrap_err();
+        assert_eq!((err.kind(), err.line()), (&ErrorKind::Syntax, Some(7)));
+
+        let text = "#NEXUS\nBEGIN DATA;\nDIMENSIONS NCHAR=4;\nMATRIX\na ACGT\n";
+        let err = read(text, &gapped).unwrap_err();
+        assert_eq!((err.kind(), err.line()), (&ErrorKind::UnexpectedEnd, None));
+
+        let err = read(&file("a ACGT\nb ACGT\nc ACGT;"), &gapped).unwrap_err();
+        assert_eq!((err.kind(), err.line()), (&ErrorKind::LengthMismatch, Some(3)));
+
+        let err = read(&file("a ACGT\n'b ACGT;"), &gapped).unwrap_err();
+        assert_eq!((err.kind(), err.line()), (&ErrorKind::Syntax, Some(6)));
+
+        let err = read(&file("a ACGT\na ACGT;"), &gapped).unwrap_err();
+        assert_eq!((err.kind(), err.line()), (&ErrorKind::DuplicateId("a".to_owned()), Some(6)));
+
+        let err = read(&file("a ACGT\nb AC?T;"), &gapped).unwrap_err();
+        assert_eq!((err.kind(), err.line()), (&ErrorKind::InvalidSymbol, Some(6)));
+    }
+}
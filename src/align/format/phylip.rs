@@ -0,0 +1,298 @@
+//! The PHYLIP alignment format used by PHYLIP, RAxML, PhyML and other phylogenetics programs.
+//!
+//! ```text
+//!  3 12
+//! seq1      ACGTACGTAC GT
+//! seq2      ACGTAC--AC GT
+//! seq3      ACCTACGTAC GA
+//! ```
+//!
+//! The first line gives the number of rows and columns. In the strict form every ID is padded
+//! to exactly 10 characters and the columns start straight after it, so IDs can contain spaces
+//! and can touch the columns. In the relaxed form IDs end at the first whitespace and can be
+//! any length.
+//!
+//! Rows can be sequential, where each row is complete before the next starts and can continue
+//! over several lines, or interleaved, where the first block of lines has the start of every
+//! row with its ID and each following block continues every row without IDs.
+
+use crate::alphabet::{Alphabet, Gapped};
+use super::super::msa::Msa;
+use super::{push_columns, check_id, id_width, split_field, ErrorKind, FormatError, Result, Rows};
+
+/// The number of characters of a strict PHYLIP ID
+const STRICT_ID_WIDTH: usize = 10;
+
+/// The form of a PHYLIP file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PhylipFormat {
+    /// IDs take up exactly 10 characters
+    Strict,
+    /// IDs end at the first whitespace
+    Relaxed,
+}
+
+/// Splits the ID from the start of the first line of a row
+fn split_id(line: &str, format: PhylipFormat) -> Option<(&str, &str)> {
+    match format {
+        PhylipFormat::Strict => {
+            let end = line.char_indices().nth(STRICT_ID_WIDTH).map_or(line.len(), |(end, _)| end);
+            let id = line[..end].trim();
+            if id.is_empty() { None } else { Some((id, &line[end..])) }
+        }
+        PhylipFormat::Relaxed => split_field(line),
+    }
+}
+
+/// Reads a PHYLIP file.
+///
+/// # Notes
+/// A file is read as interleaved if there is a blank line between two lines of the
+/// alignment and as sequential otherwise. Anything after the alignment is ignored.
+///
+/// # Errors
+/// If the first line doesn't have the number of rows and columns, the file ends early, a
+/// line has no ID, an interleaved block has the wrong number of lines, the rows don't have
+/// the number of columns in the header or contain symbols not in the alphabet.
+///
+/// # Example
+/// ```
+/// use biors::align::format::phylip::{self, PhylipFormat};
+/// use biors::alphabet::{Gapped, UnambiguousDnaAlphabet};
+///
+/// let dna = UnambiguousDnaAlphabet;
+/// let gapped = Gapped::new(&dna);
+/// let text = " 2 6\nfirst     ACGT-A\nsecond    AC-TTA\n";
+/// let msa = phylip::read(text, &gapped, PhylipFormat::Strict).unwrap();
+///
+/// assert_eq!(msa.ids(), &["first", "second"]);
+/// let relaxed = phylip::write(&msa, PhylipFormat::Relaxed).unwrap();
+/// assert_eq!(relaxed, "2 6\nfirst  ACGT-A\nsecond AC-TTA\n");
+/// ```
+pub fn read<'a, A: Alphabet>(
+    text: &str,
+    alphabet: &'a Gapped<'a, A>,
+    format: PhylipFormat
+) -> Result<Msa<'a, A>> {
+    let mut lines = text.lines()
+        .enumerate()
+        .map(|(line_number, line)| (line_number + 1, line.trim_end()))
+        .skip_while(|(_, line)| line.is_empty());
+
+    let (header_line, header) = lines.next().ok_or_else(|| {
+        FormatError::new(ErrorKind::UnexpectedEnd, None, "The file is empty".to_owned())
+    })?;
+    let sizes: Vec<usize> = header.split_whitespace()
+        .take(2)
+        .map(|field| field.parse())
+        .collect::<std::result::Result<_, _>>()
+        .unwrap_or_default();
+    let (num_rows, num_columns) = match sizes[..] {
+        [num_rows, num_columns] if num_rows > 0 => (num_rows, num_columns),
+        _ => {
+            let description =
+                format!("The header {:?} must give the number of rows and columns", header);
+            return Err(FormatError::new(ErrorKind::Syntax, Some(header_line), description));
+        }
+    };
+
+    // Group the lines into blocks separated by blank lines
+    let mut blocks: Vec<Vec<(usize, &str)>> = vec![vec![]];
+    for (line_number, line) in lines {
+        if line.is_empty() {
+            if !blocks[blocks.len() - 1].is_empty() {
+                blocks.push(vec![]);
+            }
+        } else {
+            let last = blocks.len() - 1;
+            blocks[last].push((line_number, line));
+        }
+    }
+    if blocks[blocks.len() - 1].is_empty() {
+        blocks.pop();
+    }
+
+    let end_error = || {
+        let description = format!("The file ended before all {} rows were read", num_rows);
+        FormatError::new(ErrorKind::UnexpectedEnd, None, description)
+    };
+    let id_error = |line_number: usize| {
+        let description = "The line has no ID".to_owned();
+        FormatError::new(ErrorKind::Syntax, Some(line_number), description)
+    };
+
+    let mut rows = Rows::new(alphabet);
+    if blocks.len() > 1 {
+        for (block_index, block) in blocks.iter().enumerate() {
+            if block.len() != num_rows {
+                let description = format!(
+                    "The block has {} lines but there are {} rows", block.len(), num_rows);
+                return Err(FormatError::new(ErrorKind::Syntax, Some(block[0].0), description));
+            }
+
+            for (row, &(line_number, line)) in block.iter().enumerate() {
+                let symbols = if block_index == 0 {
+                    let (id, symbols) =
+                        split_id(line, format).ok_or_else(|| id_error(line_number))?;
+                    rows.add(id, line_number)?;
+                    symbols
+                } else {
+                    line
+                };
+                rows.extend(row, symbols, line_number)?;
+            }
+        }
+    } else {
+        let mut lines = blocks.into_iter().flatten();
+        for _ in 0..num_rows {
+            let (line_number, line) = lines.next().ok_or_else(end_error)?;
+            let (id, symbols) = split_id(line, format).ok_or_else(|| id_error(line_number))?;
+            let row = rows.add(id, line_number)?;
+            rows.extend(row, symbols, line_number)?;
+
+            while rows.columns(row) < num_columns {
+                let (line_number, line) = lines.next().ok_or_else(end_error)?;
+                rows.extend(row, line, line_number)?;
+            }
+        }
+    }
+
+    if rows.len() < num_rows {
+        return Err(end_error());
+    }
+
+    let msa = rows.finish()?;
+    if msa.num_columns() != num_columns {
+        let description = format!(
+            "The rows have {} columns but the header gives {}", msa.num_columns(), num_columns
+        );
+        return Err(FormatError::new(ErrorKind::LengthMismatch, Some(header_line), description));
+    }
+
+    Ok(msa)
+}
+
+/// Writes an alignment in PHYLIP format with each row on one line.
+///
+/// # Errors
+/// If an ID is empty, longer than 10 characters in the strict form or contains whitespace in
+/// the relaxed form.
+pub fn write<A: Alphabet>(msa: &Msa<A>, format: PhylipFormat) -> Result<String> {
+    let width = match format {
+        PhylipFormat::Strict => {
+            for id in msa.ids() {
+                if id.trim().is_empty() || id.chars().count() > STRICT_ID_WIDTH {
+                    let description = format!("The ID {:?} must be 1 to 10 characters long", id);
+                    return Err(FormatError::new(ErrorKind::InvalidId, None, description));
+                }
+            }
+            STRICT_ID_WIDTH
+        }
+        PhylipFormat::Relaxed => {
+            for id in msa.ids() {
+                check_id(id)?;
+            }
+            id_width(msa) + 1
+        }
+    };
+
+    let mut out = format!("{} {}\n", msa.num_rows(), msa.num_columns());
+    for (id, row) in msa.ids().iter().zip(msa.rows()) {
+        out.push_str(&format!("{:width$}", id, width = width));
+        push_columns(&mut out, row, 0..row.len());
+        out.push('\n');
+    }
+
+    Ok(out)
+}
+
+//================================================================================
+// Tests
+//================================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alphabet::{ProteinAlphabet, UnambiguousDnaAlphabet};
+
+    /// Tests reading interleaved and sequential files with the same alignment
+    #[test]
+    fn layouts() {
+        let dna = UnambiguousDnaAlphabet;
+        let gapped = Gapped::new(&dna);
+
+        let interleaved = "  3 14
+Turkey    AAGCTAGGGC
+Salmo gairAAGCCTTGGC
+H. SapiensACCGGTTGGC
+
+ATGT
+AT-T
+CCGT
+";
+        let sequential = "3 14
+Turkey    AAGCT
+AGGGCATGT
+Salmo gairAAGCCTTGGC AT-T
+H. SapiensACCGG TTGGC
+CCGT
+";
+        let msa = read(interleaved, &gapped, PhylipFormat::Strict).unwrap();
+        assert_eq!(msa.ids(), &["Turkey", "Salmo gair", "H. Sapiens"]);
+        assert_eq!(msa.rows()[1].to_string(), "AAGCCTTGGCAT-T");
+
+        let read_back = read(sequential, &gapped, PhylipFormat::Strict).unwrap();
+        assert_eq!(read_back.ids(), msa.ids());
+        assert_eq!(read_back.rows(), msa.rows());
+    }
+
+    /// Tests writing and reading back both forms
+    #[test]
+    fn round_trip() {
+        let protein = ProteinAlphabet;
+        let gapped = Gapped::new(&protein);
+        let mut msa = Msa::new(&gapped);
+        msa.push_str("short", "MKV-LA").unwrap();
+        msa.push_str("a_much_longer_id", "MKVILA").unwrap();
+
+        let relaxed = write(&msa, PhylipFormat::Relaxed).unwrap();
+        assert_eq!(relaxed, "2 6\nshort            MKV-LA\na_much_longer_id MKVILA\n");
+        let read_back = read(&relaxed, &gapped, PhylipFormat::Relaxed).unwrap();
+        assert_eq!(read_back.ids(), msa.ids());
+        assert_eq!(read_back.rows(), msa.rows());
+
+        let err = write(&msa, PhylipFormat::Strict).unwrap_err();
+        assert_eq!(err.kind(), &ErrorKind::InvalidId);
+
+        let msa = msa.select_rows(&[0]);
+        let strict = write(&msa, PhylipFormat::Strict).unwrap();
+        assert_eq!(strict, "1 6\nshort     MKV-LA\n");
+        let read_back = read(&strict, &gapped, PhylipFormat::Strict).unwrap();
+        assert_eq!(read_back.rows(), msa.rows());
+    }
+
+    /// Tests that errors report the line they were found on
+    #[test]
+    fn errors() {
+        let dna = UnambiguousDnaAlphabet;
+        let gapped = Gapped::new(&dna);
+
+        let err = read("\n2 x\na ACGT\n", &gapped, PhylipFormat::Relaxed).unwrap_err();
+        assert_eq!((err.kind(), err.line()), (&ErrorKind::Syntax, Some(2)));
+
+        let err = read("2 4\na ACGT\n", &gapped, PhylipFormat::Relaxed).unwrap_err();
+        assert_eq!((err.kind(), err.line()), (&ErrorKind::UnexpectedEnd, None));
+
+        let err = read("2 4\na ACGT\nb AC\n", &gapped, PhylipFormat::Relaxed).unwrap_err();
+        assert_eq!((err.kind(), err.line()), (&ErrorKind::UnexpectedEnd, None));
+
+        let err = read("2 4\na AC\nb AC\n\nGT\n", &gapped, PhylipFormat::Relaxed).unwrap_err();
+        assert_eq!((err.kind(), err.line()), (&ErrorKind::Syntax, Some(5)));
+
+        let err = read("2 3\na ACGT\nb ACGT\n", &gapped, PhylipFormat::Relaxed).unwrap_err();
+        assert_eq!((err.kind(), err.line()), (&ErrorKind::LengthMismatch, Some(1)));
+
+        let err = read("2 4\na ACGT\nb AC?T\n", &gapped, PhylipFormat::Relaxed).unwrap_err();
+        assert_eq!((err.kind(), err.line()), (&ErrorKind::InvalidSymbol, Some(3)));
+    }
+}
@@ -0,0 +1,371 @@
+//! The Stockholm format used by Pfam, Rfam and HMMER, which annotates the alignment alongside
+//! its rows.
+//!
+//! ```text
+//! # STOCKHOLM 1.0
+//! #=GF ID    example
+//! #=GS seq1  AC P12345
+//! seq1       ACGT-A
+//! #=GR seq1  SS <<..>
+//! seq2       AC-TTA
+//! #=GC SS_cons <<..>>
+//! //
+//! ```
+//!
+//! There are four kinds of annotation:
+//!
+//! - `#=GF <tag> <text>` about the whole file
+//! - `#=GS <id> <tag> <text>` about one sequence
+//! - `#=GC <tag> <annotation>` with one character for each column
+//! - `#=GR <id> <tag> <annotation>` with one character for each column of one row
+//!
+//! Like rows, column and residue annotations can be split over several blocks.
+
+use std::fmt;
+use crate::alphabet::{Alphabet, Gapped};
+use super::super::msa::Msa;
+use super::{push_symbols, check_id, id_width, split_field, ErrorKind, FormatError, Result, Rows};
+
+/// An alignment with the annotations from a Stockholm file. Each kind of annotation is kept in
+/// the order it appears in the file.
+pub struct Stockholm<'a, A: Alphabet> {
+    /// The alignment
+    pub msa: Msa<'a, A>,
+    /// `#=GF` lines as a tag and its text. Tags such as CC that span several lines have an
+    /// entry for each line.
+    pub file_annotations: Vec<(String, String)>,
+    /// `#=GS` lines as the row ID, a tag and its text
+    pub sequence_annotations: Vec<(String, String, String)>,
+    /// `#=GC` lines as a tag and the annotation of every column
+    pub column_annotations: Vec<(String, String)>,
+    /// `#=GR` lines as the row ID, a tag and the annotation of every column of the row
+    pub residue_annotations: Vec<(String, String, String)>,
+}
+
+impl<'a, A: Alphabet> Stockholm<'a, A> {
+    /// An alignment without any annotations.
+    pub fn new(msa: Msa<'a, A>) -> Self {
+        Stockholm {
+            msa,
+            file_annotations: vec![],
+            sequence_annotations: vec![],
+            column_annotations: vec![],
+            residue_annotations: vec![],
+        }
+    }
+
+    /// The text of the first `#=GF` line with the tag.
+    pub fn file_annotation(&self, tag: &str) -> Option<&str> {
+        self.file_annotations.iter()
+            .find(|(file_tag, _)| file_tag == tag)
+            .map(|(_, text)| text.as_str())
+    }
+
+    /// The `#=GC` annotation with the tag.
+    pub fn column_annotation(&self, tag: &str) -> Option<&str> {
+        self.column_annotations.iter()
+            .find(|(column_tag, _)| column_tag == tag)
+            .map(|(_, annotation)| annotation.as_str())
+    }
+
+    /// The `#=GR` annotation of a row with the tag.
+    pub fn residue_annotation(&self, id: &str, tag: &str) -> Option<&str> {
+        self.residue_annotations.iter()
+            .find(|(row_id, residue_tag, _)| row_id == id && residue_tag == tag)
+            .map(|(_, _, annotation)| annotation.as_str())
+    }
+}
+
+impl<'a, A: Alphabet> fmt::Debug for Stockholm<'a, A> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Stockholm")
+            .field("msa", &self.msa)
+            .field("file_annotations", &self.file_annotations)
+            .field("sequence_annotations", &self.sequence_annotations)
+            .field("column_annotations", &self.column_annotations)
+            .field("residue_annotations", &self.residue_annotations)
+            .finish()
+    }
+}
+
+/// Appends to the annotation with the key or adds a new one
+fn extend_annotation<K: PartialEq>(
+    annotations: &mut Vec<(K, String)>,
+    lines: &mut Vec<usize>,
+    key: K,
+    annotation: &str,
+    line: usize
+) {
+    let annotation: String = annotation.split_whitespace().collect();
+
+    match annotations.iter().position(|(existing, _)| *existing == key) {
+        Some(index) => {
+            annotations[index].1.push_str(&annotation);
+            lines[index] = line;
+        }
+        None => {
+            annotations.push((key, annotation));
+            lines.push(line);
+        }
+    }
+}
+
+/// Reads the first alignment in a Stockholm file.
+///
+/// # Errors
+/// If the file doesn't start with a `# STOCKHOLM` header, doesn't end with //, has an
+/// annotation line missing fields, the rows or per column annotations have different lengths,
+/// a `#=GR` line is for a row that doesn't exist or a row contains a symbol not in the
+/// alphabet.
+///
+/// # Example
+/// ```
+/// use biors::align::format::stockholm;
+/// use biors::alphabet::{Gapped, UnambiguousDnaAlphabet};
+///
+/// let dna = UnambiguousDnaAlphabet;
+/// let gapped = Gapped::new(&dna);
+/// let text = "# STOCKHOLM 1.0\n\
+///             #=GF ID hairpin\n\
+///             a            GGA.TCC\n\
+///             b            GGAATCC\n\
+///             #=GC SS_cons <<<.>>>\n\
+///             //\n";
+/// let stockholm = stockholm::read(text, &gapped).unwrap();
+///
+/// assert_eq!(stockholm.file_annotation("ID"), Some("hairpin"));
+/// assert_eq!(stockholm.column_annotation("SS_cons"), Some("<<<.>>>"));
+/// assert_eq!(stockholm.msa.rows()[0].to_string(), "GGA.TCC");
+/// ```
+pub fn read<'a, A: Alphabet>(text: &str, alphabet: &'a Gapped<'a, A>) -> Result<Stockholm<'a, A>> {
+    let mut lines = text.lines()
+        .enumerate()
+        .map(|(line_number, line)| (line_number + 1, line.trim_end()))
+        .filter(|(_, line)| !line.is_empty());
+
+    match lines.next() {
+        Some((_, line)) if line.starts_with("# STOCKHOLM") => {}
+        Some((line_number, _)) => {
+            let description = "The file must start with a # STOCKHOLM header".to_owned();
+            return Err(FormatError::new(ErrorKind::Syntax, Some(line_number), description));
+        }
+        None => {
+            let description = "The file is empty".to_owned();
+            return Err(FormatError::new(ErrorKind::UnexpectedEnd, None, description));
+        }
+    }
+
+    let mut rows = Rows::new(alphabet);
+    let mut file_annotations = vec![];
+    let mut sequence_annotations = vec![];
+    let mut column_annotations = vec![];
+    let mut column_lines = vec![];
+    let mut residue_annotations = vec![];
+    let mut residue_lines = vec![];
+    let mut terminated = false;
+
+    for (line_number, line) in lines.by_ref() {
+        let missing_field = || {
+            let description = format!("The line {:?} is missing a field", line);
+            FormatError::new(ErrorKind::Syntax, Some(line_number), description)
+        };
+
+        if line == "//" {
+            terminated = true;
+            break;
+        } else if let Some(rest) = line.strip_prefix("#=GF") {
+            let (tag, text) = split_field(rest).ok_or_else(missing_field)?;
+            file_annotations.push((tag.to_owned(), text.to_owned()));
+        } else if let Some(rest) = line.strip_prefix("#=GS") {
+            let (id, rest) = split_field(rest).ok_or_else(missing_field)?;
+            let (tag, text) = split_field(rest).ok_or_else(missing_field)?;
+            sequence_annotations.push((id.to_owned(), tag.to_owned(), text.to_owned()));
+        } else if let Some(rest) = line.strip_prefix("#=GC") {
+            let (tag, annotation) = split_field(rest).ok_or_else(missing_field)?;
+            let key = tag.to_owned();
+            let (annotations, lines) = (&mut column_annotations, &mut column_lines);
+            extend_annotation(annotations, lines, key, annotation, line_number);
+        } else if let Some(rest) = line.strip_prefix("#=GR") {
+            let (id, rest) = split_field(rest).ok_or_else(missing_field)?;
+            let (tag, annotation) = split_field(rest).ok_or_else(missing_field)?;
+            if rows.position(id).is_none() {
+                let description = format!("There is a #=GR line for {} before its row", id);
+                return Err(FormatError::new(ErrorKind::Syntax, Some(line_number), description));
+            }
+            let key = (id.to_owned(), tag.to_owned());
+            let (annotations, lines) = (&mut residue_annotations, &mut residue_lines);
+            extend_annotation(annotations, lines, key, annotation, line_number);
+        } else if line.starts_with('#') {
+            // Other lines starting with # are comments
+        } else {
+            let (id, symbols) = split_field(line).ok_or_else(missing_field)?;
+            let row = rows.get_or_add(id, line_number)?;
+            rows.extend(row, symbols, line_number)?;
+        }
+    }
+
+    if !terminated {
+        let description = "The alignment must end with //".to_owned();
+        return Err(FormatError::new(ErrorKind::UnexpectedEnd, None, description));
+    }
+
+    let msa = rows.finish()?;
+    let columns = msa.num_columns();
+    let annotations = column_annotations.iter()
+        .map(|(tag, annotation): &(String, String)| (tag.as_str(), annotation))
+        .chain(residue_annotations.iter().map(|((_, tag), annotation)| (tag.as_str(), annotation)));
+    let lines = column_lines.iter().chain(&residue_lines);
+
+    for ((tag, annotation), &line) in annotations.zip(lines) {
+        if annotation.chars().count() != columns {
+            let description = format!(
+                "The {} annotation has {} columns but the alignment has {}",
+                tag, annotation.chars().count(), columns
+            );
+            return Err(FormatError::new(ErrorKind::LengthMismatch, Some(line), description));
+        }
+    }
+
+    Ok(Stockholm {
+        msa,
+        file_annotations,
+        sequence_annotations,
+        column_annotations,
+        residue_annotations: residue_annotations.into_iter()
+            .map(|((id, tag), annotation)| (id, tag, annotation))
+            .collect(),
+    })
+}
+
+/// Writes an alignment and its annotations in Stockholm format with each row on one line. The
+/// `#=GR` annotations of a row follow it.
+///
+/// # Errors
+/// If an ID is empty or contains whitespace.
+pub fn write<A: Alphabet>(stockholm: &Stockholm<A>) -> Result<String> {
+    let msa = &stockholm.msa;
+    for id in msa.ids() {
+        check_id(id)?;
+    }
+
+    let column_width = stockholm.column_annotations.iter().map(|(tag, _)| tag.len() + 5);
+    let residue_width =
+        stockholm.residue_annotations.iter().map(|(id, tag, _)| id.len() + tag.len() + 6);
+    let width = column_width.chain(residue_width).fold(id_width(msa), usize::max) + 1;
+
+    let mut out = "# STOCKHOLM 1.0\n".to_owned();
+    for (tag, text) in &stockholm.file_annotations {
+        out.push_str(&format!("#=GF {} {}\n", tag, text));
+    }
+    for (id, tag, text) in &stockholm.sequence_annotations {
+        out.push_str(&format!("#=GS {} {} {}\n", id, tag, text));
+    }
+    out.push('\n');
+
+    for (id, row) in msa.ids().iter().zip(msa.rows()) {
+        out.push_str(&format!("{:width$}", id, width = width));
+        push_symbols(&mut out, row, 0..row.len());
+        out.push('\n');
+
+        let residue_annotations =
+            stockholm.residue_annotations.iter().filter(|(row_id, _, _)| row_id == id);
+        for (_, tag, annotation) in residue_annotations {
+            let label = format!("#=GR {} {}", id, tag);
+            out.push_str(&format!("{:width$}{}\n", label, annotation, width = width));
+        }
+    }
+    for (tag, annotation) in &stockholm.column_annotations {
+        let label = format!("#=GC {}", tag);
+        out.push_str(&format!("{:width$}{}\n", label, annotation, width = width));
+    }
+    out.push_str("//\n");
+
+    Ok(out)
+}
+
+//================================================================================
+// Tests
+//================================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alphabet::UnambiguousDnaAlphabet;
+
+    const EXAMPLE: &str = "# STOCKHOLM 1.0
+#=GF ID   tRNA
+#=GF CC   An interleaved example
+#=GF CC   with two blocks.
+#=GS a/1-12 AC X00001.1
+#=GS b/1-11 AC X00002.1
+
+a/1-12       GCGGATTT
+#=GR a/1-12 SS <<<<....
+b/1-11       GCGGA.TT
+# a comment
+#=GC SS_cons <<<<....
+
+a/1-12       AGCTCA
+#=GR a/1-12 SS ..>>>>
+b/1-11       AGCTC-
+#=GC SS_cons ..>>>>
+//
+";
+
+    /// Tests that annotations split over blocks are joined and survive writing and reading
+    #[test]
+    fn round_trip() {
+        let dna = UnambiguousDnaAlphabet;
+        let gapped = Gapped::new(&dna);
+        let stockholm = read(EXAMPLE, &gapped).unwrap();
+
+        assert_eq!(stockholm.msa.ids(), &["a/1-12", "b/1-11"]);
+        assert_eq!(stockholm.msa.rows()[1].to_string(), "GCGGA.TTAGCTC-");
+        assert_eq!(stockholm.file_annotations.len(), 3);
+        assert_eq!(stockholm.file_annotation("CC"), Some("An interleaved example"));
+        let (id, tag, text) = &stockholm.sequence_annotations[1];
+        assert_eq!((id.as_str(), tag.as_str(), text.as_str()), ("b/1-11", "AC", "X00002.1"));
+        assert_eq!(stockholm.column_annotation("SS_cons"), Some("<<<<......>>>>"));
+        assert_eq!(stockholm.residue_annotation("a/1-12", "SS"), Some("<<<<......>>>>"));
+        assert_eq!(stockholm.residue_annotation("b/1-11", "SS"), None);
+
+        let written = write(&stockholm).unwrap();
+        assert!(written.contains("\n#=GR a/1-12 SS <<<<......>>>>\nb/1-11"));
+
+        let read_back = read(&written, &gapped).unwrap();
+        assert_eq!(read_back.msa.ids(), stockholm.msa.ids());
+        assert_eq!(read_back.msa.rows(), stockholm.msa.rows());
+        assert_eq!(read_back.file_annotations, stockholm.file_annotations);
+        assert_eq!(read_back.sequence_annotations, stockholm.sequence_annotations);
+        assert_eq!(read_back.column_annotations, stockholm.column_annotations);
+        assert_eq!(read_back.residue_annotations, stockholm.residue_annotations);
+    }
+
+    /// Tests that errors report the line they were found on
+    #[test]
+    fn errors() {
+        let dna = UnambiguousDnaAlphabet;
+        let gapped = Gapped::new(&dna);
+
+        let err = read("#STOCKHOLM\na ACGT\n//\n", &gapped).unwrap_err();
+        assert_eq!((err.kind(), err.line()), (&ErrorKind::Syntax, Some(1)));
+
+        let err = read("# STOCKHOLM 1.0\na ACGT\n", &gapped).unwrap_err();
+        assert_eq!((err.kind(), err.line()), (&ErrorKind::UnexpectedEnd, None));
+
+        let err = read("# STOCKHOLM 1.0\na ACGT\n#=GF\n//\n", &gapped).unwrap_err();
+        assert_eq!((err.kind(), err.line()), (&ErrorKind::Syntax, Some(3)));
+
+        let err = read("# STOCKHOLM 1.0\n#=GR a SS ....\na ACGT\n//\n", &gapped).unwrap_err();
+        assert_eq!((err.kind(), err.line()), (&ErrorKind::Syntax, Some(2)));
+
+        let err = read("# STOCKHOLM 1.0\na ACGT\n#=GC SS_cons ...\n//\n", &gapped).unwrap_err();
+        assert_eq!((err.kind(), err.line()), (&ErrorKind::LengthMismatch, Some(3)));
+
+        let err = read("# STOCKHOLM 1.0\na ACGT\n#=GR a SS .....\n//\n", &gapped).unwrap_err();
+        assert_eq!((err.kind(), err.line()), (&ErrorKind::LengthMismatch, Some(3)));
+
+        let err = read("# STOCKHOLM 1.0\na ACGT\nb ACGU\n//\n", &gapped).unwrap_err();
+        assert_eq!((err.kind(), err.line()), (&ErrorKind::InvalidSymbol, Some(3)));
+    }
+}
@@ -0,0 +1,390 @@
+//! Global alignment in linear space using Hirschberg's divide and conquer traceback.
+//!
+//! Rather than scoring the two halves towards each other, each subproblem is scored forwards
+//! keeping a single row, and every cell below the middle row carries where the traceback of the
+//! full DP would cross the middle row from it. Splitting there and aligning both halves
+//! recursively gives exactly the alignment of the full DP, including how ties are broken. The
+//! cost is about twice the area of the matrix. A gap that crosses the middle row is passed to
+//! both halves so that it is only charged one opening penalty.
+
+use crate::alphabet::Alphabet;
+use crate::alphabet::encoding::AlphabetEncoder;
+use crate::sequence::Sequence;
+use super::pairwise::{State, MIN_SCORE};
+use super::{sequence_indices, Aligner, Alignment, AlignmentMode, AlignmentOperation, Scoring};
+use super::SubstitutionScore;
+
+impl<S: SubstitutionScore> Aligner<S> {
+    /// Globally aligns x to y in O(n + m) memory and O(nm) time, returning the same alignment
+    /// as [global()](Aligner::global).
+    ///
+    /// # Example
+    /// ```
+    /// use biors::align::{Aligner, Scoring};
+    /// use biors::alphabet::UnambiguousDnaAlphabet;
+    /// use biors::sequence::Sequence;
+    ///
+    /// let alphabet = UnambiguousDnaAlphabet;
+    /// let mut x = Sequence::new(&alphabet);
+    /// let mut y = Sequence::new(&alphabet);
+    /// x.push("ACGTACGTTTTTACGTACGT").unwrap();
+    /// y.push("ACGTACGTACGTACGA").unwrap();
+    ///
+    /// let aligner = Aligner::new(Scoring::match_mismatch(2, -3, -5, -1));
+    /// let alignment = aligner.hirschberg(&x, &y);
+    ///
+    /// assert_eq!(alignment, aligner.global(&x, &y));
+    /// ```
+    pub fn hirschberg<A, E>(&self, x: &Sequence<A, E>, y: &Sequence<A, E>) -> Alignment
+    where
+        A: Alphabet,
+        E: AlphabetEncoder<A>
+    {
+        self.hirschberg_indices(&sequence_indices(x), &sequence_indices(y))
+    }
+
+    /// Linear space global alignment of two sequences given as the indices of their symbols in
+    /// the alphabet. See [hirschberg()](Aligner::hirschberg).
+    pub fn hirschberg_indices(&self, x: &[usize], y: &[usize]) -> Alignment {
+        let scoring = self.scoring();
+        let mut operations = Vec::with_capacity(x.len().max(y.len()));
+
+        divide(scoring, x, y, false, false, &mut operations);
+        let score = scoring.score_operations(x, y, 0, 0, &operations);
+
+        Alignment {
+            score,
+            x_start: 0,
+            x_end: x.len(),
+            y_start: 0,
+            y_end: y.len(),
+            x_len: x.len(),
+            y_len: y.len(),
+            mode: AlignmentMode::Global,
+            operations,
+        }
+    }
+}
+
+/// One row of the matrices of the full DP
+struct Row {
+    best: Vec<i32>,
+    insertion: Vec<i32>,
+    deletion: Vec<i32>,
+}
+
+/// Where a traceback first reaches the middle row: the column and whether it's in an insertion
+#[derive(Debug, Clone, Copy)]
+struct Crossing {
+    column: usize,
+    insertion: bool,
+}
+
+/// Appends the operations of the alignment of x to y that the full DP would choose. When
+/// start_insertion is true the alignment continues an insertion from the subproblem before it
+/// and when end_insertion is true it has to end in an insertion that the next one continues.
+fn divide<S: SubstitutionScore>(
+    scoring: &Scoring<S>,
+    x: &[usize],
+    y: &[usize],
+    start_insertion: bool,
+    end_insertion: bool,
+    operations: &mut Vec<AlignmentOperation>
+) {
+    let n = x.len();
+
+    let mut row = first_row(scoring, y.len(), start_insertion);
+    if n <= 1 {
+        let mut rows = vec![];
+        if n == 1 {
+            let next = next_row(scoring, x[0], y, &row);
+            rows.push(row);
+            row = next;
+        }
+        rows.push(row);
+        traceback(scoring, x, y, &rows, end_insertion, operations);
+        return;
+    }
+
+    let middle = n / 2;
+    for &a in &x[..middle] {
+        row = next_row(scoring, a, y, &row);
+    }
+
+    let mut best_crossing: Vec<Crossing> = (0..=y.len())
+        .map(|column| Crossing { column, insertion: false })
+        .collect();
+    let mut insertion_crossing: Vec<Crossing> = (0..=y.len())
+        .map(|column| Crossing { column, insertion: true })
+        .collect();
+
+    // Each choice is the one the traceback of the full DP makes at that cell
+    for &a in &x[middle..] {
+        let next = next_row(scoring, a, y, &row);
+        let mut next_best = Vec::with_capacity(y.len() + 1);
+        let mut next_insertion = Vec::with_capacity(y.len() + 1);
+        let mut deletion = best_crossing[0];
+
+        for j in 0..=y.len() {
+            let insertion = if extends_insertion(scoring, &next, &row, j) {
+                insertion_crossing[j]
+            } else {
+                best_crossing[j]
+            };
+            if j > 0 && !extends_deletion(scoring, &next, j) {
+                deletion = next_best[j - 1];
+            }
+
+            next_best.push(match best_state(scoring, a, y, &next, &row, j) {
+                State::Diagonal => best_crossing[j - 1],
+                State::Insertion => insertion,
+                _ => deletion,
+            });
+            next_insertion.push(insertion);
+        }
+
+        row = next;
+        best_crossing = next_best;
+        insertion_crossing = next_insertion;
+    }
+
+    let m = y.len();
+    let crossing = if end_insertion { insertion_crossing[m] } else { best_crossing[m] };
+    let (column, insertion) = (crossing.column, crossing.insertion);
+    // Free the O(m) rows before recursing so the whole alignment stays in linear space
+    drop((row, best_crossing, insertion_crossing));
+    divide(scoring, &x[..middle], &y[..column], start_insertion, insertion, operations);
+    divide(scoring, &x[middle..], &y[column..], insertion, end_insertion, operations);
+}
+
+/// The first row of the matrices, starting either afresh or in an insertion
+fn first_row<S: SubstitutionScore>(scoring: &Scoring<S>, m: usize, start_insertion: bool) -> Row {
+    let open = scoring.gap_open + scoring.gap_extend;
+    let extend = scoring.gap_extend;
+
+    let mut row = Row {
+        best: vec![0; m + 1],
+        insertion: vec![MIN_SCORE; m + 1],
+        deletion: vec![MIN_SCORE; m + 1],
+    };
+    if start_insertion {
+        row.insertion[0] = 0;
+    }
+
+    for j in 1..=m {
+        row.deletion[j] = (row.best[j - 1] + open).max(row.deletion[j - 1] + extend);
+        row.best[j] = row.deletion[j];
+    }
+
+    row
+}
+
+/// Scores the row of a, the next symbol of x, from the previous row
+fn next_row<S: SubstitutionScore>(
+    scoring: &Scoring<S>,
+    a: usize,
+    y: &[usize],
+    previous: &Row
+) -> Row {
+    let open = scoring.gap_open + scoring.gap_extend;
+    let extend = scoring.gap_extend;
+    let m = y.len();
+
+    let mut row = Row {
+        best: vec![0; m + 1],
+        insertion: vec![MIN_SCORE; m + 1],
+        deletion: vec![MIN_SCORE; m + 1],
+    };
+
+    row.insertion[0] = (previous.best[0] + open).max(previous.insertion[0] + extend);
+    row.best[0] = row.insertion[0];
+
+    for j in 1..=m {
+        let diagonal = previous.best[j - 1] + scoring.substitution.score(a, y[j - 1]);
+        row.insertion[j] = (previous.best[j] + open).max(previous.insertion[j] + extend);
+        row.deletion[j] = (row.best[j - 1] + open).max(row.deletion[j - 1] + extend);
+        row.best[j] = diagonal.max(row.insertion[j]).max(row.deletion[j]);
+    }
+
+    row
+}
+
+/// Which matrix the traceback moves to from the best score in column j of the row of a
+fn best_state<S: SubstitutionScore>(
+    scoring: &Scoring<S>,
+    a: usize,
+    y: &[usize],
+    row: &Row,
+    previous: &Row,
+    j: usize
+) -> State {
+    if j > 0 && row.best[j] == previous.best[j - 1] + scoring.substitution.score(a, y[j - 1]) {
+        State::Diagonal
+    } else if row.best[j] == row.insertion[j] {
+        State::Insertion
+    } else {
+        State::Deletion
+    }
+}
+
+/// Whether the insertion ending in column j of the row extends one from the previous row
+fn extends_insertion<S: SubstitutionScore>(
+    scoring: &Scoring<S>,
+    row: &Row,
+    previous: &Row,
+    j: usize
+) -> bool {
+    row.insertion[j] == previous.insertion[j] + scoring.gap_extend
+}
+
+/// Whether the deletion ending in column j of the row extends one from column j - 1
+fn extends_deletion<S: SubstitutionScore>(scoring: &Scoring<S>, row: &Row, j: usize) -> bool {
+    j > 0 && row.deletion[j] == row.deletion[j - 1] + scoring.gap_extend
+}
+
+/// Appends the operations found by following the rows of a subproblem with at most one symbol
+/// of x back from the end, making the same choices as the full DP
+fn traceback<S: SubstitutionScore>(
+    scoring: &Scoring<S>,
+    x: &[usize],
+    y: &[usize],
+    rows: &[Row],
+    end_insertion: bool,
+    operations: &mut Vec<AlignmentOperation>
+) {
+    let (mut i, mut j) = (rows.len() - 1, y.len());
+    let mut state = if end_insertion { State::Insertion } else { State::Best };
+    let mut reversed = vec![];
+
+    while i > 0 || j > 0 {
+        if state == State::Best {
+            state = if i == 0 {
+                State::Deletion
+            } else {
+                best_state(scoring, x[i - 1], y, &rows[i], &rows[i - 1], j)
+            };
+        }
+
+        match state {
+            State::Diagonal => {
+                reversed.push(if x[i - 1] == y[j - 1] {
+                    AlignmentOperation::Match
+                } else {
+                    AlignmentOperation::Substitution
+                });
+                i -= 1;
+                j -= 1;
+                state = State::Best;
+            }
+            State::Insertion => {
+                reversed.push(AlignmentOperation::Insertion);
+                if !extends_insertion(scoring, &rows[i], &rows[i - 1], j) {
+                    state = State::Best;
+                }
+                i -= 1;
+            }
+            State::Deletion => {
+                reversed.push(AlignmentOperation::Deletion);
+                if !extends_deletion(scoring, &rows[i], j) {
+                    state = State::Best;
+                }
+                j -= 1;
+            }
+            State::Best => unreachable!(),
+        }
+    }
+
+    operations.extend(reversed.into_iter().rev());
+}
+
+//================================================================================
+// Tests
+//================================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sequence::test_util::random_indices;
+
+    /// Checks that a Hirschberg alignment is the one the full DP finds
+    fn check<S: SubstitutionScore>(aligner: &Aligner<S>, x: &[usize], y: &[usize]) {
+        let full = aligner.align_indices(AlignmentMode::Global, x, y);
+        let linear = aligner.hirschberg_indices(x, y);
+
+        assert_eq!(linear, full, "x: {:?}\ny: {:?}", x, y);
+    }
+
+    /// Tests against the full DP on random sequences with several gap penalties
+    #[test]
+    fn matches_full_dp() {
+        let scorings = [
+            Scoring::match_mismatch(1, -1, 0, -1),
+            Scoring::match_mismatch(2, -3, -5, -2),
+            Scoring::match_mismatch(5, -4, -10, -1),
+            Scoring::match_mismatch(1, -2, -20, 0),
+        ];
+
+        for scoring in &scorings {
+            let aligner = Aligner::new(scoring.clone());
+
+            for seed in 1..40 {
+                let x = random_indices(seed, (seed * 7 % 50) as usize, 4);
+                let y = random_indices(seed * 104_729, (seed * 13 % 60) as usize, 4);
+                check(&aligner, &x, &y);
+            }
+        }
+    }
+
+    /// Tests related sequences where long gaps cross the middle row
+    #[test]
+    fn long_gaps() {
+        let aligner = Aligner::new(Scoring::match_mismatch(2, -3, -8, -1));
+
+        for seed in 1..20 {
+            let y = random_indices(seed, 120, 4);
+            let mut x = y[..30].to_vec();
+            x.extend(&y[30 + seed as usize..]);
+            x.extend(random_indices(seed + 1000, seed as usize, 4));
+
+            check(&aligner, &x, &y);
+            check(&aligner, &y, &x);
+        }
+    }
+
+    /// Tests that ties between co-optimal alignments are broken as in the full DP
+    #[test]
+    fn ties() {
+        let scorings = [
+            Scoring::match_mismatch(0, 0, 0, 0),
+            Scoring::match_mismatch(1, -1, 0, -1),
+            Scoring::match_mismatch(2, -2, -2, -1),
+            Scoring::match_mismatch(1, 0, -1, 0),
+        ];
+
+        for scoring in &scorings {
+            let aligner = Aligner::new(scoring.clone());
+
+            for seed in 1..30 {
+                let x = random_indices(seed, (seed * 5 % 40) as usize, 2);
+                let y = random_indices(seed * 7_919, (seed * 11 % 45) as usize, 2);
+                check(&aligner, &x, &y);
+
+                let repeat: Vec<usize> = (0..seed as usize * 2).map(|i| i % 2).collect();
+                let shorter: Vec<usize> = (0..seed as usize).map(|i| i % 2).collect();
+                check(&aligner, &repeat, &shorter);
+                check(&aligner, &shorter, &repeat);
+                check(&aligner, &repeat, &vec![1; seed as usize]);
+            }
+        }
+    }
+
+    /// Tests that alignments with empty sequences are a single gap
+    #[test]
+    fn empty() {
+        let aligner = Aligner::new(Scoring::match_mismatch(1, -1, -2, -1));
+
+        assert_eq!(aligner.hirschberg_indices(&[0, 1, 2], &[]).cigar(), "3I");
+        assert_eq!(aligner.hirschberg_indices(&[], &[0, 1]).cigar(), "2D");
+        assert!(aligner.hirschberg_indices(&[], &[]).is_empty());
+    }
+}
@@ -0,0 +1,396 @@
+//! Substitution matrices that score every pair of symbols of an alphabet.
+//!
+//! The common BLOSUM and PAM protein matrices and the NUC.4.4 nucleotide matrix are built in.
+//! Others can be read from the format used by NCBI BLAST and EMBOSS: comment lines starting with
+//! #, a header line of column symbols and then one line per row starting with its symbol.
+
+use std::error::Error;
+use std::fmt;
+use std::marker::PhantomData;
+use crate::alphabet::Alphabet;
+use super::SubstitutionScore;
+
+/// The type of Results returned when constructing a substitution matrix.
+pub type Result<T> = std::result::Result<T, MatrixError>;
+
+/// The substitution matrices that are built in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BuiltinMatrix {
+    /// BLOSUM45 for distantly related proteins
+    Blosum45,
+    /// BLOSUM50
+    Blosum50,
+    /// BLOSUM62, the default for most protein searches
+    Blosum62,
+    /// BLOSUM80
+    Blosum80,
+    /// BLOSUM90 for closely related proteins
+    Blosum90,
+    /// PAM30 for short, closely related peptides
+    Pam30,
+    /// PAM70
+    Pam70,
+    /// PAM250 for distantly related proteins
+    Pam250,
+    /// NUC.4.4 which scores IUPAC nucleotide codes
+    Nuc44,
+}
+
+impl BuiltinMatrix {
+    /// The name the matrix is usually known by
+    pub fn name(self) -> &'static str {
+        match self {
+            BuiltinMatrix::Blosum45 => "BLOSUM45",
+            BuiltinMatrix::Blosum50 => "BLOSUM50",
+            BuiltinMatrix::Blosum62 => "BLOSUM62",
+            BuiltinMatrix::Blosum80 => "BLOSUM80",
+            BuiltinMatrix::Blosum90 => "BLOSUM90",
+            BuiltinMatrix::Pam30 => "PAM30",
+            BuiltinMatrix::Pam70 => "PAM70",
+            BuiltinMatrix::Pam250 => "PAM250",
+            BuiltinMatrix::Nuc44 => "NUC.4.4",
+        }
+    }
+
+    /// The matrix in NCBI format
+    pub fn ncbi_text(self) -> &'static str {
+        match self {
+            BuiltinMatrix::Blosum45 => include_str!("matrices/BLOSUM45"),
+            BuiltinMatrix::Blosum50 => include_str!("matrices/BLOSUM50"),
+            BuiltinMatrix::Blosum62 => include_str!("matrices/BLOSUM62"),
+            BuiltinMatrix::Blosum80 => include_str!("matrices/BLOSUM80"),
+            BuiltinMatrix::Blosum90 => include_str!("matrices/BLOSUM90"),
+            BuiltinMatrix::Pam30 => include_str!("matrices/PAM30"),
+            BuiltinMatrix::Pam70 => include_str!("matrices/PAM70"),
+            BuiltinMatrix::Pam250 => include_str!("matrices/PAM250"),
+            BuiltinMatrix::Nuc44 => include_str!("matrices/NUC.4.4"),
+        }
+    }
+}
+
+/// Scores for every pair of symbols of the alphabet A indexed by the position of each symbol in
+/// [Alphabet::symbols()](crate::alphabet::Alphabet::symbols).
+///
+/// # Example
+/// ```
+/// use biors::align::{Aligner, Scoring};
+/// use biors::align::matrix::{BuiltinMatrix, SubstitutionMatrix};
+/// use biors::alphabet::ProteinAlphabet;
+/// use biors::sequence::Sequence;
+///
+/// let alphabet = ProteinAlphabet;
+/// let blosum62 = SubstitutionMatrix::builtin(BuiltinMatrix::Blosum62, &alphabet).unwrap();
+/// assert_eq!(blosum62.score_symbols(&alphabet, "W", "W"), Some(11));
+///
+/// let mut x = Sequence::new(&alphabet);
+/// let mut y = Sequence::new(&alphabet);
+/// x.push("HEAGAWGHEE").unwrap();
+/// y.push("PAWHEAE").unwrap();
+///
+/// let aligner = Aligner::new(Scoring::new(blosum62, -10, -1));
+/// let alignment = aligner.local(&x, &y);
+/// assert_eq!(alignment.score, 17);
+/// ```
+#[derive(Debug)]
+pub struct SubstitutionMatrix<A: Alphabet> {
+    size: usize,
+    scores: Vec<i32>,
+    phantom: PhantomData<A>,
+}
+
+impl<A: Alphabet> SubstitutionMatrix<A> {
+    /// Constructs one of the built in matrices for the alphabet.
+    ///
+    /// # Errors
+    /// If the matrix doesn't have a score for every symbol of the alphabet. The protein
+    /// matrices cover the [ExtendedProteinAlphabet](crate::alphabet::ExtendedProteinAlphabet) and
+    /// NUC.4.4 covers the [AmbiguousDnaAlphabet](crate::alphabet::AmbiguousDnaAlphabet).
+    pub fn builtin(matrix: BuiltinMatrix, alphabet: &A) -> Result<Self> {
+        SubstitutionMatrix::from_ncbi(matrix.ncbi_text(), alphabet)
+    }
+
+    /// Parses a matrix in NCBI format and maps its symbols onto those of the alphabet. Symbols
+    /// of the matrix that are not in the alphabet are ignored.
+    ///
+    /// # Errors
+    /// If the text is not a valid matrix or it doesn't have a row and a column for every symbol
+    /// of the alphabet.
+    pub fn from_ncbi(text: &str, alphabet: &A) -> Result<Self> {
+        let mut lines = text.lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'));
+
+        let header: Vec<&str> = match lines.next() {
+            Some(line) => line.split_whitespace().collect(),
+            None => {
+                return Err(MatrixError::new(ErrorKind::Parse, "The matrix is empty".to_owned()));
+            }
+        };
+
+        let mut rows: Vec<(&str, Vec<i32>)> = vec![];
+        for line in lines {
+            let mut fields = line.split_whitespace();
+            let symbol = fields.next().unwrap_or_default();
+            let scores = fields
+                .map(|field| field.parse::<i32>())
+                .collect::<std::result::Result<Vec<i32>, _>>()
+                .map_err(|err| {
+                    let description = format!("Invalid score in row {}: {}", symbol, err);
+                    MatrixError::new(ErrorKind::Parse, description)
+                })?;
+
+            if scores.len() != header.len() {
+                let description = format!(
+                    "Row {} has {} scores but the header has {} symbols",
+                    symbol, scores.len(), header.len()
+                );
+                return Err(MatrixError::new(ErrorKind::Parse, description));
+            }
+
+            rows.push((symbol, scores));
+        }
+
+        let symbols = alphabet.symbols();
+        let size = symbols.len();
+        let mut columns = Vec::with_capacity(size);
+        let mut row_scores = Vec::with_capacity(size);
+
+        for symbol in symbols {
+            let column = header.iter().position(|s| s == symbol);
+            let row = rows.iter().find(|(s, _)| s == symbol);
+
+            match (column, row) {
+                (Some(column), Some((_, scores))) => {
+                    columns.push(column);
+                    row_scores.push(scores);
+                }
+                _ => {
+                    let kind = ErrorKind::MissingSymbol((*symbol).to_owned());
+                    let description = format!("The matrix has no scores for the symbol {}", symbol);
+                    return Err(MatrixError::new(kind, description));
+                }
+            }
+        }
+
+        let scores = row_scores.iter()
+            .flat_map(|row| columns.iter().map(move |&column| row[column]))
+            .collect();
+
+        Ok(SubstitutionMatrix { size, scores, phantom: PhantomData })
+    }
+
+    /// Constructs a matrix by calling a function with the index of every pair of symbols of the
+    /// alphabet.
+    pub fn from_fn<F>(alphabet: &A, f: F) -> Self
+    where
+        F: Fn(usize, usize) -> i32
+    {
+        let size = alphabet.symbols().len();
+        let scores = (0..size)
+            .flat_map(|a| (0..size).map(move |b| (a, b)))
+            .map(|(a, b)| f(a, b))
+            .collect();
+
+        SubstitutionMatrix { size, scores, phantom: PhantomData }
+    }
+
+    /// The number of symbols in the alphabet of the matrix
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// The score for the pair of symbols, None if either is not in the alphabet
+    pub fn score_symbols(&self, alphabet: &A, a: &str, b: &str) -> Option<i32> {
+        let symbols = alphabet.symbols();
+        let a = symbols.iter().position(|s| *s == a)?;
+        let b = symbols.iter().position(|s| *s == b)?;
+
+        Some(self.score(a, b))
+    }
+
+    /// The lowest score in the matrix
+    pub fn min_score(&self) -> i32 {
+        self.scores.iter().copied().min().unwrap_or(0)
+    }
+
+    /// The highest score in the matrix
+    pub fn max_score(&self) -> i32 {
+        self.scores.iter().copied().max().unwrap_or(0)
+    }
+
+    /// Returns true if the score of a against b is the same as b against a for every pair
+    pub fn is_symmetric(&self) -> bool {
+        (0..self.size).all(|a| (0..a).all(|b| self.score(a, b) == self.score(b, a)))
+    }
+}
+
+impl<A: Alphabet> SubstitutionScore for SubstitutionMatrix<A> {
+    /// # Panics
+    /// If either index is not in the alphabet.
+    #[inline]
+    fn score(&self, a: usize, b: usize) -> i32 {
+        self.scores[a * self.size + b]
+    }
+}
+
+// Implemented by hand so that the alphabet itself doesn't need to be Clone
+impl<A: Alphabet> Clone for SubstitutionMatrix<A> {
+    fn clone(&self) -> Self {
+        SubstitutionMatrix { size: self.size, scores: self.scores.clone(), phantom: PhantomData }
+    }
+}
+
+//================================================================================
+// Errors
+//================================================================================
+
+/// Represents the kind of error that occurred while constructing a substitution matrix.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ErrorKind {
+    /// The matrix text was not in NCBI format
+    Parse,
+
+    /// The matrix has no row or column for a symbol of the alphabet
+    MissingSymbol(String),
+}
+
+/// The type of error returned when a substitution matrix cannot be constructed
+#[derive(Debug, Clone)]
+pub struct MatrixError {
+    kind: ErrorKind,
+    description: String,
+}
+
+impl MatrixError {
+    /// Construct a new MatrixError from the given ErrorKind and description
+    pub fn new(kind: ErrorKind, description: String) -> MatrixError {
+        MatrixError { kind, description }
+    }
+
+    /// Get the associated ErrorKind for this error
+    pub fn kind(&self) -> &ErrorKind {
+        &self.kind
+    }
+
+    /// Get the associated description for this error
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+}
+
+impl Error for MatrixError {}
+
+impl fmt::Display for MatrixError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Substitution matrix error: {:?}:\n\t{}", self.kind, self.description)
+    }
+}
+
+//================================================================================
+// Tests
+//================================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alphabet::{
+        AmbiguousDnaAlphabet, ExtendedProteinAlphabet, ProteinAlphabet, UnambiguousDnaAlphabet
+    };
+
+    const PROTEIN_MATRICES: [BuiltinMatrix; 8] = [
+        BuiltinMatrix::Blosum45,
+        BuiltinMatrix::Blosum50,
+        BuiltinMatrix::Blosum62,
+        BuiltinMatrix::Blosum80,
+        BuiltinMatrix::Blosum90,
+        BuiltinMatrix::Pam30,
+        BuiltinMatrix::Pam70,
+        BuiltinMatrix::Pam250,
+    ];
+
+    /// Tests that every built in protein matrix parses, is symmetric and scores identities highest
+    #[test]
+    fn builtin_protein() {
+        let a = ExtendedProteinAlphabet;
+
+        for &builtin in &PROTEIN_MATRICES {
+            let matrix = SubstitutionMatrix::builtin(builtin, &a).unwrap();
+            assert_eq!(matrix.size(), 24);
+            assert!(matrix.is_symmetric(), "{} is not symmetric", builtin.name());
+
+            for i in 0..20 {
+                assert!(matrix.score(i, i) > 0);
+                assert!((0..20).all(|j| matrix.score(i, i) >= matrix.score(i, j)),
+                    "{} scores a substitution of {} above its identity", builtin.name(), i);
+            }
+        }
+    }
+
+    /// Tests some well known BLOSUM62 scores
+    #[test]
+    fn blosum62() {
+        let a = ProteinAlphabet;
+        let matrix = SubstitutionMatrix::builtin(BuiltinMatrix::Blosum62, &a).unwrap();
+
+        assert_eq!(matrix.score_symbols(&a, "W", "W"), Some(11));
+        assert_eq!(matrix.score_symbols(&a, "C", "C"), Some(9));
+        assert_eq!(matrix.score_symbols(&a, "A", "A"), Some(4));
+        assert_eq!(matrix.score_symbols(&a, "I", "V"), Some(3));
+        assert_eq!(matrix.score_symbols(&a, "W", "D"), Some(-4));
+        assert_eq!(matrix.score_symbols(&a, "A", "X"), None);
+        assert_eq!((matrix.min_score(), matrix.max_score()), (-4, 11));
+    }
+
+    /// Tests that NUC.4.4 maps onto both DNA alphabets despite their different symbol orders
+    #[test]
+    fn nuc44() {
+        let ambiguous = AmbiguousDnaAlphabet;
+        let unambiguous = UnambiguousDnaAlphabet;
+
+        let full = SubstitutionMatrix::builtin(BuiltinMatrix::Nuc44, &ambiguous).unwrap();
+        let small = SubstitutionMatrix::builtin(BuiltinMatrix::Nuc44, &unambiguous).unwrap();
+
+        assert!(full.is_symmetric());
+        assert_eq!(full.score_symbols(&ambiguous, "A", "R"), Some(1));
+        assert_eq!(full.score_symbols(&ambiguous, "N", "N"), Some(-1));
+        assert_eq!(small.score_symbols(&unambiguous, "G", "G"), Some(5));
+        assert_eq!(small.score_symbols(&unambiguous, "T", "C"), Some(-4));
+    }
+
+    /// Tests that matrices that don't cover the alphabet are rejected
+    #[test]
+    fn missing_symbol() {
+        let err = SubstitutionMatrix::builtin(BuiltinMatrix::Nuc44, &ProteinAlphabet).unwrap_err();
+        assert_eq!(err.kind(), &ErrorKind::MissingSymbol("Q".to_owned()));
+
+        // The T column is missing
+        let text = "   A  C  G\nA  1  0  0\nC  0  1  0\nG  0  0  1\nT  0  0  0\n";
+        let err = SubstitutionMatrix::from_ncbi(text, &UnambiguousDnaAlphabet).unwrap_err();
+        assert_eq!(err.kind(), &ErrorKind::MissingSymbol("T".to_owned()));
+    }
+
+    /// Tests parsing a small custom matrix including errors
+    #[test]
+    fn parse() {
+        let a = UnambiguousDnaAlphabet;
+        let text = "# Transitions score higher than transversions\n\
+                    \x20  A  C  G  T\n\
+                    A  2 -2  0 -2\n\
+                    C -2  2 -2  0\n\
+                    G  0 -2  2 -2\n\
+                    T -2  0 -2  2\n";
+
+        let matrix = SubstitutionMatrix::from_ncbi(text, &a).unwrap();
+        assert_eq!(matrix.score_symbols(&a, "A", "G"), Some(0));
+        assert_eq!(matrix.score_symbols(&a, "T", "G"), Some(-2));
+        assert_eq!(matrix.score_symbols(&a, "C", "T"), Some(0));
+
+        let short_row = "   A  C\nA  1\nC  0  1\n";
+        let err = SubstitutionMatrix::from_ncbi(short_row, &a).unwrap_err();
+        assert_eq!(err.kind(), &ErrorKind::Parse);
+
+        let bad_score = "   A  C\nA  1  x\nC  0  1\n";
+        let err = SubstitutionMatrix::from_ncbi(bad_score, &a).unwrap_err();
+        assert_eq!(err.kind(), &ErrorKind::Parse);
+    }
+}
@@ -0,0 +1,486 @@
+//! Aligning pairs of [Sequence](crate::sequence::Sequence)s.
+//!
+//! An alignment lines up the symbols of a query sequence x with those of a reference sequence y
+//! by inserting gaps so that similar symbols end up in the same column. Alignments are scored by
+//! a [Scoring] which combines a [SubstitutionScore] for each pair of aligned symbols with affine
+//! gap penalties.
+//!
+//! Symbols are compared by their index in
+//! [Alphabet::symbols()](crate::alphabet::Alphabet::symbols) so any encoder can be used and
+//! substitution scores can be looked up directly.
+
+pub use self::aligned::AlignedSequence;
+pub use self::pairwise::Aligner;
+
+use std::fmt::Write;
+use crate::alphabet::{Alphabet, Gapped};
+use crate::alphabet::encoding::AlphabetEncoder;
+use crate::sequence::Sequence;
+
+pub mod aligned;
+pub mod banded;
+pub mod format;
+pub mod hirschberg;
+pub mod matrix;
+pub mod msa;
+pub mod pairwise;
+pub mod progressive;
+pub mod striped;
+
+/// Which parts of the sequences must be aligned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AlignmentMode {
+    /// Needleman-Wunsch: both sequences are aligned from end to end
+    Global,
+    /// The whole of x is aligned to part of y. Gaps before and after x are free. Useful for
+    /// finding where a short query best fits in a longer reference.
+    SemiGlobal,
+    /// A suffix of one sequence is aligned to a prefix of the other. Gaps at either end of
+    /// either sequence are free. Useful for finding overlapping reads.
+    Overlap,
+    /// Smith-Waterman: the highest scoring pair of substrings is aligned
+    Local,
+}
+
+/// A single column of an alignment between a query x and a reference y.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AlignmentOperation {
+    /// The symbol of x is the same as the symbol of y
+    Match,
+    /// The symbol of x is aligned to a different symbol of y
+    Substitution,
+    /// The symbol of x is aligned to a gap, it is not in y
+    Insertion,
+    /// The symbol of y is aligned to a gap, it is missing from x
+    Deletion,
+}
+
+impl AlignmentOperation {
+    /// The character used for the operation in an extended CIGAR string
+    pub fn cigar_char(self) -> char {
+        match self {
+            AlignmentOperation::Match => '=',
+            AlignmentOperation::Substitution => 'X',
+            AlignmentOperation::Insertion => 'I',
+            AlignmentOperation::Deletion => 'D',
+        }
+    }
+}
+
+//================================================================================
+// Scoring
+//================================================================================
+
+/// Scores aligning the symbol with index a to the symbol with index b.
+///
+/// Implemented for closures so scores can be computed on the fly.
+pub trait SubstitutionScore {
+    /// The score for aligning symbol a of x to symbol b of y. Positive scores favour aligning
+    /// the symbols.
+    fn score(&self, a: usize, b: usize) -> i32;
+}
+
+impl<F: Fn(usize, usize) -> i32> SubstitutionScore for F {
+    #[inline]
+    fn score(&self, a: usize, b: usize) -> i32 {
+        self(a, b)
+    }
+}
+
+/// Gives one score to every pair of identical symbols and another to every pair of different
+/// symbols.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct MatchMismatch {
+    /// The score for aligning identical symbols
+    pub match_score: i32,
+    /// The score for aligning different symbols
+    pub mismatch_score: i32,
+}
+
+impl SubstitutionScore for MatchMismatch {
+    #[inline]
+    fn score(&self, a: usize, b: usize) -> i32 {
+        if a == b { self.match_score } else { self.mismatch_score }
+    }
+}
+
+/// Substitution scores along with affine gap penalties.
+///
+/// A gap of length L scores gap_open + L * gap_extend so both penalties should be negative.
+#[derive(Debug, Clone)]
+pub struct Scoring<S> {
+    /// Scores each pair of aligned symbols
+    pub substitution: S,
+    /// The extra score for starting a gap
+    pub gap_open: i32,
+    /// The score for each symbol aligned to a gap
+    pub gap_extend: i32,
+}
+
+impl<S: SubstitutionScore> Scoring<S> {
+    /// Constructs a scoring from substitution scores and gap penalties.
+    ///
+    /// # Panics
+    /// If either gap penalty is positive.
+    pub fn new(substitution: S, gap_open: i32, gap_extend: i32) -> Self {
+        assert!(
+            gap_open <= 0 && gap_extend <= 0,
+            "Gap penalties must not be positive but got open {} and extend {}",
+            gap_open, gap_extend
+        );
+
+        Scoring { substitution, gap_open, gap_extend }
+    }
+
+    /// The score of a gap of the given length
+    #[inline]
+    pub fn gap(&self, len: usize) -> i32 {
+        if len == 0 { 0 } else { self.gap_open + self.gap_extend * len as i32 }
+    }
+
+    /// Computes the score of a list of operations aligning x\[x_start..\] to y\[y_start..\].
+    ///
+    /// # Panics
+    /// If the operations run past the end of either sequence.
+    pub fn score_operations(
+        &self,
+        x: &[usize],
+        y: &[usize],
+        x_start: usize,
+        y_start: usize,
+        operations: &[AlignmentOperation]
+    ) -> i32 {
+        let (mut i, mut j) = (x_start, y_start);
+        let mut score = 0;
+        let mut previous = None;
+
+        for &op in operations {
+            match op {
+                AlignmentOperation::Match | AlignmentOperation::Substitution => {
+                    score += self.substitution.score(x[i], y[j]);
+                    i += 1;
+                    j += 1;
+                }
+                AlignmentOperation::Insertion | AlignmentOperation::Deletion => {
+                    if previous != Some(op) {
+                        score += self.gap_open;
+                    }
+                    score += self.gap_extend;
+
+                    if op == AlignmentOperation::Insertion { i += 1 } else { j += 1 }
+                }
+            }
+
+            previous = Some(op);
+        }
+
+        score
+    }
+}
+
+impl Scoring<MatchMismatch> {
+    /// Constructs a scoring that uses the same score for every match and every mismatch.
+    ///
+    /// # Panics
+    /// If either gap penalty is positive.
+    pub fn match_mismatch(
+        match_score: i32,
+        mismatch_score: i32,
+        gap_open: i32,
+        gap_extend: i32
+    ) -> Self {
+        Scoring::new(MatchMismatch { match_score, mismatch_score }, gap_open, gap_extend)
+    }
+}
+
+//================================================================================
+// Alignment
+//================================================================================
+
+/// The result of aligning a query x to a reference y.
+///
+/// Coordinates are 0 based and end exclusive. The operations cover x\[x_start..x_end\] and
+/// y\[y_start..y_end\].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Alignment {
+    /// The score of the alignment
+    pub score: i32,
+    /// The position in x of the first aligned symbol
+    pub x_start: usize,
+    /// The position in x one past the last aligned symbol
+    pub x_end: usize,
+    /// The position in y of the first aligned symbol
+    pub y_start: usize,
+    /// The position in y one past the last aligned symbol
+    pub y_end: usize,
+    /// The length of x
+    pub x_len: usize,
+    /// The length of y
+    pub y_len: usize,
+    /// The mode the alignment was made in
+    pub mode: AlignmentMode,
+    /// The columns of the alignment from left to right
+    pub operations: Vec<AlignmentOperation>,
+}
+
+impl Alignment {
+    /// Returns the operations as an extended CIGAR string using = for matches and X for
+    /// substitutions. Unaligned symbols at the ends of x are not included.
+    ///
+    /// # Example
+    /// ```
+    /// use biors::align::{Aligner, Scoring};
+    /// use biors::alphabet::UnambiguousDnaAlphabet;
+    /// use biors::sequence::Sequence;
+    ///
+    /// let alphabet = UnambiguousDnaAlphabet;
+    /// let mut x = Sequence::new(&alphabet);
+    /// let mut y = Sequence::new(&alphabet);
+    /// x.push("ACGTTTACGA").unwrap();
+    /// y.push("ACGTACCA").unwrap();
+    ///
+    /// let aligner = Aligner::new(Scoring::match_mismatch(2, -1, -3, -1));
+    /// let alignment = aligner.global(&x, &y);
+    ///
+    /// assert_eq!(alignment.cigar(), "3=2I3=1X1=");
+    /// ```
+    pub fn cigar(&self) -> String {
+        let mut cigar = String::new();
+        let mut ops = self.operations.iter().peekable();
+
+        while let Some(&op) = ops.next() {
+            let mut count = 1;
+            while ops.peek() == Some(&&op) {
+                ops.next();
+                count += 1;
+            }
+
+            write!(cigar, "{}{}", count, op.cigar_char()).unwrap();
+        }
+
+        cigar
+    }
+
+    /// The number of aligned columns, including gaps
+    pub fn len(&self) -> usize {
+        self.operations.len()
+    }
+
+    /// Returns true if nothing was aligned
+    pub fn is_empty(&self) -> bool {
+        self.operations.is_empty()
+    }
+
+    /// The fraction of columns that are matches, 0 if the alignment is empty
+    pub fn identity(&self) -> f64 {
+        if self.operations.is_empty() {
+            return 0.0;
+        }
+
+        let matches = self.operations.iter().filter(|&&op| op == AlignmentOperation::Match).count();
+        matches as f64 / self.operations.len() as f64
+    }
+
+    /// Formats the aligned parts of the sequences as three lines: x, a line marking matches with
+    /// | and substitutions with ., and y. Gaps are shown as -.
+    ///
+    /// # Panics
+    /// If the sequences are not the ones that were aligned or cannot be decoded.
+    pub fn pretty<A, E>(&self, x: &Sequence<A, E>, y: &Sequence<A, E>) -> String
+    where
+        A: Alphabet,
+        E: AlphabetEncoder<A>
+    {
+        let symbols = x.alphabet().symbols();
+        let width = x.alphabet().symbol_size();
+        let x = sequence_indices(x);
+        let y = sequence_indices(y);
+
+        let mut lines = [String::new(), String::new(), String::new()];
+        let (mut i, mut j) = (self.x_start, self.y_start);
+
+        for &op in &self.operations {
+            let (top, middle, bottom) = match op {
+                AlignmentOperation::Match => (Some(x[i]), '|', Some(y[j])),
+                AlignmentOperation::Substitution => (Some(x[i]), '.', Some(y[j])),
+                AlignmentOperation::Insertion => (Some(x[i]), ' ', None),
+                AlignmentOperation::Deletion => (None, ' ', Some(y[j])),
+            };
+
+            for (line, index) in [(0, top), (2, bottom)] {
+                match index {
+                    Some(index) => lines[line].push_str(symbols[index]),
+                    None => lines[line].extend(std::iter::repeat_n('-', width)),
+                }
+            }
+            lines[1].extend(std::iter::repeat_n(middle, width));
+
+            if top.is_some() { i += 1 }
+            if bottom.is_some() { j += 1 }
+        }
+
+        lines.join("\n")
+    }
+
+    /// The aligned parts of x and y as a pair of [AlignedSequence]s of the same length with a gap
+    /// opposite every inserted or deleted symbol.
+    ///
+    /// # Panics
+    /// If the sequences are not the ones that were aligned or cannot be decoded.
+    ///
+    /// # Example
+    /// ```
+    /// use biors::align::{Aligner, Scoring};
+    /// use biors::alphabet::{Gapped, UnambiguousDnaAlphabet};
+    /// use biors::sequence::Sequence;
+    ///
+    /// let alphabet = UnambiguousDnaAlphabet;
+    /// let mut x = Sequence::new(&alphabet);
+    /// let mut y = Sequence::new(&alphabet);
+    /// x.push("ACGTTTACGA").unwrap();
+    /// y.push("ACGTACCA").unwrap();
+    ///
+    /// let aligner = Aligner::new(Scoring::match_mismatch(2, -1, -3, -1));
+    /// let gapped = Gapped::new(&alphabet);
+    /// let (x_row, y_row) = aligner.global(&x, &y).aligned_sequences(&x, &y, &gapped);
+    ///
+    /// assert_eq!(x_row.to_string(), "ACGTTTACGA");
+    /// assert_eq!(y_row.to_string(), "ACG--TACCA");
+    /// ```
+    pub fn aligned_sequences<'g, A, E>(
+        &self,
+        x: &Sequence<A, E>,
+        y: &Sequence<A, E>,
+        gapped: &'g Gapped<'g, A>
+    ) -> (AlignedSequence<'g, A>, AlignedSequence<'g, A>)
+    where
+        A: Alphabet,
+        E: AlphabetEncoder<A>
+    {
+        let x = sequence_indices(x);
+        let y = sequence_indices(y);
+        let gap = gapped.gap_index();
+
+        let mut x_row = Vec::with_capacity(self.operations.len());
+        let mut y_row = Vec::with_capacity(self.operations.len());
+        let (mut i, mut j) = (self.x_start, self.y_start);
+
+        for &op in &self.operations {
+            match op {
+                AlignmentOperation::Match | AlignmentOperation::Substitution => {
+                    x_row.push(x[i] as u8);
+                    y_row.push(y[j] as u8);
+                    i += 1;
+                    j += 1;
+                }
+                AlignmentOperation::Insertion => {
+                    x_row.push(x[i] as u8);
+                    y_row.push(gap);
+                    i += 1;
+                }
+                AlignmentOperation::Deletion => {
+                    x_row.push(gap);
+                    y_row.push(y[j] as u8);
+                    j += 1;
+                }
+            }
+        }
+
+        (AlignedSequence::from_indices(gapped, x_row), AlignedSequence::from_indices(gapped, y_row))
+    }
+}
+
+//================================================================================
+// Utility Functions
+//================================================================================
+
+/// The index of each symbol of a sequence in its alphabet
+///
+/// # Panics
+/// If the sequence cannot be decoded.
+pub(crate) fn sequence_indices<A, E>(seq: &Sequence<A, E>) -> Vec<usize>
+where
+    A: Alphabet,
+    E: AlphabetEncoder<A>
+{
+    match seq.symbol_indices() {
+        Ok(indices) => indices,
+        Err(err) => panic!("Unable to align sequence.\n{}", err),
+    }
+}
+
+//================================================================================
+// Tests
+//================================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alphabet::UnambiguousDnaAlphabet;
+    use AlignmentOperation::*;
+
+    /// Tests that runs of operations are collapsed in the CIGAR string
+    #[test]
+    fn cigar() {
+        let alignment = Alignment {
+            score: 0,
+            x_start: 0,
+            x_end: 5,
+            y_start: 0,
+            y_end: 5,
+            x_len: 5,
+            y_len: 5,
+            mode: AlignmentMode::Global,
+            operations: vec![Match, Match, Substitution, Insertion, Insertion, Deletion, Match],
+        };
+
+        assert_eq!(alignment.cigar(), "2=1X2I1D1=");
+        assert!((alignment.identity() - 3.0 / 7.0).abs() < 1e-12);
+    }
+
+    /// Tests the three lines of a pretty printed alignment
+    #[test]
+    fn pretty() {
+        let a = UnambiguousDnaAlphabet;
+        let mut x = Sequence::new(&a);
+        let mut y = Sequence::new(&a);
+        x.push("GACGTA").unwrap();
+        y.push("ACTTGA").unwrap();
+
+        let alignment = Alignment {
+            score: 0,
+            x_start: 1,
+            x_end: 6,
+            y_start: 0,
+            y_end: 5,
+            x_len: 6,
+            y_len: 6,
+            mode: AlignmentMode::Local,
+            operations: vec![Match, Match, Insertion, Match, Deletion, Substitution],
+        };
+
+        assert_eq!(alignment.pretty(&x, &y), "ACGT-A\n|| | .\nAC-TTG");
+    }
+
+    /// Tests that affine gaps are scored once per gap
+    #[test]
+    fn score_operations() {
+        let scoring = Scoring::match_mismatch(1, -1, -5, -2);
+        let x = [0, 1, 2, 3];
+        let y = [0, 3];
+
+        let ops = [Match, Insertion, Insertion, Match];
+        assert_eq!(scoring.score_operations(&x, &y, 0, 0, &ops), -7);
+        // Separate gaps are opened separately
+        let ops = [Insertion, Substitution, Insertion, Match];
+        assert_eq!(scoring.score_operations(&x, &y, 0, 0, &ops), -14);
+        assert_eq!(scoring.gap(2), -9);
+    }
+
+    /// Tests that positive gap penalties are rejected
+    #[test]
+    #[should_panic]
+    fn positive_gap() {
+        Scoring::match_mismatch(1, -1, 1, -1);
+    }
+}
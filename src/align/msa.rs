@@ -0,0 +1,563 @@
+//! A multiple sequence alignment: several [AlignedSequence]s of the same length with an ID each.
+
+use std::error::Error;
+use std::fmt;
+use crate::alphabet::{Alphabet, Gapped};
+use crate::alphabet::dna::{iupac_bases, iupac_symbol, BASE_A, BASE_C, BASE_G, BASE_T};
+use super::AlignedSequence;
+
+/// Result type for building an Msa
+pub type Result<T> = std::result::Result<T, MsaError>;
+
+/// A multiple sequence alignment. Every row is an [AlignedSequence] with the same number of
+/// columns and a unique ID.
+///
+/// Column statistics only count residues, gaps are reported separately by
+/// [gap_fraction()](Msa::gap_fraction).
+///
+/// # Example
+/// ```
+/// use biors::align::msa::Msa;
+/// use biors::alphabet::{Gapped, UnambiguousDnaAlphabet};
+///
+/// let dna = UnambiguousDnaAlphabet;
+/// let gapped = Gapped::new(&dna);
+/// let mut msa = Msa::new(&gapped);
+/// msa.push_str("seq1", "ACGT-A").unwrap();
+/// msa.push_str("seq2", "ACGTTA").unwrap();
+/// msa.push_str("seq3", "ACCT-A").unwrap();
+///
+/// assert_eq!(msa.num_columns(), 6);
+/// assert_eq!(msa.consensus(0.6, "N"), "ACGT-A");
+/// assert_eq!(msa.iupac_consensus(0.9), "ACST-A");
+/// ```
+pub struct Msa<'a, A: Alphabet> {
+    alphabet: &'a Gapped<'a, A>,
+    ids: Vec<String>,
+    rows: Vec<AlignedSequence<'a, A>>,
+}
+
+impl<'a, A: Alphabet> Msa<'a, A> {
+    /// Constructs an alignment with no rows.
+    pub fn new(alphabet: &'a Gapped<'a, A>) -> Self {
+        Msa { alphabet, ids: vec![], rows: vec![] }
+    }
+
+    /// Adds a row to the bottom of the alignment.
+    ///
+    /// # Errors
+    /// If the row has a different number of columns to the existing rows or the ID is already
+    /// used.
+    pub fn push<S: Into<String>>(&mut self, id: S, row: AlignedSequence<'a, A>) -> Result<()> {
+        let id = id.into();
+
+        if let Some(first) = self.rows.first() {
+            if first.len() != row.len() {
+                let description = format!(
+                    "Row {} has {} columns but the alignment has {}", id, row.len(), first.len()
+                );
+                return Err(MsaError::new(ErrorKind::LengthMismatch, description));
+            }
+        }
+        if self.ids.contains(&id) {
+            let description = format!("There is already a row with the ID {}", id);
+            return Err(MsaError::new(ErrorKind::DuplicateId(id), description));
+        }
+
+        self.ids.push(id);
+        self.rows.push(row);
+        Ok(())
+    }
+
+    /// Adds a row given as a string of residues and gaps.
+    ///
+    /// # Errors
+    /// If the string contains a symbol that isn't in the gapped alphabet or for the same
+    /// reasons as [push()](Msa::push).
+    pub fn push_str<S: Into<String>>(&mut self, id: S, symbols: &str) -> Result<()> {
+        let id = id.into();
+        let mut row = AlignedSequence::new(self.alphabet);
+
+        if let Err(err) = row.push(symbols) {
+            let description = format!("Row {} could not be encoded: {}", id, err);
+            return Err(MsaError::new(ErrorKind::InvalidSymbol, description));
+        }
+
+        self.push(id, row)
+    }
+
+    /// The gapped alphabet of the rows.
+    pub fn alphabet(&self) -> &'a Gapped<'a, A> {
+        self.alphabet
+    }
+
+    /// The number of rows.
+    pub fn num_rows(&self) -> usize {
+        self.rows.len()
+    }
+
+    /// The number of columns, 0 if there are no rows.
+    pub fn num_columns(&self) -> usize {
+        self.rows.first().map_or(0, |row| row.len())
+    }
+
+    /// Returns true if there are no rows.
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+
+    /// The ID of every row in order.
+    pub fn ids(&self) -> &[String] {
+        &self.ids
+    }
+
+    /// Every row in order.
+    pub fn rows(&self) -> &[AlignedSequence<'a, A>] {
+        &self.rows
+    }
+
+    /// The row with the given ID.
+    pub fn get(&self, id: &str) -> Option<&AlignedSequence<'a, A>> {
+        self.ids.iter().position(|row_id| row_id == id).map(|row| &self.rows[row])
+    }
+
+    /// The index in the gapped alphabet of the symbol of every row in a column.
+    ///
+    /// # Panics
+    /// If the column is out of bounds.
+    pub fn column(&self, column: usize) -> Vec<u8> {
+        assert!(column < self.num_columns(), "Column {} is out of bounds.", column);
+        self.rows.iter().map(|row| row.as_bytes()[column]).collect()
+    }
+
+    /// Iterates over every [column()](Msa::column) from left to right.
+    pub fn columns(&self) -> Columns<'_, 'a, A> {
+        Columns { msa: self, column: 0 }
+    }
+
+    //================================================================================
+    // Column Statistics
+    //================================================================================
+
+    /// The number of times each symbol of the gapped alphabet occurs in a column. The last two
+    /// counts are the two gap symbols.
+    ///
+    /// # Panics
+    /// If the column is out of bounds.
+    pub fn column_counts(&self, column: usize) -> Vec<usize> {
+        let mut counts = vec![0; self.alphabet.symbols().len()];
+        for index in self.column(column) {
+            counts[index as usize] += 1;
+        }
+        counts
+    }
+
+    /// The frequency of each symbol of the ungapped alphabet among the residues of a column.
+    /// All 0 if the column is only gaps.
+    ///
+    /// # Panics
+    /// If the column is out of bounds.
+    pub fn column_frequencies(&self, column: usize) -> Vec<f64> {
+        let mut counts = self.column_counts(column);
+        counts.truncate(self.alphabet.gap_index() as usize);
+
+        let residues: usize = counts.iter().sum();
+        counts.into_iter()
+            .map(|count| if residues == 0 { 0.0 } else { count as f64 / residues as f64 })
+            .collect()
+    }
+
+    /// The fraction of rows with a gap in a column.
+    ///
+    /// # Panics
+    /// If the column is out of bounds.
+    pub fn gap_fraction(&self, column: usize) -> f64 {
+        let column = self.column(column);
+        let gaps = column.into_iter().filter(|&index| self.alphabet.is_gap(index)).count();
+        gaps as f64 / self.num_rows() as f64
+    }
+
+    /// The Shannon entropy in bits of the residues in a column. 0 if every residue is the same
+    /// or the column is only gaps.
+    ///
+    /// # Panics
+    /// If the column is out of bounds.
+    pub fn entropy(&self, column: usize) -> f64 {
+        -self.column_frequencies(column).into_iter()
+            .filter(|&frequency| frequency > 0.0)
+            .map(|frequency| frequency * frequency.log2())
+            .sum::<f64>()
+    }
+
+    /// How conserved a column is from 0 to 1.
+    ///
+    /// The entropy is scaled by the largest possible entropy for the alphabet so that a column
+    /// with one residue scores 1 and a column with every residue equally often scores 0. This is
+    /// then multiplied by the fraction of rows that aren't gaps so gappy columns score less.
+    ///
+    /// # Panics
+    /// If the column is out of bounds.
+    pub fn conservation(&self, column: usize) -> f64 {
+        let residues = 1.0 - self.gap_fraction(column);
+        let max_entropy = (self.alphabet.gap_index() as f64).log2();
+
+        if max_entropy > 0.0 {
+            residues * (1.0 - self.entropy(column) / max_entropy)
+        } else {
+            residues
+        }
+    }
+
+    //================================================================================
+    // Consensus
+    //================================================================================
+
+    /// The consensus of the alignment with one symbol per column.
+    ///
+    /// A column where more than half of the rows are gaps gets a -. Otherwise the most common
+    /// residue is used if it makes up at least threshold of the residues, else ambiguous. Ties
+    /// go to the residue that comes first in the alphabet.
+    ///
+    /// # Panics
+    /// If any column is out of bounds.
+    pub fn consensus(&self, threshold: f64, ambiguous: &str) -> String {
+        let symbols = self.alphabet.symbols();
+        let mut consensus = String::with_capacity(self.num_columns());
+
+        for column in 0..self.num_columns() {
+            if self.gap_fraction(column) > 0.5 {
+                consensus.push_str(self.alphabet.gap_symbol());
+                continue;
+            }
+
+            let frequencies = self.column_frequencies(column);
+            let (best, frequency) = frequencies.iter()
+                .enumerate()
+                .fold((0, 0.0), |best, (index, &frequency)| {
+                    if frequency > best.1 { (index, frequency) } else { best }
+                });
+
+            consensus.push_str(if frequency >= threshold { symbols[best] } else { ambiguous });
+        }
+
+        consensus
+    }
+
+    /// The consensus of a nucleotide alignment using IUPAC ambiguity codes.
+    ///
+    /// A column where more than half of the rows are gaps gets a -. Otherwise bases are taken
+    /// from most to least common until together they make up at least threshold of the residues
+    /// and the IUPAC code for those bases is used. Ambiguous residues such as R count as a
+    /// fraction of each base they stand for and residues that aren't IUPAC codes are skipped.
+    /// A column with no bases gets an N.
+    ///
+    /// # Panics
+    /// If any column is out of bounds.
+    pub fn iupac_consensus(&self, threshold: f64) -> String {
+        let bases_of: Vec<u8> = self.alphabet.symbols().iter()
+            .map(|symbol| iupac_bases(symbol).unwrap_or(0))
+            .collect();
+        let mut consensus = String::with_capacity(self.num_columns());
+
+        for column in 0..self.num_columns() {
+            if self.gap_fraction(column) > 0.5 {
+                consensus.push_str(self.alphabet.gap_symbol());
+                continue;
+            }
+
+            let mut weights = [(BASE_A, 0.0), (BASE_C, 0.0), (BASE_G, 0.0), (BASE_T, 0.0)];
+            for index in self.column(column) {
+                let bases = bases_of[index as usize];
+                let share = 1.0 / bases.count_ones() as f64;
+                for (base, weight) in weights.iter_mut() {
+                    if bases & *base != 0 {
+                        *weight += share;
+                    }
+                }
+            }
+
+            let total: f64 = weights.iter().map(|(_, weight)| weight).sum();
+            weights.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+            let mut chosen = 0;
+            let mut covered = 0.0;
+            for (base, weight) in weights {
+                if weight == 0.0 || covered >= threshold * total {
+                    break;
+                }
+                chosen |= base;
+                covered += weight;
+            }
+
+            consensus.push_str(iupac_symbol(chosen).unwrap_or("N"));
+        }
+
+        consensus
+    }
+
+    //================================================================================
+    // Editing
+    //================================================================================
+
+    /// Removes every column that is only gaps.
+    pub fn remove_all_gap_columns(&mut self) {
+        self.retain_columns(|msa, column| msa.gap_fraction(column) < 1.0);
+    }
+
+    /// Removes every column where the fraction of rows with a gap is more than max_gap_fraction.
+    pub fn remove_gappy_columns(&mut self, max_gap_fraction: f64) {
+        self.retain_columns(|msa, column| msa.gap_fraction(column) <= max_gap_fraction);
+    }
+
+    /// A new alignment of the given rows in the given order.
+    ///
+    /// # Panics
+    /// If a row is out of bounds.
+    pub fn select_rows(&self, rows: &[usize]) -> Self {
+        Msa {
+            alphabet: self.alphabet,
+            ids: rows.iter().map(|&row| self.ids[row].clone()).collect(),
+            rows: rows.iter().map(|&row| self.rows[row].clone()).collect(),
+        }
+    }
+
+    /// A new alignment of the given columns in the given order.
+    ///
+    /// # Panics
+    /// If a column is out of bounds.
+    pub fn select_columns(&self, columns: &[usize]) -> Self {
+        let rows = self.rows.iter()
+            .map(|row| {
+                let indices = columns.iter().map(|&column| row.as_bytes()[column]).collect();
+                AlignedSequence::from_indices(self.alphabet, indices)
+            })
+            .collect();
+
+        Msa { alphabet: self.alphabet, ids: self.ids.clone(), rows }
+    }
+
+    /// Keeps only the columns the predicate returns true for
+    fn retain_columns<F: Fn(&Self, usize) -> bool>(&mut self, keep: F) {
+        let columns: Vec<usize> = (0..self.num_columns())
+            .filter(|&column| keep(self, column))
+            .collect();
+        *self = self.select_columns(&columns);
+    }
+}
+
+/// Iterator over the columns of an [Msa], see [columns()](Msa::columns).
+pub struct Columns<'m, 'a, A: Alphabet> {
+    msa: &'m Msa<'a, A>,
+    column: usize,
+}
+
+impl<'m, 'a, A: Alphabet> Iterator for Columns<'m, 'a, A> {
+    type Item = Vec<u8>;
+
+    fn next(&mut self) -> Option<Vec<u8>> {
+        if self.column < self.msa.num_columns() {
+            self.column += 1;
+            Some(self.msa.column(self.column - 1))
+        } else {
+            None
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.msa.num_columns() - self.column;
+        (remaining, Some(remaining))
+    }
+}
+
+impl<'m, 'a, A: Alphabet> ExactSizeIterator for Columns<'m, 'a, A> {}
+
+impl<'a, A: Alphabet> Clone for Msa<'a, A> {
+    fn clone(&self) -> Self {
+        Msa { alphabet: self.alphabet, ids: self.ids.clone(), rows: self.rows.clone() }
+    }
+}
+
+impl<'a, A: Alphabet> fmt::Debug for Msa<'a, A> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_map().entries(self.ids.iter().zip(&self.rows)).finish()
+    }
+}
+
+//================================================================================
+// Errors
+//================================================================================
+
+/// Represents the kind of error that occurred while adding a row to an Msa.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ErrorKind {
+    /// The row has a different number of columns to the alignment
+    LengthMismatch,
+
+    /// The row has a symbol that isn't in the gapped alphabet
+    InvalidSymbol,
+
+    /// The alignment already has a row with the ID
+    DuplicateId(String),
+}
+
+/// The type of error returned when a row cannot be added to an Msa
+#[derive(Debug, Clone)]
+pub struct MsaError {
+    kind: ErrorKind,
+    description: String,
+}
+
+impl MsaError {
+    /// Construct a new MsaError from the given ErrorKind and description
+    pub fn new(kind: ErrorKind, description: String) -> MsaError {
+        MsaError { kind, description }
+    }
+
+    /// Get the associated ErrorKind for this error
+    pub fn kind(&self) -> &ErrorKind {
+        &self.kind
+    }
+
+    /// Get the associated description for this error
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+}
+
+impl Error for MsaError {}
+
+impl fmt::Display for MsaError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Msa error: {:?}:\n\t{}", self.kind, self.description)
+    }
+}
+
+//================================================================================
+// Tests
+//================================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alphabet::{AmbiguousDnaAlphabet, ProteinAlphabet, UnambiguousDnaAlphabet};
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!((actual - expected).abs() < 1e-9, "{} != {}", actual, expected);
+    }
+
+    /// Tests that rows must have the same length and a unique ID
+    #[test]
+    fn push() {
+        let dna = UnambiguousDnaAlphabet;
+        let gapped = Gapped::new(&dna);
+        let mut msa = Msa::new(&gapped);
+
+        assert_eq!(msa.num_columns(), 0);
+        msa.push_str("a", "AC-T").unwrap();
+        msa.push_str("b", "ACGT").unwrap();
+
+        assert_eq!(msa.push_str("c", "ACG").unwrap_err().kind(), &ErrorKind::LengthMismatch);
+        assert_eq!(msa.push_str("c", "ACGN").unwrap_err().kind(), &ErrorKind::InvalidSymbol);
+        assert_eq!(
+            msa.push_str("a", "ACGT").unwrap_err().kind(),
+            &ErrorKind::DuplicateId("a".to_owned())
+        );
+
+        assert_eq!(msa.num_rows(), 2);
+        assert_eq!(msa.ids(), &["a", "b"]);
+        assert_eq!(msa.get("a").unwrap().to_string(), "AC-T");
+        assert!(msa.get("c").is_none());
+    }
+
+    /// Tests the column statistics
+    #[test]
+    fn column_statistics() {
+        let dna = UnambiguousDnaAlphabet;
+        let gapped = Gapped::new(&dna);
+        let mut msa = Msa::new(&gapped);
+        msa.push_str("a", "AAC-").unwrap();
+        msa.push_str("b", "ACT-").unwrap();
+        msa.push_str("c", "AGG.").unwrap();
+        msa.push_str("d", "ATG-").unwrap();
+
+        let columns: Vec<Vec<u8>> = msa.columns().collect();
+        assert_eq!(columns[0], vec![0, 0, 0, 0]);
+        assert_eq!(columns[3], vec![4, 4, 5, 4]);
+
+        // Symbols are A C T G - .
+        assert_eq!(msa.column_counts(2), vec![0, 1, 1, 2, 0, 0]);
+        assert_eq!(msa.column_frequencies(2), vec![0.0, 0.25, 0.25, 0.5]);
+        assert_eq!(msa.column_frequencies(3), vec![0.0; 4]);
+        assert_close(msa.gap_fraction(3), 1.0);
+
+        assert_close(msa.entropy(0), 0.0);
+        assert_close(msa.entropy(1), 2.0);
+        assert_close(msa.entropy(2), 1.5);
+        assert_close(msa.entropy(3), 0.0);
+
+        assert_close(msa.conservation(0), 1.0);
+        assert_close(msa.conservation(1), 0.0);
+        assert_close(msa.conservation(2), 0.25);
+        assert_close(msa.conservation(3), 0.0);
+    }
+
+    /// Tests consensus calling with thresholds
+    #[test]
+    fn consensus() {
+        let protein = ProteinAlphabet;
+        let gapped = Gapped::new(&protein);
+        let mut msa = Msa::new(&gapped);
+        msa.push_str("a", "MKV-LA").unwrap();
+        msa.push_str("b", "MKI-LW").unwrap();
+        msa.push_str("c", "MRI-L-").unwrap();
+        msa.push_str("d", "MRL.L-").unwrap();
+
+        // R comes before K in the alphabet so wins the tie
+        assert_eq!(msa.consensus(0.5, "X"), "MRI-LA");
+        assert_eq!(msa.consensus(0.75, "X"), "MXX-LX");
+        // Column 5 is half gaps which isn't more than half
+        assert_eq!(msa.consensus(1.0, "X"), "MXX-LX");
+    }
+
+    /// Tests IUPAC consensus including ambiguous residues
+    #[test]
+    fn iupac_consensus() {
+        let dna = AmbiguousDnaAlphabet;
+        let gapped = Gapped::new(&dna);
+        let mut msa = Msa::new(&gapped);
+        msa.push_str("a", "AAAR-C").unwrap();
+        msa.push_str("b", "AAGR-C").unwrap();
+        msa.push_str("c", "AGCA-T").unwrap();
+        msa.push_str("d", "ACTG.Y").unwrap();
+
+        assert_eq!(msa.iupac_consensus(0.5), "AAMA-C");
+        assert_eq!(msa.iupac_consensus(0.75), "AMVR-Y");
+        assert_eq!(msa.iupac_consensus(1.0), "AVNR-Y");
+    }
+
+    /// Tests removing gappy columns and selecting rows and columns
+    #[test]
+    fn edit() {
+        let dna = UnambiguousDnaAlphabet;
+        let gapped = Gapped::new(&dna);
+        let mut msa = Msa::new(&gapped);
+        msa.push_str("a", "A-C-G").unwrap();
+        msa.push_str("b", "A-CTG").unwrap();
+        msa.push_str("c", "A.--G").unwrap();
+
+        let rows = msa.select_rows(&[2, 0]);
+        assert_eq!(rows.ids(), &["c", "a"]);
+        assert_eq!(rows.rows()[0].to_string(), "A.--G");
+
+        let columns = msa.select_columns(&[4, 0]);
+        assert_eq!(columns.rows()[1].to_string(), "GA");
+
+        let mut gappy = msa.clone();
+        gappy.remove_gappy_columns(0.5);
+        assert_eq!(gappy.rows()[1].to_string(), "ACG");
+
+        msa.remove_all_gap_columns();
+        assert_eq!(msa.num_columns(), 4);
+        assert_eq!(msa.rows()[2].to_string(), "A--G");
+    }
+}
@@ -0,0 +1,467 @@
+//! Full dynamic programming alignment of two sequences with affine gap penalties.
+
+use crate::alphabet::Alphabet;
+use crate::alphabet::encoding::AlphabetEncoder;
+use crate::sequence::Sequence;
+use super::{sequence_indices, Alignment, AlignmentMode, AlignmentOperation, Scoring};
+use super::SubstitutionScore;
+
+/// A score low enough that it never wins but can have penalties added without overflowing
+pub(crate) const MIN_SCORE: i32 = i32::MIN / 4;
+
+/// Aligns pairs of sequences using Gotoh's algorithm.
+///
+/// Three matrices are filled in, one for alignments ending in each kind of column, so that gaps
+/// can be scored with separate open and extend penalties. Takes O(nm) time and memory for
+/// sequences of length n and m.
+///
+/// # Example
+/// ```
+/// use biors::align::{Aligner, Scoring};
+/// use biors::alphabet::UnambiguousDnaAlphabet;
+/// use biors::sequence::Sequence;
+///
+/// let alphabet = UnambiguousDnaAlphabet;
+/// let mut x = Sequence::new(&alphabet);
+/// let mut y = Sequence::new(&alphabet);
+/// x.push("GATTACA").unwrap();
+/// y.push("TTTTGATTGCATT").unwrap();
+///
+/// let aligner = Aligner::new(Scoring::match_mismatch(1, -1, -2, -1));
+/// let alignment = aligner.semi_global(&x, &y);
+///
+/// assert_eq!((alignment.y_start, alignment.y_end), (4, 11));
+/// assert_eq!(alignment.pretty(&x, &y), "GATTACA\n||||.||\nGATTGCA");
+/// ```
+#[derive(Debug, Clone)]
+pub struct Aligner<S> {
+    scoring: Scoring<S>,
+}
+
+/// Which matrix a cell of the traceback is in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum State {
+    /// The best alignment ending at the cell
+    Best,
+    /// Alignments ending with x and y aligned
+    Diagonal,
+    /// Alignments ending with x aligned to a gap
+    Insertion,
+    /// Alignments ending with y aligned to a gap
+    Deletion,
+}
+
+impl<S: SubstitutionScore> Aligner<S> {
+    /// Constructs an aligner that uses the scoring.
+    pub fn new(scoring: Scoring<S>) -> Self {
+        Aligner { scoring }
+    }
+
+    /// The scoring used by the aligner
+    pub fn scoring(&self) -> &Scoring<S> {
+        &self.scoring
+    }
+
+    /// Aligns all of x to all of y.
+    pub fn global<A, E>(&self, x: &Sequence<A, E>, y: &Sequence<A, E>) -> Alignment
+    where
+        A: Alphabet,
+        E: AlphabetEncoder<A>
+    {
+        self.align(AlignmentMode::Global, x, y)
+    }
+
+    /// Aligns all of x to the part of y that it fits best.
+    pub fn semi_global<A, E>(&self, x: &Sequence<A, E>, y: &Sequence<A, E>) -> Alignment
+    where
+        A: Alphabet,
+        E: AlphabetEncoder<A>
+    {
+        self.align(AlignmentMode::SemiGlobal, x, y)
+    }
+
+    /// Finds the best overlap between the end of one sequence and the start of the other.
+    pub fn overlap<A, E>(&self, x: &Sequence<A, E>, y: &Sequence<A, E>) -> Alignment
+    where
+        A: Alphabet,
+        E: AlphabetEncoder<A>
+    {
+        self.align(AlignmentMode::Overlap, x, y)
+    }
+
+    /// Aligns the highest scoring pair of substrings of x and y.
+    pub fn local<A, E>(&self, x: &Sequence<A, E>, y: &Sequence<A, E>) -> Alignment
+    where
+        A: Alphabet,
+        E: AlphabetEncoder<A>
+    {
+        self.align(AlignmentMode::Local, x, y)
+    }
+
+    /// Aligns two sequences in the given mode.
+    ///
+    /// # Panics
+    /// If either sequence cannot be decoded.
+    pub fn align<A, E>(
+        &self,
+        mode: AlignmentMode,
+        x: &Sequence<A, E>,
+        y: &Sequence<A, E>
+    ) -> Alignment
+    where
+        A: Alphabet,
+        E: AlphabetEncoder<A>
+    {
+        self.align_indices(mode, &sequence_indices(x), &sequence_indices(y))
+    }
+
+    /// Aligns two sequences given as the indices of their symbols in the alphabet.
+    pub fn align_indices(&self, mode: AlignmentMode, x: &[usize], y: &[usize]) -> Alignment {
+        let matrices = Matrices::fill(&self.scoring, mode, x, y);
+        let (i, j) = matrices.end(mode);
+        let score = matrices.best[matrices.cell(i, j)];
+
+        let (x_start, y_start, operations) = matrices.traceback(&self.scoring, mode, x, y, i, j);
+
+        Alignment {
+            score,
+            x_start,
+            x_end: i,
+            y_start,
+            y_end: j,
+            x_len: x.len(),
+            y_len: y.len(),
+            mode,
+            operations,
+        }
+    }
+}
+
+/// The filled in dynamic programming matrices stored row by row
+struct Matrices {
+    cols: usize,
+    best: Vec<i32>,
+    diagonal: Vec<i32>,
+    insertion: Vec<i32>,
+    deletion: Vec<i32>,
+}
+
+impl Matrices {
+    /// Fills in the matrices for aligning x (rows) to y (columns)
+    fn fill<S: SubstitutionScore>(
+        scoring: &Scoring<S>,
+        mode: AlignmentMode,
+        x: &[usize],
+        y: &[usize]
+    ) -> Self {
+        let cols = y.len() + 1;
+        let size = (x.len() + 1) * cols;
+        let mut matrices = Matrices {
+            cols,
+            best: vec![0; size],
+            diagonal: vec![MIN_SCORE; size],
+            insertion: vec![MIN_SCORE; size],
+            deletion: vec![MIN_SCORE; size],
+        };
+
+        let (free_x_start, free_y_start) = free_starts(mode);
+        for i in 1..=x.len() {
+            matrices.best[i * cols] = if free_x_start { 0 } else { scoring.gap(i) };
+        }
+        for j in 1..=y.len() {
+            matrices.best[j] = if free_y_start { 0 } else { scoring.gap(j) };
+        }
+
+        let open = scoring.gap_open + scoring.gap_extend;
+        let extend = scoring.gap_extend;
+
+        for (i, &a) in x.iter().enumerate().map(|(i, a)| (i + 1, a)) {
+            for (j, &b) in y.iter().enumerate().map(|(j, b)| (j + 1, b)) {
+                let cell = i * cols + j;
+                let up = cell - cols;
+                let left = cell - 1;
+
+                let diagonal = matrices.best[up - 1] + scoring.substitution.score(a, b);
+                let insertion = (matrices.best[up] + open).max(matrices.insertion[up] + extend);
+                let deletion = (matrices.best[left] + open).max(matrices.deletion[left] + extend);
+
+                let mut best = diagonal.max(insertion).max(deletion);
+                if mode == AlignmentMode::Local {
+                    best = best.max(0);
+                }
+
+                matrices.diagonal[cell] = diagonal;
+                matrices.insertion[cell] = insertion;
+                matrices.deletion[cell] = deletion;
+                matrices.best[cell] = best;
+            }
+        }
+
+        matrices
+    }
+
+    /// The index of a cell
+    #[inline]
+    fn cell(&self, i: usize, j: usize) -> usize {
+        i * self.cols + j
+    }
+
+    /// The cell the best alignment ends at. Ties go to the earliest cell.
+    fn end(&self, mode: AlignmentMode) -> (usize, usize) {
+        let rows = self.best.len() / self.cols;
+        let (n, m) = (rows - 1, self.cols - 1);
+
+        let candidates: Vec<(usize, usize)> = match mode {
+            AlignmentMode::Global => vec![(n, m)],
+            AlignmentMode::SemiGlobal => (0..=m).map(|j| (n, j)).collect(),
+            AlignmentMode::Overlap => {
+                (0..=m).map(|j| (n, j)).chain((0..n).map(|i| (i, m))).collect()
+            }
+            AlignmentMode::Local => {
+                (0..=n).flat_map(|i| (0..=m).map(move |j| (i, j))).collect()
+            }
+        };
+
+        let mut end = candidates[0];
+        for &(i, j) in &candidates[1..] {
+            if self.best[self.cell(i, j)] > self.best[self.cell(end.0, end.1)] {
+                end = (i, j);
+            }
+        }
+
+        end
+    }
+
+    /// Follows the matrices back from (i, j) to the start of the alignment returning where it
+    /// starts in x and y and its operations
+    fn traceback<S: SubstitutionScore>(
+        &self,
+        scoring: &Scoring<S>,
+        mode: AlignmentMode,
+        x: &[usize],
+        y: &[usize],
+        mut i: usize,
+        mut j: usize
+    ) -> (usize, usize, Vec<AlignmentOperation>) {
+        let (free_x_start, free_y_start) = free_starts(mode);
+        let extend = scoring.gap_extend;
+        let mut operations = vec![];
+        let mut state = State::Best;
+
+        loop {
+            let cell = self.cell(i, j);
+
+            if state == State::Best {
+                if mode == AlignmentMode::Local && self.best[cell] == 0 {
+                    break;
+                }
+
+                // Along the edges of the matrix the rest of the alignment is a single gap
+                if i == 0 || j == 0 {
+                    if i > 0 && !free_x_start {
+                        operations.extend(std::iter::repeat_n(AlignmentOperation::Insertion, i));
+                        i = 0;
+                    } else if j > 0 && !free_y_start {
+                        operations.extend(std::iter::repeat_n(AlignmentOperation::Deletion, j));
+                        j = 0;
+                    }
+                    break;
+                }
+
+                state = if self.best[cell] == self.diagonal[cell] {
+                    State::Diagonal
+                } else if self.best[cell] == self.insertion[cell] {
+                    State::Insertion
+                } else {
+                    State::Deletion
+                };
+            }
+
+            match state {
+                State::Diagonal => {
+                    let op = if x[i - 1] == y[j - 1] {
+                        AlignmentOperation::Match
+                    } else {
+                        AlignmentOperation::Substitution
+                    };
+                    operations.push(op);
+                    i -= 1;
+                    j -= 1;
+                    state = State::Best;
+                }
+                State::Insertion => {
+                    operations.push(AlignmentOperation::Insertion);
+                    let up = cell - self.cols;
+                    if i == 1 || self.insertion[cell] != self.insertion[up] + extend {
+                        state = State::Best;
+                    }
+                    i -= 1;
+                }
+                State::Deletion => {
+                    operations.push(AlignmentOperation::Deletion);
+                    if j == 1 || self.deletion[cell] != self.deletion[cell - 1] + extend {
+                        state = State::Best;
+                    }
+                    j -= 1;
+                }
+                State::Best => unreachable!(),
+            }
+        }
+
+        operations.reverse();
+        (i, j, operations)
+    }
+}
+
+/// Whether gaps before the start of x and before the start of y are free in a mode
+fn free_starts(mode: AlignmentMode) -> (bool, bool) {
+    match mode {
+        AlignmentMode::Global => (false, false),
+        AlignmentMode::SemiGlobal => (false, true),
+        AlignmentMode::Overlap | AlignmentMode::Local => (true, true),
+    }
+}
+
+//================================================================================
+// Tests
+//================================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::align::MatchMismatch;
+    use crate::alphabet::UnambiguousDnaAlphabet;
+    use crate::sequence::test_util::{dna, random_indices};
+
+    /// Checks that the operations consume the reported ranges and add up to the score
+    fn check(scoring: &Scoring<MatchMismatch>, x: &[usize], y: &[usize], alignment: &Alignment) {
+        let consumed_x = alignment.operations.iter()
+            .filter(|&&op| op != AlignmentOperation::Deletion)
+            .count();
+        let consumed_y = alignment.operations.iter()
+            .filter(|&&op| op != AlignmentOperation::Insertion)
+            .count();
+
+        assert_eq!(alignment.x_start + consumed_x, alignment.x_end);
+        assert_eq!(alignment.y_start + consumed_y, alignment.y_end);
+
+        let score = scoring.score_operations(
+            x, y, alignment.x_start, alignment.y_start, &alignment.operations
+        );
+        assert_eq!(score, alignment.score);
+    }
+
+    /// Tests a global alignment against a hand computed one
+    #[test]
+    fn global() {
+        let a = UnambiguousDnaAlphabet;
+        let aligner = Aligner::new(Scoring::match_mismatch(1, -1, -1, -1));
+        let alignment = aligner.global(&dna(&a, "GATTACA"), &dna(&a, "GCATGCT"));
+
+        assert_eq!((alignment.x_start, alignment.x_end), (0, 7));
+        assert_eq!((alignment.y_start, alignment.y_end), (0, 7));
+        assert_eq!(alignment.score, -1);
+    }
+
+    /// Tests that affine gaps prefer one long gap to several short ones
+    #[test]
+    fn affine_gaps() {
+        let a = UnambiguousDnaAlphabet;
+        let x = dna(&a, "ACGTACGTTTTTACGTACGT");
+        let y = dna(&a, "ACGTACGTACGTACGT");
+
+        let aligner = Aligner::new(Scoring::match_mismatch(2, -3, -5, -1));
+        let alignment = aligner.global(&x, &y);
+
+        // The gap can start at either end of the run of Ts
+        let cigar = alignment.cigar();
+        assert!(cigar == "7=4I9=" || cigar == "8=4I8=", "{}", cigar);
+        assert_eq!(alignment.score, 16 * 2 - 5 - 4);
+    }
+
+    /// Tests that a local alignment ignores the unrelated ends of both sequences
+    #[test]
+    fn local() {
+        let a = UnambiguousDnaAlphabet;
+        let x = dna(&a, "TTTTTGCATGCAGGG");
+        let y = dna(&a, "CCCCGCATGCACCC");
+
+        let aligner = Aligner::new(Scoring::match_mismatch(2, -3, -5, -2));
+        let alignment = aligner.local(&x, &y);
+
+        assert_eq!((alignment.x_start, alignment.x_end), (5, 12));
+        assert_eq!((alignment.y_start, alignment.y_end), (4, 11));
+        assert_eq!(alignment.score, 14);
+        assert_eq!(alignment.cigar(), "7=");
+    }
+
+    /// Tests that an overlap alignment joins the end of one read to the start of another
+    #[test]
+    fn overlap() {
+        let a = UnambiguousDnaAlphabet;
+        let x = dna(&a, "GGGGGGACGTACGA");
+        let y = dna(&a, "ACGTACGATTTTTT");
+
+        let aligner = Aligner::new(Scoring::match_mismatch(1, -2, -3, -1));
+        let alignment = aligner.overlap(&x, &y);
+
+        assert_eq!((alignment.x_start, alignment.x_end), (6, 14));
+        assert_eq!((alignment.y_start, alignment.y_end), (0, 8));
+        assert_eq!(alignment.score, 8);
+    }
+
+    /// Tests that a local alignment of unrelated sequences is empty
+    #[test]
+    fn local_empty() {
+        let a = UnambiguousDnaAlphabet;
+        let aligner = Aligner::new(Scoring::match_mismatch(1, -1, -1, -1));
+        let alignment = aligner.local(&dna(&a, "AAAA"), &dna(&a, "CCCC"));
+
+        assert_eq!(alignment.score, 0);
+        assert!(alignment.is_empty());
+    }
+
+    /// Tests that alignments of empty sequences are gaps
+    #[test]
+    fn empty_sequences() {
+        let a = UnambiguousDnaAlphabet;
+        let aligner = Aligner::new(Scoring::match_mismatch(1, -1, -2, -1));
+
+        let alignment = aligner.global(&dna(&a, "ACG"), &dna(&a, ""));
+        assert_eq!(alignment.cigar(), "3I");
+        assert_eq!(alignment.score, -5);
+
+        let alignment = aligner.semi_global(&dna(&a, ""), &dna(&a, "ACG"));
+        assert_eq!(alignment.score, 0);
+        assert!(alignment.is_empty());
+    }
+
+    /// Tests that the traceback of every mode is consistent with its score on random sequences
+    #[test]
+    fn traceback_scores() {
+        let scoring = Scoring::match_mismatch(2, -3, -4, -1);
+        let aligner = Aligner::new(scoring.clone());
+
+        for seed in 1..20 {
+            let x = random_indices(seed, 30 + seed as usize, 4);
+            let y = random_indices(seed * 7919, 50 - seed as usize, 4);
+
+            for &mode in &[
+                AlignmentMode::Global,
+                AlignmentMode::SemiGlobal,
+                AlignmentMode::Overlap,
+                AlignmentMode::Local
+            ] {
+                let alignment = aligner.align_indices(mode, &x, &y);
+                check(&scoring, &x, &y, &alignment);
+
+                if mode != AlignmentMode::Local {
+                    assert!(alignment.x_start == 0 || alignment.y_start == 0);
+                }
+                if mode == AlignmentMode::Global || mode == AlignmentMode::SemiGlobal {
+                    assert_eq!((alignment.x_start, alignment.x_end), (0, x.len()));
+                }
+            }
+        }
+    }
+}
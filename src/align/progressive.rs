@@ -0,0 +1,662 @@
+//! Progressive multiple sequence alignment in the spirit of ClustalW.
+//!
+//! Every pair of sequences is aligned to estimate how far apart they are, a guide tree is built
+//! from those distances and then the sequences are aligned from the leaves of the tree to the
+//! root. At each internal node the alignments (profiles) of its two children are aligned to
+//! each other with affine gaps, scoring each pair of columns by the average substitution score
+//! of every pair of residues in them. Gaps once placed are never moved.
+//!
+//! Every sequence has the same weight and the gap penalties are the same at every position.
+
+use std::fmt::Write;
+use crate::alphabet::{Alphabet, Gapped};
+use crate::sequence::Sequence;
+use super::{
+    Aligner, AlignedSequence, AlignmentMode, AlignmentOperation, Scoring, SubstitutionScore
+};
+use super::msa::{self, Msa};
+
+/// Profile scores are fractional so are multiplied by this before rounding to an integer
+const PROFILE_SCALE: f64 = 100.0;
+
+/// How to build a guide tree from a distance matrix.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TreeMethod {
+    /// Unweighted pair group method with arithmetic mean: repeatedly joins the closest pair of
+    /// clusters. Assumes a molecular clock.
+    Upgma,
+    /// Saitou and Nei's neighbour joining which allows different rates along each branch. The
+    /// tree is rooted at the last join.
+    NeighborJoining,
+}
+
+//================================================================================
+// Guide Tree
+//================================================================================
+
+/// A node of a [GuideTree].
+#[derive(Debug, Clone, PartialEq)]
+pub enum Node {
+    /// One of the sequences, given by its index
+    Leaf(usize),
+    /// Joins two nodes, given by their index in [GuideTree::nodes()]
+    Internal {
+        /// The first child
+        left: usize,
+        /// The second child
+        right: usize,
+        /// The length of the branch to the first child
+        left_length: f64,
+        /// The length of the branch to the second child
+        right_length: f64,
+    },
+}
+
+/// A rooted binary tree with a leaf for every sequence that sets the order sequences are aligned
+/// in. Children always come before their parent in [nodes()](GuideTree::nodes) so the root is
+/// the last node.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GuideTree {
+    nodes: Vec<Node>,
+}
+
+impl GuideTree {
+    /// Builds a tree from a symmetric matrix of distances between the sequences.
+    ///
+    /// # Panics
+    /// If the matrix is empty or not square.
+    ///
+    /// # Example
+    /// ```
+    /// use biors::align::progressive::{GuideTree, TreeMethod};
+    ///
+    /// let distances = vec![
+    ///     vec![0.0, 2.0, 6.0],
+    ///     vec![2.0, 0.0, 6.0],
+    ///     vec![6.0, 6.0, 0.0],
+    /// ];
+    /// let tree = GuideTree::new(&distances, TreeMethod::Upgma);
+    ///
+    /// assert_eq!(tree.newick(&["a", "b", "c"]), "((a:1,b:1):2,c:3);");
+    /// ```
+    pub fn new(distances: &[Vec<f64>], method: TreeMethod) -> Self {
+        let n = distances.len();
+        assert!(n > 0, "A guide tree needs at least one sequence.");
+        assert!(distances.iter().all(|row| row.len() == n), "The distance matrix must be square.");
+
+        match method {
+            TreeMethod::Upgma => GuideTree::upgma(distances),
+            TreeMethod::NeighborJoining => GuideTree::neighbor_joining(distances),
+        }
+    }
+
+    /// Every node with children before their parents.
+    pub fn nodes(&self) -> &[Node] {
+        &self.nodes
+    }
+
+    /// The index of the root node.
+    pub fn root(&self) -> usize {
+        self.nodes.len() - 1
+    }
+
+    /// The tree in Newick format with branch lengths, naming each leaf from names.
+    ///
+    /// # Panics
+    /// If there are fewer names than leaves.
+    pub fn newick<S: AsRef<str>>(&self, names: &[S]) -> String {
+        let mut newick = String::new();
+        self.write_newick(self.root(), names, &mut newick);
+        newick.push(';');
+        newick
+    }
+
+    fn write_newick<S: AsRef<str>>(&self, node: usize, names: &[S], newick: &mut String) {
+        match self.nodes[node] {
+            Node::Leaf(sequence) => newick.push_str(names[sequence].as_ref()),
+            Node::Internal { left, right, left_length, right_length } => {
+                newick.push('(');
+                self.write_newick(left, names, newick);
+                write!(newick, ":{},", left_length).unwrap();
+                self.write_newick(right, names, newick);
+                write!(newick, ":{})", right_length).unwrap();
+            }
+        }
+    }
+
+    fn upgma(distances: &[Vec<f64>]) -> Self {
+        let mut nodes: Vec<Node> = (0..distances.len()).map(Node::Leaf).collect();
+        let mut distances = distances.to_vec();
+
+        // The node, number of leaves and height of every cluster still to be joined
+        let mut clusters: Vec<(usize, usize, f64)> =
+            (0..distances.len()).map(|i| (i, 1, 0.0)).collect();
+
+        while clusters.len() > 1 {
+            let (i, j) = closest_pair(clusters.len(), |i, j| distances[i][j]);
+            let height = distances[i][j] / 2.0;
+            let (left, left_size, left_height) = clusters[i];
+            let (right, right_size, right_height) = clusters[j];
+
+            nodes.push(Node::Internal {
+                left,
+                right,
+                left_length: (height - left_height).max(0.0),
+                right_length: (height - right_height).max(0.0),
+            });
+
+            // The new cluster replaces i and j is removed
+            let size = left_size + right_size;
+            let row = distances[i].iter()
+                .zip(&distances[j])
+                .map(|(a, b)| (a * left_size as f64 + b * right_size as f64) / size as f64)
+                .collect();
+            replace_distances(&mut distances, i, row);
+            clusters[i] = (nodes.len() - 1, size, height);
+            remove_cluster(&mut distances, &mut clusters, j);
+        }
+
+        GuideTree { nodes }
+    }
+
+    fn neighbor_joining(distances: &[Vec<f64>]) -> Self {
+        let mut nodes: Vec<Node> = (0..distances.len()).map(Node::Leaf).collect();
+        let mut distances = distances.to_vec();
+        let mut clusters: Vec<usize> = (0..distances.len()).collect();
+
+        while clusters.len() > 1 {
+            let n = clusters.len();
+
+            let (i, j, left_length) = if n == 2 {
+                (0, 1, distances[0][1] / 2.0)
+            } else {
+                let totals: Vec<f64> = distances.iter().map(|row| row.iter().sum()).collect();
+                let (i, j) = closest_pair(n, |i, j| {
+                    (n - 2) as f64 * distances[i][j] - totals[i] - totals[j]
+                });
+                (i, j, distances[i][j] / 2.0 + (totals[i] - totals[j]) / (2.0 * (n - 2) as f64))
+            };
+            let right_length = distances[i][j] - left_length;
+
+            nodes.push(Node::Internal {
+                left: clusters[i],
+                right: clusters[j],
+                left_length: left_length.max(0.0),
+                right_length: right_length.max(0.0),
+            });
+
+            let joined = distances[i][j];
+            let row = distances[i].iter()
+                .zip(&distances[j])
+                .map(|(a, b)| (a + b - joined) / 2.0)
+                .collect();
+            replace_distances(&mut distances, i, row);
+            clusters[i] = nodes.len() - 1;
+            remove_cluster(&mut distances, &mut clusters, j);
+        }
+
+        GuideTree { nodes }
+    }
+}
+
+/// The pair i < j with the smallest value, the first found on a tie
+fn closest_pair<F: Fn(usize, usize) -> f64>(n: usize, value: F) -> (usize, usize) {
+    let mut best = (0, 1, f64::INFINITY);
+    for i in 0..n {
+        for j in i + 1..n {
+            let value = value(i, j);
+            if value < best.2 {
+                best = (i, j, value);
+            }
+        }
+    }
+    (best.0, best.1)
+}
+
+/// Sets the distances from a cluster to every other cluster
+fn replace_distances(distances: &mut [Vec<f64>], cluster: usize, mut row: Vec<f64>) {
+    row[cluster] = 0.0;
+    for (other, &distance) in distances.iter_mut().zip(&row) {
+        other[cluster] = distance;
+    }
+    distances[cluster] = row;
+}
+
+/// Removes a cluster and its row and column of distances
+fn remove_cluster<T>(distances: &mut Vec<Vec<f64>>, clusters: &mut Vec<T>, cluster: usize) {
+    distances.remove(cluster);
+    for row in distances.iter_mut() {
+        row.remove(cluster);
+    }
+    clusters.remove(cluster);
+}
+
+//================================================================================
+// Progressive Aligner
+//================================================================================
+
+/// Builds multiple sequence alignments by progressively aligning profiles along a guide tree.
+///
+/// # Example
+/// ```
+/// use biors::align::Scoring;
+/// use biors::align::progressive::ProgressiveAligner;
+/// use biors::alphabet::{Gapped, UnambiguousDnaAlphabet};
+/// use biors::sequence::Sequence;
+///
+/// let dna = UnambiguousDnaAlphabet;
+/// let mut sequences = vec![];
+/// for (id, symbols) in [("a", "ACGTTGCA"), ("b", "ACGTGCA"), ("c", "ACGTTGCA")] {
+///     let mut seq = Sequence::new(&dna);
+///     seq.push(symbols).unwrap();
+///     sequences.push((id, seq));
+/// }
+///
+/// let aligner = ProgressiveAligner::new(Scoring::match_mismatch(2, -1, -4, -1));
+/// let gapped = Gapped::new(&dna);
+/// let msa = aligner.align(&gapped, &sequences).unwrap();
+///
+/// assert_eq!(msa.num_columns(), 8);
+/// assert_eq!(msa.get("b").unwrap().residue_count(), 7);
+/// ```
+#[derive(Debug, Clone)]
+pub struct ProgressiveAligner<S: SubstitutionScore> {
+    aligner: Aligner<S>,
+    method: TreeMethod,
+}
+
+impl<S: SubstitutionScore> ProgressiveAligner<S> {
+    /// Constructs an aligner that scores both the pairwise and profile alignments with scoring.
+    ///
+    /// # Default
+    /// The guide tree is built with [TreeMethod::NeighborJoining].
+    pub fn new(scoring: Scoring<S>) -> Self {
+        ProgressiveAligner { aligner: Aligner::new(scoring), method: TreeMethod::NeighborJoining }
+    }
+
+    /// Sets how the guide tree is built.
+    pub fn tree_method(mut self, method: TreeMethod) -> Self {
+        self.method = method;
+        self
+    }
+
+    /// Aligns the sequences. The rows of the alignment are in the same order as the sequences.
+    ///
+    /// # Errors
+    /// If two sequences have the same ID.
+    pub fn align<'g, A, I>(
+        &self,
+        gapped: &'g Gapped<'g, A>,
+        sequences: &[(I, Sequence<A>)]
+    ) -> msa::Result<Msa<'g, A>>
+    where
+        A: Alphabet,
+        I: AsRef<str>
+    {
+        if sequences.is_empty() {
+            return Ok(Msa::new(gapped));
+        }
+
+        let tree = self.guide_tree(sequences);
+        self.align_with_tree(gapped, sequences, &tree)
+    }
+
+    /// The distance between every pair of sequences, calculated as one minus the fraction of
+    /// aligned pairs of residues that are identical in their global alignment.
+    pub fn distances<A: Alphabet, I>(&self, sequences: &[(I, Sequence<A>)]) -> Vec<Vec<f64>> {
+        let indices: Vec<Vec<usize>> = sequences.iter()
+            .map(|(_, seq)| seq.as_bytes().iter().map(|&index| index as usize).collect())
+            .collect();
+        let n = sequences.len();
+        let mut distances = vec![vec![0.0; n]; n];
+
+        for i in 0..n {
+            for j in i + 1..n {
+                let alignment =
+                    self.aligner.align_indices(AlignmentMode::Global, &indices[i], &indices[j]);
+                let (mut matches, mut pairs) = (0, 0);
+                for op in alignment.operations {
+                    match op {
+                        AlignmentOperation::Match => { matches += 1; pairs += 1 }
+                        AlignmentOperation::Substitution => pairs += 1,
+                        _ => {}
+                    }
+                }
+
+                let distance = if pairs == 0 { 1.0 } else { 1.0 - matches as f64 / pairs as f64 };
+                distances[i][j] = distance;
+                distances[j][i] = distance;
+            }
+        }
+
+        distances
+    }
+
+    /// The guide tree built from the [distances()](ProgressiveAligner::distances) of the
+    /// sequences.
+    ///
+    /// # Panics
+    /// If there are no sequences.
+    pub fn guide_tree<A: Alphabet, I>(&self, sequences: &[(I, Sequence<A>)]) -> GuideTree {
+        GuideTree::new(&self.distances(sequences), self.method)
+    }
+
+    /// Aligns the sequences following a guide tree that has been built separately.
+    ///
+    /// # Errors
+    /// If two sequences have the same ID.
+    ///
+    /// # Panics
+    /// If the leaves of the tree are not the sequences.
+    pub fn align_with_tree<'g, A, I>(
+        &self,
+        gapped: &'g Gapped<'g, A>,
+        sequences: &[(I, Sequence<A>)],
+        tree: &GuideTree
+    ) -> msa::Result<Msa<'g, A>>
+    where
+        A: Alphabet,
+        I: AsRef<str>
+    {
+        let symbols = gapped.gap_index() as usize;
+        let mut profiles: Vec<Option<Profile>> = Vec::with_capacity(tree.nodes().len());
+
+        for node in tree.nodes() {
+            let profile = match *node {
+                Node::Leaf(sequence) => Profile {
+                    members: vec![sequence],
+                    rows: vec![sequences[sequence].1.as_bytes().to_vec()],
+                },
+                Node::Internal { left, right, .. } => {
+                    let left = profiles[left].take().expect("Each node must have one parent");
+                    let right = profiles[right].take().expect("Each node must have one parent");
+                    self.align_profiles(left, right, symbols, gapped.gap_index())
+                }
+            };
+            profiles.push(Some(profile));
+        }
+
+        let root = profiles.pop().flatten().expect("The tree must have a root");
+        let mut rows: Vec<Option<Vec<u8>>> = vec![None; sequences.len()];
+        for (member, row) in root.members.into_iter().zip(root.rows) {
+            rows[member] = Some(row);
+        }
+
+        let mut msa = Msa::new(gapped);
+        for ((id, _), row) in sequences.iter().zip(rows) {
+            let row = row.expect("Every sequence must be a leaf of the tree");
+            msa.push(id.as_ref(), AlignedSequence::from_indices(gapped, row))?;
+        }
+
+        Ok(msa)
+    }
+
+    /// Globally aligns two profiles and merges them into one
+    fn align_profiles(&self, x: Profile, y: Profile, symbols: usize, gap: u8) -> Profile {
+        let scoring = self.aligner.scoring();
+        let x_frequencies = x.frequencies(symbols);
+        let y_frequencies = y.frequencies(symbols);
+
+        // The expected score of each symbol against each column of x
+        let x_scores: Vec<Vec<f64>> = x_frequencies.iter()
+            .map(|frequencies| {
+                (0..symbols)
+                    .map(|b| {
+                        frequencies.iter()
+                            .enumerate()
+                            .map(|(a, &frequency)| {
+                                frequency * scoring.substitution.score(a, b) as f64
+                            })
+                            .sum()
+                    })
+                    .collect()
+            })
+            .collect();
+
+        let column_score = |i: usize, j: usize| -> i32 {
+            let score: f64 = x_scores[i].iter().zip(&y_frequencies[j]).map(|(s, f)| s * f).sum();
+            (score * PROFILE_SCALE).round() as i32
+        };
+        let profile_scoring = Scoring::new(
+            column_score,
+            (scoring.gap_open as f64 * PROFILE_SCALE) as i32,
+            (scoring.gap_extend as f64 * PROFILE_SCALE) as i32
+        );
+
+        let x_columns: Vec<usize> = (0..x.len()).collect();
+        let y_columns: Vec<usize> = (0..y.len()).collect();
+        let alignment = Aligner::new(profile_scoring)
+            .align_indices(AlignmentMode::Global, &x_columns, &y_columns);
+
+        let mut rows = vec![Vec::with_capacity(alignment.len()); x.rows.len() + y.rows.len()];
+        let (mut i, mut j) = (0, 0);
+        for op in alignment.operations {
+            let (x_column, y_column) = match op {
+                AlignmentOperation::Match | AlignmentOperation::Substitution => (Some(i), Some(j)),
+                AlignmentOperation::Insertion => (Some(i), None),
+                AlignmentOperation::Deletion => (None, Some(j)),
+            };
+
+            let x_symbols = x.rows.iter().map(|row| x_column.map_or(gap, |i| row[i]));
+            let y_symbols = y.rows.iter().map(|row| y_column.map_or(gap, |j| row[j]));
+            for (row, symbol) in rows.iter_mut().zip(x_symbols.chain(y_symbols)) {
+                row.push(symbol);
+            }
+
+            if x_column.is_some() { i += 1 }
+            if y_column.is_some() { j += 1 }
+        }
+
+        let mut members = x.members;
+        members.extend(y.members);
+        Profile { members, rows }
+    }
+}
+
+/// An alignment of some of the sequences
+struct Profile {
+    /// The index of the sequence in each row
+    members: Vec<usize>,
+    /// Indices in the gapped alphabet
+    rows: Vec<Vec<u8>>,
+}
+
+impl Profile {
+    fn len(&self) -> usize {
+        self.rows[0].len()
+    }
+
+    /// The fraction of rows with each residue in each column. Gaps aren't counted so the
+    /// fractions of a column with gaps add up to less than 1.
+    fn frequencies(&self, symbols: usize) -> Vec<Vec<f64>> {
+        let share = 1.0 / self.rows.len() as f64;
+        (0..self.len())
+            .map(|column| {
+                let mut frequencies = vec![0.0; symbols];
+                for row in &self.rows {
+                    if let Some(frequency) = frequencies.get_mut(row[column] as usize) {
+                        *frequency += share;
+                    }
+                }
+                frequencies
+            })
+            .collect()
+    }
+}
+
+//================================================================================
+// Tests
+//================================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::align::matrix::{BuiltinMatrix, SubstitutionMatrix};
+    use crate::alphabet::{ProteinAlphabet, UnambiguousDnaAlphabet};
+    use crate::sequence::test_util::sequence;
+
+    /// Names each sequence after its position
+    fn sequences<'a, A: Alphabet>(
+        alphabet: &'a A,
+        symbols: &[&str]
+    ) -> Vec<(String, Sequence<'a, A>)> {
+        symbols.iter()
+            .enumerate()
+            .map(|(i, symbols)| (format!("seq{}", i), sequence(alphabet, symbols)))
+            .collect()
+    }
+
+    /// The sum of the branch lengths between two leaves
+    fn path_length(tree: &GuideTree, a: usize, b: usize) -> f64 {
+        // Distance from each node down to each leaf below it
+        let mut below: Vec<Vec<(usize, f64)>> = vec![];
+        for (index, node) in tree.nodes().iter().enumerate() {
+            let leaves = match *node {
+                Node::Leaf(leaf) => vec![(leaf, 0.0)],
+                Node::Internal { left, right, left_length, right_length } => {
+                    let left_leaves = below[left].iter().map(|&(leaf, d)| (leaf, d + left_length));
+                    let right_leaves =
+                        below[right].iter().map(|&(leaf, d)| (leaf, d + right_length));
+                    let leaves: Vec<_> = left_leaves.chain(right_leaves).collect();
+
+                    let a_distance = leaves.iter().find(|&&(leaf, _)| leaf == a);
+                    let b_distance = leaves.iter().find(|&&(leaf, _)| leaf == b);
+                    if let (Some(&(_, da)), Some(&(_, db))) = (a_distance, b_distance) {
+                        return da + db;
+                    }
+                    leaves
+                }
+            };
+            assert_eq!(below.len(), index);
+            below.push(leaves);
+        }
+        unreachable!()
+    }
+
+    /// Tests UPGMA against the example from Wikipedia
+    #[test]
+    fn upgma() {
+        let distances = vec![
+            vec![0.0, 17.0, 21.0, 31.0, 23.0],
+            vec![17.0, 0.0, 30.0, 34.0, 21.0],
+            vec![21.0, 30.0, 0.0, 28.0, 39.0],
+            vec![31.0, 34.0, 28.0, 0.0, 43.0],
+            vec![23.0, 21.0, 39.0, 43.0, 0.0],
+        ];
+        let tree = GuideTree::new(&distances, TreeMethod::Upgma);
+
+        assert_eq!(
+            tree.newick(&["a", "b", "c", "d", "e"]),
+            "(((a:8.5,b:8.5):2.5,e:11):5.5,(c:14,d:14):2.5);"
+        );
+    }
+
+    /// Tests that neighbour joining recovers the branch lengths of an additive tree
+    #[test]
+    fn neighbor_joining() {
+        let distances = vec![
+            vec![0.0, 5.0, 9.0, 9.0, 8.0],
+            vec![5.0, 0.0, 10.0, 10.0, 9.0],
+            vec![9.0, 10.0, 0.0, 8.0, 7.0],
+            vec![9.0, 10.0, 8.0, 0.0, 3.0],
+            vec![8.0, 9.0, 7.0, 3.0, 0.0],
+        ];
+        let tree = GuideTree::new(&distances, TreeMethod::NeighborJoining);
+
+        assert_eq!(tree.nodes().len(), 9);
+        for (a, row) in distances.iter().enumerate() {
+            for (b, &distance) in row.iter().enumerate().skip(a + 1) {
+                assert!((path_length(&tree, a, b) - distance).abs() < 1e-9);
+            }
+        }
+
+        let single = GuideTree::new(&[vec![0.0]], TreeMethod::NeighborJoining);
+        assert_eq!(single.nodes(), &[Node::Leaf(0)]);
+        assert_eq!(single.newick(&["a"]), "a;");
+    }
+
+    /// Tests that identical sequences are aligned without gaps
+    #[test]
+    fn identical() {
+        let dna = UnambiguousDnaAlphabet;
+        let gapped = Gapped::new(&dna);
+        let input = sequences(&dna, &["ACGTACGT"; 4]);
+
+        let msa = ProgressiveAligner::new(Scoring::match_mismatch(1, -1, -2, -1))
+            .align(&gapped, &input)
+            .unwrap();
+
+        assert_eq!(msa.num_columns(), 8);
+        assert!(msa.rows().iter().all(|row| row.to_string() == "ACGTACGT"));
+    }
+
+    /// Tests that deletions shared by a clade are aligned together and rows keep their order
+    #[test]
+    fn shared_deletion() {
+        let dna = UnambiguousDnaAlphabet;
+        let gapped = Gapped::new(&dna);
+        let input = sequences(&dna, &[
+            "ATGCGTACGTTAGCCATGAC",
+            "ATGCGTACGTAGCCATGAC",
+            "ATGCGTACGTTAGCCATGAC",
+            "ATGCGTACGTAGCCATGAC",
+        ]);
+
+        for &method in &[TreeMethod::Upgma, TreeMethod::NeighborJoining] {
+            let msa = ProgressiveAligner::new(Scoring::match_mismatch(2, -3, -5, -2))
+                .tree_method(method)
+                .align(&gapped, &input)
+                .unwrap();
+
+            assert_eq!(msa.ids(), &["seq0", "seq1", "seq2", "seq3"]);
+            assert_eq!(msa.num_columns(), 20);
+            assert_eq!(msa.rows()[1].to_string(), msa.rows()[3].to_string());
+            for ((_, seq), row) in input.iter().zip(msa.rows()) {
+                assert_eq!(row.ungap().as_bytes(), seq.as_bytes());
+            }
+        }
+    }
+
+    /// Tests aligning related protein sequences with BLOSUM62
+    #[test]
+    fn protein() {
+        let protein = ProteinAlphabet;
+        let gapped = Gapped::new(&protein);
+        let matrix = SubstitutionMatrix::builtin(BuiltinMatrix::Blosum62, &protein).unwrap();
+        let input = sequences(&protein, &[
+            "MKTAYIAKQRQISFVKSHFSRQ",
+            "MKTAYIAKQRQISFVKSHFSRQ",
+            "MKTAYIAKQISFVKSHFSRQ",
+            "MKSAYIAKQRQISFVRSHFSRQ",
+            "MKTAYLAKQRQISFVKSHF",
+        ]);
+
+        let aligner = ProgressiveAligner::new(Scoring::new(matrix, -10, -1));
+        let distances = aligner.distances(&input);
+        assert_eq!(distances[0][1], 0.0);
+        assert!(distances[0][3] > 0.0);
+
+        let msa = aligner.align(&gapped, &input).unwrap();
+        assert_eq!(msa.num_columns(), 22);
+        assert_eq!(msa.rows()[2].to_string().matches('-').count(), 2);
+        assert_eq!(msa.rows()[4].to_string(), "MKTAYLAKQRQISFVKSHF---");
+        for ((_, seq), row) in input.iter().zip(msa.rows()) {
+            assert_eq!(row.ungap().as_bytes(), seq.as_bytes());
+        }
+    }
+
+    /// Tests that no sequences give an empty alignment and duplicate IDs are rejected
+    #[test]
+    fn edge_cases() {
+        let dna = UnambiguousDnaAlphabet;
+        let gapped = Gapped::new(&dna);
+        let aligner = ProgressiveAligner::new(Scoring::match_mismatch(1, -1, -2, -1));
+
+        let none: Vec<(String, Sequence<UnambiguousDnaAlphabet>)> = vec![];
+        assert!(aligner.align(&gapped, &none).unwrap().is_empty());
+
+        let mut input = sequences(&dna, &["ACGT", "ACGA"]);
+        input[1].0 = "seq0".to_owned();
+        assert!(aligner.align(&gapped, &input).is_err());
+    }
+}
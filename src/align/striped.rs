@@ -0,0 +1,803 @@
+//! Fast local alignment scores using Farrar's striped SIMD Smith-Waterman.
+//!
+//! Database scans usually only need the score of the best local alignment of a query against
+//! each reference, with a full alignment computed for the few that score highly.
+//! [StripedAligner] precomputes a query profile once and then scores references using 8-bit
+//! saturating arithmetic, rescoring with 16-bit and then 32-bit arithmetic if the score
+//! overflows.
+//!
+//! The query is split into segments that are processed in parallel lanes so that the
+//! dependencies between cells in a column only cross lanes once per column. Gaps that cross a
+//! lane are then fixed up by the lazy F loop which rarely runs for more than a few steps.
+//!
+//! SSE2 and AVX2 are used on x86 if the CPU supports them, otherwise a scalar implementation is
+//! used. Sequences are taken as
+//! [AsciiIndexEncoder](crate::alphabet::encoding::index_encoder::AsciiIndexEncoder) encoded
+//! bytes which are already the symbol indices so no decoding is needed.
+
+use crate::alphabet::Alphabet;
+use crate::sequence::Sequence;
+use super::{Scoring, SubstitutionScore};
+
+/// The instruction set used to score alignments.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Backend {
+    /// Plain 32-bit arithmetic that runs on any CPU
+    Scalar,
+    /// 128-bit vectors of 16 8-bit or 8 16-bit scores
+    Sse2,
+    /// 256-bit vectors of 32 8-bit or 16 16-bit scores
+    Avx2,
+}
+
+impl Backend {
+    /// The fastest backend supported by the current CPU.
+    pub fn detect() -> Self {
+        if Backend::Avx2.is_supported() {
+            Backend::Avx2
+        } else if Backend::Sse2.is_supported() {
+            Backend::Sse2
+        } else {
+            Backend::Scalar
+        }
+    }
+
+    /// Whether the current CPU supports the backend.
+    pub fn is_supported(self) -> bool {
+        match self {
+            Backend::Scalar => true,
+            #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+            Backend::Sse2 => is_x86_feature_detected!("sse2"),
+            #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+            Backend::Avx2 => is_x86_feature_detected!("avx2"),
+            #[cfg(not(any(target_arch = "x86", target_arch = "x86_64")))]
+            _ => false,
+        }
+    }
+
+    /// The number of 8-bit scores in a vector, the number of 16-bit scores is half this.
+    fn lanes(self) -> usize {
+        match self {
+            Backend::Scalar => 0,
+            Backend::Sse2 => 16,
+            Backend::Avx2 => 32,
+        }
+    }
+}
+
+/// The score of the best local alignment and where it ends.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct LocalScore {
+    /// The score of the alignment, never negative
+    pub score: i32,
+    /// The position in the query after the end of the alignment
+    pub x_end: usize,
+    /// The position in the reference after the end of the alignment
+    pub y_end: usize,
+}
+
+/// Scores local alignments of a single query against many references.
+///
+/// When several alignments have the best score the one ending earliest in the reference, and
+/// then earliest in the query, is reported.
+///
+/// # Example
+/// ```
+/// use biors::align::{Aligner, Scoring};
+/// use biors::align::matrix::{BuiltinMatrix, SubstitutionMatrix};
+/// use biors::align::striped::StripedAligner;
+/// use biors::alphabet::ProteinAlphabet;
+/// use biors::sequence::Sequence;
+///
+/// let alphabet = ProteinAlphabet;
+/// let matrix = SubstitutionMatrix::builtin(BuiltinMatrix::Blosum62, &alphabet).unwrap();
+/// let scoring = Scoring::new(matrix, -10, -1);
+///
+/// let mut query = Sequence::new(&alphabet);
+/// let mut reference = Sequence::new(&alphabet);
+/// query.push("HEAGAWGHEE").unwrap();
+/// reference.push("PAWHEAE").unwrap();
+///
+/// let striped = StripedAligner::new(&query, &scoring);
+/// let result = striped.score(&reference);
+///
+/// assert_eq!(result.score, Aligner::new(scoring).local(&query, &reference).score);
+/// ```
+#[derive(Debug, Clone)]
+pub struct StripedAligner {
+    backend: Backend,
+    query_len: usize,
+    alphabet_size: usize,
+    /// The score of every query position against every symbol, indexed by symbol then position
+    scores: Vec<i32>,
+    /// The cost of the first symbol of a gap
+    gap_open: i32,
+    /// The cost of every later symbol of a gap
+    gap_extend: i32,
+    /// Added to the 8-bit profile so that it isn't negative
+    bias: i32,
+    max_score: i32,
+    /// Only read by the vector backends, which are all x86
+    #[cfg_attr(not(any(target_arch = "x86", target_arch = "x86_64")), allow(dead_code))]
+    profile_u8: Option<Vec<u8>>,
+    #[cfg_attr(not(any(target_arch = "x86", target_arch = "x86_64")), allow(dead_code))]
+    profile_i16: Option<Vec<i16>>,
+}
+
+impl StripedAligner {
+    /// Builds the query profile for a query sequence using the fastest supported backend.
+    pub fn new<A, S>(query: &Sequence<A>, scoring: &Scoring<S>) -> Self
+    where
+        A: Alphabet,
+        S: SubstitutionScore
+    {
+        StripedAligner::from_indices(query.as_bytes(), query.alphabet().symbols().len(), scoring)
+    }
+
+    /// Builds the query profile for a query given as the indices of its symbols in an alphabet
+    /// of alphabet_size symbols.
+    ///
+    /// # Panics
+    /// If a symbol of the query is not less than alphabet_size.
+    pub fn from_indices<S: SubstitutionScore>(
+        query: &[u8],
+        alphabet_size: usize,
+        scoring: &Scoring<S>
+    ) -> Self {
+        check_indices(query, alphabet_size);
+
+        let n = query.len();
+        let mut scores = Vec::with_capacity(alphabet_size * n);
+        for a in 0..alphabet_size {
+            scores.extend(query.iter().map(|&q| scoring.substitution.score(q as usize, a)));
+        }
+
+        // Padding at the end of the profile scores 0 so it is included in the range
+        let min_score = scores.iter().copied().fold(0, i32::min);
+        let max_score = scores.iter().copied().fold(0, i32::max);
+
+        let mut aligner = StripedAligner {
+            backend: Backend::Scalar,
+            query_len: n,
+            alphabet_size,
+            scores,
+            gap_open: -(scoring.gap_open + scoring.gap_extend),
+            gap_extend: -scoring.gap_extend,
+            bias: -min_score,
+            max_score,
+            profile_u8: None,
+            profile_i16: None,
+        };
+        aligner.build_profiles(Backend::detect());
+        aligner
+    }
+
+    /// Uses a specific backend, for example to compare them.
+    ///
+    /// # Panics
+    /// If the backend isn't supported by the current CPU.
+    pub fn with_backend(mut self, backend: Backend) -> Self {
+        assert!(backend.is_supported(), "The {:?} backend is not supported by this CPU.", backend);
+        self.build_profiles(backend);
+        self
+    }
+
+    /// The backend used to score alignments.
+    pub fn backend(&self) -> Backend {
+        self.backend
+    }
+
+    /// The length of the query.
+    pub fn query_len(&self) -> usize {
+        self.query_len
+    }
+
+    /// Scores the best local alignment of the query to a reference sequence.
+    ///
+    /// # Panics
+    /// If the reference has symbols outside the alphabet of the query.
+    pub fn score<A: Alphabet>(&self, reference: &Sequence<A>) -> LocalScore {
+        self.score_indices(reference.as_bytes())
+    }
+
+    /// Scores the best local alignment of the query to a reference given as the indices of its
+    /// symbols. See [score()](StripedAligner::score).
+    ///
+    /// # Panics
+    /// If a symbol of the reference is not less than the alphabet size of the query.
+    pub fn score_indices(&self, reference: &[u8]) -> LocalScore {
+        check_indices(reference, self.alphabet_size);
+
+        if self.query_len == 0 || reference.is_empty() {
+            return LocalScore::default();
+        }
+
+        self.score_u8(reference)
+            .or_else(|| self.score_i16(reference))
+            .unwrap_or_else(|| self.score_scalar(reference))
+    }
+
+    /// Builds the striped 8 and 16-bit profiles for the backend where the scores fit
+    fn build_profiles(&mut self, backend: Backend) {
+        self.backend = backend;
+        self.profile_u8 = None;
+        self.profile_i16 = None;
+
+        if backend == Backend::Scalar {
+            return;
+        }
+
+        let u8_max = i32::from(u8::MAX);
+        if self.max_score + self.bias < u8_max && self.gap_open <= u8_max {
+            let profile = self.striped_profile(backend.lanes(), self.bias);
+            self.profile_u8 = Some(profile.into_iter().map(|score| score as u8).collect());
+        }
+
+        let i16_max = i32::from(i16::MAX);
+        if self.max_score < i16_max && self.bias <= i16_max && self.gap_open <= i16_max {
+            let profile = self.striped_profile(backend.lanes() / 2, 0);
+            self.profile_i16 = Some(profile.into_iter().map(|score| score as i16).collect());
+        }
+    }
+
+    /// Lays out the scores so that each vector holds one position of every segment of the query
+    fn striped_profile(&self, lanes: usize, bias: i32) -> Vec<i32> {
+        let n = self.query_len;
+        let segment_len = n.div_ceil(lanes);
+        let mut profile = Vec::with_capacity(self.alphabet_size * segment_len * lanes);
+
+        for a in 0..self.alphabet_size {
+            for i in 0..segment_len {
+                for lane in 0..lanes {
+                    let position = lane * segment_len + i;
+                    let score = if position < n { self.scores[a * n + position] } else { 0 };
+                    profile.push(score + bias);
+                }
+            }
+        }
+
+        profile
+    }
+
+    /// Scores with 8-bit vectors, returns None if the score overflowed
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    fn score_u8(&self, reference: &[u8]) -> Option<LocalScore> {
+        let profile = self.profile_u8.as_ref()?;
+        let params = Params {
+            query_len: self.query_len,
+            gap_open: self.gap_open as u8,
+            gap_extend: self.gap_extend as u8,
+            bias: self.bias as u8,
+            limit: i32::from(u8::MAX) - self.bias,
+        };
+
+        match self.backend {
+            Backend::Sse2 => unsafe { x86::sse2_u8(profile, reference, &params) },
+            Backend::Avx2 => unsafe { x86::avx2_u8(profile, reference, &params) },
+            Backend::Scalar => None,
+        }
+    }
+
+    /// Scores with 16-bit vectors, returns None if the score overflowed
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    fn score_i16(&self, reference: &[u8]) -> Option<LocalScore> {
+        let profile = self.profile_i16.as_ref()?;
+        let params = Params {
+            query_len: self.query_len,
+            gap_open: self.gap_open as i16,
+            gap_extend: self.gap_extend as i16,
+            bias: 0,
+            limit: i32::from(i16::MAX),
+        };
+
+        match self.backend {
+            Backend::Sse2 => unsafe { x86::sse2_i16(profile, reference, &params) },
+            Backend::Avx2 => unsafe { x86::avx2_i16(profile, reference, &params) },
+            Backend::Scalar => None,
+        }
+    }
+
+    /// There are no vector backends on this target so the scalar backend is always used
+    #[cfg(not(any(target_arch = "x86", target_arch = "x86_64")))]
+    fn score_u8(&self, _reference: &[u8]) -> Option<LocalScore> {
+        None
+    }
+
+    /// There are no vector backends on this target so the scalar backend is always used
+    #[cfg(not(any(target_arch = "x86", target_arch = "x86_64")))]
+    fn score_i16(&self, _reference: &[u8]) -> Option<LocalScore> {
+        None
+    }
+
+    /// Scores one column of the query at a time with 32-bit scores which can't overflow
+    fn score_scalar(&self, reference: &[u8]) -> LocalScore {
+        let n = self.query_len;
+        let none = i32::MIN / 2;
+
+        // The best score and the score ending in a gap in the reference of each query position
+        // in the previous column
+        let mut best = vec![0; n];
+        let mut gap = vec![none; n];
+        let mut result = LocalScore::default();
+
+        for (j, &symbol) in reference.iter().enumerate() {
+            let scores = &self.scores[symbol as usize * n..][..n];
+            let mut diagonal = 0;
+            let mut above = 0;
+            let mut query_gap = none;
+
+            for i in 0..n {
+                gap[i] = (gap[i] - self.gap_extend).max(best[i] - self.gap_open);
+                query_gap = (query_gap - self.gap_extend).max(above - self.gap_open);
+
+                let score = (diagonal + scores[i]).max(gap[i]).max(query_gap).max(0);
+                diagonal = best[i];
+                best[i] = score;
+                above = score;
+
+                if score > result.score {
+                    result = LocalScore { score, x_end: i + 1, y_end: j + 1 };
+                }
+            }
+        }
+
+        result
+    }
+}
+
+/// Panics if any of the indices are outside the alphabet
+fn check_indices(indices: &[u8], alphabet_size: usize) {
+    if let Some(&index) = indices.iter().find(|&&index| index as usize >= alphabet_size) {
+        panic!("Symbol index {} is outside the alphabet of {} symbols.", index, alphabet_size);
+    }
+}
+
+//================================================================================
+// Striped Kernel
+//================================================================================
+
+/// Everything the kernel needs apart from the profile and the reference
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+struct Params<T> {
+    query_len: usize,
+    gap_open: T,
+    gap_extend: T,
+    bias: T,
+    /// A column maximum at or above this may have saturated
+    limit: i32,
+}
+
+/// A vector of saturating scores. Only implemented for types whose instructions are supported
+/// by the CPU and only called from functions that enable them.
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+trait Vector: Copy {
+    type Score: Copy + Default + Into<i32>;
+    const LANES: usize;
+
+    unsafe fn splat(score: Self::Score) -> Self;
+    unsafe fn load(ptr: *const Self::Score) -> Self;
+    unsafe fn store(self, ptr: *mut Self::Score);
+    unsafe fn adds(self, other: Self) -> Self;
+    unsafe fn subs(self, other: Self) -> Self;
+    unsafe fn max(self, other: Self) -> Self;
+    /// Moves every score up one lane, shifting in 0
+    unsafe fn shift(self) -> Self;
+    /// Whether any lane of self is greater than the same lane of other
+    unsafe fn any_gt(self, other: Self) -> bool;
+    unsafe fn horizontal_max(self) -> Self::Score;
+}
+
+/// Farrar's striped Smith-Waterman. Returns None if the scores may have saturated.
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+#[inline(always)]
+unsafe fn striped<V: Vector>(
+    profile: &[V::Score],
+    reference: &[u8],
+    params: &Params<V::Score>
+) -> Option<LocalScore> {
+    let lanes = V::LANES;
+    let segment_len = params.query_len.div_ceil(lanes);
+    let column_len = segment_len * lanes;
+
+    let zero = V::splat(V::Score::default());
+    let gap_open = V::splat(params.gap_open);
+    let gap_extend = V::splat(params.gap_extend);
+    let bias = V::splat(params.bias);
+
+    let mut h_load = vec![V::Score::default(); column_len];
+    let mut h_store = vec![V::Score::default(); column_len];
+    let mut e = vec![V::Score::default(); column_len];
+    let mut best_column = vec![V::Score::default(); column_len];
+    let mut best = 0;
+    let mut y_end = 0;
+
+    for (j, &symbol) in reference.iter().enumerate() {
+        let column_profile = profile[symbol as usize * column_len..].as_ptr();
+        let mut f = zero;
+        let mut column_max = zero;
+
+        // The diagonal of the first segment comes from the last segment of the previous column
+        let mut h = V::load(h_store.as_ptr().add(column_len - lanes)).shift();
+        std::mem::swap(&mut h_load, &mut h_store);
+
+        for i in 0..segment_len {
+            let offset = i * lanes;
+            h = h.adds(V::load(column_profile.add(offset))).subs(bias).max(zero);
+
+            let e_i = V::load(e.as_ptr().add(offset));
+            h = h.max(e_i).max(f);
+            column_max = column_max.max(h);
+            h.store(h_store.as_mut_ptr().add(offset));
+
+            let open = h.subs(gap_open);
+            e_i.subs(gap_extend).max(open).store(e.as_mut_ptr().add(offset));
+            f = f.subs(gap_extend).max(open);
+
+            h = V::load(h_load.as_ptr().add(offset));
+        }
+
+        // Lazy F: gaps in the query that cross into the next lane weren't seen by the segments
+        // below them so keep applying them until they can't improve anything
+        f = f.shift();
+        let mut i = 0;
+        let mut passes = 0;
+        loop {
+            let offset = i * lanes;
+            let h = V::load(h_store.as_ptr().add(offset));
+            if !f.any_gt(h.subs(gap_open)) {
+                break;
+            }
+
+            let h = h.max(f);
+            column_max = column_max.max(h);
+            h.store(h_store.as_mut_ptr().add(offset));
+
+            let e_i = V::load(e.as_ptr().add(offset));
+            e_i.max(h.subs(gap_open)).store(e.as_mut_ptr().add(offset));
+            f = f.subs(gap_extend);
+
+            i += 1;
+            if i == segment_len {
+                // Once every lane has been shifted out nothing is left to propagate
+                passes += 1;
+                if passes == lanes {
+                    break;
+                }
+                f = f.shift();
+                i = 0;
+            }
+        }
+
+        let column_best = column_max.horizontal_max().into();
+        if column_best >= params.limit {
+            return None;
+        }
+        if column_best > best {
+            best = column_best;
+            y_end = j + 1;
+            best_column.copy_from_slice(&h_store);
+        }
+    }
+
+    if best == 0 {
+        return Some(LocalScore::default());
+    }
+
+    let x_end = (0..params.query_len)
+        .find(|&position| {
+            let index = (position % segment_len) * lanes + position / segment_len;
+            best_column[index].into() == best
+        })
+        .map_or(0, |position| position + 1);
+
+    Some(LocalScore { score: best, x_end, y_end })
+}
+
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+mod x86 {
+    #[cfg(target_arch = "x86")]
+    use std::arch::x86::*;
+    #[cfg(target_arch = "x86_64")]
+    use std::arch::x86_64::*;
+    use super::{striped, LocalScore, Params, Vector};
+
+    #[target_feature(enable = "sse2")]
+    pub(super) unsafe fn sse2_u8(
+        profile: &[u8],
+        reference: &[u8],
+        params: &Params<u8>
+    ) -> Option<LocalScore> {
+        striped::<Sse2U8>(profile, reference, params)
+    }
+
+    #[target_feature(enable = "sse2")]
+    pub(super) unsafe fn sse2_i16(
+        profile: &[i16],
+        reference: &[u8],
+        params: &Params<i16>
+    ) -> Option<LocalScore> {
+        striped::<Sse2I16>(profile, reference, params)
+    }
+
+    #[target_feature(enable = "avx2")]
+    pub(super) unsafe fn avx2_u8(
+        profile: &[u8],
+        reference: &[u8],
+        params: &Params<u8>
+    ) -> Option<LocalScore> {
+        striped::<Avx2U8>(profile, reference, params)
+    }
+
+    #[target_feature(enable = "avx2")]
+    pub(super) unsafe fn avx2_i16(
+        profile: &[i16],
+        reference: &[u8],
+        params: &Params<i16>
+    ) -> Option<LocalScore> {
+        striped::<Avx2I16>(profile, reference, params)
+    }
+
+    #[derive(Clone, Copy)]
+    struct Sse2U8(__m128i);
+
+    impl Vector for Sse2U8 {
+        type Score = u8;
+        const LANES: usize = 16;
+
+        #[inline(always)]
+        unsafe fn splat(score: u8) -> Self { Sse2U8(_mm_set1_epi8(score as i8)) }
+        #[inline(always)]
+        unsafe fn load(ptr: *const u8) -> Self { Sse2U8(_mm_loadu_si128(ptr as *const __m128i)) }
+        #[inline(always)]
+        unsafe fn store(self, ptr: *mut u8) { _mm_storeu_si128(ptr as *mut __m128i, self.0) }
+        #[inline(always)]
+        unsafe fn adds(self, other: Self) -> Self { Sse2U8(_mm_adds_epu8(self.0, other.0)) }
+        #[inline(always)]
+        unsafe fn subs(self, other: Self) -> Self { Sse2U8(_mm_subs_epu8(self.0, other.0)) }
+        #[inline(always)]
+        unsafe fn max(self, other: Self) -> Self { Sse2U8(_mm_max_epu8(self.0, other.0)) }
+        #[inline(always)]
+        unsafe fn shift(self) -> Self { Sse2U8(_mm_slli_si128::<1>(self.0)) }
+
+        #[inline(always)]
+        unsafe fn any_gt(self, other: Self) -> bool {
+            // There is no unsigned comparison but a saturating difference is only 0 if not greater
+            let difference = _mm_subs_epu8(self.0, other.0);
+            _mm_movemask_epi8(_mm_cmpeq_epi8(difference, _mm_setzero_si128())) != 0xFFFF
+        }
+
+        #[inline(always)]
+        unsafe fn horizontal_max(self) -> u8 {
+            let mut scores = [0; 16];
+            self.store(scores.as_mut_ptr());
+            scores.iter().copied().fold(0, u8::max)
+        }
+    }
+
+    #[derive(Clone, Copy)]
+    struct Sse2I16(__m128i);
+
+    impl Vector for Sse2I16 {
+        type Score = i16;
+        const LANES: usize = 8;
+
+        #[inline(always)]
+        unsafe fn splat(score: i16) -> Self { Sse2I16(_mm_set1_epi16(score)) }
+        #[inline(always)]
+        unsafe fn load(ptr: *const i16) -> Self { Sse2I16(_mm_loadu_si128(ptr as *const __m128i)) }
+        #[inline(always)]
+        unsafe fn store(self, ptr: *mut i16) { _mm_storeu_si128(ptr as *mut __m128i, self.0) }
+        #[inline(always)]
+        unsafe fn adds(self, other: Self) -> Self { Sse2I16(_mm_adds_epi16(self.0, other.0)) }
+        #[inline(always)]
+        unsafe fn subs(self, other: Self) -> Self { Sse2I16(_mm_subs_epi16(self.0, other.0)) }
+        #[inline(always)]
+        unsafe fn max(self, other: Self) -> Self { Sse2I16(_mm_max_epi16(self.0, other.0)) }
+        #[inline(always)]
+        unsafe fn shift(self) -> Self { Sse2I16(_mm_slli_si128::<2>(self.0)) }
+
+        #[inline(always)]
+        unsafe fn any_gt(self, other: Self) -> bool {
+            _mm_movemask_epi8(_mm_cmpgt_epi16(self.0, other.0)) != 0
+        }
+
+        #[inline(always)]
+        unsafe fn horizontal_max(self) -> i16 {
+            let mut scores = [0; 8];
+            self.store(scores.as_mut_ptr());
+            scores.iter().copied().fold(i16::MIN, i16::max)
+        }
+    }
+
+    /// Moves a 256-bit vector up by N bytes across the two 128-bit halves. SHIFT must be 16 - N.
+    #[inline(always)]
+    unsafe fn shift_256<const SHIFT: i32>(vector: __m256i) -> __m256i {
+        // The low half of the permuted vector is zero and the high half is the low half of vector
+        _mm256_alignr_epi8::<SHIFT>(vector, _mm256_permute2x128_si256::<0x08>(vector, vector))
+    }
+
+    #[derive(Clone, Copy)]
+    struct Avx2U8(__m256i);
+
+    impl Vector for Avx2U8 {
+        type Score = u8;
+        const LANES: usize = 32;
+
+        #[inline(always)]
+        unsafe fn splat(score: u8) -> Self { Avx2U8(_mm256_set1_epi8(score as i8)) }
+        #[inline(always)]
+        unsafe fn load(ptr: *const u8) -> Self { Avx2U8(_mm256_loadu_si256(ptr as *const __m256i)) }
+        #[inline(always)]
+        unsafe fn store(self, ptr: *mut u8) { _mm256_storeu_si256(ptr as *mut __m256i, self.0) }
+        #[inline(always)]
+        unsafe fn adds(self, other: Self) -> Self { Avx2U8(_mm256_adds_epu8(self.0, other.0)) }
+        #[inline(always)]
+        unsafe fn subs(self, other: Self) -> Self { Avx2U8(_mm256_subs_epu8(self.0, other.0)) }
+        #[inline(always)]
+        unsafe fn max(self, other: Self) -> Self { Avx2U8(_mm256_max_epu8(self.0, other.0)) }
+        #[inline(always)]
+        unsafe fn shift(self) -> Self { Avx2U8(shift_256::<15>(self.0)) }
+
+        #[inline(always)]
+        unsafe fn any_gt(self, other: Self) -> bool {
+            let difference = _mm256_subs_epu8(self.0, other.0);
+            _mm256_movemask_epi8(_mm256_cmpeq_epi8(difference, _mm256_setzero_si256())) != -1
+        }
+
+        #[inline(always)]
+        unsafe fn horizontal_max(self) -> u8 {
+            let mut scores = [0; 32];
+            self.store(scores.as_mut_ptr());
+            scores.iter().copied().fold(0, u8::max)
+        }
+    }
+
+    #[derive(Clone, Copy)]
+    struct Avx2I16(__m256i);
+
+    impl Vector for Avx2I16 {
+        type Score = i16;
+        const LANES: usize = 16;
+
+        #[inline(always)]
+        unsafe fn splat(score: i16) -> Self { Avx2I16(_mm256_set1_epi16(score)) }
+        #[inline(always)]
+        unsafe fn load(ptr: *const i16) -> Self {
+            Avx2I16(_mm256_loadu_si256(ptr as *const __m256i))
+        }
+        #[inline(always)]
+        unsafe fn store(self, ptr: *mut i16) { _mm256_storeu_si256(ptr as *mut __m256i, self.0) }
+        #[inline(always)]
+        unsafe fn adds(self, other: Self) -> Self { Avx2I16(_mm256_adds_epi16(self.0, other.0)) }
+        #[inline(always)]
+        unsafe fn subs(self, other: Self) -> Self { Avx2I16(_mm256_subs_epi16(self.0, other.0)) }
+        #[inline(always)]
+        unsafe fn max(self, other: Self) -> Self { Avx2I16(_mm256_max_epi16(self.0, other.0)) }
+        #[inline(always)]
+        unsafe fn shift(self) -> Self { Avx2I16(shift_256::<14>(self.0)) }
+
+        #[inline(always)]
+        unsafe fn any_gt(self, other: Self) -> bool {
+            _mm256_movemask_epi8(_mm256_cmpgt_epi16(self.0, other.0)) != 0
+        }
+
+        #[inline(always)]
+        unsafe fn horizontal_max(self) -> i16 {
+            let mut scores = [0; 16];
+            self.store(scores.as_mut_ptr());
+            scores.iter().copied().fold(i16::MIN, i16::max)
+        }
+    }
+}
+
+//================================================================================
+// Tests
+//================================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::align::{Aligner, AlignmentMode};
+    use crate::align::matrix::{BuiltinMatrix, SubstitutionMatrix};
+    use crate::alphabet::ProteinAlphabet;
+    use crate::sequence::test_util::random_bytes;
+
+    /// The backends the current CPU supports
+    fn supported_backends() -> Vec<Backend> {
+        vec![Backend::Scalar, Backend::Sse2, Backend::Avx2]
+            .into_iter()
+            .filter(|backend| backend.is_supported())
+            .collect()
+    }
+
+    /// Checks every backend against the full local alignment
+    fn check<S: SubstitutionScore + Clone>(
+        scoring: &Scoring<S>,
+        alphabet_size: usize,
+        x: &[u8],
+        y: &[u8]
+    ) {
+        let x_indices: Vec<usize> = x.iter().map(|&a| a as usize).collect();
+        let y_indices: Vec<usize> = y.iter().map(|&a| a as usize).collect();
+        let aligner = Aligner::new(scoring.clone());
+        let expected = aligner.align_indices(AlignmentMode::Local, &x_indices, &y_indices).score;
+
+        let striped = StripedAligner::from_indices(x, alphabet_size, scoring);
+        let scalar = striped.clone().with_backend(Backend::Scalar).score_indices(y);
+        assert_eq!(scalar.score, expected);
+
+        for backend in supported_backends() {
+            let result = striped.clone().with_backend(backend).score_indices(y);
+            assert_eq!(result, scalar, "{:?}\nx: {:?}\ny: {:?}", backend, x, y);
+        }
+    }
+
+    /// Tests random protein sequences with BLOSUM62 against the full DP
+    #[test]
+    fn protein() {
+        let alphabet = ProteinAlphabet;
+        let matrix = SubstitutionMatrix::builtin(BuiltinMatrix::Blosum62, &alphabet).unwrap();
+
+        for &(open, extend) in &[(-10, -1), (-5, -2), (0, -4), (-3, 0)] {
+            let scoring = Scoring::new(matrix.clone(), open, extend);
+
+            for seed in 1..30 {
+                let x = random_bytes(seed, (seed * 11 % 90) as usize + 1, 20);
+                let y = random_bytes(seed * 7919, (seed * 17 % 120) as usize, 20);
+                check(&scoring, 20, &x, &y);
+            }
+        }
+    }
+
+    /// Tests related DNA sequences so that alignments cross many lanes and contain long gaps
+    #[test]
+    fn related_dna() {
+        let scoring = Scoring::match_mismatch(2, -3, -5, -2);
+
+        for seed in 1..20 {
+            let y = random_bytes(seed, 150, 4);
+            let mut x = y[20..60].to_vec();
+            x.extend(&y[60 + seed as usize..130]);
+
+            check(&scoring, 4, &x, &y);
+            check(&scoring, 4, &y, &x);
+        }
+    }
+
+    /// Tests that scores too large for 8 and 16 bits fall back to wider scores
+    #[test]
+    fn overflow() {
+        let scoring = Scoring::match_mismatch(20, -4, -10, -1);
+
+        let y = random_bytes(3, 300, 4);
+        check(&scoring, 4, &y, &y);
+
+        let y = random_bytes(5, 1700, 4);
+        let striped = StripedAligner::from_indices(&y, 4, &scoring);
+        for backend in supported_backends() {
+            let result = striped.clone().with_backend(backend).score_indices(&y);
+            assert_eq!(result, LocalScore { score: 34000, x_end: 1700, y_end: 1700 });
+        }
+    }
+
+    /// Tests that no alignment scores 0
+    #[test]
+    fn no_alignment() {
+        let scoring = Scoring::match_mismatch(1, -1, -2, -1);
+        let striped = StripedAligner::from_indices(&[0, 0, 0], 4, &scoring);
+
+        for backend in supported_backends() {
+            let striped = striped.clone().with_backend(backend);
+            assert_eq!(striped.score_indices(&[1, 2, 3, 1]), LocalScore::default());
+            assert_eq!(striped.score_indices(&[]), LocalScore::default());
+        }
+    }
+
+    /// Tests that reference symbols outside the alphabet are rejected
+    #[test]
+    #[should_panic]
+    fn symbol_outside_alphabet() {
+        let scoring = Scoring::match_mismatch(1, -1, -2, -1);
+        StripedAligner::from_indices(&[0, 1], 4, &scoring).score_indices(&[4]);
+    }
+}
@@ -183,6 +183,78 @@ impl fmt::Display for AmbiguousDnaAlphabet {
     }
 }
 
+//================================================================================
+// IUPAC Codes
+//================================================================================
+
+/// Bit representing adenine in the sets returned by [iupac_bases()]
+pub const BASE_A: u8 = 0b0001;
+/// Bit representing cytosine in the sets returned by [iupac_bases()]
+pub const BASE_C: u8 = 0b0010;
+/// Bit representing guanine in the sets returned by [iupac_bases()]
+pub const BASE_G: u8 = 0b0100;
+/// Bit representing thymine in the sets returned by [iupac_bases()]
+pub const BASE_T: u8 = 0b1000;
+
+/// Returns the set of bases that an IUPAC nucleotide symbol stands for as bits made up of
+/// [BASE_A], [BASE_C], [BASE_G] and [BASE_T]. U is treated the same as T.
+/// Returns None if the symbol is not an IUPAC nucleotide code.
+///
+/// # Example
+/// ```
+/// use biors::alphabet::dna::{iupac_bases, BASE_A, BASE_G};
+///
+/// assert_eq!(iupac_bases("R"), Some(BASE_A | BASE_G));
+/// assert_eq!(iupac_bases("X"), None);
+/// ```
+pub fn iupac_bases(symbol: &str) -> Option<u8> {
+    let bases = match symbol {
+        "A" => BASE_A,
+        "C" => BASE_C,
+        "G" => BASE_G,
+        "T" | "U" => BASE_T,
+        "Y" => BASE_C | BASE_T,
+        "R" => BASE_A | BASE_G,
+        "W" => BASE_A | BASE_T,
+        "S" => BASE_G | BASE_C,
+        "K" => BASE_T | BASE_G,
+        "M" => BASE_C | BASE_A,
+        "D" => BASE_A | BASE_G | BASE_T,
+        "V" => BASE_A | BASE_C | BASE_G,
+        "H" => BASE_A | BASE_C | BASE_T,
+        "B" => BASE_C | BASE_G | BASE_T,
+        "N" => BASE_A | BASE_C | BASE_G | BASE_T,
+        _ => return None,
+    };
+
+    Some(bases)
+}
+
+/// Returns the IUPAC symbol that stands for a set of bases, the reverse of [iupac_bases()].
+/// Returns None for the empty set.
+pub fn iupac_symbol(bases: u8) -> Option<&'static str> {
+    let symbol = match bases & 0b1111 {
+        0b0001 => "A",
+        0b0010 => "C",
+        0b0100 => "G",
+        0b1000 => "T",
+        0b1010 => "Y",
+        0b0101 => "R",
+        0b1001 => "W",
+        0b0110 => "S",
+        0b1100 => "K",
+        0b0011 => "M",
+        0b1101 => "D",
+        0b0111 => "V",
+        0b1011 => "H",
+        0b1110 => "B",
+        0b1111 => "N",
+        _ => return None,
+    };
+
+    Some(symbol)
+}
+
 //================================================================================
 // Tests
 //================================================================================
@@ -253,4 +325,30 @@ mod tests {
 
         assert_eq!(comp, a.complement(&seq).as_slice());
     }
+
+    /// Ensures every ambiguous symbol maps to a set of bases and back again
+    #[test]
+    fn iupac_round_trip() {
+        let a = AmbiguousDnaAlphabet;
+
+        for symbol in a.symbols() {
+            let bases = iupac_bases(symbol).unwrap();
+            assert_eq!(iupac_symbol(bases), Some(*symbol));
+        }
+
+        assert_eq!(iupac_symbol(0), None);
+    }
+
+    /// Ensures the base sets of complementary symbols are complementary
+    #[test]
+    fn iupac_complement() {
+        let a = AmbiguousDnaAlphabet;
+        let complement = |bases: u8| {
+            (0..4).filter(|bit| bases & (1 << bit) != 0).map(|bit| 1 << (3 - bit)).sum::<u8>()
+        };
+
+        for (symbol, comp) in a.symbols().iter().zip(a.complement_mapping()) {
+            assert_eq!(complement(iupac_bases(symbol).unwrap()), iupac_bases(comp).unwrap());
+        }
+    }
 }
\ No newline at end of file
@@ -63,6 +63,13 @@ impl<'a, A: Alphabet> AsciiIndexEncoder<'a, A> {
     }
 }
 
+// Implemented by hand so that the alphabet itself doesn't need to be Clone
+impl<'a, A: Alphabet> Clone for AsciiIndexEncoder<'a, A> {
+    fn clone(&self) -> Self {
+        AsciiIndexEncoder { alphabet: self.alphabet, mapping: self.mapping.clone() }
+    }
+}
+
 impl<'a, A: Alphabet> AlphabetEncoder<A> for AsciiIndexEncoder<'a, A> {
     fn encode(&self, symbol: &str) -> Result<Vec<u8>> {
         let res = self.mapping.get_by_left(&symbol);
@@ -97,7 +104,7 @@ impl<'a, A: Alphabet> AlphabetEncoder<A> for AsciiIndexEncoder<'a, A> {
     }
 
     fn decode_all(&self, symbols: &[u8]) -> Result<Vec<&str>> {
-        let iter = symbols.into_iter();
+        let iter = symbols.iter();
 
         // Use size_hint to estimate how much space will be needed to store the result
         let mut decoded = match iter.size_hint() {
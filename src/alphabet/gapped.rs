@@ -0,0 +1,200 @@
+//! Adds gap symbols to an existing alphabet so that aligned sequences can be stored.
+
+pub use super::{Alphabet, Complement};
+use std::fmt;
+use std::sync::OnceLock;
+
+/// Gap symbols for every symbol size up to the longest supported, sliced to the needed size
+const DASHES: &str = "----------------";
+const DOTS: &str = "................";
+
+/// An alphabet made up of the symbols of another alphabet followed by two gap symbols: - and .
+/// Both gap symbols mean the same thing, . is used by some formats (such as Stockholm) for gaps
+/// in columns that are mostly gaps.
+///
+/// The symbols of the wrapped alphabet keep their index so a sequence encoded with the wrapped
+/// alphabet's [AsciiIndexEncoder](crate::alphabet::encoding::index_encoder::AsciiIndexEncoder)
+/// has the same bytes when encoded with the gapped alphabet.
+///
+/// For alphabets with symbols longer than one character the gap symbols are repeated to the
+/// same length, for example -- for an alphabet of two character symbols.
+///
+/// # Example
+/// ```
+/// use biors::alphabet::{Alphabet, Gapped, UnambiguousDnaAlphabet};
+/// use biors::sequence::Sequence;
+///
+/// let dna = UnambiguousDnaAlphabet;
+/// let gapped = Gapped::new(&dna);
+/// assert_eq!(gapped.symbols(), &["A", "C", "T", "G", "-", "."]);
+///
+/// let mut seq = Sequence::new(&gapped);
+/// seq.push("AC--TG").unwrap();
+/// ```
+pub struct Gapped<'a, A: Alphabet> {
+    alphabet: &'a A,
+    symbols: Vec<&'a str>,
+    complement: OnceLock<Vec<&'a str>>,
+}
+
+impl<'a, A: Alphabet> Gapped<'a, A> {
+    /// Wraps an alphabet to add the gap symbols.
+    ///
+    /// # Panics
+    /// If the alphabet already contains a gap symbol or its symbols are longer than 16
+    /// characters.
+    pub fn new(alphabet: &'a A) -> Self {
+        let size = alphabet.symbol_size();
+        assert!(
+            size <= DASHES.len(),
+            "Gaps are only supported for symbols up to 16 characters long."
+        );
+
+        let gaps = [&DASHES[..size], &DOTS[..size]];
+        if let Some(gap) = gaps.iter().find(|gap| alphabet.contains(gap)) {
+            panic!(
+                "Alphabet with symbols {:?} already contains the gap {}.", alphabet.symbols(), gap);
+        }
+
+        let mut symbols = alphabet.symbols().to_vec();
+        symbols.extend(gaps);
+
+        Gapped { alphabet, symbols, complement: OnceLock::new() }
+    }
+
+    /// The alphabet without gaps.
+    pub fn ungapped(&self) -> &'a A {
+        self.alphabet
+    }
+
+    /// The index of the - gap symbol which is the number of symbols in the wrapped alphabet.
+    pub fn gap_index(&self) -> u8 {
+        (self.symbols.len() - 2) as u8
+    }
+
+    /// The - gap symbol.
+    pub fn gap_symbol(&self) -> &str {
+        self.symbols[self.symbols.len() - 2]
+    }
+
+    /// Whether the symbol index is one of the gap symbols.
+    #[inline]
+    pub fn is_gap(&self, index: u8) -> bool {
+        index as usize >= self.symbols.len() - 2
+    }
+}
+
+impl<'a, A: Alphabet> Alphabet for Gapped<'a, A> {
+    #[inline]
+    fn symbols(&self) -> &[&str] {
+        &self.symbols
+    }
+
+    #[inline]
+    fn symbol_size(&self) -> usize {
+        self.alphabet.symbol_size()
+    }
+
+    #[inline]
+    fn max_alphabet_size(&self) -> usize {
+        (self.alphabet.max_alphabet_size() + 2).min(256)
+    }
+}
+
+/// Gaps are their own complement.
+impl<'a, A: Complement> Complement for Gapped<'a, A> {
+    fn complement_mapping(&self) -> &[&str] {
+        self.complement.get_or_init(|| {
+            let mut mapping = self.alphabet.complement_mapping().to_vec();
+            mapping.extend(&self.symbols[self.symbols.len() - 2..]);
+            mapping
+        })
+    }
+}
+
+impl<'a, A: Alphabet> fmt::Debug for Gapped<'a, A> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Gapped").field("symbols", &self.symbols).finish()
+    }
+}
+
+impl<'a, A: Alphabet> fmt::Display for Gapped<'a, A> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Gapped Alphabet containing symbols: {:?}", self.symbols())
+    }
+}
+
+//================================================================================
+// Tests
+//================================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alphabet::{AmbiguousDnaAlphabet, ProteinAlphabet};
+    use crate::sequence::Sequence;
+
+    /// Alphabet with two character symbols
+    struct PairAlphabet;
+
+    impl Alphabet for PairAlphabet {
+        fn symbols(&self) -> &[&str] {
+            &["AA", "BB"]
+        }
+
+        fn symbol_size(&self) -> usize {
+            2
+        }
+    }
+
+    /// Alphabet that already has a gap
+    struct DashAlphabet;
+
+    impl Alphabet for DashAlphabet {
+        fn symbols(&self) -> &[&str] {
+            &["A", "-"]
+        }
+    }
+
+    /// Tests that the gap symbols follow the wrapped symbols
+    #[test]
+    fn symbols() {
+        let protein = ProteinAlphabet;
+        let gapped = Gapped::new(&protein);
+
+        assert_eq!(gapped.symbols().len(), 22);
+        assert_eq!(&gapped.symbols()[..20], protein.symbols());
+        assert_eq!(gapped.gap_index(), 20);
+        assert_eq!(gapped.gap_symbol(), "-");
+        assert!(!gapped.is_gap(19) && gapped.is_gap(20) && gapped.is_gap(21));
+
+        let pairs = PairAlphabet;
+        let gapped = Gapped::new(&pairs);
+        assert_eq!(gapped.symbols(), &["AA", "BB", "--", ".."]);
+
+        let mut seq = Sequence::new(&gapped);
+        seq.push("AA--BB..").unwrap();
+        assert_eq!(seq.as_bytes(), &[0, 2, 1, 3]);
+    }
+
+    /// Tests that the complement of the wrapped alphabet is kept and gaps complement themselves
+    #[test]
+    fn complement() {
+        let dna = AmbiguousDnaAlphabet;
+        let gapped = Gapped::new(&dna);
+
+        assert_eq!(gapped.complement(&["A", "-", "R", "."]), vec!["T", "-", "Y", "."]);
+
+        let mut seq = Sequence::new(&gapped);
+        seq.push("AC-GN.").unwrap();
+        seq.reverse_complement();
+        assert_eq!(seq.to_string(), "Sequence: .NC-GT");
+    }
+
+    /// Tests that wrapping an alphabet that has a gap symbol panics
+    #[test]
+    #[should_panic]
+    fn existing_gap() {
+        Gapped::new(&DashAlphabet);
+    }
+}
@@ -6,9 +6,13 @@
 use std::collections::HashMap;
 
 pub use self::dna::{UnambiguousDnaAlphabet, AmbiguousDnaAlphabet};
+pub use self::gapped::Gapped;
+pub use self::protein::{ProteinAlphabet, ExtendedProteinAlphabet};
 
 pub mod encoding;
 pub mod dna;
+pub mod gapped;
+pub mod protein;
 
 // TODO: Need to decide how I want to handle case sensitivity for now everything is case sensitive
 // TODO: Need to get the documentation links to work
@@ -89,6 +93,24 @@ pub trait Complement: Alphabet {
 
         input.iter().map(|s| *mapping[&s.as_ref()]).collect()
     }
+
+    /// Returns a vector where the element at position i is the index in [Alphabet::symbols()] of
+    /// the complement of the symbol at position i.
+    /// This is the form of the mapping that is useful when working with encoded symbols.
+    ///
+    /// # Panics
+    /// If the [Complement::complement_mapping()] method does not meet the required invariant.
+    fn complement_indices(&self) -> Vec<usize> {
+        let symbols = self.symbols();
+
+        self.complement_mapping().iter()
+            .map(|comp| {
+                symbols.iter()
+                    .position(|s| s == comp)
+                    .unwrap_or_else(|| panic!("Complement {} is not in the alphabet", comp))
+            })
+            .collect()
+    }
 }
 
 //================================================================================
@@ -164,4 +186,12 @@ mod tests {
 
         assert_eq!(seq_comp, a.complement(&seq).as_slice());
     }
+
+    /// Tests that the complement indices point at the complement of each symbol
+    #[test]
+    fn complement_indices() {
+        let a = TestAlphabet;
+
+        assert_eq!(a.complement_indices(), vec![2, 0, 1]);
+    }
 }
\ No newline at end of file
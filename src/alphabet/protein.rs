@@ -0,0 +1,273 @@
+//! Defines alphabets of amino acids for protein sequences
+
+pub use super::Alphabet;
+use std::fmt;
+
+//================================================================================
+// Protein Alphabet
+//================================================================================
+
+/// An alphabet that contains the 20 standard amino acids ARNDCQEGHILKMFPSTWYV.
+///
+/// The symbols are in the same order as the rows of the NCBI substitution matrices.
+/// # Symbol Meaning
+/// <table>
+///   <tr>
+///     <th>Symbol</th>
+///     <th>Meaning</th>
+///   </tr>
+///   <tr>
+///     <td>A</td>
+///     <td>Alanine</td>
+///   </tr>
+///   <tr>
+///     <td>R</td>
+///     <td>Arginine</td>
+///   </tr>
+///   <tr>
+///     <td>N</td>
+///     <td>Asparagine</td>
+///   </tr>
+///   <tr>
+///     <td>D</td>
+///     <td>Aspartic acid</td>
+///   </tr>
+///   <tr>
+///     <td>C</td>
+///     <td>Cysteine</td>
+///   </tr>
+///   <tr>
+///     <td>Q</td>
+///     <td>Glutamine</td>
+///   </tr>
+///   <tr>
+///     <td>E</td>
+///     <td>Glutamic acid</td>
+///   </tr>
+///   <tr>
+///     <td>G</td>
+///     <td>Glycine</td>
+///   </tr>
+///   <tr>
+///     <td>H</td>
+///     <td>Histidine</td>
+///   </tr>
+///   <tr>
+///     <td>I</td>
+///     <td>Isoleucine</td>
+///   </tr>
+///   <tr>
+///     <td>L</td>
+///     <td>Leucine</td>
+///   </tr>
+///   <tr>
+///     <td>K</td>
+///     <td>Lysine</td>
+///   </tr>
+///   <tr>
+///     <td>M</td>
+///     <td>Methionine</td>
+///   </tr>
+///   <tr>
+///     <td>F</td>
+///     <td>Phenylalanine</td>
+///   </tr>
+///   <tr>
+///     <td>P</td>
+///     <td>Proline</td>
+///   </tr>
+///   <tr>
+///     <td>S</td>
+///     <td>Serine</td>
+///   </tr>
+///   <tr>
+///     <td>T</td>
+///     <td>Threonine</td>
+///   </tr>
+///   <tr>
+///     <td>W</td>
+///     <td>Tryptophan</td>
+///   </tr>
+///   <tr>
+///     <td>Y</td>
+///     <td>Tyrosine</td>
+///   </tr>
+///   <tr>
+///     <td>V</td>
+///     <td>Valine</td>
+///   </tr>
+/// </table>
+#[derive(Debug)]
+pub struct ProteinAlphabet;
+
+impl ProteinAlphabet {
+    const SYMBOLS: [&'static str; 20] = [
+        "A", "R", "N", "D", "C", "Q", "E", "G", "H", "I",
+        "L", "K", "M", "F", "P", "S", "T", "W", "Y", "V"
+    ];
+}
+
+impl Alphabet for ProteinAlphabet {
+    #[inline]
+    fn symbols(&self) -> &[&str] {
+        &ProteinAlphabet::SYMBOLS
+    }
+}
+
+impl fmt::Display for ProteinAlphabet {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Protein Alphabet containing symbols: {:?}", self.symbols())
+    }
+}
+
+//================================================================================
+// Extended Protein Alphabet
+//================================================================================
+
+/// An alphabet that contains the 20 standard amino acids along with the ambiguity codes B, Z and
+/// X and * for a stop codon.
+/// # Symbol Meaning
+/// <table>
+///   <tr>
+///     <th>Symbol</th>
+///     <th>Meaning</th>
+///   </tr>
+///   <tr>
+///     <td>A</td>
+///     <td>Alanine</td>
+///   </tr>
+///   <tr>
+///     <td>R</td>
+///     <td>Arginine</td>
+///   </tr>
+///   <tr>
+///     <td>N</td>
+///     <td>Asparagine</td>
+///   </tr>
+///   <tr>
+///     <td>D</td>
+///     <td>Aspartic acid</td>
+///   </tr>
+///   <tr>
+///     <td>C</td>
+///     <td>Cysteine</td>
+///   </tr>
+///   <tr>
+///     <td>Q</td>
+///     <td>Glutamine</td>
+///   </tr>
+///   <tr>
+///     <td>E</td>
+///     <td>Glutamic acid</td>
+///   </tr>
+///   <tr>
+///     <td>G</td>
+///     <td>Glycine</td>
+///   </tr>
+///   <tr>
+///     <td>H</td>
+///     <td>Histidine</td>
+///   </tr>
+///   <tr>
+///     <td>I</td>
+///     <td>Isoleucine</td>
+///   </tr>
+///   <tr>
+///     <td>L</td>
+///     <td>Leucine</td>
+///   </tr>
+///   <tr>
+///     <td>K</td>
+///     <td>Lysine</td>
+///   </tr>
+///   <tr>
+///     <td>M</td>
+///     <td>Methionine</td>
+///   </tr>
+///   <tr>
+///     <td>F</td>
+///     <td>Phenylalanine</td>
+///   </tr>
+///   <tr>
+///     <td>P</td>
+///     <td>Proline</td>
+///   </tr>
+///   <tr>
+///     <td>S</td>
+///     <td>Serine</td>
+///   </tr>
+///   <tr>
+///     <td>T</td>
+///     <td>Threonine</td>
+///   </tr>
+///   <tr>
+///     <td>W</td>
+///     <td>Tryptophan</td>
+///   </tr>
+///   <tr>
+///     <td>Y</td>
+///     <td>Tyrosine</td>
+///   </tr>
+///   <tr>
+///     <td>V</td>
+///     <td>Valine</td>
+///   </tr>
+///   <tr>
+///     <td>B</td>
+///     <td>Asparagine or aspartic acid</td>
+///   </tr>
+///   <tr>
+///     <td>Z</td>
+///     <td>Glutamine or glutamic acid</td>
+///   </tr>
+///   <tr>
+///     <td>X</td>
+///     <td>Any amino acid</td>
+///   </tr>
+///   <tr>
+///     <td>*</td>
+///     <td>Stop</td>
+///   </tr>
+/// </table>
+#[derive(Debug)]
+pub struct ExtendedProteinAlphabet;
+
+impl ExtendedProteinAlphabet {
+    const SYMBOLS: [&'static str; 24] = [
+        "A", "R", "N", "D", "C", "Q", "E", "G", "H", "I", "L", "K",
+        "M", "F", "P", "S", "T", "W", "Y", "V", "B", "Z", "X", "*"
+    ];
+}
+
+impl Alphabet for ExtendedProteinAlphabet {
+    #[inline]
+    fn symbols(&self) -> &[&str] {
+        &ExtendedProteinAlphabet::SYMBOLS
+    }
+}
+
+impl fmt::Display for ExtendedProteinAlphabet {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Extended Protein Alphabet containing symbols: {:?}", self.symbols())
+    }
+}
+
+//================================================================================
+// Tests
+//================================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tests that the extended alphabet starts with the standard amino acids in the same order
+    #[test]
+    fn extended_contains_standard() {
+        let standard = ProteinAlphabet;
+        let extended = ExtendedProteinAlphabet;
+
+        assert_eq!(&extended.symbols()[..20], standard.symbols());
+        assert!(extended.is_word(&["B", "Z", "X", "*"]));
+        assert!(!standard.contains("X"));
+    }
+}
@@ -0,0 +1,287 @@
+//! Genetic codes that map each codon to an amino acid.
+//!
+//! Codes are given in the form used by the NCBI, as a string of 64 amino acids and a string of
+//! 64 start markers with the codons in the order TTT, TTC, TTA, TTG, TCT, ... GGG, where the
+//! bases are ordered T, C, A, G:
+//!
+//! ```text
+//! AAs    = FFLLSSSSYY**CC*WLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG
+//! Starts = ---M---------------M---------------M----------------------------
+//! ```
+//!
+//! An * is a stop codon and an M in the starts is a codon that can start translation.
+
+use super::{codon_index, CodonError, ErrorKind, Result, CODONS};
+
+/// The NCBI number, name, amino acids and starts of each of the built in genetic codes
+const NCBI_CODES: [(usize, &str, &str, &str); 8] = [
+    (
+        1,
+        "Standard",
+        "FFLLSSSSYY**CC*WLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+        "---M---------------M---------------M----------------------------",
+    ),
+    (
+        2,
+        "Vertebrate Mitochondrial",
+        "FFLLSSSSYY**CCWWLLLLPPPPHHQQRRRRIIMMTTTTNNKKSS**VVVVAAAADDEEGGGG",
+        "--------------------------------MMMM---------------M------------",
+    ),
+    (
+        3,
+        "Yeast Mitochondrial",
+        "FFLLSSSSYY**CCWWTTTTPPPPHHQQRRRRIIMMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+        "----------------------------------MM----------------------------",
+    ),
+    (
+        4,
+        "Mold, Protozoan, and Coelenterate Mitochondrial and Mycoplasma/Spiroplasma",
+        "FFLLSSSSYY**CCWWLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+        "--MM---------------M------------MMMM---------------M------------",
+    ),
+    (
+        5,
+        "Invertebrate Mitochondrial",
+        "FFLLSSSSYY**CCWWLLLLPPPPHHQQRRRRIIMMTTTTNNKKSSSSVVVVAAAADDEEGGGG",
+        "---M----------------------------MMMM---------------M------------",
+    ),
+    (
+        6,
+        "Ciliate, Dasycladacean and Hexamita Nuclear",
+        "FFLLSSSSYYQQCC*WLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+        "-----------------------------------M----------------------------",
+    ),
+    (
+        9,
+        "Echinoderm and Flatworm Mitochondrial",
+        "FFLLSSSSYY**CCWWLLLLPPPPHHQQRRRRIIIMTTTTNNNKSSSSVVVVAAAADDEEGGGG",
+        "-----------------------------------M---------------M------------",
+    ),
+    (
+        11,
+        "Bacterial, Archaeal and Plant Plastid",
+        "FFLLSSSSYY**CC*WLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+        "---M---------------M------------MMMM---------------M------------",
+    ),
+];
+
+/// A genetic code that translates each of the 64 codons into an amino acid or a stop.
+///
+/// # Example
+/// ```
+/// use biors::codon::GeneticCode;
+///
+/// let standard = GeneticCode::standard();
+/// assert_eq!(standard.amino_acid("TGA"), Some('*'));
+/// assert_eq!(standard.synonymous_codons('I'), vec!["ATT", "ATC", "ATA"]);
+///
+/// let mitochondrial = GeneticCode::vertebrate_mitochondrial();
+/// assert_eq!(mitochondrial.amino_acid("TGA"), Some('W'));
+/// assert_eq!(mitochondrial.synonymous_codons('I'), vec!["ATT", "ATC"]);
+/// assert!(mitochondrial.is_stop("AGA"));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct GeneticCode {
+    name: String,
+    amino_acids: Vec<char>,
+    starts: Vec<bool>,
+}
+
+impl GeneticCode {
+    /// Constructs a genetic code from strings of 64 amino acids and 64 start markers in the NCBI
+    /// form described in the [module](self) documentation. The amino acids are upper case
+    /// letters or * for a stop and the starts are M for a start codon or - (or *) otherwise.
+    ///
+    /// # Errors
+    /// If either string isn't 64 characters long or has a character that isn't allowed.
+    pub fn new(name: &str, amino_acids: &str, starts: &str) -> Result<Self> {
+        let invalid =
+            |description: String| Err(CodonError::new(ErrorKind::InvalidCode, description));
+
+        if amino_acids.chars().count() != CODONS || starts.chars().count() != CODONS {
+            let description =
+                format!("The amino acids and starts of {} must both have 64 codons", name);
+            return invalid(description);
+        }
+        if let Some(c) = amino_acids.chars().find(|&c| !(c.is_ascii_uppercase() || c == '*')) {
+            let description =
+                format!("{:?} in the amino acids of {} isn't an amino acid or *", c, name);
+            return invalid(description);
+        }
+        if let Some(c) = starts.chars().find(|&c| !matches!(c, 'M' | '-' | '*')) {
+            return invalid(format!("{:?} in the starts of {} isn't M, - or *", c, name));
+        }
+
+        Ok(GeneticCode {
+            name: name.to_owned(),
+            amino_acids: amino_acids.chars().collect(),
+            starts: starts.chars().map(|c| c == 'M').collect(),
+        })
+    }
+
+    /// The genetic code with the NCBI translation table number, if it's one of the built in
+    /// codes: 1 to 6, 9 and 11.
+    pub fn ncbi(id: usize) -> Option<Self> {
+        NCBI_CODES.iter()
+            .find(|(number, _, _, _)| *number == id)
+            .map(|(_, name, amino_acids, starts)| {
+                GeneticCode::new(name, amino_acids, starts)
+                    .expect("The built in codes must be valid")
+            })
+    }
+
+    /// The standard code (NCBI table 1)
+    pub fn standard() -> Self {
+        GeneticCode::ncbi(1).unwrap()
+    }
+
+    /// The vertebrate mitochondrial code (NCBI table 2), where AGA and AGG are stops, ATA is
+    /// methionine and TGA is tryptophan
+    pub fn vertebrate_mitochondrial() -> Self {
+        GeneticCode::ncbi(2).unwrap()
+    }
+
+    /// The yeast mitochondrial code (NCBI table 3), where CTN is threonine
+    pub fn yeast_mitochondrial() -> Self {
+        GeneticCode::ncbi(3).unwrap()
+    }
+
+    /// The invertebrate mitochondrial code (NCBI table 5), where AGA and AGG are serine
+    pub fn invertebrate_mitochondrial() -> Self {
+        GeneticCode::ncbi(5).unwrap()
+    }
+
+    /// The bacterial, archaeal and plant plastid code (NCBI table 11), which has the amino
+    /// acids of the standard code but more start codons
+    pub fn bacterial() -> Self {
+        GeneticCode::ncbi(11).unwrap()
+    }
+
+    /// The name of the code
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The amino acid a codon of T (or U), C, A and G codes for, which is * for a stop. Returns
+    /// None if it isn't a codon.
+    pub fn amino_acid(&self, codon: &str) -> Option<char> {
+        codon_index(codon).map(|index| self.amino_acids[index])
+    }
+
+    /// Returns true if the codon is a stop codon
+    pub fn is_stop(&self, codon: &str) -> bool {
+        self.amino_acid(codon) == Some('*')
+    }
+
+    /// Returns true if translation can start at the codon
+    pub fn is_start(&self, codon: &str) -> bool {
+        codon_index(codon).is_some_and(|index| self.starts[index])
+    }
+
+    /// The codons that code for the amino acid, or the stop codons for *, written with T
+    pub fn synonymous_codons(&self, amino_acid: char) -> Vec<String> {
+        (0..CODONS)
+            .filter(|&index| self.amino_acids[index] == amino_acid)
+            .map(super::codon)
+            .collect()
+    }
+
+    /// The number of codons that aren't stops
+    pub fn sense_codons(&self) -> usize {
+        self.amino_acids.iter().filter(|&&amino_acid| amino_acid != '*').count()
+    }
+
+    /// The amino acid of the codon with the index
+    pub(crate) fn amino_acid_at(&self, index: usize) -> char {
+        self.amino_acids[index]
+    }
+
+    /// The indices of the codons of each amino acid in the order the amino acids first appear,
+    /// not including the stops
+    pub(crate) fn families(&self) -> Vec<Vec<usize>> {
+        let mut amino_acids: Vec<char> = vec![];
+        let mut families: Vec<Vec<usize>> = vec![];
+
+        let coding = self.amino_acids.iter().enumerate().filter(|(_, &aa)| aa != '*');
+        for (index, &amino_acid) in coding {
+            match amino_acids.iter().position(|&other| other == amino_acid) {
+                Some(position) => families[position].push(index),
+                None => {
+                    amino_acids.push(amino_acid);
+                    families.push(vec![index]);
+                }
+            }
+        }
+
+        families
+    }
+}
+
+impl Default for GeneticCode {
+    /// The standard code
+    fn default() -> Self {
+        GeneticCode::standard()
+    }
+}
+
+//================================================================================
+// Tests
+//================================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tests the standard code and its start codons
+    #[test]
+    fn standard() {
+        let code = GeneticCode::standard();
+        assert_eq!(code.name(), "Standard");
+        assert_eq!(code.amino_acid("ATG"), Some('M'));
+        assert_eq!(code.amino_acid("AUG"), Some('M'));
+        assert_eq!(code.amino_acid("GGN"), None);
+        assert_eq!(code.amino_acid("AT"), None);
+        assert!(code.is_start("ATG") && code.is_start("TTG") && !code.is_start("ATT"));
+        assert_eq!(code.synonymous_codons('*'), vec!["TAA", "TAG", "TGA"]);
+        assert_eq!(code.synonymous_codons('L').len(), 6);
+        assert_eq!(code.sense_codons(), 61);
+
+        let families = code.families();
+        assert_eq!(families.len(), 20);
+        assert_eq!(families.iter().map(Vec::len).sum::<usize>(), 61);
+    }
+
+    /// Tests that the mitochondrial codes change the families of synonymous codons
+    #[test]
+    fn mitochondrial() {
+        let vertebrate = GeneticCode::vertebrate_mitochondrial();
+        assert_eq!(vertebrate, GeneticCode::ncbi(2).unwrap());
+        assert_eq!(vertebrate.sense_codons(), 60);
+        assert_eq!(vertebrate.synonymous_codons('R').len(), 4);
+        assert_eq!(vertebrate.synonymous_codons('M'), vec!["ATA", "ATG"]);
+
+        let yeast = GeneticCode::yeast_mitochondrial();
+        assert_eq!(yeast.synonymous_codons('T').len(), 8);
+        assert_eq!(yeast.synonymous_codons('L').len(), 2);
+        assert_eq!(yeast.families().len(), 20);
+
+        let invertebrate = GeneticCode::invertebrate_mitochondrial();
+        assert_eq!(invertebrate.synonymous_codons('S').len(), 8);
+
+        assert!(GeneticCode::bacterial().is_start("GTG"));
+        assert!(GeneticCode::ncbi(7).is_none());
+    }
+
+    /// Tests that invalid codes are rejected
+    #[test]
+    fn invalid() {
+        let amino_acids = "FFLLSSSSYY**CC*WLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG";
+        let starts = "---M---------------M---------------M----------------------------";
+        assert!(GeneticCode::new("Copy", amino_acids, starts).is_ok());
+
+        let err = GeneticCode::new("Short", &amino_acids[1..], starts).unwrap_err();
+        assert_eq!(err.kind(), &ErrorKind::InvalidCode);
+        assert!(GeneticCode::new("Lower", &amino_acids.to_lowercase(), starts).is_err());
+        assert!(GeneticCode::new("Starts", amino_acids, &starts.replace('M', "X")).is_err());
+    }
+}
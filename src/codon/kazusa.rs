@@ -0,0 +1,194 @@
+//! The codon usage table format of the Kazusa codon usage database.
+//!
+//! ```text
+//! Escherichia coli K12 [gbbct]: 5 CDS's (1608 codons)
+//!
+//! UUU 24.4(    39)  UCU 13.1(    21)  UAU 21.8(    35)  UGU  5.0(     8)
+//! UUC 13.1(    21)  UCC  9.3(    15)  UAC 14.3(    23)  UGC  3.1(     5)
+//! ...
+//! ```
+//!
+//! Each codon is followed by the number of times it's used per thousand codons and the number of
+//! times it's used in parentheses. Lines that don't start with a codon, such as the header, are
+//! skipped. The form with the amino acid and the fraction of its codons before the frequency,
+//! such as `UUU F 0.57 22.1 (1118)`, is also read.
+
+use super::{codon, codon_index, CodonError, CodonUsage, ErrorKind, Result, CODONS};
+
+/// Reads a codon usage table. Codons that aren't in the table aren't used.
+///
+/// # Errors
+/// If a codon doesn't have a count in parentheses or appears more than once.
+///
+/// # Example
+/// ```
+/// use biors::codon::kazusa;
+///
+/// let text = "UUU 17.6(714298)  UCU 15.2(618711)  UAU 12.2(495699)  UGU 10.6(430311)\n";
+/// let usage = kazusa::read(text).unwrap();
+/// assert_eq!(usage.count("TTT"), 714298);
+/// assert_eq!(usage.count("GGG"), 0);
+/// ```
+pub fn read(text: &str) -> Result<CodonUsage> {
+    let mut counts = [None; CODONS];
+
+    for (line_number, line) in text.lines().enumerate() {
+        let error = |description: String| {
+            let description = format!("Line {}: {}", line_number + 1, description);
+            Err(CodonError::new(ErrorKind::Parse, description))
+        };
+
+        // Counts can be padded inside their parentheses so the spaces there are taken out
+        let mut normalised = String::new();
+        let mut in_count = false;
+        for c in line.chars() {
+            match c {
+                '(' => {
+                    normalised.push_str(" (");
+                    in_count = true;
+                }
+                ')' => {
+                    normalised.push(')');
+                    in_count = false;
+                }
+                c if in_count && c.is_whitespace() => {}
+                c => normalised.push(c),
+            }
+        }
+
+        let tokens: Vec<&str> = normalised.split_whitespace().collect();
+        if tokens.first().is_none_or(|token| codon_index(token).is_none()) {
+            continue;
+        }
+
+        let mut position = 0;
+        while position < tokens.len() {
+            let codon = tokens[position];
+            let index = match codon_index(codon) {
+                Some(index) => index,
+                None => return error(format!("Expected a codon but found {:?}", codon)),
+            };
+
+            let fields: Vec<&str> = tokens[position + 1..]
+                .iter()
+                .take_while(|token| codon_index(token).is_none())
+                .copied()
+                .collect();
+            position += fields.len() + 1;
+
+            let count = fields.iter()
+                .find_map(|field| {
+                    field.strip_prefix('(').and_then(|field| field.strip_suffix(')'))
+                });
+            let count = match count.map(str::parse::<usize>) {
+                Some(Ok(count)) => count,
+                _ => return error(format!("{} doesn't have a count in parentheses", codon)),
+            };
+
+            if counts[index].is_some() {
+                return error(format!("{} appears more than once", codon));
+            }
+            counts[index] = Some(count);
+        }
+    }
+
+    let mut usage = CodonUsage::new();
+    for (index, count) in counts.iter().enumerate() {
+        if let Some(count) = count {
+            usage.add(&codon(index), *count);
+        }
+    }
+
+    Ok(usage)
+}
+
+/// Writes codon usage as a table with U rather than T, which can be read back by [read()].
+///
+/// The codons with the same first base are in a block of four lines, each with the codons that
+/// share a third base, in the order used by the database.
+pub fn write(usage: &CodonUsage) -> String {
+    let mut text = String::new();
+
+    for first in 0..4 {
+        if first > 0 {
+            text.push('\n');
+        }
+        for third in 0..4 {
+            let entries: Vec<String> = (0..4)
+                .map(|second| {
+                    let codon = codon(first * 16 + second * 4 + third);
+                    let frequency = usage.per_thousand(&codon);
+                    let count = usage.count(&codon);
+                    format!("{} {:4.1}({:6})", codon.replace('T', "U"), frequency, count)
+                })
+                .collect();
+            text.push_str(&entries.join("  "));
+            text.push('\n');
+        }
+    }
+
+    text
+}
+
+//================================================================================
+// Tests
+//================================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tests reading a table with a header, padded counts and each form of entry
+    #[test]
+    fn read_table() {
+        let text = "Homo sapiens [gbpri]: 93487 CDS's (40662582 codons)
+
+fields: [triplet] [frequency: per thousand] ([number])
+
+UUU 17.6(714298)  UCU 15.2(618711)  UAU 12.2(495699)  UGU 10.6(430311)
+UAA  1.0( 40285)  UGA  1.6( 63237)
+AUG F 0.46 22.0 ( 893123)
+
+Coding GC 52.27% 1st letter GC 55.72% 2nd letter GC 42.54% 3rd letter GC 58.55%
+";
+        let usage = read(text).unwrap();
+        assert_eq!(usage.count("TTT"), 714298);
+        assert_eq!(usage.count("TAA"), 40285);
+        assert_eq!(usage.count("ATG"), 893123);
+        assert_eq!(usage.total(), 714298 + 618711 + 495699 + 430311 + 40285 + 63237 + 893123);
+    }
+
+    /// Tests that tables are written in the order of the database and can be read back
+    #[test]
+    fn round_trip() {
+        let mut usage = CodonUsage::new();
+        usage.add("TTT", 3);
+        usage.add("TGT", 1);
+        usage.add("GGG", 996);
+
+        let text = write(&usage);
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), 19);
+        assert_eq!(
+            lines[0],
+            "UUU  3.0(     3)  UCU  0.0(     0)  UAU  0.0(     0)  UGU  1.0(     1)"
+        );
+        assert_eq!(lines[4], "");
+        assert_eq!(
+            lines[18],
+            "GUG  0.0(     0)  GCG  0.0(     0)  GAG  0.0(     0)  GGG 996.0(   996)"
+        );
+        assert_eq!(read(&text).unwrap(), usage);
+    }
+
+    /// Tests that codons without counts or repeated codons are rejected
+    #[test]
+    fn invalid() {
+        let err = read("UUU 17.6  UCU 15.2(618711)\n").unwrap_err();
+        assert_eq!(err.kind(), &ErrorKind::Parse);
+        assert!(err.description().starts_with("Line 1"));
+
+        assert!(read("UUU 17.6(1)\nTTT 17.6(1)\n").is_err());
+        assert!(read("UUU 17.6(x)\n").is_err());
+    }
+}
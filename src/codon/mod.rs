@@ -0,0 +1,567 @@
+//! Codon usage of coding DNA sequences and the measures of codon bias built on it.
+//!
+//! [CodonUsage] counts the codons of one or more coding sequences read in frame from their
+//! first base. From the counts it gives the relative synonymous codon usage (RSCU) of each
+//! codon, the effective number of codons (ENC) of Wright (1990), the GC content at the third
+//! position of codons and the codon adaptation index (CAI) of Sharp and Li (1987) against the
+//! usage of a reference set of genes, such as highly expressed ones.
+//!
+//! Which codons are synonymous depends on the [GeneticCode], so every measure that groups codons
+//! by amino acid takes one. Codon usage tables can be read from and written to the format of the
+//! [kazusa] codon usage database.
+//!
+//! Codons are written with the bases T (or U), C, A and G, and the symbols of a sequence are
+//! recognised by name so any DNA alphabet can be used.
+//!
+//! # Example
+//! ```
+//! use biors::alphabet::UnambiguousDnaAlphabet;
+//! use biors::codon::{CodonUsage, GeneticCode};
+//! use biors::sequence::Sequence;
+//!
+//! let dna = UnambiguousDnaAlphabet;
+//! let mut gene = Sequence::new(&dna);
+//! gene.push("ATGAAAAAAAAGCTGCTGTTATAA").unwrap();
+//!
+//! let code = GeneticCode::standard();
+//! let usage = CodonUsage::from_sequence(&gene);
+//! assert_eq!(usage.total(), 8);
+//! assert_eq!(usage.count("AAA"), 2);
+//! assert!((usage.rscu("AAA", &code).unwrap() - 4.0 / 3.0).abs() < 1e-9);
+//! assert!((usage.rscu("CTG", &code).unwrap() - 4.0).abs() < 1e-9);
+//! assert_eq!(usage.gc3(), 0.5);
+//! ```
+
+use std::error::Error;
+use std::fmt;
+use crate::alphabet::Alphabet;
+use crate::sequence::Sequence;
+
+pub use self::genetic_code::GeneticCode;
+
+pub mod genetic_code;
+pub mod kazusa;
+
+/// Result type for genetic codes and codon usage tables
+pub type Result<T> = std::result::Result<T, CodonError>;
+
+/// The number of codons
+pub const CODONS: usize = 64;
+
+/// The bases in the order used to number codons
+const BASES: [char; 4] = ['T', 'C', 'A', 'G'];
+
+/// The count given to a codon that isn't used by the reference when calculating its relative
+/// adaptiveness, as suggested by Sharp and Li (1987)
+const MISSING_CODON_COUNT: f64 = 0.5;
+
+/// The position of a base in the order T, C, A, G, where U is the same as T
+fn base_code(base: &str) -> Option<usize> {
+    match base {
+        "T" | "U" => Some(0),
+        "C" => Some(1),
+        "A" => Some(2),
+        "G" => Some(3),
+        _ => None,
+    }
+}
+
+/// The number of a codon, which orders codons by their first base, then their second and then
+/// their third, with the bases ordered T, C, A, G. Returns None if the codon isn't three of the
+/// bases T, U, C, A and G.
+///
+/// # Example
+/// ```
+/// use biors::codon;
+///
+/// assert_eq!(codon::codon_index("TTT"), Some(0));
+/// assert_eq!(codon::codon_index("AUG"), Some(35));
+/// assert_eq!(codon::codon(35), "ATG");
+/// assert_eq!(codon::codon_index("ATN"), None);
+/// ```
+pub fn codon_index(codon: &str) -> Option<usize> {
+    if codon.len() != 3 || !codon.is_ascii() {
+        return None;
+    }
+
+    (0..3).try_fold(0, |index, i| Some(index * 4 + base_code(&codon[i..=i])?))
+}
+
+/// The codon with the number, written with T. See [codon_index()].
+///
+/// # Panics
+/// If the index isn't less than [CODONS].
+pub fn codon(index: usize) -> String {
+    assert!(index < CODONS, "There are only 64 codons");
+    [index / 16, index / 4 % 4, index % 4].iter().map(|&base| BASES[base]).collect()
+}
+
+/// Returns true if the codon with the number ends in G or C
+fn is_gc3(index: usize) -> bool {
+    matches!(BASES[index % 4], 'G' | 'C')
+}
+
+//================================================================================
+// Codon Usage
+//================================================================================
+
+/// The number of times each codon is used by a set of coding sequences.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CodonUsage {
+    counts: [usize; CODONS],
+}
+
+impl CodonUsage {
+    /// Constructs codon usage where every count is 0
+    pub fn new() -> Self {
+        CodonUsage { counts: [0; CODONS] }
+    }
+
+    /// Constructs codon usage from the counts of each codon in the order of [codon_index()]
+    pub fn from_counts(counts: [usize; CODONS]) -> Self {
+        CodonUsage { counts }
+    }
+
+    /// Counts the codons of a coding sequence. See [CodonUsage::add_sequence()].
+    pub fn from_sequence<A: Alphabet>(seq: &Sequence<A>) -> Self {
+        let mut usage = CodonUsage::new();
+        usage.add_sequence(seq);
+        usage
+    }
+
+    /// Adds the codons of a coding sequence to the counts.
+    ///
+    /// # Notes
+    /// The sequence is read in frame from its first base and any bases left over at the end are
+    /// ignored, as are codons with a symbol other than T, U, C, A or G. Stop codons are counted
+    /// like any other codon.
+    pub fn add_sequence<A: Alphabet>(&mut self, seq: &Sequence<A>) {
+        let symbols = seq.alphabet().symbols();
+
+        for codon in seq.as_bytes().chunks_exact(3) {
+            let index = codon.iter().try_fold(0, |index, &byte| {
+                Some(index * 4 + base_code(symbols[byte as usize])?)
+            });
+            if let Some(index) = index {
+                self.counts[index] += 1;
+            }
+        }
+    }
+
+    /// Adds a number of uses of a codon.
+    ///
+    /// # Panics
+    /// If the codon isn't three of the bases T, U, C, A and G.
+    pub fn add(&mut self, codon: &str, count: usize) {
+        let index = codon_index(codon).unwrap_or_else(|| panic!("{:?} isn't a codon", codon));
+        self.counts[index] += count;
+    }
+
+    /// The number of times the codon is used, which is 0 if it isn't a codon
+    pub fn count(&self, codon: &str) -> usize {
+        codon_index(codon).map_or(0, |index| self.counts[index])
+    }
+
+    /// The counts of every codon in the order of [codon_index()]
+    pub fn counts(&self) -> &[usize; CODONS] {
+        &self.counts
+    }
+
+    /// The number of codons counted
+    pub fn total(&self) -> usize {
+        self.counts.iter().sum()
+    }
+
+    /// The number of times the codon is used per thousand codons. Returns 0 if no codons have
+    /// been counted.
+    pub fn per_thousand(&self, codon: &str) -> f64 {
+        match self.total() {
+            0 => 0.0,
+            total => 1000.0 * self.count(codon) as f64 / total as f64,
+        }
+    }
+
+    /// The relative synonymous codon usage of the codon, which is how many times it's used
+    /// divided by how many times it would be used if every codon of its amino acid were used
+    /// equally. A codon that is used as often as expected has an RSCU of 1.
+    ///
+    /// Returns None if it isn't a codon or none of the codons of its amino acid are used. The
+    /// stop codons are treated as a family of their own.
+    pub fn rscu(&self, codon: &str, code: &GeneticCode) -> Option<f64> {
+        let index = codon_index(codon)?;
+        let amino_acid = code.amino_acid_at(index);
+        let family: Vec<usize> = (0..CODONS)
+            .filter(|&other| code.amino_acid_at(other) == amino_acid)
+            .collect();
+        let used: usize = family.iter().map(|&other| self.counts[other]).sum();
+
+        if used == 0 {
+            return None;
+        }
+        Some(self.counts[index] as f64 * family.len() as f64 / used as f64)
+    }
+
+    /// The relative adaptiveness of the codon in this usage as a reference, which is how many
+    /// times it's used divided by how many times the most used codon of its amino acid is.
+    /// A codon that isn't used counts as being used half a time.
+    ///
+    /// Returns None if it isn't a codon, is a stop, is the only codon of its amino acid or none
+    /// of the codons of its amino acid are used.
+    pub fn relative_adaptiveness(&self, codon: &str, code: &GeneticCode) -> Option<f64> {
+        let index = codon_index(codon)?;
+        self.weights(code)[index]
+    }
+
+    /// The relative adaptiveness of every codon
+    fn weights(&self, code: &GeneticCode) -> [Option<f64>; CODONS] {
+        let mut weights = [None; CODONS];
+
+        for family in code.families().iter().filter(|family| family.len() > 1) {
+            let most = family.iter().map(|&index| self.counts[index]).max().unwrap_or(0);
+            if most == 0 {
+                continue;
+            }
+            for &index in family {
+                let count = match self.counts[index] {
+                    0 => MISSING_CODON_COUNT,
+                    count => count as f64,
+                };
+                weights[index] = Some(count / most as f64);
+            }
+        }
+
+        weights
+    }
+
+    /// The codon adaptation index of the codons counted here against the reference usage, which
+    /// is the geometric mean of the [relative adaptiveness](CodonUsage::relative_adaptiveness())
+    /// of every codon in the reference. A gene that only uses the codons the reference uses most
+    /// has a CAI of 1.
+    ///
+    /// Returns None if none of the codons counted here have a relative adaptiveness.
+    ///
+    /// # Example
+    /// ```
+    /// use biors::alphabet::UnambiguousDnaAlphabet;
+    /// use biors::codon::{CodonUsage, GeneticCode};
+    /// use biors::sequence::Sequence;
+    ///
+    /// let code = GeneticCode::standard();
+    /// let mut reference = CodonUsage::new();
+    /// reference.add("AAA", 30);
+    /// reference.add("AAG", 10);
+    ///
+    /// let dna = UnambiguousDnaAlphabet;
+    /// let mut gene = Sequence::new(&dna);
+    /// gene.push("ATGAAAAAG").unwrap();
+    ///
+    /// // ATG is the only codon of methionine so it's left out
+    /// let cai = CodonUsage::from_sequence(&gene).cai(&reference, &code).unwrap();
+    /// assert!((cai - (1.0f64 / 3.0).sqrt()).abs() < 1e-9);
+    /// ```
+    pub fn cai(&self, reference: &CodonUsage, code: &GeneticCode) -> Option<f64> {
+        let weights = reference.weights(code);
+        let mut log_sum = 0.0;
+        let mut codons = 0;
+
+        for (&count, weight) in self.counts.iter().zip(weights.iter()) {
+            if let (Some(weight), true) = (weight, count > 0) {
+                log_sum += count as f64 * weight.ln();
+                codons += count;
+            }
+        }
+
+        if codons == 0 {
+            return None;
+        }
+        Some((log_sum / codons as f64).exp())
+    }
+
+    /// The effective number of codons of Wright (1990), which goes from the number of amino
+    /// acids when each only uses one of its codons to the number of sense codons when every
+    /// codon of an amino acid is used equally.
+    ///
+    /// The homozygosity F of every amino acid used at least twice is averaged over the amino
+    /// acids with the same number of codons, and the ENC is the number of amino acids with one
+    /// codon plus, for each size of family, the number of amino acids with that many codons
+    /// divided by their average F.
+    ///
+    /// # Notes
+    /// If no amino acid with three codons is used the average F of the amino acids with two and
+    /// four codons is used for them, as suggested by Wright. If a size of family is missing
+    /// otherwise the average over all the other sizes is used instead. The ENC is never more
+    /// than the number of sense codons.
+    ///
+    /// Returns None if no amino acid with more than one codon is used at least twice.
+    pub fn enc(&self, code: &GeneticCode) -> Option<f64> {
+        let families = code.families();
+        let max_size = families.iter().map(Vec::len).max().unwrap_or(0);
+
+        // The number of amino acids and the sum and number of values of F for each family size
+        let mut amino_acids = vec![0usize; max_size + 1];
+        let mut homozygosity = vec![(0.0, 0usize); max_size + 1];
+
+        for family in &families {
+            let size = family.len();
+            amino_acids[size] += 1;
+
+            let used: usize = family.iter().map(|&index| self.counts[index]).sum();
+            if size > 1 && used > 1 {
+                let n = used as f64;
+                let squares: f64 = family.iter()
+                    .map(|&index| (self.counts[index] as f64 / n).powi(2))
+                    .sum();
+                homozygosity[size].0 += (n * squares - 1.0) / (n - 1.0);
+                homozygosity[size].1 += 1;
+            }
+        }
+
+        let average = |size: usize| match homozygosity[size] {
+            (_, 0) => None,
+            (sum, count) => Some(sum / count as f64),
+        };
+        let known: Vec<f64> = (2..=max_size).filter_map(average).collect();
+        if known.is_empty() {
+            return None;
+        }
+        let overall = known.iter().sum::<f64>() / known.len() as f64;
+
+        let mut enc = amino_acids.get(1).copied().unwrap_or(0) as f64;
+        for size in (2..=max_size).filter(|&size| amino_acids[size] > 0) {
+            let f = match (average(size), size) {
+                (Some(f), _) => f,
+                (None, 3) => match (average(2), average(4)) {
+                    (Some(f2), Some(f4)) => (f2 + f4) / 2.0,
+                    _ => overall,
+                },
+                (None, _) => overall,
+            };
+            enc += amino_acids[size] as f64 / f;
+        }
+
+        Some(enc.min(code.sense_codons() as f64))
+    }
+
+    /// The fraction of codons that end in G or C. Returns 0 if no codons have been counted.
+    pub fn gc3(&self) -> f64 {
+        gc_fraction(self.counts.iter().enumerate())
+    }
+
+    /// The fraction of synonymous codons that end in G or C, which leaves out stops and the
+    /// amino acids with a single codon, such as methionine and tryptophan in the standard code.
+    /// Returns 0 if no synonymous codons have been counted.
+    pub fn gc3s(&self, code: &GeneticCode) -> f64 {
+        let synonymous: Vec<usize> = code.families()
+            .into_iter()
+            .filter(|family| family.len() > 1)
+            .flatten()
+            .collect();
+        gc_fraction(self.counts.iter().enumerate().filter(|(index, _)| synonymous.contains(index)))
+    }
+}
+
+impl Default for CodonUsage {
+    fn default() -> Self {
+        CodonUsage::new()
+    }
+}
+
+/// The fraction of the counts of codons that end in G or C
+fn gc_fraction<'a, I: Iterator<Item = (usize, &'a usize)>>(counts: I) -> f64 {
+    let (gc, total) = counts.fold((0, 0), |(gc, total), (index, &count)| {
+        (gc + if is_gc3(index) { count } else { 0 }, total + count)
+    });
+
+    if total == 0 { 0.0 } else { gc as f64 / total as f64 }
+}
+
+//================================================================================
+// Errors
+//================================================================================
+
+/// Represents the kind of error that occurred while building a genetic code or reading a codon
+/// usage table.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ErrorKind {
+    /// A genetic code doesn't have an amino acid and a start marker for every codon
+    InvalidCode,
+
+    /// A codon usage table could not be parsed
+    Parse,
+}
+
+/// The type of error returned when a genetic code can't be built or a codon usage table can't be
+/// read
+#[derive(Debug, Clone)]
+pub struct CodonError {
+    kind: ErrorKind,
+    description: String,
+}
+
+impl CodonError {
+    /// Construct a new CodonError from the given ErrorKind and description
+    pub fn new(kind: ErrorKind, description: String) -> CodonError {
+        CodonError { kind, description }
+    }
+
+    /// Get the associated ErrorKind for this error
+    pub fn kind(&self) -> &ErrorKind {
+        &self.kind
+    }
+
+    /// Get the associated description for this error
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+}
+
+impl Error for CodonError {}
+
+impl fmt::Display for CodonError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Codon error: {:?}:\n\t{}", self.kind, self.description)
+    }
+}
+
+//================================================================================
+// Tests
+//================================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alphabet::{AmbiguousDnaAlphabet, UnambiguousDnaAlphabet};
+
+    /// Tests numbering codons and back
+    #[test]
+    fn codon_numbers() {
+        for index in 0..CODONS {
+            assert_eq!(codon_index(&codon(index)), Some(index));
+        }
+        assert_eq!(codon(63), "GGG");
+        assert_eq!(codon_index("UGA"), codon_index("TGA"));
+        assert_eq!(codon_index("atg"), None);
+        assert_eq!(codon_index("ATGA"), None);
+    }
+
+    /// Tests counting the codons of sequences with ambiguous symbols and a partial codon
+    #[test]
+    fn counting() {
+        let alphabet = AmbiguousDnaAlphabet;
+        let mut seq = Sequence::new(&alphabet);
+        seq.push("ATGNCGGCGTAAGC").unwrap();
+
+        let mut usage = CodonUsage::from_sequence(&seq);
+        assert_eq!(usage.total(), 3);
+        assert_eq!((usage.count("ATG"), usage.count("GCG"), usage.count("TAA")), (1, 1, 1));
+        assert_eq!(usage.count("NCG"), 0);
+
+        usage.add_sequence(&seq);
+        usage.add("GCT", 2);
+        assert_eq!(usage.total(), 8);
+        assert_eq!(usage.per_thousand("GCG"), 250.0);
+        assert_eq!(CodonUsage::new().per_thousand("GCG"), 0.0);
+        assert_eq!(CodonUsage::from_counts(*usage.counts()), usage);
+    }
+
+    /// Tests RSCU with the standard and vertebrate mitochondrial codes
+    #[test]
+    fn rscu() {
+        let mut usage = CodonUsage::new();
+        usage.add("AGA", 3);
+        usage.add("CGT", 1);
+
+        let standard = GeneticCode::standard();
+        assert_eq!(usage.rscu("AGA", &standard), Some(4.5));
+        assert_eq!(usage.rscu("CGC", &standard), Some(0.0));
+        assert_eq!(usage.rscu("GGG", &standard), None);
+        assert_eq!(usage.rscu("NNN", &standard), None);
+
+        // AGA is a stop in vertebrate mitochondria so arginine only has four codons
+        let mitochondrial = GeneticCode::vertebrate_mitochondrial();
+        assert_eq!(usage.rscu("CGT", &mitochondrial), Some(4.0));
+        assert_eq!(usage.rscu("AGA", &mitochondrial), Some(4.0));
+    }
+
+    /// Tests the relative adaptiveness and CAI
+    #[test]
+    fn cai() {
+        let code = GeneticCode::standard();
+        let mut reference = CodonUsage::new();
+        reference.add("CTG", 40);
+        reference.add("CTT", 10);
+        reference.add("TGG", 20);
+        reference.add("AAA", 5);
+
+        assert_eq!(reference.relative_adaptiveness("CTG", &code), Some(1.0));
+        assert_eq!(reference.relative_adaptiveness("CTT", &code), Some(0.25));
+        assert_eq!(reference.relative_adaptiveness("TTA", &code), Some(0.5 / 40.0));
+        assert_eq!(reference.relative_adaptiveness("TGG", &code), None);
+        assert_eq!(reference.relative_adaptiveness("TAA", &code), None);
+        assert_eq!(reference.relative_adaptiveness("GGG", &code), None);
+
+        let mut gene = CodonUsage::new();
+        gene.add("CTG", 2);
+        gene.add("TGG", 5);
+        gene.add("GGG", 5);
+        assert_eq!(gene.cai(&reference, &code), Some(1.0));
+
+        gene.add("CTT", 1);
+        gene.add("AAG", 1);
+        let expected = (0.25f64 * 0.1).powf(0.25);
+        assert!((gene.cai(&reference, &code).unwrap() - expected).abs() < 1e-9);
+
+        assert_eq!(CodonUsage::new().cai(&reference, &code), None);
+    }
+
+    /// Tests the ENC at its extremes and with amino acids missing
+    #[test]
+    fn enc() {
+        let code = GeneticCode::standard();
+        let families = code.families();
+
+        // Each amino acid only uses its first codon
+        let mut usage = CodonUsage::new();
+        for family in &families {
+            usage.add(&codon(family[0]), 10);
+        }
+        assert!((usage.enc(&code).unwrap() - 20.0).abs() < 1e-9);
+
+        // Every codon is used equally
+        let mut usage = CodonUsage::new();
+        for family in &families {
+            for &index in family {
+                usage.add(&codon(index), 100);
+            }
+        }
+        assert_eq!(usage.enc(&code), Some(61.0));
+
+        // Isoleucine is missing so its F is the average of the two and four codon families
+        let mut usage = CodonUsage::new();
+        for family in families.iter().filter(|family| family.len() > 1 && family.len() != 3) {
+            usage.add(&codon(family[0]), 10);
+            usage.add(&codon(family[1]), 10);
+        }
+        let f = (20.0 * 0.5 - 1.0) / 19.0;
+        let expected = 2.0 + 9.0 / f + 1.0 / f + 5.0 / f + 3.0 / f;
+        assert!((usage.enc(&code).unwrap() - expected).abs() < 1e-9);
+
+        let mut usage = CodonUsage::new();
+        usage.add("ATG", 10);
+        usage.add("AAA", 1);
+        assert_eq!(usage.enc(&code), None);
+    }
+
+    /// Tests GC3 with and without the codons that aren't synonymous
+    #[test]
+    fn gc3() {
+        let alphabet = UnambiguousDnaAlphabet;
+        let mut seq = Sequence::new(&alphabet);
+        seq.push("ATGTGGCTGCTAAAGTAG").unwrap();
+
+        let usage = CodonUsage::from_sequence(&seq);
+        assert_eq!(usage.gc3(), 5.0 / 6.0);
+        assert_eq!(usage.gc3s(&GeneticCode::standard()), 2.0 / 3.0);
+        assert_eq!(CodonUsage::new().gc3(), 0.0);
+    }
+}
@@ -0,0 +1,694 @@
+//! Distances between pairs of [Sequence](crate::sequence::Sequence)s.
+//!
+//! The edit distances ([hamming], [levenshtein] and [damerau_levenshtein]) compare the encoded
+//! symbols directly so every symbol, including gaps and ambiguous symbols, is treated as a
+//! symbol in its own right. N only matches N, never A.
+//!
+//! The evolutionary distances estimate the number of substitutions per site between two aligned
+//! sequences of the same length. They recognise symbols by name, like the
+//! [stats](crate::stats) module, so they work with any alphabet that uses the usual symbols.
+//! Sites are compared using pairwise deletion:
+//! - A site where either sequence has a gap (- or .) is skipped by every evolutionary distance.
+//! - The nucleotide distances only compare sites where both symbols are A, C, G, T or U (U is
+//!   treated as T). Sites with an ambiguous IUPAC symbol from the
+//!   [AmbiguousDnaAlphabet](crate::alphabet::AmbiguousDnaAlphabet), such as N or R, are skipped.
+//! - The protein distances only compare sites where both symbols are one of the 20 standard
+//!   amino acids so B, Z, X and * are skipped.
+//! - [p_distance] works with any alphabet and compares every symbol other than a gap literally.
+
+use std::error::Error;
+use std::fmt;
+use crate::alphabet::Alphabet;
+use crate::sequence::Sequence;
+
+/// Result type for evolutionary distances
+pub type Result<T> = std::result::Result<T, DistanceError>;
+
+/// The symbols that mark a gap in an aligned sequence
+const GAP_SYMBOLS: [&str; 2] = ["-", "."];
+
+/// The 20 standard amino acids
+const AMINO_ACIDS: [&str; 20] = [
+    "A", "R", "N", "D", "C", "Q", "E", "G", "H", "I",
+    "L", "K", "M", "F", "P", "S", "T", "W", "Y", "V"
+];
+
+//================================================================================
+// Edit Distances
+//================================================================================
+
+/// The number of positions at which two sequences of the same length have different symbols.
+///
+/// # Errors
+/// If the sequences have different lengths.
+///
+/// # Example
+/// ```
+/// use biors::alphabet::UnambiguousDnaAlphabet;
+/// use biors::distance;
+/// use biors::sequence::Sequence;
+///
+/// let alphabet = UnambiguousDnaAlphabet;
+/// let mut x = Sequence::new(&alphabet);
+/// let mut y = Sequence::new(&alphabet);
+/// x.push("ACGTACGT").unwrap();
+/// y.push("ACCTACGA").unwrap();
+///
+/// assert_eq!(distance::hamming(&x, &y).unwrap(), 2);
+/// ```
+pub fn hamming<A: Alphabet>(x: &Sequence<A>, y: &Sequence<A>) -> Result<usize> {
+    check_lengths(x, y)?;
+
+    Ok(x.as_bytes().iter()
+        .zip(y.as_bytes())
+        .filter(|(a, b)| a != b)
+        .count())
+}
+
+/// The minimum number of single symbol insertions, deletions and substitutions needed to turn x
+/// into y.
+///
+/// # Notes
+/// Takes O(nm) time and O(m) memory.
+///
+/// # Example
+/// ```
+/// use biors::alphabet::UnambiguousDnaAlphabet;
+/// use biors::distance;
+/// use biors::sequence::Sequence;
+///
+/// let alphabet = UnambiguousDnaAlphabet;
+/// let mut x = Sequence::new(&alphabet);
+/// let mut y = Sequence::new(&alphabet);
+/// x.push("GATTACA").unwrap();
+/// y.push("GCATGCT").unwrap();
+///
+/// assert_eq!(distance::levenshtein(&x, &y), 4);
+/// ```
+pub fn levenshtein<A: Alphabet>(x: &Sequence<A>, y: &Sequence<A>) -> usize {
+    let (x, y) = (x.as_bytes(), y.as_bytes());
+    let mut previous: Vec<usize> = (0..=y.len()).collect();
+    let mut current = vec![0; y.len() + 1];
+
+    for (i, &a) in x.iter().enumerate() {
+        current[0] = i + 1;
+        for (j, &b) in y.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a != b);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+
+    previous[y.len()]
+}
+
+/// The Levenshtein distance where swapping two adjacent symbols also counts as a single edit.
+///
+/// This is the unrestricted Damerau-Levenshtein distance so symbols can be edited again after
+/// being transposed, for example CA becomes AGC with 2 edits (CA to AC to AGC).
+///
+/// # Notes
+/// Takes O(nm) time and memory.
+///
+/// # Example
+/// ```
+/// use biors::alphabet::UnambiguousDnaAlphabet;
+/// use biors::distance;
+/// use biors::sequence::Sequence;
+///
+/// let alphabet = UnambiguousDnaAlphabet;
+/// let mut x = Sequence::new(&alphabet);
+/// let mut y = Sequence::new(&alphabet);
+/// x.push("ACGT").unwrap();
+/// y.push("CAGT").unwrap();
+///
+/// assert_eq!(distance::damerau_levenshtein(&x, &y), 1);
+/// assert_eq!(distance::levenshtein(&x, &y), 2);
+/// ```
+pub fn damerau_levenshtein<A: Alphabet>(x: &Sequence<A>, y: &Sequence<A>) -> usize {
+    let (x, y) = (x.as_bytes(), y.as_bytes());
+    let (n, m) = (x.len(), y.len());
+    let infinity = n + m;
+    let width = m + 2;
+
+    // Row and column 0 hold infinity so that transpositions can't start before the sequences
+    let mut d = vec![infinity; (n + 2) * width];
+    for i in 0..=n {
+        d[(i + 1) * width + 1] = i;
+    }
+    for j in 0..=m {
+        d[width + j + 1] = j;
+    }
+
+    // The last row each symbol of x was seen in
+    let mut last_row = [0; 256];
+
+    for i in 1..=n {
+        // The last column in this row where x matched y
+        let mut last_match_column = 0;
+
+        for j in 1..=m {
+            let k = last_row[y[j - 1] as usize];
+            let l = last_match_column;
+            let cost = if x[i - 1] == y[j - 1] {
+                last_match_column = j;
+                0
+            } else {
+                1
+            };
+
+            let substitution = d[i * width + j] + cost;
+            let insertion = d[(i + 1) * width + j] + 1;
+            let deletion = d[i * width + j + 1] + 1;
+            let transposition = d[k * width + l] + (i - k - 1) + 1 + (j - l - 1);
+
+            d[(i + 1) * width + j + 1] =
+                substitution.min(insertion).min(deletion).min(transposition);
+        }
+
+        last_row[x[i - 1] as usize] = i;
+    }
+
+    d[(n + 1) * width + m + 1]
+}
+
+//================================================================================
+// Evolutionary Distances
+//================================================================================
+
+/// The proportion of compared sites at which two aligned sequences differ. Sites with a gap in
+/// either sequence are skipped and all other symbols are compared literally.
+///
+/// # Errors
+/// If the sequences have different lengths or there are no sites without a gap.
+pub fn p_distance<A: Alphabet>(x: &Sequence<A>, y: &Sequence<A>) -> Result<f64> {
+    check_lengths(x, y)?;
+
+    let x_gaps = gap_table(x.alphabet());
+    let y_gaps = gap_table(y.alphabet());
+    let (x_symbols, y_symbols) = (x.alphabet().symbols(), y.alphabet().symbols());
+
+    let mut sites = 0;
+    let mut differences = 0;
+    for (&a, &b) in x.as_bytes().iter().zip(y.as_bytes()) {
+        if !x_gaps[a as usize] && !y_gaps[b as usize] {
+            sites += 1;
+            differences += usize::from(x_symbols[a as usize] != y_symbols[b as usize]);
+        }
+    }
+
+    proportion(differences, sites)
+}
+
+/// The Jukes-Cantor (1969) distance between two aligned nucleotide sequences. Assumes every
+/// substitution is equally likely and every base is equally frequent.
+///
+/// d = -3/4 ln(1 - 4p/3)
+///
+/// # Errors
+/// If the sequences have different lengths, have no sites where both are unambiguous bases or
+/// are too different for the distance to be defined (p ≥ 0.75).
+///
+/// # Example
+/// ```
+/// use biors::alphabet::AmbiguousDnaAlphabet;
+/// use biors::distance;
+/// use biors::sequence::Sequence;
+///
+/// let alphabet = AmbiguousDnaAlphabet;
+/// let mut x = Sequence::new(&alphabet);
+/// let mut y = Sequence::new(&alphabet);
+/// x.push("ACGTACGTACNN").unwrap();
+/// y.push("ACGTACGTAGCA").unwrap();
+///
+/// // The last two sites are skipped so 1 of 10 sites differ
+/// let d = distance::jukes_cantor(&x, &y).unwrap();
+/// assert!((d - 0.107326).abs() < 1e-6);
+/// ```
+pub fn jukes_cantor<A: Alphabet>(x: &Sequence<A>, y: &Sequence<A>) -> Result<f64> {
+    NucleotideSites::new(x, y)?.jukes_cantor()
+}
+
+/// The Kimura two-parameter (1980) distance between two aligned nucleotide sequences. Allows
+/// transitions and transversions to occur at different rates.
+///
+/// # Errors
+/// If the sequences have different lengths, have no sites where both are unambiguous bases or
+/// are too different for the distance to be defined.
+pub fn kimura_2p<A: Alphabet>(x: &Sequence<A>, y: &Sequence<A>) -> Result<f64> {
+    NucleotideSites::new(x, y)?.kimura_2p()
+}
+
+/// The Tamura-Nei (1993) distance between two aligned nucleotide sequences. Allows unequal base
+/// frequencies and different rates for purine transitions, pyrimidine transitions and
+/// transversions.
+///
+/// # Errors
+/// If the sequences have different lengths, have no sites where both are unambiguous bases or
+/// are too different for the distance to be defined.
+pub fn tamura_nei<A: Alphabet>(x: &Sequence<A>, y: &Sequence<A>) -> Result<f64> {
+    NucleotideSites::new(x, y)?.tamura_nei()
+}
+
+/// The Poisson corrected distance between two aligned protein sequences.
+///
+/// d = -ln(1 - p)
+///
+/// # Errors
+/// If the sequences have different lengths, have no sites where both are standard amino acids
+/// or every compared site differs.
+pub fn poisson<A: Alphabet>(x: &Sequence<A>, y: &Sequence<A>) -> Result<f64> {
+    let p = protein_p_distance(x, y)?;
+    corrected_log(1.0 - p, 1.0, "Poisson")
+}
+
+/// Kimura's (1983) empirical distance between two aligned protein sequences.
+///
+/// d = -ln(1 - p - 0.2p²)
+///
+/// # Errors
+/// If the sequences have different lengths, have no sites where both are standard amino acids
+/// or are too different for the distance to be defined (p ≥ 0.854).
+pub fn kimura_protein<A: Alphabet>(x: &Sequence<A>, y: &Sequence<A>) -> Result<f64> {
+    let p = protein_p_distance(x, y)?;
+    corrected_log(1.0 - p - 0.2 * p * p, 1.0, "Kimura protein")
+}
+
+/// Counts of the kinds of differences between two aligned nucleotide sequences, from which every
+/// nucleotide distance can be calculated without comparing the sequences again.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct NucleotideSites {
+    /// The number of sites where both sequences have A, C, G or T
+    pub sites: usize,
+    /// The number of compared sites with an A and a G
+    pub purine_transitions: usize,
+    /// The number of compared sites with a C and a T
+    pub pyrimidine_transitions: usize,
+    /// The number of compared sites with a purine and a pyrimidine
+    pub transversions: usize,
+    /// The number of A, C, G and T symbols, in that order, at the compared sites of both
+    /// sequences
+    pub base_counts: [usize; 4],
+}
+
+impl NucleotideSites {
+    /// Compares two aligned nucleotide sequences, skipping sites where either has a gap or an
+    /// ambiguous symbol.
+    ///
+    /// # Errors
+    /// If the sequences have different lengths.
+    pub fn new<A: Alphabet>(x: &Sequence<A>, y: &Sequence<A>) -> Result<Self> {
+        check_lengths(x, y)?;
+
+        let x_bases = base_table(x.alphabet());
+        let y_bases = base_table(y.alphabet());
+        let mut counts = NucleotideSites::default();
+
+        for (&a, &b) in x.as_bytes().iter().zip(y.as_bytes()) {
+            let (a, b) = match (x_bases[a as usize], y_bases[b as usize]) {
+                (Some(a), Some(b)) => (a, b),
+                _ => continue,
+            };
+
+            counts.sites += 1;
+            counts.base_counts[a] += 1;
+            counts.base_counts[b] += 1;
+
+            // A = 0, C = 1, G = 2, T = 3 so purines are even
+            match (a, b) {
+                _ if a == b => {}
+                (0, 2) | (2, 0) => counts.purine_transitions += 1,
+                (1, 3) | (3, 1) => counts.pyrimidine_transitions += 1,
+                _ => counts.transversions += 1,
+            }
+        }
+
+        Ok(counts)
+    }
+
+    /// The proportion of compared sites that differ.
+    ///
+    /// # Errors
+    /// If no sites were compared.
+    pub fn p_distance(&self) -> Result<f64> {
+        let differences =
+            self.purine_transitions + self.pyrimidine_transitions + self.transversions;
+        proportion(differences, self.sites)
+    }
+
+    /// The Jukes-Cantor distance. See [jukes_cantor()].
+    ///
+    /// # Errors
+    /// If no sites were compared or p ≥ 0.75.
+    pub fn jukes_cantor(&self) -> Result<f64> {
+        let p = self.p_distance()?;
+        corrected_log(1.0 - 4.0 * p / 3.0, 0.75, "Jukes-Cantor")
+    }
+
+    /// The Kimura two-parameter distance. See [kimura_2p()].
+    ///
+    /// d = -1/2 ln(1 - 2P - Q) - 1/4 ln(1 - 2Q)
+    ///
+    /// Where P is the proportion of transitions and Q the proportion of transversions.
+    ///
+    /// # Errors
+    /// If no sites were compared or either logarithm is undefined.
+    pub fn kimura_2p(&self) -> Result<f64> {
+        let (p, q) = self.proportions()?;
+        let p = p.0 + p.1;
+
+        Ok(corrected_log(1.0 - 2.0 * p - q, 0.5, "Kimura two-parameter")?
+            + corrected_log(1.0 - 2.0 * q, 0.25, "Kimura two-parameter")?)
+    }
+
+    /// The Tamura-Nei distance. See [tamura_nei()].
+    ///
+    /// Base frequencies are estimated from the compared sites of both sequences. If a base never
+    /// occurs then its transitions can't occur either and their term is left out.
+    ///
+    /// # Errors
+    /// If no sites were compared or any logarithm is undefined.
+    pub fn tamura_nei(&self) -> Result<f64> {
+        let ((p1, p2), q) = self.proportions()?;
+
+        let total = (2 * self.sites) as f64;
+        let [a, c, g, t] = self.base_counts.map(|count| count as f64 / total);
+        let purines = a + g;
+        let pyrimidines = c + t;
+
+        let mut distance = 0.0;
+        if a * g > 0.0 {
+            let argument = 1.0 - purines * p1 / (2.0 * a * g) - q / (2.0 * purines);
+            distance += corrected_log(argument, 2.0 * a * g / purines, "Tamura-Nei")?;
+        }
+        if c * t > 0.0 {
+            let argument = 1.0 - pyrimidines * p2 / (2.0 * c * t) - q / (2.0 * pyrimidines);
+            distance += corrected_log(argument, 2.0 * c * t / pyrimidines, "Tamura-Nei")?;
+        }
+        if purines * pyrimidines > 0.0 {
+            let weight = 2.0 * (purines * pyrimidines
+                - a * g * pyrimidines / purines
+                - c * t * purines / pyrimidines);
+            let argument = 1.0 - q / (2.0 * purines * pyrimidines);
+            distance += corrected_log(argument, weight, "Tamura-Nei")?;
+        }
+
+        Ok(distance)
+    }
+
+    /// The proportions of purine and pyrimidine transitions and of transversions
+    fn proportions(&self) -> Result<((f64, f64), f64)> {
+        if self.sites == 0 {
+            return Err(no_sites());
+        }
+
+        let sites = self.sites as f64;
+        Ok((
+            (self.purine_transitions as f64 / sites, self.pyrimidine_transitions as f64 / sites),
+            self.transversions as f64 / sites
+        ))
+    }
+}
+
+//================================================================================
+// Errors
+//================================================================================
+
+/// Represents the kind of error that occurred while calculating a distance.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorKind {
+    /// The sequences must be aligned but have different lengths
+    LengthMismatch,
+
+    /// There were no sites that could be compared
+    NoSites,
+
+    /// The sequences are too different for the corrected distance to be defined
+    Saturated,
+}
+
+/// The type of error returned when a distance cannot be calculated
+#[derive(Debug, Clone)]
+pub struct DistanceError {
+    kind: ErrorKind,
+    description: String,
+}
+
+impl DistanceError {
+    /// Construct a new DistanceError from the given ErrorKind and description
+    pub fn new(kind: ErrorKind, description: String) -> DistanceError {
+        DistanceError { kind, description }
+    }
+
+    /// Get the associated ErrorKind for this error
+    pub fn kind(&self) -> ErrorKind {
+        self.kind
+    }
+
+    /// Get the associated description for this error
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+}
+
+impl Error for DistanceError {}
+
+impl fmt::Display for DistanceError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Distance error: {:?}:\n\t{}", self.kind, self.description)
+    }
+}
+
+//================================================================================
+// Utility Functions
+//================================================================================
+
+fn check_lengths<A: Alphabet>(x: &Sequence<A>, y: &Sequence<A>) -> Result<()> {
+    if x.len() == y.len() {
+        Ok(())
+    } else {
+        let description = format!("The sequences have lengths {} and {}", x.len(), y.len());
+        Err(DistanceError::new(ErrorKind::LengthMismatch, description))
+    }
+}
+
+fn no_sites() -> DistanceError {
+    let description = "The sequences have no sites that can be compared".to_owned();
+    DistanceError::new(ErrorKind::NoSites, description)
+}
+
+fn proportion(differences: usize, sites: usize) -> Result<f64> {
+    if sites == 0 {
+        Err(no_sites())
+    } else {
+        Ok(differences as f64 / sites as f64)
+    }
+}
+
+/// Returns -weight * ln(argument) or an error if the logarithm is undefined
+fn corrected_log(argument: f64, weight: f64, name: &str) -> Result<f64> {
+    if argument > 0.0 {
+        Ok(-weight * argument.ln())
+    } else {
+        let description = format!("The sequences are too different for the {} distance", name);
+        Err(DistanceError::new(ErrorKind::Saturated, description))
+    }
+}
+
+/// Whether each symbol index of the alphabet is a gap
+fn gap_table<A: Alphabet>(alphabet: &A) -> Vec<bool> {
+    alphabet.symbols().iter().map(|symbol| GAP_SYMBOLS.contains(symbol)).collect()
+}
+
+/// Maps each symbol index of the alphabet to 0 for A, 1 for C, 2 for G and 3 for T or U
+fn base_table<A: Alphabet>(alphabet: &A) -> Vec<Option<usize>> {
+    alphabet.symbols().iter()
+        .map(|&symbol| match symbol {
+            "A" => Some(0),
+            "C" => Some(1),
+            "G" => Some(2),
+            "T" | "U" => Some(3),
+            _ => None,
+        })
+        .collect()
+}
+
+/// The proportion of sites with two standard amino acids that differ
+fn protein_p_distance<A: Alphabet>(x: &Sequence<A>, y: &Sequence<A>) -> Result<f64> {
+    check_lengths(x, y)?;
+
+    let standard = |alphabet: &A| -> Vec<bool> {
+        alphabet.symbols().iter().map(|symbol| AMINO_ACIDS.contains(symbol)).collect()
+    };
+    let x_standard = standard(x.alphabet());
+    let y_standard = standard(y.alphabet());
+    let (x_symbols, y_symbols) = (x.alphabet().symbols(), y.alphabet().symbols());
+
+    let mut sites = 0;
+    let mut differences = 0;
+    for (&a, &b) in x.as_bytes().iter().zip(y.as_bytes()) {
+        if x_standard[a as usize] && y_standard[b as usize] {
+            sites += 1;
+            differences += usize::from(x_symbols[a as usize] != y_symbols[b as usize]);
+        }
+    }
+
+    proportion(differences, sites)
+}
+
+//================================================================================
+// Tests
+//================================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alphabet::{
+        AmbiguousDnaAlphabet, ExtendedProteinAlphabet, ProteinAlphabet, UnambiguousDnaAlphabet
+    };
+    use crate::sequence::test_util::{dna, random_dna, sequence};
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!((actual - expected).abs() < 1e-9, "{} != {}", actual, expected);
+    }
+
+    /// Tests the edit distances on small examples
+    #[test]
+    fn edit_distances() {
+        let alphabet = UnambiguousDnaAlphabet;
+
+        assert_eq!(hamming(&dna(&alphabet, "ACGT"), &dna(&alphabet, "ACGT")).unwrap(), 0);
+        assert_eq!(hamming(&dna(&alphabet, "ACGT"), &dna(&alphabet, "TGCA")).unwrap(), 4);
+        let err = hamming(&dna(&alphabet, "ACG"), &dna(&alphabet, "ACGT")).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::LengthMismatch);
+
+        assert_eq!(levenshtein(&dna(&alphabet, ""), &dna(&alphabet, "ACG")), 3);
+        assert_eq!(levenshtein(&dna(&alphabet, "ACGT"), &dna(&alphabet, "")), 4);
+        assert_eq!(levenshtein(&dna(&alphabet, "ACGTTGCA"), &dna(&alphabet, "ACGTGCA")), 1);
+        assert_eq!(levenshtein(&dna(&alphabet, "CA"), &dna(&alphabet, "AGC")), 3);
+
+        assert_eq!(damerau_levenshtein(&dna(&alphabet, ""), &dna(&alphabet, "ACG")), 3);
+        assert_eq!(damerau_levenshtein(&dna(&alphabet, "CA"), &dna(&alphabet, "AGC")), 2);
+        assert_eq!(damerau_levenshtein(&dna(&alphabet, "ACGTAC"), &dna(&alphabet, "CAGTCA")), 2);
+        assert_eq!(damerau_levenshtein(&dna(&alphabet, "ACGTTGCA"), &dna(&alphabet, "ACGTGCA")), 1);
+    }
+
+    /// Tests that Damerau-Levenshtein is never more than Levenshtein or less than half of it
+    #[test]
+    fn damerau_bounds() {
+        let alphabet = UnambiguousDnaAlphabet;
+
+        for len in 0..30 {
+            let x = random_dna(&alphabet, 2 * len as u64 + 7, len);
+            let y = random_dna(&alphabet, 2 * len as u64 + 8, len + len % 3);
+            let (lev, dam) = (levenshtein(&x, &y), damerau_levenshtein(&x, &y));
+            assert!(dam <= lev && 2 * dam >= lev);
+            assert_eq!(levenshtein(&y, &x), lev);
+            assert_eq!(damerau_levenshtein(&y, &x), dam);
+        }
+    }
+
+    /// Tests that gaps and ambiguous symbols are skipped by the nucleotide distances
+    #[test]
+    fn nucleotide_sites() {
+        let alphabet = AmbiguousDnaAlphabet;
+        let x = sequence(&alphabet, "AACGTTNRCA");
+        let y = sequence(&alphabet, "AGCTTCAAYA");
+        let sites = NucleotideSites::new(&x, &y).unwrap();
+
+        assert_eq!(sites.sites, 7);
+        assert_eq!(sites.purine_transitions, 1);
+        assert_eq!(sites.pyrimidine_transitions, 1);
+        assert_eq!(sites.transversions, 1);
+        assert_eq!(sites.base_counts, [5, 3, 2, 4]);
+        assert_close(sites.p_distance().unwrap(), 3.0 / 7.0);
+
+        let n = sequence(&alphabet, "NNNN");
+        let err = jukes_cantor(&n, &n).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::NoSites);
+    }
+
+    /// Tests the nucleotide distances against values calculated by hand
+    #[test]
+    fn nucleotide_distances() {
+        let alphabet = UnambiguousDnaAlphabet;
+        let x = dna(&alphabet, &"ACGT".repeat(10));
+
+        // One purine transition, one pyrimidine transition and two transversions in 40 sites
+        let mut changed = "ACGT".repeat(10).into_bytes();
+        changed[0] = b'G';
+        changed[5] = b'T';
+        changed[10] = b'C';
+        changed[15] = b'A';
+        let y = dna(&alphabet, std::str::from_utf8(&changed).unwrap());
+
+        let (p, q): (f64, f64) = (2.0 / 40.0, 2.0 / 40.0);
+        assert_close(p_distance(&x, &y).unwrap(), 0.1);
+        assert_close(jukes_cantor(&x, &y).unwrap(), -0.75 * (1.0 - 4.0 * 0.1 / 3.0f64).ln());
+        assert_close(
+            kimura_2p(&x, &y).unwrap(),
+            -0.5 * (1.0 - 2.0 * p - q).ln() - 0.25 * (1.0 - 2.0 * q).ln()
+        );
+
+        // With equal base frequencies and transition types Tamura-Nei is Kimura two-parameter
+        let sites = NucleotideSites::new(&x, &y).unwrap();
+        assert_eq!(sites.base_counts, [20, 20, 20, 20]);
+        assert_close(sites.tamura_nei().unwrap(), sites.kimura_2p().unwrap());
+
+        assert_close(jukes_cantor(&x, &x).unwrap(), 0.0);
+        assert_close(tamura_nei(&x, &x).unwrap(), 0.0);
+    }
+
+    /// Tests Tamura-Nei with skewed base frequencies and missing bases
+    #[test]
+    fn tamura_nei_unequal_frequencies() {
+        let alphabet = UnambiguousDnaAlphabet;
+        let x = dna(&alphabet, "AAAAAAAAAAGGCCCCTTAA");
+        let y = dna(&alphabet, "AAAAAAAAGAGGCCCCTTAC");
+        let sites = NucleotideSites::new(&x, &y).unwrap();
+
+        let [a, c, g, t]: [f64; 4] = [22.0 / 40.0, 9.0 / 40.0, 5.0 / 40.0, 4.0 / 40.0];
+        let (r, yy) = (a + g, c + t);
+        let (p1, p2, q) = (1.0 / 20.0, 0.0, 1.0 / 20.0);
+        let expected = -2.0 * a * g / r * (1.0 - r * p1 / (2.0 * a * g) - q / (2.0 * r)).ln()
+            - 2.0 * c * t / yy * (1.0 - yy * p2 / (2.0 * c * t) - q / (2.0 * yy)).ln()
+            - 2.0 * (r * yy - a * g * yy / r - c * t * r / yy) * (1.0 - q / (2.0 * r * yy)).ln();
+        assert_close(sites.tamura_nei().unwrap(), expected);
+
+        // Without any G or T only transversions between A and C are possible
+        let x = dna(&alphabet, "AACCAACC");
+        let y = dna(&alphabet, "AACAAACC");
+        assert!(tamura_nei(&x, &y).unwrap() > 0.0);
+    }
+
+    /// Tests that distances are rejected when the sequences are too different
+    #[test]
+    fn saturated() {
+        let alphabet = UnambiguousDnaAlphabet;
+        let x = dna(&alphabet, "ACGTACGT");
+        let y = dna(&alphabet, "CATGCATG");
+
+        assert_eq!(jukes_cantor(&x, &y).unwrap_err().kind(), ErrorKind::Saturated);
+        assert_eq!(kimura_2p(&x, &y).unwrap_err().kind(), ErrorKind::Saturated);
+        assert_eq!(tamura_nei(&x, &y).unwrap_err().kind(), ErrorKind::Saturated);
+    }
+
+    /// Tests the protein distances including skipping non-standard amino acids
+    #[test]
+    fn protein_distances() {
+        let alphabet = ExtendedProteinAlphabet;
+        let x = sequence(&alphabet, "ARNDCQEGHIXB");
+        let y = sequence(&alphabet, "ARNDCQEGHLAA");
+
+        // 1 of 10 sites differ
+        assert_close(poisson(&x, &y).unwrap(), -(0.9f64).ln());
+        assert_close(kimura_protein(&x, &y).unwrap(), -(1.0 - 0.1 - 0.2 * 0.01f64).ln());
+        assert_close(p_distance(&x, &y).unwrap(), 3.0 / 12.0);
+
+        let alphabet = ProteinAlphabet;
+        let x = sequence(&alphabet, "ARND");
+        let y = sequence(&alphabet, "CQEG");
+        assert_eq!(poisson(&x, &y).unwrap_err().kind(), ErrorKind::Saturated);
+        assert_eq!(kimura_protein(&x, &y).unwrap_err().kind(), ErrorKind::Saturated);
+    }
+}
@@ -0,0 +1,546 @@
+//! Counting the packed k-mers of many sequences.
+
+use std::collections::HashMap;
+use std::hash::{BuildHasherDefault, Hasher};
+use std::io::{self, Read, Write};
+
+use crate::alphabet::Complement;
+use crate::sequence::Sequence;
+use super::{mix64, reverse_complement_packed, unpack_kmer, MAX_PACKED_K};
+
+/// Identifies the compact binary format written by [KmerCounter::write_binary()]
+const MAGIC: &[u8; 4] = b"BKMC";
+
+/// The version of the binary format
+const VERSION: u8 = 1;
+
+/// Decides whether a k-mer and its reverse complement are counted together.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CountMode {
+    /// Each k-mer is counted under its canonical form so a k-mer and its reverse complement
+    /// share a count. Use this when the strand of a sequence is unknown, as with most reads.
+    Canonical,
+    /// Each k-mer is counted exactly as it appears in the sequence.
+    Stranded,
+}
+
+/// A hasher for packed k-mers that is much cheaper than the default SipHash.
+///
+/// Packed k-mers are already integers so a single round of [mix64()](super::mix64) is enough to
+/// spread them across the table.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct KmerHasher(u64);
+
+impl Hasher for KmerHasher {
+    #[inline]
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    #[inline]
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 = mix64(self.0 ^ byte as u64);
+        }
+    }
+
+    #[inline]
+    fn write_u64(&mut self, i: u64) {
+        self.0 = mix64(self.0 ^ i);
+    }
+}
+
+/// Counts the occurrences of packed k-mers across any number of sequences.
+///
+/// # Notes
+/// Counts are stored in a hash table of u64 k-mers to u32 counts (16 bytes per distinct k-mer
+/// plus the table's overhead) keyed with the [KmerHasher]. Counts saturate at u32::MAX rather
+/// than overflowing.
+///
+/// # Example
+/// ```
+/// use biors::alphabet::UnambiguousDnaAlphabet;
+/// use biors::sequence::Sequence;
+/// use biors::kmer::{CountMode, KmerCounter};
+///
+/// let alphabet = UnambiguousDnaAlphabet;
+/// let mut seq = Sequence::new(&alphabet);
+/// seq.push("AAAAC").unwrap();
+///
+/// let mut counter = KmerCounter::new(2, CountMode::Canonical);
+/// counter.add_sequence(&seq);
+///
+/// // AA occurs 3 times, TT is its reverse complement and shares its count
+/// assert_eq!(counter.get(0b00_00), 3);
+/// assert_eq!(counter.get(0b11_11), 3);
+/// assert_eq!(counter.spectrum(), vec![(1, 1), (3, 1)]);
+/// ```
+#[derive(Debug, Clone)]
+pub struct KmerCounter {
+    k: usize,
+    mode: CountMode,
+    counts: HashMap<u64, u32, BuildHasherDefault<KmerHasher>>,
+}
+
+impl KmerCounter {
+    /// Constructs an empty counter for k-mers of length k.
+    ///
+    /// # Panics
+    /// If k is 0 or greater than [MAX_PACKED_K](super::MAX_PACKED_K).
+    pub fn new(k: usize, mode: CountMode) -> Self {
+        KmerCounter::with_capacity(k, mode, 0)
+    }
+
+    /// Constructs an empty counter with room for at least capacity distinct k-mers.
+    ///
+    /// # Panics
+    /// If k is 0 or greater than [MAX_PACKED_K](super::MAX_PACKED_K).
+    pub fn with_capacity(k: usize, mode: CountMode, capacity: usize) -> Self {
+        assert!(
+            k > 0 && k <= MAX_PACKED_K,
+            "k must be between 1 and {} to count packed k-mers but was {}", MAX_PACKED_K, k
+        );
+
+        let counts = HashMap::with_capacity_and_hasher(capacity, Default::default());
+        KmerCounter { k, mode, counts }
+    }
+
+    /// The length of the k-mers being counted.
+    pub fn k(&self) -> usize {
+        self.k
+    }
+
+    /// Whether k-mers are counted canonically or by strand.
+    pub fn mode(&self) -> CountMode {
+        self.mode
+    }
+
+    /// The number of distinct k-mers that have been counted.
+    pub fn len(&self) -> usize {
+        self.counts.len()
+    }
+
+    /// Returns true if no k-mers have been counted.
+    pub fn is_empty(&self) -> bool {
+        self.counts.is_empty()
+    }
+
+    /// The sum of the counts of every k-mer.
+    pub fn total(&self) -> u64 {
+        self.counts.values().map(|&count| count as u64).sum()
+    }
+
+    /// Counts every k-mer of the sequence. K-mers that can't be packed are skipped as described
+    /// in [PackedKmers](super::PackedKmers).
+    pub fn add_sequence<A: Complement>(&mut self, seq: &Sequence<A>) {
+        for kmer in seq.packed_kmers(self.k) {
+            let key = match self.mode {
+                CountMode::Canonical => kmer.canonical(),
+                CountMode::Stranded => kmer.forward,
+            };
+
+            self.add_count(key, 1);
+        }
+    }
+
+    /// Adds count occurrences of a packed k-mer. In canonical mode the k-mer is converted to its
+    /// canonical form first.
+    pub fn add_count(&mut self, kmer: u64, count: u32) {
+        let key = self.key(kmer);
+        let entry = self.counts.entry(key).or_insert(0);
+        *entry = entry.saturating_add(count);
+    }
+
+    /// The number of times the packed k-mer has been counted. In canonical mode this is the
+    /// same for a k-mer and its reverse complement.
+    pub fn get(&self, kmer: u64) -> u32 {
+        self.counts.get(&self.key(kmer)).cloned().unwrap_or(0)
+    }
+
+    /// An iterator over each distinct k-mer and its count in no particular order.
+    /// In canonical mode only the canonical form of each k-mer is returned.
+    pub fn iter(&self) -> impl Iterator<Item=(u64, u32)> + '_ {
+        self.counts.iter().map(|(&kmer, &count)| (kmer, count))
+    }
+
+    /// Adds all of the counts from another counter to this one.
+    ///
+    /// # Panics
+    /// If the counters have a different k or mode.
+    pub fn merge(&mut self, other: &KmerCounter) {
+        assert_eq!(self.k, other.k, "Tried to merge k-mer counters with different k");
+        assert_eq!(self.mode, other.mode, "Tried to merge k-mer counters with different modes");
+
+        self.counts.reserve(other.counts.len());
+        for (&kmer, &count) in other.counts.iter() {
+            let entry = self.counts.entry(kmer).or_insert(0);
+            *entry = entry.saturating_add(count);
+        }
+    }
+
+    /// Removes every k-mer with a count less than min or greater than max.
+    pub fn retain_counts(&mut self, min: u32, max: u32) {
+        self.counts.retain(|_, count| *count >= min && *count <= max);
+    }
+
+    /// The k-mer abundance spectrum. Each element pairs a count with the number of distinct
+    /// k-mers that occurred that many times, in increasing order of count. Counts that no k-mer
+    /// has are left out.
+    pub fn spectrum(&self) -> Vec<(u32, u64)> {
+        let mut histogram: HashMap<u32, u64> = HashMap::new();
+        for &count in self.counts.values() {
+            *histogram.entry(count).or_insert(0) += 1;
+        }
+
+        let mut spectrum: Vec<_> = histogram.into_iter().collect();
+        spectrum.sort_unstable();
+        spectrum
+    }
+
+    /// Writes each k-mer and its count as a line of tab separated text sorted by k-mer.
+    ///
+    /// # Example
+    /// ```
+    /// use biors::kmer::{CountMode, KmerCounter};
+    ///
+    /// let mut counter = KmerCounter::new(2, CountMode::Stranded);
+    /// counter.add_count(0b00_01, 4);
+    /// counter.add_count(0b11_10, 1);
+    ///
+    /// let mut text = vec![];
+    /// counter.write_text(&mut text).unwrap();
+    /// assert_eq!(String::from_utf8(text).unwrap(), "AC\t4\nTG\t1\n");
+    /// ```
+    pub fn write_text<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        for (kmer, count) in self.sorted() {
+            writeln!(writer, "{}\t{}", unpack_kmer(kmer, self.k), count)?;
+        }
+
+        Ok(())
+    }
+
+    /// Writes the counter in a compact binary form that can be read back with
+    /// [read_binary()](KmerCounter::read_binary).
+    ///
+    /// # Format
+    /// A header of the bytes `BKMC`, a version byte, k, the mode (0 for canonical, 1 for
+    /// stranded), a reserved 0 byte and the number of distinct k-mers as a little endian u64.
+    /// This is followed by each k-mer in increasing order as the difference from the previous
+    /// k-mer and then its count, both written as LEB128 variable length integers.
+    pub fn write_binary<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        let mode = match self.mode {
+            CountMode::Canonical => 0,
+            CountMode::Stranded => 1,
+        };
+
+        writer.write_all(MAGIC)?;
+        writer.write_all(&[VERSION, self.k as u8, mode, 0])?;
+        writer.write_all(&(self.counts.len() as u64).to_le_bytes())?;
+
+        let mut previous = 0;
+        for (kmer, count) in self.sorted() {
+            write_varint(&mut writer, kmer - previous)?;
+            write_varint(&mut writer, count as u64)?;
+            previous = kmer;
+        }
+
+        Ok(())
+    }
+
+    /// Reads a counter written by [write_binary()](KmerCounter::write_binary).
+    ///
+    /// # Errors
+    /// An error of kind [InvalidData](std::io::ErrorKind::InvalidData) is returned if the data
+    /// is not a valid counter, including when a k-mer is too large to have k bases, a k-mer is
+    /// repeated or not canonical in a canonical counter, or a count is 0.
+    pub fn read_binary<R: Read>(mut reader: R) -> io::Result<KmerCounter> {
+        let mut header = [0u8; 16];
+        reader.read_exact(&mut header)?;
+
+        if &header[0..4] != MAGIC {
+            return Err(invalid_data("Not a binary k-mer counter"));
+        }
+        if header[4] != VERSION {
+            return Err(invalid_data("Unsupported binary k-mer counter version"));
+        }
+
+        let k = header[5] as usize;
+        if k == 0 || k > MAX_PACKED_K {
+            return Err(invalid_data("Binary k-mer counter has an invalid k"));
+        }
+
+        let mode = match header[6] {
+            0 => CountMode::Canonical,
+            1 => CountMode::Stranded,
+            _ => return Err(invalid_data("Binary k-mer counter has an invalid mode")),
+        };
+        if header[7] != 0 {
+            return Err(invalid_data("Binary k-mer counter has a non-zero reserved byte"));
+        }
+
+        let mut len_bytes = [0u8; 8];
+        len_bytes.copy_from_slice(&header[8..16]);
+        let len = u64::from_le_bytes(len_bytes) as usize;
+
+        // Don't trust the length too much when reserving space
+        let mut counter = KmerCounter::with_capacity(k, mode, len.min(1 << 20));
+        let max_kmer = if k == MAX_PACKED_K { u64::MAX } else { (1 << (2 * k)) - 1 };
+        let mut kmer: u64 = 0;
+        for i in 0..len {
+            // The k-mers are sorted so only the first one may have a delta of 0
+            let delta = read_varint(&mut reader)?;
+            if i > 0 && delta == 0 {
+                return Err(invalid_data("Binary k-mer counter contains a repeated k-mer"));
+            }
+            kmer = kmer.checked_add(delta)
+                .filter(|&kmer| kmer <= max_kmer && counter.key(kmer) == kmer)
+                .ok_or_else(|| invalid_data("Binary k-mer counter contains an invalid k-mer"))?;
+            let count = read_varint(&mut reader)?;
+            if count == 0 || count > u32::MAX as u64 {
+                return Err(invalid_data("Binary k-mer counter contains an invalid count"));
+            }
+
+            counter.counts.insert(kmer, count as u32);
+        }
+
+        Ok(counter)
+    }
+
+    /// Converts a k-mer into the form used as a key in the table
+    #[inline]
+    fn key(&self, kmer: u64) -> u64 {
+        match self.mode {
+            CountMode::Canonical => kmer.min(reverse_complement_packed(kmer, self.k)),
+            CountMode::Stranded => kmer,
+        }
+    }
+
+    /// The k-mers and their counts sorted by k-mer
+    fn sorted(&self) -> Vec<(u64, u32)> {
+        let mut sorted: Vec<_> = self.iter().collect();
+        sorted.sort_unstable();
+        sorted
+    }
+}
+
+//================================================================================
+// Utility Functions
+//================================================================================
+
+/// Writes a LEB128 variable length integer
+fn write_varint<W: Write>(writer: &mut W, mut value: u64) -> io::Result<()> {
+    let mut buf = [0u8; 10];
+    let mut len = 0;
+
+    loop {
+        let byte = (value & 0x7F) as u8;
+        value >>= 7;
+
+        if value == 0 {
+            buf[len] = byte;
+            len += 1;
+            break;
+        }
+
+        buf[len] = byte | 0x80;
+        len += 1;
+    }
+
+    writer.write_all(&buf[..len])
+}
+
+/// Reads a LEB128 variable length integer
+fn read_varint<R: Read>(reader: &mut R) -> io::Result<u64> {
+    let mut value = 0u64;
+    let mut byte = [0u8];
+
+    for shift in (0..64).step_by(7) {
+        reader.read_exact(&mut byte)?;
+        // Only the lowest bit of the tenth byte fits in a u64
+        if shift == 63 && byte[0] & 0x7E != 0 {
+            return Err(invalid_data("Variable length integer is too large"));
+        }
+        value |= ((byte[0] & 0x7F) as u64) << shift;
+
+        if byte[0] & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+
+    Err(invalid_data("Variable length integer is too long"))
+}
+
+/// Constructs an io::Error for malformed input
+fn invalid_data(description: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, description)
+}
+
+//================================================================================
+// Tests
+//================================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alphabet::UnambiguousDnaAlphabet;
+
+    /// Constructs a counter from the k-mers of the given strings
+    fn count(k: usize, mode: CountMode, seqs: &[&str]) -> KmerCounter {
+        let a = UnambiguousDnaAlphabet;
+        let mut counter = KmerCounter::new(k, mode);
+
+        for string in seqs {
+            let mut s = Sequence::new(&a);
+            s.push(string).unwrap();
+            counter.add_sequence(&s);
+        }
+
+        counter
+    }
+
+    /// Tests that stranded counting keeps a k-mer and its reverse complement apart
+    #[test]
+    fn stranded_counts() {
+        let counter = count(3, CountMode::Stranded, &["ACGTACG", "CGT"]);
+
+        // ACG CGT GTA TAC ACG + CGT
+        assert_eq!(counter.get(0b00_01_10), 2); // ACG
+        assert_eq!(counter.get(0b01_10_11), 2); // CGT
+        assert_eq!(counter.get(0b10_11_00), 1); // GTA
+        assert_eq!(counter.len(), 4);
+        assert_eq!(counter.total(), 6);
+    }
+
+    /// Tests that canonical counting combines a k-mer and its reverse complement
+    #[test]
+    fn canonical_counts() {
+        let counter = count(3, CountMode::Canonical, &["ACGTACG", "CGT"]);
+
+        // ACG/CGT are each other's reverse complement as are GTA/TAC
+        assert_eq!(counter.get(0b00_01_10), 4);
+        assert_eq!(counter.get(0b01_10_11), 4);
+        assert_eq!(counter.get(0b11_00_01), 2);
+        assert_eq!(counter.len(), 2);
+    }
+
+    /// Tests that merging two counters is the same as counting everything in one
+    #[test]
+    fn merge() {
+        let mut left = count(4, CountMode::Canonical, &["GATTACAGATTACA"]);
+        let right = count(4, CountMode::Canonical, &["TTTGATTAAC"]);
+        let both = count(4, CountMode::Canonical, &["GATTACAGATTACA", "TTTGATTAAC"]);
+
+        left.merge(&right);
+
+        assert_eq!(left.sorted(), both.sorted());
+    }
+
+    /// Tests that counters with different k can't be merged
+    #[test]
+    #[should_panic]
+    fn merge_different_k() {
+        let mut left = KmerCounter::new(4, CountMode::Canonical);
+        left.merge(&KmerCounter::new(5, CountMode::Canonical));
+    }
+
+    /// Tests the spectrum and filtering by count
+    #[test]
+    fn spectrum_and_filter() {
+        let mut counter = count(2, CountMode::Stranded, &["AAAAACACAT"]);
+
+        // AA x4, AC x2, CA x2, AT x1
+        assert_eq!(counter.spectrum(), vec![(1, 1), (2, 2), (4, 1)]);
+
+        counter.retain_counts(2, 3);
+        assert_eq!(counter.spectrum(), vec![(2, 2)]);
+        assert_eq!(counter.get(0b00_00), 0);
+    }
+
+    /// Tests that counts saturate instead of overflowing
+    #[test]
+    fn saturating_counts() {
+        let mut counter = KmerCounter::new(1, CountMode::Stranded);
+        counter.add_count(0, u32::MAX);
+        counter.add_count(0, 10);
+
+        assert_eq!(counter.get(0), u32::MAX);
+    }
+
+    /// Tests that the binary form reads back into an identical counter
+    #[test]
+    fn binary_round_trip() {
+        let counter = count(31, CountMode::Canonical, &["GATTACAGATTACAGGCATTACCAGTAGGACTTATGCA"]);
+
+        let mut bytes = vec![];
+        counter.write_binary(&mut bytes).unwrap();
+        let read = KmerCounter::read_binary(bytes.as_slice()).unwrap();
+
+        assert_eq!(read.k(), 31);
+        assert_eq!(read.mode(), CountMode::Canonical);
+        assert_eq!(read.sorted(), counter.sorted());
+    }
+
+    /// Tests that malformed binary data is rejected
+    #[test]
+    fn binary_invalid() {
+        let counter = count(3, CountMode::Stranded, &["ACGTACG"]);
+        let mut bytes = vec![];
+        counter.write_binary(&mut bytes).unwrap();
+
+        let truncated = &bytes[..bytes.len() - 1];
+        assert!(KmerCounter::read_binary(truncated).is_err());
+
+        bytes[0] = b'X';
+        let err = KmerCounter::read_binary(bytes.as_slice()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+        // A single 3-mer stored as 64, which is past the last 3-mer TTT = 63
+        let mut corrupt = b"BKMC".to_vec();
+        corrupt.extend_from_slice(&[VERSION, 3, 1, 0]);
+        corrupt.extend_from_slice(&1u64.to_le_bytes());
+        corrupt.extend_from_slice(&[64, 1]);
+        let err = KmerCounter::read_binary(corrupt.as_slice()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+        let last = corrupt.len() - 2;
+        corrupt[last] = 63;
+        assert!(KmerCounter::read_binary(corrupt.as_slice()).is_ok());
+
+        // A count of 0, then a non-zero reserved byte
+        let mut invalid = corrupt.clone();
+        invalid[last + 1] = 0;
+        let err = KmerCounter::read_binary(invalid.as_slice()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        let mut invalid = corrupt.clone();
+        invalid[7] = 1;
+        let err = KmerCounter::read_binary(invalid.as_slice()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+        // Two 3-mers where the second repeats the first with a delta of 0
+        let mut repeated = corrupt[..8].to_vec();
+        repeated.extend_from_slice(&2u64.to_le_bytes());
+        repeated.extend_from_slice(&[5, 1, 0, 1]);
+        let err = KmerCounter::read_binary(repeated.as_slice()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+        // A 10-byte delta with bits above the 64th set
+        let mut overlong = corrupt[..8].to_vec();
+        overlong.extend_from_slice(&1u64.to_le_bytes());
+        overlong.extend_from_slice(&[0xFF; 9]);
+        overlong.extend_from_slice(&[0x03, 1]);
+        let err = KmerCounter::read_binary(overlong.as_slice()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        let mut cursor = &overlong[16..];
+        assert!(read_varint(&mut cursor).is_err());
+        overlong[25] = 0x01;
+        let mut cursor = &overlong[16..];
+        assert_eq!(read_varint(&mut cursor).unwrap(), u64::MAX);
+
+        // TTT = 63 is the reverse complement of AAA = 0 so it isn't canonical
+        corrupt[6] = 0;
+        let err = KmerCounter::read_binary(corrupt.as_slice()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        corrupt[last] = 0;
+        assert!(KmerCounter::read_binary(corrupt.as_slice()).is_ok());
+    }
+}
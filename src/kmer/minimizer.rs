@@ -0,0 +1,496 @@
+//! Sampling a subset of the k-mers of a sequence using minimizers and syncmers.
+//!
+//! Both schemes choose k-mers using only the sequence around them so the same k-mers are
+//! sampled from a read as from the region of the genome it came from. This makes them useful
+//! as seeds when mapping reads.
+
+use std::collections::VecDeque;
+
+use crate::alphabet::Complement;
+use crate::sequence::{Sequence, Strand};
+use super::{kmer_count, KmerHash, MixHash, PackedKmer, MAX_PACKED_K};
+
+/// A k-mer chosen by a sampling scheme.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SampledKmer {
+    /// Position of the first symbol of the k-mer in the sequence
+    pub position: usize,
+    /// The packed k-mer. This is its canonical form if the scheme is canonical.
+    pub kmer: u64,
+    /// The hash of the packed k-mer
+    pub hash: u64,
+    /// The strand the k-mer was taken from. Always forward unless the scheme is canonical.
+    pub strand: Strand,
+}
+
+/// Turns a packed k-mer into a sampled k-mer
+fn sample<H: KmerHash>(kmer: PackedKmer, canonical: bool, hasher: &H) -> SampledKmer {
+    let (packed, strand) = if canonical {
+        (kmer.canonical(), kmer.strand())
+    } else {
+        (kmer.forward, Strand::Forward)
+    };
+
+    SampledKmer { position: kmer.position, kmer: packed, hash: hasher.hash_kmer(packed), strand }
+}
+
+//================================================================================
+// Minimizers
+//================================================================================
+
+/// Chooses the k-mer with the smallest hash from every window of w consecutive k-mers.
+///
+/// Each window is guaranteed to contain at least one sampled k-mer. Runs of fewer than w k-mers,
+/// such as those between two N symbols, are too short to form a window and are not sampled.
+/// When several k-mers in a window share the smallest hash the leftmost is chosen.
+///
+/// # Example
+/// ```
+/// use biors::alphabet::UnambiguousDnaAlphabet;
+/// use biors::sequence::Sequence;
+/// use biors::kmer::{MinimizerScheme, LexicographicHash};
+///
+/// let alphabet = UnambiguousDnaAlphabet;
+/// let mut seq = Sequence::new(&alphabet);
+/// seq.push("TTTGATTAC").unwrap();
+///
+/// let scheme = MinimizerScheme::new(3, 3)
+///     .canonical(false)
+///     .with_hasher(LexicographicHash);
+///
+/// // TTT TTG TGA | GAT ATT TTA | TAC
+/// let positions: Vec<_> = scheme.minimizers(&seq).iter().map(|m| m.position).collect();
+/// assert_eq!(positions, vec![2, 3, 4]);
+/// ```
+#[derive(Debug, Clone)]
+pub struct MinimizerScheme<H = MixHash> {
+    w: usize,
+    k: usize,
+    canonical: bool,
+    hasher: H,
+}
+
+impl MinimizerScheme<MixHash> {
+    /// Constructs a scheme that samples one canonical k-mer from every w consecutive k-mers using
+    /// [MixHash](super::MixHash).
+    ///
+    /// # Panics
+    /// If w is 0 or k is 0 or greater than [MAX_PACKED_K](super::MAX_PACKED_K).
+    pub fn new(w: usize, k: usize) -> Self {
+        assert!(w > 0, "w must be greater than 0");
+        assert!(
+            k > 0 && k <= MAX_PACKED_K,
+            "k must be between 1 and {} to pack a k-mer into a u64 but was {}", MAX_PACKED_K, k
+        );
+
+        MinimizerScheme { w, k, canonical: true, hasher: MixHash }
+    }
+}
+
+impl<H: KmerHash> MinimizerScheme<H> {
+    /// Sets whether k-mers are compared by their canonical form. Canonical minimizers are the
+    /// same on both strands of the sequence and report which strand they were found on.
+    /// Defaults to true.
+    pub fn canonical(mut self, canonical: bool) -> Self {
+        self.canonical = canonical;
+        self
+    }
+
+    /// Uses a different hash function to order the k-mers.
+    pub fn with_hasher<H2: KmerHash>(self, hasher: H2) -> MinimizerScheme<H2> {
+        MinimizerScheme { w: self.w, k: self.k, canonical: self.canonical, hasher }
+    }
+
+    /// Finds the minimizers of the sequence in order of position in O(n) time. Each minimizer
+    /// is reported once even if it is the smallest in several windows.
+    ///
+    /// If the sequence is [circular](crate::sequence::Sequence::circular) the windows that wrap
+    /// around the origin are also included.
+    pub fn minimizers<A: Complement>(&self, seq: &Sequence<A>) -> Vec<SampledKmer> {
+        let len = seq.len();
+        let mut kmers: Vec<_> = seq.packed_kmers(self.k)
+            .map(|kmer| sample(kmer, self.canonical, &self.hasher))
+            .collect();
+
+        // Windows that wrap around the origin continue on to the first w - 1 k-mers again
+        if seq.circular {
+            let wrapped: Vec<_> = kmers.iter()
+                .take_while(|kmer| kmer.position < self.w - 1)
+                .map(|kmer| SampledKmer { position: kmer.position + len, ..*kmer })
+                .collect();
+            kmers.extend(wrapped);
+        }
+
+        // Indices into kmers with strictly increasing hashes. The front is the current minimum.
+        let mut queue: VecDeque<usize> = VecDeque::with_capacity(self.w);
+        let mut minimizers: Vec<SampledKmer> = vec![];
+        let mut run_start = 0;
+        let mut previous: Option<usize> = None;
+
+        for (i, kmer) in kmers.iter().enumerate() {
+            // A skipped k-mer breaks the run of consecutive k-mers
+            if previous.is_none_or(|p| kmer.position != p + 1) {
+                queue.clear();
+                run_start = kmer.position;
+            }
+            previous = Some(kmer.position);
+
+            while queue.back().is_some_and(|&back| kmers[back].hash > kmer.hash) {
+                queue.pop_back();
+            }
+            queue.push_back(i);
+
+            if kmer.position + 1 < run_start + self.w {
+                continue;
+            }
+
+            let window_start = kmer.position + 1 - self.w;
+            while kmers[queue[0]].position < window_start {
+                queue.pop_front();
+            }
+
+            let minimum = kmers[queue[0]];
+            if minimizers.last().is_none_or(|last| last.position != minimum.position) {
+                minimizers.push(minimum);
+            }
+        }
+
+        if seq.circular {
+            unwrap_positions(minimizers, len)
+        } else {
+            minimizers
+        }
+    }
+}
+
+/// Moves k-mers found past the end of a circular sequence back to their true position and
+/// removes any that were already found at that position
+fn unwrap_positions(sampled: Vec<SampledKmer>, len: usize) -> Vec<SampledKmer> {
+    let split = sampled.iter().position(|kmer| kmer.position >= len).unwrap_or(sampled.len());
+    let (within, wrapped) = sampled.split_at(split);
+
+    let mut unwrapped = within.to_vec();
+    for kmer in wrapped {
+        let position = kmer.position - len;
+        if within.binary_search_by_key(&position, |k| k.position).is_err() {
+            unwrapped.push(SampledKmer { position, ..*kmer });
+        }
+    }
+
+    unwrapped.sort_by_key(|kmer| kmer.position);
+    unwrapped
+}
+
+//================================================================================
+// Syncmers
+//================================================================================
+
+/// The rule that decides whether a k-mer is a syncmer based on the position of its smallest
+/// s-mer (substring of length s).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyncmerKind {
+    /// The smallest s-mer is at the given offset from the start of the k-mer
+    Open(usize),
+    /// The smallest s-mer is at the start or the end of the k-mer
+    Closed,
+}
+
+/// Chooses k-mers based on the position of the smallest s-mer within them.
+///
+/// Unlike minimizers whether a k-mer is chosen depends only on the k-mer itself and not its
+/// neighbours so a mutation can only affect the k-mers that overlap it.
+///
+/// # Example
+/// ```
+/// use biors::alphabet::UnambiguousDnaAlphabet;
+/// use biors::sequence::Sequence;
+/// use biors::kmer::{SyncmerScheme, SyncmerKind, LexicographicHash};
+///
+/// let alphabet = UnambiguousDnaAlphabet;
+/// let mut seq = Sequence::new(&alphabet);
+/// seq.push("TACGTT").unwrap();
+///
+/// let scheme = SyncmerScheme::new(4, 2, SyncmerKind::Closed)
+///     .canonical(false)
+///     .with_hasher(LexicographicHash);
+///
+/// // TACG has its smallest 2-mer AC in the middle, ACGT and CGTT start/end with theirs
+/// let positions: Vec<_> = scheme.syncmers(&seq).iter().map(|s| s.position).collect();
+/// assert_eq!(positions, vec![1, 2]);
+/// ```
+#[derive(Debug, Clone)]
+pub struct SyncmerScheme<H = MixHash> {
+    k: usize,
+    s: usize,
+    kind: SyncmerKind,
+    canonical: bool,
+    hasher: H,
+}
+
+impl SyncmerScheme<MixHash> {
+    /// Constructs a scheme that samples canonical k-mers based on the position of their
+    /// smallest s-mer using [MixHash](super::MixHash).
+    ///
+    /// # Panics
+    /// If s is 0, s is greater than k, k is greater than [MAX_PACKED_K](super::MAX_PACKED_K) or
+    /// the offset of an open syncmer is greater than k - s.
+    pub fn new(k: usize, s: usize, kind: SyncmerKind) -> Self {
+        assert!(s > 0 && s <= k, "s must be between 1 and k but was {}", s);
+        assert!(
+            k <= MAX_PACKED_K,
+            "k must be between 1 and {} to pack a k-mer into a u64 but was {}", MAX_PACKED_K, k
+        );
+        if let SyncmerKind::Open(offset) = kind {
+            assert!(
+                offset <= k - s,
+                "Open syncmer offset must be at most k - s but was {}", offset
+            );
+        }
+
+        SyncmerScheme { k, s, kind, canonical: true, hasher: MixHash }
+    }
+}
+
+impl<H: KmerHash> SyncmerScheme<H> {
+    /// Sets whether k-mers and s-mers are compared by their canonical form. Defaults to true.
+    ///
+    /// # Notes
+    /// Canonical closed syncmers are the same on both strands but canonical open syncmers are
+    /// only the same on both strands if the offset is in the middle of the k-mer.
+    pub fn canonical(mut self, canonical: bool) -> Self {
+        self.canonical = canonical;
+        self
+    }
+
+    /// Uses a different hash function to order the s-mers. The k-mers are hashed with the same
+    /// function.
+    pub fn with_hasher<H2: KmerHash>(self, hasher: H2) -> SyncmerScheme<H2> {
+        SyncmerScheme { k: self.k, s: self.s, kind: self.kind, canonical: self.canonical, hasher }
+    }
+
+    /// Finds the syncmers of the sequence in order of position in O(n) time.
+    ///
+    /// If the sequence is [circular](crate::sequence::Sequence::circular) the k-mers that wrap
+    /// around the origin are also included.
+    pub fn syncmers<A: Complement>(&self, seq: &Sequence<A>) -> Vec<SampledKmer> {
+        let smer_count = kmer_count(seq.len(), self.s, seq.circular);
+        let mut smer_hashes = vec![0; smer_count];
+
+        for smer in seq.packed_kmers(self.s) {
+            smer_hashes[smer.position] = sample(smer, self.canonical, &self.hasher).hash;
+        }
+
+        // Positions of s-mers with strictly increasing hashes. The front is the current minimum.
+        let mut queue: VecDeque<(usize, u64)> = VecDeque::with_capacity(self.k - self.s + 1);
+        let mut next_smer = 0;
+        let mut previous: Option<usize> = None;
+        let mut syncmers = vec![];
+
+        for kmer in seq.packed_kmers(self.k) {
+            let start = kmer.position;
+
+            // Every s-mer of a k-mer is valid but the queue can't be reused across a gap
+            if previous.is_none_or(|p| start != p + 1) {
+                queue.clear();
+                next_smer = start;
+            }
+            previous = Some(start);
+
+            // Positions can go past the end of a circular sequence and wrap around
+            let last_smer = start + self.k - self.s;
+            while next_smer <= last_smer {
+                let hash = smer_hashes[next_smer % smer_count];
+                while queue.back().is_some_and(|back| back.1 > hash) {
+                    queue.pop_back();
+                }
+                queue.push_back((next_smer, hash));
+                next_smer += 1;
+            }
+
+            while queue[0].0 < start {
+                queue.pop_front();
+            }
+
+            let offset = queue[0].0 - start;
+            let selected = match self.kind {
+                SyncmerKind::Open(t) => offset == t,
+                SyncmerKind::Closed => offset == 0 || offset == self.k - self.s,
+            };
+
+            if selected {
+                syncmers.push(sample(kmer, self.canonical, &self.hasher));
+            }
+        }
+
+        syncmers
+    }
+}
+
+//================================================================================
+// Tests
+//================================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alphabet::{AmbiguousDnaAlphabet, UnambiguousDnaAlphabet};
+    use crate::kmer::LexicographicHash;
+
+    const SEQUENCE: &str = "GATTACAGGCATTACCAGTAGGACTTATGCATCCAGGATTACAACGTTGCAGTCAGT";
+
+    /// Finds the minimizers by checking every window
+    fn brute_force_minimizers<A: Complement>(seq: &Sequence<A>, w: usize, k: usize) -> Vec<usize> {
+        let kmers: Vec<_> = seq.packed_kmers(k).collect();
+        let mut positions: Vec<usize> = kmers.windows(w)
+            .map(|window| {
+                let mut best = window[0];
+                for kmer in window {
+                    if MixHash.hash_kmer(kmer.canonical()) < MixHash.hash_kmer(best.canonical()) {
+                        best = *kmer;
+                    }
+                }
+                best.position
+            })
+            .collect();
+
+        positions.dedup();
+        positions
+    }
+
+    /// Tests that the monotone queue agrees with checking every window
+    #[test]
+    fn minimizers_match_brute_force() {
+        let a = UnambiguousDnaAlphabet;
+        let mut s = Sequence::new(&a);
+        s.push(SEQUENCE).unwrap();
+
+        for &(w, k) in &[(1, 5), (4, 7), (10, 15), (5, 31)] {
+            let scheme = MinimizerScheme::new(w, k);
+            let positions: Vec<_> = scheme.minimizers(&s).iter().map(|m| m.position).collect();
+
+            assert_eq!(positions, brute_force_minimizers(&s, w, k));
+        }
+    }
+
+    /// Tests that canonical minimizers are the same k-mers on the reverse complement
+    #[test]
+    fn canonical_minimizers_both_strands() {
+        let a = UnambiguousDnaAlphabet;
+        let mut s = Sequence::new(&a);
+        s.push(SEQUENCE).unwrap();
+        let mut rc = s.clone();
+        rc.reverse_complement();
+
+        let scheme = MinimizerScheme::new(5, 11);
+        let forward = scheme.minimizers(&s);
+        let mut reverse = scheme.minimizers(&rc);
+        reverse.reverse();
+
+        let len = s.len();
+        for (f, r) in forward.iter().zip(reverse.iter()) {
+            assert_eq!(f.kmer, r.kmer);
+            assert_eq!(f.position, len - r.position - 11);
+        }
+    }
+
+    /// Tests that an N in the sequence splits it into separate runs of windows
+    #[test]
+    fn minimizers_skip_ambiguous() {
+        let a = AmbiguousDnaAlphabet;
+        let mut s = Sequence::new(&a);
+        s.push("ACGTTNCCGTAAT").unwrap();
+
+        let scheme = MinimizerScheme::new(3, 3).with_hasher(LexicographicHash);
+        let positions: Vec<_> = scheme.minimizers(&s).iter().map(|m| m.position).collect();
+
+        // ACGTT has 3 k-mers making a single window, CCGTAAT has 5 making 3 windows
+        assert_eq!(positions, vec![2, 7, 10]);
+    }
+
+    /// Tests that windows wrapping around the origin of a circular sequence are sampled
+    #[test]
+    fn circular_minimizers() {
+        let a = UnambiguousDnaAlphabet;
+        let mut s = Sequence::new(&a).circular(true);
+        s.push("CCCCCCACC").unwrap();
+
+        let scheme = MinimizerScheme::new(4, 2).canonical(false).with_hasher(LexicographicHash);
+        let minimizers = scheme.minimizers(&s);
+        let positions: Vec<_> = minimizers.iter().map(|m| m.position).collect();
+
+        // CA at 5 and AC at 6 are the smallest, every other window is all CC
+        assert_eq!(positions, vec![0, 1, 5, 6, 7, 8]);
+        assert!(minimizers.iter().all(|m| m.strand == Strand::Forward));
+
+        // Linear sequences miss the windows that wrap
+        s.circular = false;
+        let positions: Vec<_> = scheme.minimizers(&s).iter().map(|m| m.position).collect();
+        assert_eq!(positions, vec![0, 1, 5, 6]);
+    }
+
+    /// Finds the syncmers by checking every s-mer of every k-mer
+    fn brute_force_syncmers(seq: &str, k: usize, s: usize, kind: SyncmerKind) -> Vec<usize> {
+        let a = UnambiguousDnaAlphabet;
+        let mut positions = vec![];
+
+        for start in 0..=seq.len() - k {
+            let mut kmer = Sequence::new(&a);
+            kmer.push(&seq[start..start + k]).unwrap();
+
+            let smers: Vec<_> = kmer.packed_kmers(s).map(|smer| smer.forward).collect();
+            let min = smers.iter().min().unwrap();
+            let offset = smers.iter().position(|smer| smer == min).unwrap();
+
+            let selected = match kind {
+                SyncmerKind::Open(t) => offset == t,
+                SyncmerKind::Closed => offset == 0 || offset == k - s,
+            };
+
+            if selected {
+                positions.push(start);
+            }
+        }
+
+        positions
+    }
+
+    /// Tests that open and closed syncmers agree with checking every s-mer
+    #[test]
+    fn syncmers_match_brute_force() {
+        let a = UnambiguousDnaAlphabet;
+        let mut s = Sequence::new(&a);
+        s.push(SEQUENCE).unwrap();
+
+        for &kind in &[SyncmerKind::Closed, SyncmerKind::Open(0), SyncmerKind::Open(2)] {
+            let scheme = SyncmerScheme::new(8, 3, kind)
+                .canonical(false)
+                .with_hasher(LexicographicHash);
+            let positions: Vec<_> = scheme.syncmers(&s).iter().map(|m| m.position).collect();
+
+            assert_eq!(positions, brute_force_syncmers(SEQUENCE, 8, 3, kind));
+        }
+    }
+
+    /// Tests that syncmers wrap around the origin of a circular sequence
+    #[test]
+    fn circular_syncmers() {
+        let a = UnambiguousDnaAlphabet;
+        let mut s = Sequence::new(&a).circular(true);
+        s.push("CGTTTTAC").unwrap();
+
+        let scheme = SyncmerScheme::new(4, 2, SyncmerKind::Open(0))
+            .canonical(false)
+            .with_hasher(LexicographicHash);
+        let positions: Vec<_> = scheme.syncmers(&s).iter().map(|m| m.position).collect();
+
+        // ACCG at 6 and CCGT at 7 wrap around the origin
+        assert_eq!(positions, vec![0, 1, 2, 6, 7]);
+    }
+
+    /// Tests that an open syncmer offset past the end of the k-mer is rejected
+    #[test]
+    #[should_panic]
+    fn syncmer_invalid_offset() {
+        SyncmerScheme::new(5, 3, SyncmerKind::Open(3));
+    }
+}
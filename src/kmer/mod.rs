@@ -0,0 +1,641 @@
+//! Iteration over the k-mers (substrings of length k) of a [Sequence](crate::sequence::Sequence).
+//!
+//! Nucleotide k-mers of up to 32 symbols can be packed into a single u64 using 2 bits per
+//! nucleotide. This is far more compact than the encoded bytes and allows k-mers to be compared,
+//! hashed and complemented with a handful of integer operations.
+//!
+//! # 2-bit Encoding
+//! The symbols A, C, G and T (or U) are packed as 0, 1, 2 and 3 respectively with the first
+//! symbol of the k-mer in the most significant bits. Symbols are recognised by name so this works
+//! with any alphabet that uses them such as the [DNA alphabets](crate::alphabet::dna). Any other
+//! symbol, such as N, cannot be packed and every k-mer containing it is skipped.
+
+use std::borrow::Cow;
+
+use crate::alphabet::{Alphabet, Complement};
+use crate::sequence::{Sequence, Strand};
+
+pub use self::counter::{CountMode, KmerCounter};
+pub use self::minimizer::{MinimizerScheme, SampledKmer, SyncmerKind, SyncmerScheme};
+
+pub mod counter;
+pub mod minimizer;
+
+/// The largest k that can be packed into a u64
+pub const MAX_PACKED_K: usize = 32;
+
+/// Marks an encoded byte that has no 2-bit code
+const NO_CODE: u8 = 0xFF;
+
+//================================================================================
+// K-mer Slices
+//================================================================================
+
+/// An iterator over the encoded bytes of each k-mer in a sequence in order of position.
+///
+/// K-mers that lie entirely within the sequence borrow its bytes. If the sequence is
+/// [circular](crate::sequence::Sequence::circular) the k - 1 k-mers that wrap around the origin
+/// are also produced and these have to be copied.
+///
+/// # Notes
+/// Unlike [PackedKmers] this works for any alphabet and no k-mers are ever skipped so the n-th
+/// item is always the k-mer that starts at position n.
+///
+/// # Example
+/// ```
+/// use biors::alphabet::UnambiguousDnaAlphabet;
+/// use biors::sequence::Sequence;
+///
+/// let alphabet = UnambiguousDnaAlphabet;
+/// let mut seq = Sequence::new(&alphabet).circular(true);
+/// seq.push("ACT").unwrap();
+///
+/// // Symbols are encoded as their index in the alphabet: A, C, T, G
+/// let kmers: Vec<_> = seq.kmers(2).map(|kmer| kmer.into_owned()).collect();
+/// assert_eq!(kmers, vec![vec![0, 1], vec![1, 2], vec![2, 0]]);
+/// ```
+#[derive(Debug, Clone)]
+pub struct Kmers<'s> {
+    bytes: &'s [u8],
+    k: usize,
+    position: usize,
+    end: usize,
+}
+
+impl<'s> Kmers<'s> {
+    /// Constructs an iterator over the k-mers of the sequence.
+    ///
+    /// # Panics
+    /// If k is 0.
+    pub fn new<A: Alphabet>(seq: &'s Sequence<A>, k: usize) -> Self {
+        assert!(k > 0, "k must be greater than 0");
+
+        let bytes = seq.as_bytes();
+        let end = kmer_count(bytes.len(), k, seq.circular);
+
+        Kmers { bytes, k, position: 0, end }
+    }
+}
+
+impl<'s> Iterator for Kmers<'s> {
+    type Item = Cow<'s, [u8]>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.position >= self.end {
+            return None;
+        }
+
+        let start = self.position;
+        self.position += 1;
+
+        if start + self.k <= self.bytes.len() {
+            Some(Cow::Borrowed(&self.bytes[start..start + self.k]))
+        } else {
+            // Wraps around the origin of a circular sequence
+            let overhang = start + self.k - self.bytes.len();
+            let mut kmer = Vec::with_capacity(self.k);
+            kmer.extend_from_slice(&self.bytes[start..]);
+            kmer.extend_from_slice(&self.bytes[..overhang]);
+            Some(Cow::Owned(kmer))
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.end - self.position;
+        (remaining, Some(remaining))
+    }
+}
+
+impl<'s> ExactSizeIterator for Kmers<'s> {}
+
+//================================================================================
+// Rolling 2-bit Encoding
+//================================================================================
+
+/// Packs a k-mer into a u64 one 2-bit code at a time updating both the forward k-mer and its
+/// reverse complement in constant time per step.
+///
+/// # Example
+/// ```
+/// use biors::kmer::RollingKmer;
+///
+/// // A = 0, C = 1, G = 2, T = 3
+/// let mut rolling = RollingKmer::new(2);
+///
+/// assert!(!rolling.push(0, 3)); // A
+/// assert!( rolling.push(1, 2)); // C
+///
+/// assert_eq!(rolling.forward(), 0b00_01); // AC
+/// assert_eq!(rolling.reverse(), 0b10_11); // GT
+/// ```
+#[derive(Debug, Clone)]
+pub struct RollingKmer {
+    k: usize,
+    mask: u64,
+    forward: u64,
+    reverse: u64,
+    filled: usize,
+}
+
+impl RollingKmer {
+    /// Constructs a new empty rolling k-mer.
+    ///
+    /// # Panics
+    /// If k is 0 or greater than [MAX_PACKED_K].
+    pub fn new(k: usize) -> Self {
+        assert!(
+            k > 0 && k <= MAX_PACKED_K,
+            "k must be between 1 and {} to pack a k-mer into a u64 but was {}", MAX_PACKED_K, k
+        );
+
+        let mask = if k == MAX_PACKED_K { u64::MAX } else { (1 << (2 * k)) - 1 };
+
+        RollingKmer { k, mask, forward: 0, reverse: 0, filled: 0 }
+    }
+
+    /// The number of symbols in the k-mer.
+    pub fn k(&self) -> usize {
+        self.k
+    }
+
+    /// Appends the 2-bit code of the next symbol to the k-mer dropping the first symbol if it
+    /// was already full. The complement code is prepended to the reverse complement.
+    ///
+    /// Returns true if the k-mer now contains k symbols.
+    #[inline]
+    pub fn push(&mut self, code: u8, complement: u8) -> bool {
+        self.forward = ((self.forward << 2) | code as u64) & self.mask;
+        self.reverse = (self.reverse >> 2) | ((complement as u64) << (2 * (self.k - 1)));
+        self.filled = (self.filled + 1).min(self.k);
+
+        self.is_full()
+    }
+
+    /// Returns true if the k-mer contains k symbols.
+    #[inline]
+    pub fn is_full(&self) -> bool {
+        self.filled == self.k
+    }
+
+    /// Empties the k-mer. Used when a symbol that can't be packed is found.
+    #[inline]
+    pub fn reset(&mut self) {
+        self.forward = 0;
+        self.reverse = 0;
+        self.filled = 0;
+    }
+
+    /// The packed forward k-mer.
+    #[inline]
+    pub fn forward(&self) -> u64 {
+        self.forward
+    }
+
+    /// The packed reverse complement of the k-mer.
+    #[inline]
+    pub fn reverse(&self) -> u64 {
+        self.reverse
+    }
+}
+
+/// Maps the symbols of an alphabet to their 2-bit codes.
+#[derive(Clone)]
+pub struct TwoBitCoding {
+    codes: [u8; 256],
+    complements: [u8; 256],
+}
+
+impl TwoBitCoding {
+    /// Constructs the 2-bit codes for each symbol of the alphabet and their complements.
+    /// Symbols whose complement can't be packed are treated as though they can't be packed
+    /// either.
+    pub fn new<A: Complement>(alphabet: &A) -> Self {
+        let mut codes = [NO_CODE; 256];
+        let mut complements = [NO_CODE; 256];
+
+        for (index, symbol) in alphabet.symbols().iter().enumerate().take(256) {
+            codes[index] = symbol_code(symbol);
+        }
+
+        for (index, complement) in alphabet.complement_indices().into_iter().enumerate().take(256) {
+            complements[index] = codes[complement];
+            if complements[index] == NO_CODE {
+                codes[index] = NO_CODE;
+            }
+        }
+
+        TwoBitCoding { codes, complements }
+    }
+
+    /// The 2-bit code of the encoded byte and the code of its complement or None if the
+    /// symbol can't be packed.
+    #[inline]
+    pub fn code(&self, byte: u8) -> Option<(u8, u8)> {
+        match self.codes[byte as usize] {
+            NO_CODE => None,
+            code => Some((code, self.complements[byte as usize])),
+        }
+    }
+}
+
+/// The 2-bit code of a nucleotide symbol
+fn symbol_code(symbol: &str) -> u8 {
+    match symbol {
+        "A" => 0,
+        "C" => 1,
+        "G" => 2,
+        "T" | "U" => 3,
+        _ => NO_CODE,
+    }
+}
+
+/// Turns a packed k-mer back into a string of nucleotides. T is used for the code 3.
+///
+/// # Example
+/// ```
+/// use biors::kmer::unpack_kmer;
+///
+/// assert_eq!(unpack_kmer(0b00_01_11_10, 4), "ACTG");
+/// ```
+pub fn unpack_kmer(kmer: u64, k: usize) -> String {
+    (0..k).rev()
+        .map(|i| match (kmer >> (2 * i)) & 0b11 {
+            0 => 'A',
+            1 => 'C',
+            2 => 'G',
+            _ => 'T',
+        })
+        .collect()
+}
+
+/// Calculates the reverse complement of a packed k-mer without unpacking it.
+///
+/// # Example
+/// ```
+/// use biors::kmer::reverse_complement_packed;
+///
+/// // AACG -> CGTT
+/// assert_eq!(reverse_complement_packed(0b00_00_01_10, 4), 0b01_10_11_11);
+/// ```
+pub fn reverse_complement_packed(kmer: u64, k: usize) -> u64 {
+    // Complementing a 2-bit code is the same as flipping both of its bits
+    let mut x = !kmer;
+
+    // Reverse the order of the 2-bit codes within each byte and then reverse the bytes
+    x = ((x >> 2) & 0x3333_3333_3333_3333) | ((x & 0x3333_3333_3333_3333) << 2);
+    x = ((x >> 4) & 0x0F0F_0F0F_0F0F_0F0F) | ((x & 0x0F0F_0F0F_0F0F_0F0F) << 4);
+    x = x.swap_bytes();
+
+    x >> (64 - 2 * k)
+}
+
+/// Murmur3's 64-bit finaliser. Scrambles the bits of a packed k-mer so that k-mers sharing a
+/// prefix don't share a hash.
+///
+/// # Notes
+/// The function is invertible so distinct k-mers never collide.
+#[inline]
+pub fn mix64(mut x: u64) -> u64 {
+    x ^= x >> 33;
+    x = x.wrapping_mul(0xff51_afd7_ed55_8ccd);
+    x ^= x >> 33;
+    x = x.wrapping_mul(0xc4ce_b9fe_1a85_ec53);
+    x ^= x >> 33;
+    x
+}
+
+/// A hash function over packed k-mers. Used to choose which k-mers are sampled by schemes such
+/// as [minimizers](self::minimizer).
+///
+/// Any `Fn(u64) -> u64` can be used as a KmerHash.
+pub trait KmerHash {
+    /// Hashes a packed k-mer
+    fn hash_kmer(&self, kmer: u64) -> u64;
+}
+
+impl<F: Fn(u64) -> u64> KmerHash for F {
+    #[inline]
+    fn hash_kmer(&self, kmer: u64) -> u64 {
+        self(kmer)
+    }
+}
+
+/// Hashes packed k-mers using [mix64()]. This avoids the bias towards k-mers like AAAA... that
+/// comes from ordering k-mers lexicographically.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MixHash;
+
+impl KmerHash for MixHash {
+    #[inline]
+    fn hash_kmer(&self, kmer: u64) -> u64 {
+        mix64(kmer)
+    }
+}
+
+/// Uses the packed k-mer itself as its hash which orders k-mers lexicographically.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LexicographicHash;
+
+impl KmerHash for LexicographicHash {
+    #[inline]
+    fn hash_kmer(&self, kmer: u64) -> u64 {
+        kmer
+    }
+}
+
+//================================================================================
+// Packed K-mers
+//================================================================================
+
+/// A k-mer packed into a u64 along with its reverse complement.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PackedKmer {
+    /// Position of the first symbol of the k-mer in the sequence
+    pub position: usize,
+    /// The k-mer as it appears in the sequence
+    pub forward: u64,
+    /// The reverse complement of the k-mer
+    pub reverse: u64,
+}
+
+impl PackedKmer {
+    /// The canonical form of the k-mer, the smaller of the forward k-mer and its reverse
+    /// complement. A k-mer and its reverse complement always share a canonical form.
+    #[inline]
+    pub fn canonical(&self) -> u64 {
+        self.forward.min(self.reverse)
+    }
+
+    /// The strand that the canonical form was taken from.
+    /// Palindromic k-mers are considered to be on the forward strand.
+    #[inline]
+    pub fn strand(&self) -> Strand {
+        if self.forward <= self.reverse {
+            Strand::Forward
+        } else {
+            Strand::Reverse
+        }
+    }
+}
+
+/// An iterator over the k-mers of a nucleotide sequence packed into u64s.
+///
+/// Each step costs O(1) regardless of k. Any k-mer that contains a symbol which can't be packed
+/// (anything other than A, C, G, T or U) is skipped. If the sequence is
+/// [circular](crate::sequence::Sequence::circular) the k-mers that wrap around the origin are
+/// also produced.
+///
+/// # Example
+/// ```
+/// use biors::alphabet::AmbiguousDnaAlphabet;
+/// use biors::sequence::Sequence;
+///
+/// let alphabet = AmbiguousDnaAlphabet;
+/// let mut seq = Sequence::new(&alphabet);
+/// seq.push("ACGNTTA").unwrap();
+///
+/// let positions: Vec<_> = seq.packed_kmers(2).map(|kmer| kmer.position).collect();
+/// assert_eq!(positions, vec![0, 1, 4, 5]);
+/// ```
+#[derive(Clone)]
+pub struct PackedKmers<'s> {
+    bytes: &'s [u8],
+    coding: TwoBitCoding,
+    rolling: RollingKmer,
+    /// Index of the next symbol to push (may be past the end of a circular sequence)
+    index: usize,
+    /// One past the index of the last symbol to push
+    end: usize,
+}
+
+impl<'s> PackedKmers<'s> {
+    /// Constructs an iterator over the packed k-mers of the sequence.
+    ///
+    /// # Panics
+    /// If k is 0 or greater than [MAX_PACKED_K].
+    pub fn new<A: Complement>(seq: &'s Sequence<A>, k: usize) -> Self {
+        let rolling = RollingKmer::new(k);
+        let bytes = seq.as_bytes();
+
+        let count = kmer_count(bytes.len(), k, seq.circular);
+        let end = if count == 0 { 0 } else { count + k - 1 };
+
+        PackedKmers { bytes, coding: TwoBitCoding::new(seq.alphabet()), rolling, index: 0, end }
+    }
+}
+
+impl<'s> Iterator for PackedKmers<'s> {
+    type Item = PackedKmer;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.index < self.end {
+            let byte = self.bytes[self.index % self.bytes.len()];
+            self.index += 1;
+
+            match self.coding.code(byte) {
+                Some((code, complement)) => {
+                    if self.rolling.push(code, complement) {
+                        return Some(PackedKmer {
+                            position: self.index - self.rolling.k(),
+                            forward: self.rolling.forward(),
+                            reverse: self.rolling.reverse(),
+                        });
+                    }
+                }
+                None => self.rolling.reset(),
+            }
+        }
+
+        None
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.end - self.index;
+        (0, Some(remaining))
+    }
+}
+
+//================================================================================
+// Utility Functions
+//================================================================================
+
+/// The number of k-mers in a sequence of length len including those that wrap around the origin
+/// if it is circular.
+pub(crate) fn kmer_count(len: usize, k: usize, circular: bool) -> usize {
+    if k > len {
+        0
+    } else if circular {
+        len
+    } else {
+        len - k + 1
+    }
+}
+
+//================================================================================
+// Tests
+//================================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alphabet::{AmbiguousDnaAlphabet, UnambiguousDnaAlphabet};
+
+    /// Packs a string of nucleotides, the reverse of unpack_kmer
+    fn pack(kmer: &str) -> u64 {
+        kmer.chars().fold(0, |packed, c| (packed << 2) | symbol_code(&c.to_string()) as u64)
+    }
+
+    /// Tests that linear sequences only produce k-mers that fit in the sequence
+    #[test]
+    fn linear_kmer_slices() {
+        let a = UnambiguousDnaAlphabet;
+        let mut s = Sequence::new(&a);
+        s.push("ACTG").unwrap();
+
+        let kmers: Vec<_> = s.kmers(3).collect();
+        assert_eq!(kmers, vec![&[0u8, 1, 2][..], &[1, 2, 3][..]]);
+        assert!(kmers.iter().all(|kmer| matches!(kmer, Cow::Borrowed(_))));
+
+        assert_eq!(s.kmers(5).count(), 0);
+    }
+
+    /// Tests that circular sequences wrap k-mers around the origin
+    #[test]
+    fn circular_kmer_slices() {
+        let a = UnambiguousDnaAlphabet;
+        let mut s = Sequence::new(&a).circular(true);
+        s.push("ACTG").unwrap();
+
+        let kmers: Vec<_> = s.kmers(3).map(|kmer| kmer.into_owned()).collect();
+        assert_eq!(kmers, vec![vec![0, 1, 2], vec![1, 2, 3], vec![2, 3, 0], vec![3, 0, 1]]);
+        assert_eq!(s.kmers(3).len(), 4);
+    }
+
+    /// Tests that the rolling k-mer only keeps the last k symbols
+    #[test]
+    fn rolling_kmer() {
+        let mut rolling = RollingKmer::new(3);
+
+        for c in "GATTC".chars() {
+            let code = symbol_code(&c.to_string());
+            rolling.push(code, 3 - code);
+        }
+
+        assert_eq!(rolling.forward(), pack("TTC"));
+        assert_eq!(rolling.reverse(), pack("GAA"));
+
+        rolling.reset();
+        assert!(!rolling.is_full());
+    }
+
+    /// Tests that a k of 32 uses the full width of the u64
+    #[test]
+    fn rolling_kmer_max_k() {
+        let mut rolling = RollingKmer::new(MAX_PACKED_K);
+
+        for _ in 0..40 {
+            rolling.push(3, 0);
+        }
+
+        assert_eq!(rolling.forward(), u64::MAX);
+        assert_eq!(rolling.reverse(), 0);
+    }
+
+    /// Tests that a k greater than 32 is rejected
+    #[test]
+    #[should_panic]
+    fn rolling_kmer_too_large() {
+        RollingKmer::new(MAX_PACKED_K + 1);
+    }
+
+    /// Tests that packed k-mers match their unpacked form and reverse complement
+    #[test]
+    fn packed_kmers() {
+        let a = UnambiguousDnaAlphabet;
+        let mut s = Sequence::new(&a);
+        s.push("AACGTT").unwrap();
+
+        let kmers: Vec<_> = s.packed_kmers(4)
+            .map(|kmer| (unpack_kmer(kmer.forward, 4), unpack_kmer(kmer.reverse, 4)))
+            .collect();
+
+        let expected = vec![("AACG", "CGTT"), ("ACGT", "ACGT"), ("CGTT", "AACG")];
+        let expected: Vec<_> = expected.into_iter()
+            .map(|(f, r)| (f.to_owned(), r.to_owned()))
+            .collect();
+
+        assert_eq!(kmers, expected);
+    }
+
+    /// Tests that a k-mer and its reverse complement share a canonical form
+    #[test]
+    fn canonical_kmers() {
+        let a = UnambiguousDnaAlphabet;
+        let mut s = Sequence::new(&a);
+        s.push("GATTACA").unwrap();
+
+        let mut rc = s.clone();
+        rc.reverse_complement();
+
+        let mut forward: Vec<_> = s.canonical_kmers(3).map(|(_, kmer)| kmer).collect();
+        let mut reverse: Vec<_> = rc.canonical_kmers(3).map(|(_, kmer)| kmer).collect();
+        forward.sort();
+        reverse.sort();
+
+        assert_eq!(forward, reverse);
+
+        let strands: Vec<_> = s.packed_kmers(3).map(|kmer| kmer.strand()).collect();
+        // GAT/ATC, ATT/AAT, TTA/TAA, TAC/GTA, ACA/TGT
+        assert_eq!(strands, vec![
+            Strand::Reverse, Strand::Reverse, Strand::Reverse, Strand::Reverse, Strand::Forward
+        ]);
+    }
+
+    /// Tests that k-mers containing ambiguous symbols are skipped
+    #[test]
+    fn skips_ambiguous() {
+        let a = AmbiguousDnaAlphabet;
+        let mut s = Sequence::new(&a);
+        s.push("ACGTNACRGTA").unwrap();
+
+        let kmers: Vec<_> = s.packed_kmers(3)
+            .map(|kmer| (kmer.position, unpack_kmer(kmer.forward, 3)))
+            .collect();
+
+        assert_eq!(kmers, vec![
+            (0, "ACG".to_owned()), (1, "CGT".to_owned()), (8, "GTA".to_owned())
+        ]);
+    }
+
+    /// Tests that circular sequences produce packed k-mers that wrap around the origin
+    #[test]
+    fn circular_packed_kmers() {
+        let a = AmbiguousDnaAlphabet;
+        let mut s = Sequence::new(&a).circular(true);
+        s.push("ACGTN").unwrap();
+        let kmers: Vec<_> = s.packed_kmers(2).map(|kmer| kmer.position).collect();
+        assert_eq!(kmers, vec![0, 1, 2]);
+
+        s.clear();
+        s.push("TNACG").unwrap();
+        let kmers: Vec<_> = s.packed_kmers(3)
+            .map(|kmer| (kmer.position, unpack_kmer(kmer.forward, 3)))
+            .collect();
+
+        assert_eq!(kmers, vec![(2, "ACG".to_owned()), (3, "CGT".to_owned())]);
+    }
+
+    /// Tests that reversing a packed k-mer agrees with the rolling reverse complement
+    #[test]
+    fn packed_reverse_complement() {
+        let a = UnambiguousDnaAlphabet;
+        let mut s = Sequence::new(&a);
+        s.push("GATTACAGGCATTACCAGTAGGACTTATGCATCCA").unwrap();
+
+        for k in &[1, 5, 31, 32] {
+            for kmer in s.packed_kmers(*k) {
+                assert_eq!(reverse_complement_packed(kmer.forward, *k), kmer.reverse);
+            }
+        }
+    }
+}
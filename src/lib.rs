@@ -1,6 +1,16 @@
 extern crate num_traits;
 extern crate bimap;
 
+pub mod align;
 pub mod alphabet;
+pub mod codon;
+pub mod distance;
+pub mod kmer;
+pub mod motif;
+pub mod primer;
+pub mod restriction;
+pub mod search;
 pub mod sequence;
+pub mod sketch;
+pub mod stats;
 
@@ -0,0 +1,255 @@
+//! The JASPAR count matrix format.
+//!
+//! ```text
+//! >MA0004.1 Arnt
+//! A  [     4     19      0      0      0      0 ]
+//! C  [    16      0     20      0      0      0 ]
+//! G  [     0      1      0     20      0     20 ]
+//! T  [     0      0      0      0     20      0 ]
+//! ```
+//!
+//! Each row gives the counts of one symbol at every position. The raw form without headers,
+//! symbols or brackets is also read, in which case the rows are in the alphabetical order of the
+//! symbols.
+
+use crate::alphabet::Alphabet;
+use super::{sorted_symbols, symbol_index, ErrorKind, Motif, MotifError, Pfm, Result};
+
+/// The rows of a motif as they are read
+struct PendingMotif<'t> {
+    id: &'t str,
+    name: &'t str,
+    line: usize,
+    /// The symbol, counts and line of each row
+    rows: Vec<(Option<&'t str>, Vec<f64>, usize)>,
+}
+
+impl<'t> PendingMotif<'t> {
+    fn finish<'a, A: Alphabet>(self, alphabet: &'a A) -> Result<Motif<'a, A>> {
+        let size = alphabet.symbols().len();
+        let len = self.rows.first().map_or(0, |(_, counts, _)| counts.len());
+        let mut counts = vec![vec![0.0; size]; len];
+
+        let unlabelled = self.rows.iter().all(|(symbol, _, _)| symbol.is_none());
+        if unlabelled && self.rows.len() != size {
+            let description = format!(
+                "Line {}: the motif has {} unlabelled rows but the alphabet has {} symbols",
+                self.line, self.rows.len(), size
+            );
+            return Err(MotifError::new(ErrorKind::Parse, description));
+        }
+
+        let order = sorted_symbols(alphabet);
+        for (row, (symbol, row_counts, line)) in self.rows.into_iter().enumerate() {
+            let index = match symbol {
+                Some(symbol) => symbol_index(alphabet, symbol, line)?,
+                None if unlabelled => order[row],
+                None => {
+                    let description = format!("Line {}: the row has no symbol", line);
+                    return Err(MotifError::new(ErrorKind::Parse, description));
+                }
+            };
+
+            if row_counts.len() != len {
+                let description = format!(
+                    "Line {}: the row has {} counts but the first has {}",
+                    line,
+                    row_counts.len(),
+                    len
+                );
+                return Err(MotifError::new(ErrorKind::LengthMismatch, description));
+            }
+            for (position, count) in row_counts.into_iter().enumerate() {
+                counts[position][index] = count;
+            }
+        }
+
+        Ok(Motif {
+            id: self.id.to_owned(),
+            name: self.name.to_owned(),
+            pfm: Pfm::new(alphabet, counts),
+        })
+    }
+}
+
+/// Reads every motif in a JASPAR file.
+///
+/// # Errors
+/// If a count isn't a non-negative number, a row's symbol isn't in the alphabet, the rows of a
+/// motif have different lengths or a motif without symbols doesn't have a row for each symbol.
+///
+/// # Example
+/// ```
+/// use biors::alphabet::UnambiguousDnaAlphabet;
+/// use biors::motif::jaspar;
+///
+/// let dna = UnambiguousDnaAlphabet;
+/// let text = ">MA0004.1 Arnt
+/// A  [ 4 19  0  0  0  0 ]
+/// C  [16  0 20  0  0  0 ]
+/// G  [ 0  1  0 20  0 20 ]
+/// T  [ 0  0  0  0 20  0 ]
+/// ";
+/// let motifs = jaspar::read(text, &dna).unwrap();
+///
+/// assert_eq!((motifs[0].id.as_str(), motifs[0].name.as_str()), ("MA0004.1", "Arnt"));
+/// assert_eq!(motifs[0].pfm.consensus(), "CACGTG");
+/// assert!(jaspar::write(&motifs).starts_with(">MA0004.1 Arnt\nA  [      4     19      0"));
+/// ```
+pub fn read<'a, A: Alphabet>(text: &str, alphabet: &'a A) -> Result<Vec<Motif<'a, A>>> {
+    let mut motifs = vec![];
+    let mut pending: Option<PendingMotif> = None;
+
+    for (line_number, line) in text.lines().enumerate() {
+        let line_number = line_number + 1;
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        if let Some(header) = line.strip_prefix('>') {
+            if let Some(motif) = pending.take() {
+                motifs.push(motif.finish(alphabet)?);
+            }
+            let header = header.trim();
+            let (id, name) = header.split_once(char::is_whitespace).unwrap_or((header, ""));
+            pending = Some(PendingMotif { id, name: name.trim(), line: line_number, rows: vec![] });
+            continue;
+        }
+
+        let mut fields = line.split(|c: char| c.is_whitespace() || c == '[' || c == ']')
+            .filter(|field| !field.is_empty())
+            .peekable();
+        let symbol = match fields.peek() {
+            Some(field) if field.parse::<f64>().is_err() => fields.next(),
+            _ => None,
+        };
+        let counts = fields
+            .map(|field| match field.parse::<f64>() {
+                Ok(count) if count >= 0.0 => Ok(count),
+                _ => {
+                    let description = format!("Line {}: {:?} is not a count", line_number, field);
+                    Err(MotifError::new(ErrorKind::Parse, description))
+                }
+            })
+            .collect::<Result<Vec<f64>>>()?;
+
+        let motif = pending.get_or_insert_with(|| {
+            PendingMotif { id: "", name: "", line: line_number, rows: vec![] }
+        });
+        motif.rows.push((symbol, counts, line_number));
+    }
+
+    if let Some(motif) = pending {
+        motifs.push(motif.finish(alphabet)?);
+    }
+
+    Ok(motifs)
+}
+
+/// Formats a count with as many digits as it needs to be read back exactly, so whole numbers
+/// have no decimal point
+fn format_count(count: f64) -> String {
+    format!("{:6}", count)
+}
+
+/// Writes motifs in JASPAR format with the rows in alphabetical order of their symbols.
+pub fn write<A: Alphabet>(motifs: &[Motif<A>]) -> String {
+    let mut out = String::new();
+
+    for motif in motifs {
+        out.push('>');
+        out.push_str(&motif.id);
+        if !motif.name.is_empty() {
+            out.push(' ');
+            out.push_str(&motif.name);
+        }
+        out.push('\n');
+
+        let symbols = motif.pfm.alphabet().symbols();
+        for index in sorted_symbols(motif.pfm.alphabet()) {
+            out.push_str(symbols[index]);
+            out.push_str("  [");
+            for row in motif.pfm.counts() {
+                out.push(' ');
+                out.push_str(&format_count(row[index]));
+            }
+            out.push_str(" ]\n");
+        }
+    }
+
+    out
+}
+
+//================================================================================
+// Tests
+//================================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alphabet::UnambiguousDnaAlphabet;
+
+    /// Tests reading several motifs including the raw form and writing them back
+    #[test]
+    fn round_trip() {
+        let dna = UnambiguousDnaAlphabet;
+        let text = ">MA0001.1 AGL3
+A  [ 0  3 79 40 66 48 65 11 65  0 ]
+C  [94 75  4  3  1  2  5  2  3  3 ]
+G  [ 1  0  3  4  1  0  5  3 28 88 ]
+T  [ 2 19 11 50 29 47 22 81  1  6 ]
+
+>MA0002.1 RUNX1
+A[10 12  4  1  2  2  0  0  0  8 13 ]
+C[ 2  2  7  1  0  8  0  0  1  2  2 ]
+G[ 3  1  1  0 23  0 26 26  0  0  4 ]
+T[11 11 14 24  1 16  0  0 25 16  7 ]
+";
+        let motifs = read(text, &dna).unwrap();
+        assert_eq!(motifs.len(), 2);
+        assert_eq!(motifs[1].name, "RUNX1");
+        assert_eq!(motifs[0].pfm.consensus(), "CCATAAATAG");
+        assert_eq!(motifs[1].pfm.len(), 11);
+
+        let read_back = read(&write(&motifs), &dna).unwrap();
+        for (motif, other) in motifs.iter().zip(&read_back) {
+            assert_eq!((&motif.id, &motif.name), (&other.id, &other.name));
+            assert_eq!(motif.pfm.counts(), other.pfm.counts());
+        }
+
+        let raw = read("0.5 1 2\n0 0 0\n3 1 0\n1 0 0\n", &dna).unwrap();
+        assert_eq!(raw[0].id, "");
+        // Rows are A, C, G, T and the alphabet is A, C, T, G
+        assert_eq!(raw[0].pfm.counts()[0], vec![0.5, 0.0, 1.0, 3.0]);
+        assert!(write(&raw).contains("\nC  [      0      0      0 ]\n"));
+        assert!(write(&raw).contains("A  [    0.5      1      2 ]\n"));
+
+        let counts = vec![vec![1.0 / 3.0, 2.0 / 3.0, 0.1, 1e-9]];
+        let thirds = Motif {
+            id: "thirds".to_owned(),
+            name: String::new(),
+            pfm: Pfm::new(&dna, counts.clone()),
+        };
+        assert_eq!(read(&write(&[thirds]), &dna).unwrap()[0].pfm.counts(), &counts[..]);
+    }
+
+    /// Tests the errors for malformed motifs
+    #[test]
+    fn errors() {
+        let dna = UnambiguousDnaAlphabet;
+
+        let err = read(">m\nA [1 2]\nC [1 x]\n", &dna).unwrap_err();
+        assert_eq!(err.kind(), &ErrorKind::Parse);
+        assert!(err.description().starts_with("Line 3"));
+
+        let err = read(">m\nA [1 2]\nU [1 2]\n", &dna).unwrap_err();
+        assert_eq!(err.kind(), &ErrorKind::MissingSymbol("U".to_owned()));
+
+        let err = read(">m\nA [1 2]\nC [1 2 3]\n", &dna).unwrap_err();
+        assert_eq!(err.kind(), &ErrorKind::LengthMismatch);
+
+        let err = read(">m\n1 2\n1 2\n", &dna).unwrap_err();
+        assert_eq!(err.kind(), &ErrorKind::Parse);
+    }
+}
@@ -0,0 +1,407 @@
+//! The MEME motif format read by the MEME suite tools such as FIMO and Tomtom.
+//!
+//! ```text
+//! MEME version 4
+//!
+//! ALPHABET= ACGT
+//!
+//! Background letter frequencies
+//! A 0.3 C 0.2 G 0.2 T 0.3
+//!
+//! MOTIF MA0004.1 Arnt
+//! letter-probability matrix: alength= 4 w= 3 nsites= 20 E= 0
+//!  0.200000  0.800000  0.000000  0.000000
+//!  0.950000  0.000000  0.050000  0.000000
+//!  0.000000  1.000000  0.000000  0.000000
+//! ```
+//!
+//! Each row of a matrix gives the probabilities of the symbols at one position in the order of
+//! the ALPHABET line. The counts of the [Pfm] are the probabilities multiplied by the number of
+//! sites. Only alphabets with single character symbols can be written.
+
+use std::fmt;
+use std::fmt::Write;
+use std::str::FromStr;
+use crate::alphabet::Alphabet;
+use super::{sorted_symbols, symbol_index, ErrorKind, Motif, MotifError, Pfm, Result};
+
+/// The number of sites assumed when a matrix doesn't give nsites, the same as MEME
+const DEFAULT_SITES: f64 = 20.0;
+
+/// The motifs and background frequencies in a MEME file.
+pub struct MemeFile<'a, A: Alphabet> {
+    /// The background frequency of each symbol, in the order of [Alphabet::symbols()], if the
+    /// file gives them. Symbols the file doesn't mention have a frequency of 0.
+    pub background: Option<Vec<f64>>,
+    /// Every motif in the order they appear
+    pub motifs: Vec<Motif<'a, A>>,
+}
+
+impl<'a, A: Alphabet> Clone for MemeFile<'a, A> {
+    fn clone(&self) -> Self {
+        MemeFile { background: self.background.clone(), motifs: self.motifs.clone() }
+    }
+}
+
+impl<'a, A: Alphabet> fmt::Debug for MemeFile<'a, A> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("MemeFile")
+            .field("background", &self.background)
+            .field("motifs", &self.motifs)
+            .finish()
+    }
+}
+
+/// Finds the number after key= in a letter-probability matrix line
+fn matrix_setting<T: FromStr>(line: &str, key: &str, line_number: usize) -> Result<Option<T>> {
+    let start = match line.find(key) {
+        Some(start) => start + key.len(),
+        None => return Ok(None),
+    };
+    let value = line[start..].split_whitespace().next().unwrap_or("");
+    value.parse().map(Some).map_err(|_| {
+        let description = format!("Line {}: {:?} is not a valid {} value", line_number, value, key);
+        MotifError::new(ErrorKind::Parse, description)
+    })
+}
+
+/// Parses a number with an error naming the line
+fn parse_number(field: &str, line: usize) -> Result<f64> {
+    match field.parse::<f64>() {
+        Ok(value) if value >= 0.0 => Ok(value),
+        _ => {
+            let description = format!("Line {}: {:?} is not a probability", line, field);
+            Err(MotifError::new(ErrorKind::Parse, description))
+        }
+    }
+}
+
+/// Reads the motifs and background from a MEME file.
+///
+/// # Errors
+/// If the file doesn't start with a MEME version line, the ALPHABET line or background has a
+/// symbol that isn't in the alphabet, a number can't be read, a matrix row doesn't have a
+/// probability for each symbol, the width or number of sites of a matrix is invalid, or a matrix
+/// has more or fewer rows than its width.
+///
+/// # Example
+/// ```
+/// use biors::alphabet::UnambiguousDnaAlphabet;
+/// use biors::motif::meme;
+///
+/// let dna = UnambiguousDnaAlphabet;
+/// let text = "MEME version 4\n\nALPHABET= ACGT\n\n\
+///             MOTIF m1 GATA\n\
+///             letter-probability matrix: alength= 4 w= 2 nsites= 10\n\
+///             0.0 0.1 0.9 0.0\n\
+///             1.0 0.0 0.0 0.0\n";
+/// let file = meme::read(text, &dna).unwrap();
+///
+/// assert_eq!(file.motifs[0].name, "GATA");
+/// // The alphabet is ordered A, C, T, G
+/// assert_eq!(file.motifs[0].pfm.counts()[0], vec![0.0, 1.0, 0.0, 9.0]);
+/// ```
+pub fn read<'a, A: Alphabet>(text: &str, alphabet: &'a A) -> Result<MemeFile<'a, A>> {
+    let size = alphabet.symbols().len();
+    let mut lines = text.lines()
+        .enumerate()
+        .map(|(line_number, line)| (line_number + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty());
+
+    match lines.next() {
+        Some((_, line)) if line.starts_with("MEME version") => {}
+        _ => {
+            let description = "The file must start with a MEME version line".to_owned();
+            return Err(MotifError::new(ErrorKind::Parse, description));
+        }
+    }
+
+    let mut columns = sorted_symbols(alphabet);
+    let mut background = None;
+    let mut motifs = vec![];
+    let mut current: Option<(&str, &str)> = None;
+    // Whether the previous line was the last row of a letter-probability matrix
+    let mut after_matrix = false;
+
+    while let Some((line_number, line)) = lines.next() {
+        let is_row = line.split_whitespace()
+            .next()
+            .is_some_and(|field| field.parse::<f64>().is_ok());
+        if after_matrix && is_row {
+            let description =
+                format!("Line {}: the matrix has more rows than its width", line_number);
+            return Err(MotifError::new(ErrorKind::Parse, description));
+        }
+        after_matrix = false;
+
+        if let Some(letters) = line.strip_prefix("ALPHABET=") {
+            columns = letters.trim()
+                .char_indices()
+                .map(|(i, c)| {
+                    symbol_index(alphabet, &letters.trim()[i..i + c.len_utf8()], line_number)
+                })
+                .collect::<Result<_>>()?;
+        } else if line.starts_with("Background letter frequencies") {
+            // The frequencies are symbol and value pairs on the following lines
+            let mut frequencies = vec![0.0; size];
+            let mut found = 0;
+            while found < columns.len() {
+                let (line_number, line) = lines.next().ok_or_else(|| {
+                    let description = "The file ended in the background frequencies".to_owned();
+                    MotifError::new(ErrorKind::Parse, description)
+                })?;
+                let fields: Vec<&str> = line.split_whitespace().collect();
+                for pair in fields.chunks(2) {
+                    let index = symbol_index(alphabet, pair[0], line_number)?;
+                    let value = pair.get(1).ok_or_else(|| {
+                        let description =
+                            format!("Line {}: {} has no frequency", line_number, pair[0]);
+                        MotifError::new(ErrorKind::Parse, description)
+                    })?;
+                    frequencies[index] = parse_number(value, line_number)?;
+                    found += 1;
+                }
+            }
+            background = Some(frequencies);
+        } else if let Some(header) = line.strip_prefix("MOTIF") {
+            let header = header.trim();
+            current = Some(header.split_once(char::is_whitespace).unwrap_or((header, "")));
+        } else if line.starts_with("letter-probability matrix") {
+            let (id, name) = current.take().ok_or_else(|| {
+                let description = format!("Line {}: the matrix has no MOTIF line", line_number);
+                MotifError::new(ErrorKind::Parse, description)
+            })?;
+            let width: usize = matrix_setting(line, "w=", line_number)?.ok_or_else(|| {
+                let description =
+                    format!("Line {}: the matrix must give its width with w=", line_number);
+                MotifError::new(ErrorKind::Parse, description)
+            })?;
+            let sites: f64 = matrix_setting(line, "nsites=", line_number)?.unwrap_or(DEFAULT_SITES);
+            if !sites.is_finite() || sites < 0.0 {
+                let description =
+                    format!("Line {}: {} is not a number of sites", line_number, sites);
+                return Err(MotifError::new(ErrorKind::Parse, description));
+            }
+
+            let mut counts = vec![];
+            for _ in 0..width {
+                let (line_number, line) = lines.next().ok_or_else(|| {
+                    let description =
+                        format!("The file ended before the {} rows of motif {}", width, id);
+                    MotifError::new(ErrorKind::Parse, description)
+                })?;
+                let fields: Vec<&str> = line.split_whitespace().collect();
+                if fields.len() != columns.len() {
+                    let description = format!(
+                        "Line {}: the row has {} probabilities but the alphabet has {} symbols",
+                        line_number, fields.len(), columns.len()
+                    );
+                    return Err(MotifError::new(ErrorKind::LengthMismatch, description));
+                }
+
+                let mut row = vec![0.0; size];
+                for (&index, field) in columns.iter().zip(fields) {
+                    row[index] = parse_number(field, line_number)? * sites;
+                }
+                counts.push(row);
+            }
+
+            motifs.push(Motif {
+                id: id.to_owned(),
+                name: name.trim().to_owned(),
+                pfm: Pfm::new(alphabet, counts),
+            });
+            after_matrix = true;
+        }
+    }
+
+    Ok(MemeFile { background, motifs })
+}
+
+/// Writes motifs in MEME format with the symbols in alphabetical order, which is the order
+/// MEME uses for DNA and protein. The background is written if one is given.
+///
+/// # Panics
+/// If the background doesn't have a frequency for each symbol.
+pub fn write<A: Alphabet>(motifs: &[Motif<A>], background: Option<&[f64]>) -> String {
+    let mut out = "MEME version 4\n\n".to_owned();
+
+    if let Some(first) = motifs.first() {
+        let alphabet = first.pfm.alphabet();
+        let symbols = alphabet.symbols();
+        let order = sorted_symbols(alphabet);
+
+        let letters: String = order.iter().map(|&index| symbols[index]).collect();
+        writeln!(out, "ALPHABET= {}\n", letters).unwrap();
+
+        if let Some(background) = background {
+            assert_eq!(
+                background.len(),
+                symbols.len(),
+                "The background must have a frequency for each symbol."
+            );
+            let pairs: Vec<String> = order.iter()
+                .map(|&index| format!("{} {:.3}", symbols[index], background[index]))
+                .collect();
+            writeln!(out, "Background letter frequencies\n{}\n", pairs.join(" ")).unwrap();
+        }
+    }
+
+    for motif in motifs {
+        let order = sorted_symbols(motif.pfm.alphabet());
+        let sites = motif.pfm.num_sites();
+
+        if motif.name.is_empty() {
+            writeln!(out, "MOTIF {}", motif.id).unwrap();
+        } else {
+            writeln!(out, "MOTIF {} {}", motif.id, motif.name).unwrap();
+        }
+        writeln!(
+            out,
+            "letter-probability matrix: alength= {} w= {} nsites= {} E= 0",
+            order.len(), motif.pfm.len(), sites
+        ).unwrap();
+
+        for row in motif.pfm.counts() {
+            let total: f64 = row.iter().sum();
+            for &index in &order {
+                let probability =
+                    if total > 0.0 { row[index] / total } else { 1.0 / order.len() as f64 };
+                write!(out, " {:.6}", probability).unwrap();
+            }
+            out.push('\n');
+        }
+        out.push('\n');
+    }
+
+    out
+}
+
+//================================================================================
+// Tests
+//================================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alphabet::{ProteinAlphabet, UnambiguousDnaAlphabet};
+
+    const EXAMPLE: &str = "MEME version 4
+
+ALPHABET= ACGT
+
+strands: + -
+
+Background letter frequencies
+A 0.303 C 0.183 G 0.209
+T 0.306
+
+MOTIF crp
+letter-probability matrix: alength= 4 w= 4 nsites= 17 E= 4.1e-009
+ 0.000000  0.176471  0.000000  0.823529
+ 0.000000  0.058824  0.647059  0.294118
+ 0.000000  0.058824  0.000000  0.941176
+ 0.176471  0.000000  0.764706  0.058824
+
+MOTIF lexA LexA repressor
+letter-probability matrix: alength= 4 w= 2
+ 0.25 0.25 0.25 0.25
+ 0.5 0.5 0.0 0.0
+";
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!((actual - expected).abs() < 1e-4, "{} != {}", actual, expected);
+    }
+
+    /// Tests reading a file with a background and two motifs and writing it back
+    #[test]
+    fn round_trip() {
+        let dna = UnambiguousDnaAlphabet;
+        let file = read(EXAMPLE, &dna).unwrap();
+
+        // A, C, T, G
+        let background = file.background.clone().unwrap();
+        assert_eq!(background, vec![0.303, 0.183, 0.306, 0.209]);
+        assert_eq!(file.motifs.len(), 2);
+        assert_eq!((file.motifs[0].id.as_str(), file.motifs[0].name.as_str()), ("crp", ""));
+        assert_eq!(file.motifs[1].name, "LexA repressor");
+        assert_close(file.motifs[0].pfm.counts()[0][2], 14.0);
+        assert_close(file.motifs[1].pfm.num_sites(), DEFAULT_SITES);
+        assert_eq!(file.motifs[0].pfm.consensus(), "TGTG");
+
+        let written = write(&file.motifs, Some(&background));
+        assert!(written.contains("A 0.303 C 0.183 G 0.209 T 0.306\n"));
+        assert!(written.contains(concat!(
+            "MOTIF lexA LexA repressor\n",
+            "letter-probability matrix: alength= 4 w= 2 nsites= 20 E= 0\n"
+        )));
+
+        let read_back = read(&written, &dna).unwrap();
+        assert_eq!(read_back.background, file.background);
+        for (motif, other) in file.motifs.iter().zip(&read_back.motifs) {
+            assert_eq!((&motif.id, &motif.name), (&other.id, &other.name));
+            for (row, other_row) in motif.pfm.counts().iter().zip(other.pfm.counts()) {
+                for (&count, &other_count) in row.iter().zip(other_row) {
+                    assert!((count - other_count).abs() < 1e-4);
+                }
+            }
+        }
+    }
+
+    /// Tests that files without an ALPHABET line use alphabetical order
+    #[test]
+    fn protein() {
+        let protein = ProteinAlphabet;
+        let mut text =
+            "MEME version 5\nMOTIF p\nletter-probability matrix: w= 1 nsites= 4\n".to_owned();
+        text.push_str(&format!("1{}\n", " 0".repeat(19)));
+
+        let file = read(&text, &protein).unwrap();
+        assert!(file.background.is_none());
+        assert_eq!(file.motifs[0].pfm.consensus(), "A");
+        assert_close(file.motifs[0].pfm.num_sites(), 4.0);
+    }
+
+    /// Tests the errors for malformed files
+    #[test]
+    fn errors() {
+        let dna = UnambiguousDnaAlphabet;
+        let header = "MEME version 4\nALPHABET= ACGT\n";
+        let matrix = format!("{}MOTIF m\nletter-probability matrix: ", header);
+
+        assert_eq!(read("MOTIF m\n", &dna).unwrap_err().kind(), &ErrorKind::Parse);
+        assert_eq!(
+            read("MEME version 4\nALPHABET= ACGU\n", &dna).unwrap_err().kind(),
+            &ErrorKind::MissingSymbol("U".to_owned())
+        );
+
+        let err = read(&format!("{}w= 1\n0.5 0.5 0\n", matrix), &dna).unwrap_err();
+        assert_eq!(err.kind(), &ErrorKind::LengthMismatch);
+        assert!(err.description().starts_with("Line 5"));
+
+        let err = read(&format!("{}w= 2\n1 0 0 0\n", matrix), &dna).unwrap_err();
+        assert_eq!(err.kind(), &ErrorKind::Parse);
+
+        let huge = format!("{}w= 1e18\n1 0 0 0\n", matrix);
+        assert_eq!(read(&huge, &dna).unwrap_err().kind(), &ErrorKind::Parse);
+
+        let err = read(&format!("{}nsites= 2\n", matrix), &dna).unwrap_err();
+        assert_eq!(err.kind(), &ErrorKind::Parse);
+
+        let settings = [
+            "w= 2.5", "w= -3", "w= 1 nsites= -1", "w= 1 nsites= nan", "w= 1 nsites= inf"
+        ];
+        for setting in &settings {
+            let text = format!("{}{}\n1 0 0 0\n1 0 0 0\n", matrix, setting);
+            assert_eq!(read(&text, &dna).unwrap_err().kind(), &ErrorKind::Parse, "{}", setting);
+        }
+
+        let err = read(&format!("{}w= 1\n1 0 0 0\n0 1 0 0\n", matrix), &dna).unwrap_err();
+        assert_eq!(err.kind(), &ErrorKind::Parse);
+        assert!(err.description().starts_with("Line 6"));
+
+        let err = read(&format!("{}letter-probability matrix: w= 1\n1 0 0 0\n", header), &dna)
+            .unwrap_err();
+        assert_eq!(err.kind(), &ErrorKind::Parse);
+    }
+}
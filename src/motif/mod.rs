@@ -0,0 +1,872 @@
+//! Sequence motifs, such as transcription factor binding sites, described by how often each
+//! symbol appears at each position.
+//!
+//! A [Pfm] (position frequency matrix) counts the symbols at each position of a set of aligned
+//! sites. Adding pseudocounts and comparing with the background frequencies of the symbols turns
+//! it into a [Pwm] (position weight matrix) of log-odds scores in bits which can be used to scan
+//! sequences for new sites. The distribution of scores of random sequences drawn from the
+//! background is calculated so hits can be filtered by p-value as well as by score.
+//!
+//! Motifs can be read from and written to the [jaspar] and [meme] formats used by public motif
+//! databases.
+
+use std::error::Error;
+use std::fmt;
+use std::sync::OnceLock;
+use crate::alphabet::{Alphabet, Complement};
+use crate::sequence::{Sequence, Strand};
+
+pub mod jaspar;
+pub mod meme;
+
+/// Result type for building and reading motifs
+pub type Result<T> = std::result::Result<T, MotifError>;
+
+/// The width in bits of each bin of the distribution of scores used for p-values
+const SCORE_RESOLUTION: f64 = 0.01;
+
+/// A background where every symbol of the alphabet is equally likely.
+pub fn uniform_background<A: Alphabet>(alphabet: &A) -> Vec<f64> {
+    let size = alphabet.symbols().len();
+    vec![1.0 / size as f64; size]
+}
+
+/// The index of each symbol in the order they are sorted, which for DNA is the A, C, G, T
+/// order used by motif databases
+fn sorted_symbols<A: Alphabet>(alphabet: &A) -> Vec<usize> {
+    let symbols = alphabet.symbols();
+    let mut order: Vec<usize> = (0..symbols.len()).collect();
+    order.sort_by_key(|&index| symbols[index]);
+    order
+}
+
+/// Finds the symbol in the alphabet, with an error naming the line if it's missing
+fn symbol_index<A: Alphabet>(alphabet: &A, symbol: &str, line: usize) -> Result<usize> {
+    alphabet.symbols().iter().position(|&other| other == symbol).ok_or_else(|| {
+        let description = format!("Line {}: the symbol {} is not in the alphabet", line, symbol);
+        MotifError::new(ErrorKind::MissingSymbol(symbol.to_owned()), description)
+    })
+}
+
+/// The index of the complement of each symbol
+fn complement_indices<A: Complement>(alphabet: &A) -> Vec<usize> {
+    let symbols = alphabet.symbols();
+
+    alphabet.complement_mapping()
+        .iter()
+        .map(|complement| {
+            symbols.iter()
+                .position(|symbol| symbol == complement)
+                .expect("The complement of every symbol must be in the alphabet")
+        })
+        .collect()
+}
+
+/// The symbol with the highest value at each position, the first in the alphabet on a tie
+fn consensus<A: Alphabet>(alphabet: &A, rows: &[Vec<f64>]) -> String {
+    let symbols = alphabet.symbols();
+
+    rows.iter()
+        .map(|row| {
+            let best = row.iter()
+                .enumerate()
+                .fold(0, |best, (index, &value)| if value > row[best] { index } else { best });
+            symbols[best]
+        })
+        .collect()
+}
+
+//================================================================================
+// Position Frequency Matrix
+//================================================================================
+
+/// A position frequency matrix: the number of times each symbol of the alphabet is seen at each
+/// position of a motif. Counts are stored as floating point numbers as some databases give
+/// fractional counts.
+///
+/// # Example
+/// ```
+/// use biors::alphabet::UnambiguousDnaAlphabet;
+/// use biors::motif::Pfm;
+/// use biors::sequence::Sequence;
+///
+/// let dna = UnambiguousDnaAlphabet;
+/// let sites: Vec<_> = ["TACGTA", "TACGTG", "AACGTA"].iter()
+///     .map(|site| {
+///         let mut seq = Sequence::new(&dna);
+///         seq.push(site).unwrap();
+///         seq
+///     })
+///     .collect();
+///
+/// let pfm = Pfm::from_sites(&dna, &sites).unwrap();
+/// assert_eq!(pfm.len(), 6);
+/// assert_eq!(pfm.consensus(), "TACGTA");
+/// // The alphabet is ordered A, C, T, G
+/// assert_eq!(pfm.counts()[0], vec![1.0, 0.0, 2.0, 0.0]);
+/// ```
+pub struct Pfm<'a, A: Alphabet> {
+    alphabet: &'a A,
+    counts: Vec<Vec<f64>>,
+}
+
+impl<'a, A: Alphabet> Pfm<'a, A> {
+    /// Constructs a matrix from the count of each symbol, in the order of
+    /// [Alphabet::symbols()], at each position.
+    ///
+    /// # Panics
+    /// If a position doesn't have a count for every symbol or a count is negative.
+    pub fn new(alphabet: &'a A, counts: Vec<Vec<f64>>) -> Self {
+        let size = alphabet.symbols().len();
+        for (position, row) in counts.iter().enumerate() {
+            assert_eq!(
+                row.len(),
+                size,
+                "Position {} must have a count for each of the {} symbols.",
+                position,
+                size
+            );
+            let non_negative = row.iter().all(|&count| count >= 0.0);
+            assert!(non_negative, "Position {} has a negative count.", position);
+        }
+
+        Pfm { alphabet, counts }
+    }
+
+    /// Counts the symbols at each position of a set of sites.
+    ///
+    /// # Errors
+    /// If there are no sites or they don't all have the same length.
+    pub fn from_sites(alphabet: &'a A, sites: &[Sequence<A>]) -> Result<Self> {
+        let len = match sites.first() {
+            Some(site) => site.len(),
+            None => {
+                let description = "There are no sites to count".to_owned();
+                return Err(MotifError::new(ErrorKind::NoSites, description));
+            }
+        };
+
+        let mut counts = vec![vec![0.0; alphabet.symbols().len()]; len];
+        for (index, site) in sites.iter().enumerate() {
+            if site.len() != len {
+                let description = format!(
+                    "Site {} has length {} but site 0 has length {}", index, site.len(), len);
+                return Err(MotifError::new(ErrorKind::LengthMismatch, description));
+            }
+
+            for (row, &symbol) in counts.iter_mut().zip(site.as_bytes()) {
+                row[symbol as usize] += 1.0;
+            }
+        }
+
+        Ok(Pfm { alphabet, counts })
+    }
+
+    /// The alphabet of the motif.
+    pub fn alphabet(&self) -> &'a A {
+        self.alphabet
+    }
+
+    /// The number of positions.
+    pub fn len(&self) -> usize {
+        self.counts.len()
+    }
+
+    /// Returns true if there are no positions.
+    pub fn is_empty(&self) -> bool {
+        self.counts.is_empty()
+    }
+
+    /// The count of each symbol at each position.
+    pub fn counts(&self) -> &[Vec<f64>] {
+        &self.counts
+    }
+
+    /// The number of sites, taken as the largest total count of any position.
+    pub fn num_sites(&self) -> f64 {
+        self.counts.iter().map(|row| row.iter().sum()).fold(0.0, f64::max)
+    }
+
+    /// The most common symbol at each position.
+    pub fn consensus(&self) -> String {
+        consensus(self.alphabet, &self.counts)
+    }
+
+    /// Converts the counts to log-odds scores against a background. The probability of symbol
+    /// a at a position is (count + pseudocount * background\[a\]) / (total + pseudocount) so
+    /// the pseudocount is shared between the symbols in proportion to the background.
+    ///
+    /// # Panics
+    /// If the background doesn't have a positive frequency for each symbol, the pseudocount is
+    /// negative or a position has no counts and the pseudocount is 0.
+    pub fn pwm(&self, pseudocount: f64, background: &[f64]) -> Pwm<'a, A> {
+        assert!(pseudocount >= 0.0, "The pseudocount must not be negative.");
+
+        let probabilities = self.counts.iter()
+            .enumerate()
+            .map(|(position, row)| {
+                let total = row.iter().sum::<f64>() + pseudocount;
+                assert!(total > 0.0, "Position {} has no counts so needs a pseudocount.", position);
+                row.iter()
+                    .zip(background)
+                    .map(|(count, frequency)| (count + pseudocount * frequency) / total)
+                    .collect()
+            })
+            .collect();
+
+        Pwm::new(self.alphabet, probabilities, background)
+    }
+}
+
+impl<'a, A: Complement> Pfm<'a, A> {
+    /// The matrix for the reverse complement of the motif.
+    pub fn reverse_complement(&self) -> Self {
+        let complement = complement_indices(self.alphabet);
+        let counts = self.counts.iter()
+            .rev()
+            .map(|row| complement.iter().map(|&index| row[index]).collect())
+            .collect();
+
+        Pfm { alphabet: self.alphabet, counts }
+    }
+}
+
+impl<'a, A: Alphabet> Clone for Pfm<'a, A> {
+    fn clone(&self) -> Self {
+        Pfm { alphabet: self.alphabet, counts: self.counts.clone() }
+    }
+}
+
+impl<'a, A: Alphabet> fmt::Debug for Pfm<'a, A> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Pfm")
+            .field("symbols", &self.alphabet.symbols())
+            .field("counts", &self.counts)
+            .finish()
+    }
+}
+
+/// A motif read from a database file.
+pub struct Motif<'a, A: Alphabet> {
+    /// The identifier, such as the JASPAR matrix ID
+    pub id: String,
+    /// The name of the motif, usually the factor that binds it. Empty if the file doesn't give
+    /// one.
+    pub name: String,
+    /// The counts at each position
+    pub pfm: Pfm<'a, A>,
+}
+
+impl<'a, A: Alphabet> Clone for Motif<'a, A> {
+    fn clone(&self) -> Self {
+        Motif { id: self.id.clone(), name: self.name.clone(), pfm: self.pfm.clone() }
+    }
+}
+
+impl<'a, A: Alphabet> fmt::Debug for Motif<'a, A> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Motif")
+            .field("id", &self.id)
+            .field("name", &self.name)
+            .field("pfm", &self.pfm)
+            .finish()
+    }
+}
+
+//================================================================================
+// Position Weight Matrix
+//================================================================================
+
+/// Which windows of a sequence are reported as hits by [Pwm::scan()].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Threshold {
+    /// Windows scoring at least this many bits
+    Score(f64),
+    /// Windows whose score has at most this p-value
+    PValue(f64),
+}
+
+/// A window of a sequence that scored above the threshold.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MotifHit {
+    /// Position of the first symbol of the window
+    pub start: usize,
+    /// Position one past the last symbol of the window. If the window wraps around the origin
+    /// of a circular sequence this will be less than or equal to start.
+    pub end: usize,
+    /// The strand the motif was found on. Positions are always given on the forward strand.
+    pub strand: Strand,
+    /// The log-odds score in bits
+    pub score: f64,
+    /// The probability that a random window drawn from the background scores at least as high
+    pub pvalue: f64,
+}
+
+/// A position weight matrix: the log-odds score in bits of each symbol at each position of a
+/// motif. The score of a window of a sequence is the sum of the scores of its symbols and says
+/// how much more likely the window is to be a site than background sequence.
+///
+/// # Notes
+/// P-values are calculated exactly for scores rounded to 0.01 bits so are very slightly
+/// approximate.
+///
+/// # Example
+/// ```
+/// use biors::alphabet::UnambiguousDnaAlphabet;
+/// use biors::motif::{self, Pfm, Threshold};
+/// use biors::sequence::{Sequence, Strand};
+///
+/// let dna = UnambiguousDnaAlphabet;
+/// // Counts in the order A, C, T, G
+/// let pfm = Pfm::new(&dna, vec![
+///     vec![0.0, 0.0, 0.0, 10.0],
+///     vec![10.0, 0.0, 0.0, 0.0],
+///     vec![0.0, 0.0, 10.0, 0.0],
+///     vec![0.0, 0.0, 10.0, 0.0],
+/// ]);
+/// let pwm = pfm.pwm(0.1, &motif::uniform_background(&dna));
+/// assert_eq!(pwm.consensus(), "GATT");
+///
+/// let mut seq = Sequence::new(&dna);
+/// seq.push("CCGATTCCAATCCC").unwrap();
+///
+/// let hits = pwm.scan_both_strands(&seq, Threshold::PValue(0.01));
+/// assert_eq!(hits.len(), 2);
+/// assert_eq!((hits[0].start, hits[0].strand), (2, Strand::Forward));
+/// // AATC is the reverse complement of GATT
+/// assert_eq!((hits[1].start, hits[1].strand), (8, Strand::Reverse));
+/// assert_eq!(hits[1].score, hits[0].score);
+/// ```
+pub struct Pwm<'a, A: Alphabet> {
+    alphabet: &'a A,
+    probabilities: Vec<Vec<f64>>,
+    background: Vec<f64>,
+    scores: Vec<Vec<f64>>,
+    distribution: ScoreDistribution,
+    reverse: OnceLock<Box<Pwm<'a, A>>>,
+}
+
+impl<'a, A: Alphabet> Pwm<'a, A> {
+    /// Constructs a matrix from the probability of each symbol, in the order of
+    /// [Alphabet::symbols()], at each position. A probability of 0 gives a score of negative
+    /// infinity so windows with that symbol are never hits.
+    ///
+    /// # Panics
+    /// If a position or the background doesn't have a value for every symbol or a background
+    /// frequency isn't positive.
+    pub fn new(alphabet: &'a A, probabilities: Vec<Vec<f64>>, background: &[f64]) -> Self {
+        let size = alphabet.symbols().len();
+        assert_eq!(
+            background.len(),
+            size,
+            "The background must have a frequency for each of the {} symbols.",
+            size
+        );
+        let positive = background.iter().all(|&frequency| frequency > 0.0);
+        assert!(positive, "Background frequencies must be positive.");
+
+        let scores: Vec<Vec<f64>> = probabilities.iter()
+            .enumerate()
+            .map(|(position, row)| {
+                assert_eq!(
+                    row.len(),
+                    size,
+                    "Position {} must have a probability for each of the {} symbols.",
+                    position,
+                    size
+                );
+                row.iter().zip(background).map(|(p, b)| (p / b).log2()).collect()
+            })
+            .collect();
+        let distribution = ScoreDistribution::new(&scores, background);
+
+        Pwm {
+            alphabet,
+            probabilities,
+            background: background.to_vec(),
+            scores,
+            distribution,
+            reverse: OnceLock::new(),
+        }
+    }
+
+    /// The alphabet of the motif.
+    pub fn alphabet(&self) -> &'a A {
+        self.alphabet
+    }
+
+    /// The number of positions.
+    pub fn len(&self) -> usize {
+        self.scores.len()
+    }
+
+    /// Returns true if there are no positions.
+    pub fn is_empty(&self) -> bool {
+        self.scores.is_empty()
+    }
+
+    /// The probability of each symbol at each position.
+    pub fn probabilities(&self) -> &[Vec<f64>] {
+        &self.probabilities
+    }
+
+    /// The background frequency of each symbol.
+    pub fn background(&self) -> &[f64] {
+        &self.background
+    }
+
+    /// The log-odds score in bits of each symbol at each position.
+    pub fn scores(&self) -> &[Vec<f64>] {
+        &self.scores
+    }
+
+    /// The most likely symbol at each position.
+    pub fn consensus(&self) -> String {
+        consensus(self.alphabet, &self.probabilities)
+    }
+
+    /// The information content in bits of each position: the relative entropy of its
+    /// probabilities to the background.
+    pub fn information_content(&self) -> Vec<f64> {
+        self.probabilities.iter()
+            .map(|row| {
+                row.iter()
+                    .zip(&self.background)
+                    .filter(|(&p, _)| p > 0.0)
+                    .map(|(p, b)| p * (p / b).log2())
+                    .sum()
+            })
+            .collect()
+    }
+
+    /// The information content of the whole motif.
+    pub fn total_information_content(&self) -> f64 {
+        self.information_content().iter().sum()
+    }
+
+    /// The lowest possible score.
+    pub fn min_score(&self) -> f64 {
+        self.scores.iter().map(|row| row.iter().copied().fold(f64::INFINITY, f64::min)).sum()
+    }
+
+    /// The highest possible score.
+    pub fn max_score(&self) -> f64 {
+        self.scores.iter().map(|row| row.iter().copied().fold(f64::NEG_INFINITY, f64::max)).sum()
+    }
+
+    /// The score of a window of symbol indices.
+    ///
+    /// # Panics
+    /// If the window isn't the same length as the motif.
+    pub fn score(&self, window: &[u8]) -> f64 {
+        assert_eq!(window.len(), self.len(), "The window must be the same length as the motif.");
+        self.scores.iter().zip(window).map(|(row, &symbol)| row[symbol as usize]).sum()
+    }
+
+    /// The probability that a window drawn from the background scores at least score.
+    pub fn pvalue(&self, score: f64) -> f64 {
+        self.distribution.pvalue(self.distribution.bin(score))
+    }
+
+    /// The lowest score with at most the given p-value, or positive infinity if no score is
+    /// that unlikely.
+    pub fn score_for_pvalue(&self, pvalue: f64) -> f64 {
+        match self.distribution.bin_for_pvalue(pvalue) {
+            Some(bin) => bin as f64 * SCORE_RESOLUTION,
+            None => f64::INFINITY,
+        }
+    }
+
+    /// Finds every window of the sequence on the forward strand that meets the threshold, in
+    /// order of position. If the sequence is [circular](Sequence::circular) windows that wrap
+    /// around its origin are included.
+    pub fn scan(&self, seq: &Sequence<A>, threshold: Threshold) -> Vec<MotifHit> {
+        self.scan_strand(seq, threshold, Strand::Forward)
+    }
+
+    fn scan_strand(
+        &self,
+        seq: &Sequence<A>,
+        threshold: Threshold,
+        strand: Strand
+    ) -> Vec<MotifHit> {
+        let bytes = seq.as_bytes();
+        let (len, m) = (bytes.len(), self.len());
+        if m == 0 || m > len {
+            return vec![];
+        }
+
+        let starts = if seq.circular { len } else { len - m + 1 };
+        let min_bin = match threshold {
+            Threshold::PValue(pvalue) => match self.distribution.bin_for_pvalue(pvalue) {
+                Some(bin) => bin,
+                None => return vec![],
+            },
+            Threshold::Score(_) => i64::MIN,
+        };
+
+        let mut hits = vec![];
+        for start in 0..starts {
+            let mut score = 0.0;
+            let mut bin = 0;
+            for (position, row) in self.scores.iter().enumerate() {
+                let symbol = bytes[(start + position) % len] as usize;
+                score += row[symbol];
+                bin += self.distribution.scaled[position][symbol];
+            }
+
+            let passes = match threshold {
+                Threshold::Score(min_score) => score >= min_score,
+                Threshold::PValue(_) => bin >= min_bin,
+            };
+            if passes && score > f64::NEG_INFINITY {
+                let end = if start + m > len { start + m - len } else { start + m };
+                let pvalue = self.distribution.pvalue(bin);
+                hits.push(MotifHit { start, end, strand, score, pvalue });
+            }
+        }
+
+        hits
+    }
+}
+
+impl<'a, A: Complement> Pwm<'a, A> {
+    /// The matrix for the reverse complement of the motif. Its background is also
+    /// complemented so a window scores the same as its reverse complement does with the
+    /// original matrix.
+    pub fn reverse_complement(&self) -> Self {
+        let complement = complement_indices(self.alphabet);
+        let probabilities = self.probabilities.iter()
+            .rev()
+            .map(|row| complement.iter().map(|&index| row[index]).collect())
+            .collect();
+        let background: Vec<f64> = complement.iter().map(|&index| self.background[index]).collect();
+
+        Pwm::new(self.alphabet, probabilities, &background)
+    }
+
+    /// Finds every window of the sequence that meets the threshold on either strand, sorted by
+    /// position with forward hits before reverse hits at the same position. Reverse hits are
+    /// windows whose reverse complement meets the threshold. The reverse complement matrix is
+    /// built on the first call and kept for later ones.
+    pub fn scan_both_strands(&self, seq: &Sequence<A>, threshold: Threshold) -> Vec<MotifHit> {
+        let reverse = self.reverse.get_or_init(|| Box::new(self.reverse_complement()));
+        let mut hits = self.scan(seq, threshold);
+        hits.extend(reverse.scan_strand(seq, threshold, Strand::Reverse));
+        hits.sort_by_key(|hit| (hit.start, hit.strand == Strand::Reverse));
+        hits
+    }
+}
+
+impl<'a, A: Alphabet> Clone for Pwm<'a, A> {
+    fn clone(&self) -> Self {
+        Pwm {
+            alphabet: self.alphabet,
+            probabilities: self.probabilities.clone(),
+            background: self.background.clone(),
+            scores: self.scores.clone(),
+            distribution: self.distribution.clone(),
+            reverse: self.reverse.clone(),
+        }
+    }
+}
+
+impl<'a, A: Alphabet> fmt::Debug for Pwm<'a, A> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Pwm")
+            .field("symbols", &self.alphabet.symbols())
+            .field("scores", &self.scores)
+            .finish()
+    }
+}
+
+/// The exact distribution of the scores, rounded to SCORE_RESOLUTION, of windows drawn from the
+/// background
+#[derive(Debug, Clone)]
+struct ScoreDistribution {
+    /// Each score as a number of bins, 0 for symbols that can't occur
+    scaled: Vec<Vec<i64>>,
+    /// The lowest possible total in bins
+    min: i64,
+    /// The probability of a total of at least min + i bins
+    tail: Vec<f64>,
+}
+
+impl ScoreDistribution {
+    fn new(scores: &[Vec<f64>], background: &[f64]) -> Self {
+        let scaled: Vec<Vec<i64>> = scores.iter()
+            .map(|row| {
+                row.iter()
+                    .map(|&score| {
+                        if score.is_finite() {
+                            (score / SCORE_RESOLUTION).round() as i64
+                        } else {
+                            0
+                        }
+                    })
+                    .collect()
+            })
+            .collect();
+
+        // The probability of each total so far, starting from min
+        let mut min = 0;
+        let mut probabilities = vec![1.0];
+        for (row, scores) in scaled.iter().zip(scores) {
+            let possible = || {
+                row.iter()
+                    .zip(scores)
+                    .filter(|(_, score)| score.is_finite())
+                    .map(|(&bins, _)| bins)
+            };
+            let (low, high) = match (possible().min(), possible().max()) {
+                (Some(low), Some(high)) => (low, high),
+                _ => {
+                    // No symbol can occur so no window can score
+                    probabilities.clear();
+                    break;
+                }
+            };
+
+            let mut next = vec![0.0; probabilities.len() + (high - low) as usize];
+            for (total, &p) in probabilities.iter().enumerate() {
+                if p == 0.0 {
+                    continue;
+                }
+                for ((&bins, score), frequency) in row.iter().zip(scores).zip(background) {
+                    if score.is_finite() {
+                        next[total + (bins - low) as usize] += p * frequency;
+                    }
+                }
+            }
+            probabilities = next;
+            min += low;
+        }
+
+        let mut tail = probabilities;
+        for i in (0..tail.len().saturating_sub(1)).rev() {
+            tail[i] += tail[i + 1];
+        }
+
+        ScoreDistribution { scaled, min, tail }
+    }
+
+    /// The bin a score falls in
+    fn bin(&self, score: f64) -> i64 {
+        if score.is_nan() || score == f64::NEG_INFINITY {
+            i64::MIN
+        } else {
+            (score / SCORE_RESOLUTION).round().min(i64::MAX as f64) as i64
+        }
+    }
+
+    /// The probability of a total of at least bin
+    fn pvalue(&self, bin: i64) -> f64 {
+        if bin <= self.min {
+            self.tail.first().copied().unwrap_or(0.0)
+        } else {
+            self.tail.get((bin - self.min) as usize).copied().unwrap_or(0.0)
+        }
+    }
+
+    /// The lowest bin whose p-value is at most pvalue
+    fn bin_for_pvalue(&self, pvalue: f64) -> Option<i64> {
+        // Allow for the rounding error of summing the tail
+        let pvalue = pvalue * (1.0 + 1e-9);
+        let index = self.tail.iter().position(|&p| p <= pvalue)?;
+        Some(self.min + index as i64)
+    }
+}
+
+//================================================================================
+// Errors
+//================================================================================
+
+/// Represents the kind of error that occurred while building or reading a motif.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ErrorKind {
+    /// There were no sites to build the motif from
+    NoSites,
+
+    /// The sites or the rows of a matrix have different lengths
+    LengthMismatch,
+
+    /// A motif file could not be parsed
+    Parse,
+
+    /// A motif file has a symbol that isn't in the alphabet
+    MissingSymbol(String),
+}
+
+/// The type of error returned when a motif can't be built or read
+#[derive(Debug, Clone)]
+pub struct MotifError {
+    kind: ErrorKind,
+    description: String,
+}
+
+impl MotifError {
+    /// Construct a new MotifError from the given ErrorKind and description
+    pub fn new(kind: ErrorKind, description: String) -> MotifError {
+        MotifError { kind, description }
+    }
+
+    /// Get the associated ErrorKind for this error
+    pub fn kind(&self) -> &ErrorKind {
+        &self.kind
+    }
+
+    /// Get the associated description for this error
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+}
+
+impl Error for MotifError {}
+
+impl fmt::Display for MotifError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Motif error: {:?}:\n\t{}", self.kind, self.description)
+    }
+}
+
+//================================================================================
+// Tests
+//================================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alphabet::UnambiguousDnaAlphabet;
+    use crate::sequence::test_util::dna;
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!((actual - expected).abs() < 1e-9, "{} != {}", actual, expected);
+    }
+
+    /// Tests building a matrix from sites and the errors for bad sites
+    #[test]
+    fn from_sites() {
+        let alphabet = UnambiguousDnaAlphabet;
+        let sites = vec![dna(&alphabet, "ACG"), dna(&alphabet, "ACC"), dna(&alphabet, "TCG")];
+        let pfm = Pfm::from_sites(&alphabet, &sites).unwrap();
+
+        // A, C, T, G
+        assert_eq!(pfm.counts(), &[
+            vec![2.0, 0.0, 1.0, 0.0],
+            vec![0.0, 3.0, 0.0, 0.0],
+            vec![0.0, 1.0, 0.0, 2.0],
+        ]);
+        assert_eq!(pfm.num_sites(), 3.0);
+        assert_eq!(pfm.consensus(), "ACG");
+
+        let rc = pfm.reverse_complement();
+        assert_eq!(rc.consensus(), "CGT");
+        assert_eq!(rc.counts()[0], vec![0.0, 2.0, 0.0, 1.0]);
+
+        let err = Pfm::from_sites(&alphabet, &[]).unwrap_err();
+        assert_eq!(err.kind(), &ErrorKind::NoSites);
+        let sites = [dna(&alphabet, "AC"), dna(&alphabet, "A")];
+        let err = Pfm::from_sites(&alphabet, &sites).unwrap_err();
+        assert_eq!(err.kind(), &ErrorKind::LengthMismatch);
+    }
+
+    /// Tests log-odds scores and information content with pseudocounts
+    #[test]
+    fn weights() {
+        let alphabet = UnambiguousDnaAlphabet;
+        let pfm = Pfm::new(&alphabet, vec![
+            vec![8.0, 0.0, 0.0, 0.0],
+            vec![2.0, 2.0, 2.0, 2.0],
+        ]);
+        let background = uniform_background(&alphabet);
+
+        let pwm = pfm.pwm(0.0, &background);
+        assert_close(pwm.scores()[0][0], 2.0);
+        assert_eq!(pwm.scores()[0][1], f64::NEG_INFINITY);
+        assert_close(pwm.scores()[1][3], 0.0);
+        assert_eq!(pwm.information_content(), vec![2.0, 0.0]);
+        assert_close(pwm.max_score(), 2.0);
+        assert_eq!(pwm.min_score(), f64::NEG_INFINITY);
+
+        // With a pseudocount of 4 each symbol gets 1 more count
+        let pwm = pfm.pwm(4.0, &background);
+        assert_close(pwm.probabilities()[0][0], 0.75);
+        assert_close(pwm.probabilities()[0][1], 1.0 / 12.0);
+        assert_close(pwm.scores()[0][0], 3.0_f64.log2());
+        assert_close(pwm.score(&[0, 1]), 3.0_f64.log2());
+
+        let expected = 0.75 * 3.0_f64.log2() + 0.25 * (1.0_f64 / 3.0).log2();
+        assert_close(pwm.total_information_content(), expected);
+    }
+
+    /// Tests p-values against counting every possible window
+    #[test]
+    fn pvalues() {
+        let alphabet = UnambiguousDnaAlphabet;
+        let pfm = Pfm::new(&alphabet, vec![
+            vec![5.0, 1.0, 0.0, 2.0],
+            vec![0.0, 3.0, 3.0, 1.0],
+            vec![1.0, 1.0, 1.0, 6.0],
+        ]);
+        let pwm = pfm.pwm(1.0, &uniform_background(&alphabet));
+
+        let mut scores = vec![];
+        for a in 0..4 {
+            for b in 0..4 {
+                for c in 0..4 {
+                    scores.push(pwm.score(&[a, b, c]));
+                }
+            }
+        }
+
+        for &score in &scores {
+            let at_least = scores.iter().filter(|&&other| other >= score - 1e-6).count();
+            assert!((pwm.pvalue(score) - at_least as f64 / 64.0).abs() < 0.05);
+        }
+        // C and T are tied at the second position so two windows have the highest score
+        assert_close(pwm.pvalue(pwm.max_score()), 2.0 / 64.0);
+        assert_close(pwm.pvalue(pwm.min_score()), 1.0);
+
+        // Nothing scores between the two highest scores so the lowest score with that p-value is
+        // just above the second highest
+        let threshold = pwm.score_for_pvalue(2.0 / 64.0);
+        assert_close(pwm.pvalue(threshold), 2.0 / 64.0);
+        assert!(pwm.pvalue(threshold - SCORE_RESOLUTION) > 2.0 / 64.0);
+        assert!(threshold < pwm.max_score());
+        assert_eq!(pwm.score_for_pvalue(1.0 / 64.0), f64::INFINITY);
+    }
+
+    /// Tests scanning both strands of linear and circular sequences
+    #[test]
+    fn scan() {
+        let alphabet = UnambiguousDnaAlphabet;
+        let sites = vec![dna(&alphabet, "TTGAC"), dna(&alphabet, "TTGAC"), dna(&alphabet, "TTGAA")];
+        let pfm = Pfm::from_sites(&alphabet, &sites).unwrap();
+        let pwm = pfm.pwm(0.5, &uniform_background(&alphabet));
+
+        let seq = dna(&alphabet, "GACTTGACGGGTCAACCC");
+        let hits = pwm.scan(&seq, Threshold::Score(5.0));
+        assert_eq!(hits.len(), 1);
+        assert_eq!((hits[0].start, hits[0].end), (3, 8));
+        assert_close(hits[0].score, pwm.score(&seq.as_bytes()[3..8]));
+        assert!(hits[0].pvalue < 0.01);
+
+        let hits = pwm.scan_both_strands(&seq, Threshold::Score(5.0));
+        assert_eq!(hits.len(), 2);
+        assert_eq!((hits[1].start, hits[1].end, hits[1].strand), (10, 15, Strand::Reverse));
+        assert_close(hits[1].score, hits[0].score);
+        assert_close(hits[1].pvalue, hits[0].pvalue);
+
+        let by_pvalue = pwm.scan_both_strands(&seq, Threshold::PValue(hits[0].pvalue));
+        assert_eq!(by_pvalue, hits);
+        // The reverse complement matrix is built once and kept by clones
+        assert_eq!(pwm.reverse.get().unwrap().scores(), pwm.reverse_complement().scores());
+        assert!(pwm.clone().reverse.get().is_some());
+
+        // The site is only complete when the sequence wraps around
+        let circular = dna(&alphabet, "GACCCCCCTT").circular(true);
+        assert!(pwm.scan(&circular.clone().circular(false), Threshold::Score(5.0)).is_empty());
+        let hits = pwm.scan(&circular, Threshold::Score(5.0));
+        assert_eq!((hits[0].start, hits[0].end), (8, 3));
+    }
+}
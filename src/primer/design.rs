@@ -0,0 +1,496 @@
+//! Designing pairs of primers that amplify a target region of a template, in the manner of a
+//! much simplified Primer3.
+//!
+//! Every primer within the size, melting temperature and GC bounds of the [DesignSettings] on
+//! either side of the target is a candidate. Each candidate is given a penalty for how far it is
+//! from the optimal size and melting temperature and for any stable hairpin or self-dimer. Pairs
+//! of candidates that give a product within the size bounds are then penalised further for the
+//! difference in their melting temperatures, how far the product is from the optimal size and
+//! any stable dimer between them, and the pairs with the smallest penalties are kept.
+//!
+//! The primers of a [circular](crate::sequence::Sequence::circular) template can be either side
+//! of the origin, so the product can span it.
+
+use std::ops::Range;
+use crate::alphabet::{Alphabet, Complement};
+use crate::sequence::{Sequence, Strand};
+use super::{codes_tm, cross_dimer, has_gc_clamp, hairpin, primer_codes, self_dimer};
+use super::{Conditions, Dimer, TmMethod};
+
+/// The bounds and optimal values used to choose primers and pairs of primers.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DesignSettings {
+    /// The fewest bases in a primer
+    pub min_size: usize,
+    /// The best number of bases in a primer
+    pub optimal_size: usize,
+    /// The most bases in a primer
+    pub max_size: usize,
+    /// The lowest melting temperature of a primer in °C
+    pub min_tm: f64,
+    /// The best melting temperature of a primer in °C
+    pub optimal_tm: f64,
+    /// The highest melting temperature of a primer in °C
+    pub max_tm: f64,
+    /// The lowest fraction of G and C in a primer
+    pub min_gc: f64,
+    /// The highest fraction of G and C in a primer
+    pub max_gc: f64,
+    /// The largest difference in melting temperature between the primers of a pair
+    pub max_tm_difference: f64,
+    /// The fewest bases in the product
+    pub min_product: usize,
+    /// The best number of bases in the product
+    pub optimal_product: usize,
+    /// The most bases in the product
+    pub max_product: usize,
+    /// Only use primers with a [GC clamp](super::has_gc_clamp())
+    pub require_gc_clamp: bool,
+    /// The free energy in kcal/mol below which self-dimers and dimers between the primers of a
+    /// pair are penalised
+    pub dimer_threshold: f64,
+    /// The free energy in kcal/mol below which hairpins are penalised
+    pub hairpin_threshold: f64,
+    /// The conditions the melting temperatures are calculated in
+    pub conditions: Conditions,
+}
+
+impl Default for DesignSettings {
+    /// Primers of 18 to 25 bases with melting temperatures of 57 to 63 °C and 30 to 70% GC,
+    /// giving products of 100 to 500 bases, which are close to the defaults of Primer3.
+    fn default() -> Self {
+        DesignSettings {
+            min_size: 18,
+            optimal_size: 20,
+            max_size: 25,
+            min_tm: 57.0,
+            optimal_tm: 60.0,
+            max_tm: 63.0,
+            min_gc: 0.3,
+            max_gc: 0.7,
+            max_tm_difference: 3.0,
+            min_product: 100,
+            optimal_product: 200,
+            max_product: 500,
+            require_gc_clamp: false,
+            dimer_threshold: -6.0,
+            hairpin_threshold: -2.0,
+            conditions: Conditions::default(),
+        }
+    }
+}
+
+/// A primer chosen from one strand of a template.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Primer {
+    /// The primer written 5' to 3'
+    pub sequence: String,
+    /// The position of the first base of the template the primer covers
+    pub start: usize,
+    /// The position one past the last base of the template the primer covers. If the primer
+    /// wraps around the origin of a circular template this will be less than or equal to start.
+    pub end: usize,
+    /// Forward for a primer that is the same as the top strand, reverse for one that is the
+    /// reverse complement of it
+    pub strand: Strand,
+    /// The melting temperature in °C
+    pub tm: f64,
+    /// The fraction of G and C
+    pub gc: f64,
+    /// How far the primer is from the optimal primer, where 0 is best
+    pub penalty: f64,
+}
+
+/// The product of a PCR.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Amplicon {
+    /// The position of the first base of the template in the product
+    pub start: usize,
+    /// The position one past the last base of the template in the product. If the product spans
+    /// the origin of a circular template this will be less than or equal to start.
+    pub end: usize,
+    /// The top strand of the product
+    pub sequence: String,
+}
+
+impl Amplicon {
+    /// The number of bases in the product
+    pub fn size(&self) -> usize {
+        self.sequence.len()
+    }
+}
+
+/// A pair of primers and the product they amplify.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PrimerPair {
+    /// The forward primer at the start of the product
+    pub left: Primer,
+    /// The reverse primer at the end of the product
+    pub right: Primer,
+    /// The product of the pair
+    pub amplicon: Amplicon,
+    /// The difference between the melting temperatures of the primers in °C
+    pub tm_difference: f64,
+    /// The most stable dimer between the primers, if they can form one
+    pub dimer: Option<Dimer>,
+    /// How far the pair is from the optimal pair, where 0 is best
+    pub penalty: f64,
+}
+
+/// A primer together with its symbols and where its 5' end is on the unrolled template
+struct Candidate<'a, A: Alphabet> {
+    primer: Primer,
+    sequence: Sequence<'a, A>,
+    five_prime: isize,
+}
+
+/// The symbol indices of the template from start to end, which can be outside the template if
+/// it is circular
+fn indices<A: Alphabet>(template: &Sequence<A>, start: isize, end: isize) -> Vec<u8> {
+    let bytes = template.as_bytes();
+    let len = bytes.len() as isize;
+
+    (start..end).map(|position| bytes[position.rem_euclid(len) as usize]).collect()
+}
+
+/// The symbols of a sequence as a string
+pub(crate) fn symbols<A: Alphabet>(sequence: &Sequence<A>) -> String {
+    let symbols = sequence.alphabet().symbols();
+    sequence.as_bytes().iter().map(|&byte| symbols[byte as usize]).collect()
+}
+
+/// The start and end of a region of the unrolled template on the template itself
+pub(crate) fn region(start: isize, end: isize, len: isize) -> (usize, usize) {
+    (start.rem_euclid(len) as usize, ((end - 1).rem_euclid(len) + 1) as usize)
+}
+
+/// How far below a threshold a free energy is
+fn excess(delta_g: Option<f64>, threshold: f64) -> f64 {
+    delta_g.map_or(0.0, |delta_g| (threshold - delta_g).max(0.0))
+}
+
+/// Builds the candidate covering start..end of the unrolled template on the strand, or None if
+/// it is outside the bounds of the settings
+fn candidate<'a, A: Complement>(
+    template: &'a Sequence<A>,
+    start: isize,
+    end: isize,
+    strand: Strand,
+    settings: &DesignSettings
+) -> Option<Candidate<'a, A>> {
+    let mut sequence = Sequence::from_indices(template.alphabet(), indices(template, start, end));
+    if strand == Strand::Reverse {
+        sequence.reverse_complement();
+    }
+
+    let codes = primer_codes(&sequence)?;
+    let tm = codes_tm(&codes, TmMethod::NearestNeighbor(settings.conditions))?;
+    let gc_count = codes.iter().filter(|&&code| code == 1 || code == 2).count();
+    let gc = gc_count as f64 / codes.len() as f64;
+    if tm < settings.min_tm
+        || tm > settings.max_tm
+        || gc < settings.min_gc
+        || gc > settings.max_gc
+    {
+        return None;
+    }
+    if settings.require_gc_clamp && !has_gc_clamp(&sequence) {
+        return None;
+    }
+
+    let penalty = (tm - settings.optimal_tm).abs()
+        + (codes.len() as f64 - settings.optimal_size as f64).abs()
+        + excess(hairpin(&sequence).map(|hairpin| hairpin.delta_g), settings.hairpin_threshold)
+        + excess(self_dimer(&sequence).map(|dimer| dimer.delta_g), settings.dimer_threshold);
+
+    let five_prime = if strand == Strand::Forward { start } else { end };
+    let (start, end) = region(start, end, template.len() as isize);
+    let primer = Primer { sequence: symbols(&sequence), start, end, strand, tm, gc, penalty };
+
+    Some(Candidate { primer, sequence, five_prime })
+}
+
+/// Finds the best pairs of primers that amplify the target region of the template, sorted from
+/// the smallest penalty to the largest.
+///
+/// The forward primer is chosen from the top strand before the target and the reverse primer
+/// from the bottom strand after it, so the target is in the product but neither primer covers
+/// any of it. If the template is circular a target whose end is before its start spans the
+/// origin. A target whose start and end are the same is empty, so the pairs only have to
+/// flank that position.
+///
+/// # Notes
+/// Primers with a symbol other than A, C, G, T or U aren't used, so any N in the template is
+/// avoided. The product of a circular template can't be longer than the template.
+///
+/// # Panics
+/// If the start or end of the target is past the end of the template or the end is before the
+/// start for a linear template.
+///
+/// # Example
+/// ```
+/// use biors::alphabet::UnambiguousDnaAlphabet;
+/// use biors::primer::design::{design, DesignSettings};
+/// use biors::sequence::Sequence;
+///
+/// // A made up template of 600 bases
+/// let dna = UnambiguousDnaAlphabet;
+/// let mut template = Sequence::new(&dna);
+/// let mut state = 1u32;
+/// for _ in 0..600 {
+///     state = state.wrapping_mul(1103515245).wrapping_add(12345);
+///     template.push(["A", "C", "G", "T"][(state >> 16) as usize % 4]).unwrap();
+/// }
+///
+/// let pairs = design(&template, 250..300, &DesignSettings::default(), 5);
+/// assert_eq!(pairs.len(), 5);
+///
+/// let best = &pairs[0];
+/// assert!(best.left.end <= 250 && best.right.start >= 300);
+/// assert!(best.amplicon.size() >= 100 && best.amplicon.size() <= 500);
+/// assert!(best.tm_difference <= 3.0);
+/// ```
+pub fn design<A: Complement>(
+    template: &Sequence<A>,
+    target: Range<usize>,
+    settings: &DesignSettings,
+    count: usize
+) -> Vec<PrimerPair> {
+    let len = template.len();
+    assert!(target.start <= len && target.end <= len, "The target must be inside the template");
+    assert!(
+        template.circular || target.start <= target.end,
+        "The target must not end before it starts"
+    );
+
+    if count == 0 || len == 0 {
+        return vec![];
+    }
+
+    let len = len as isize;
+    let target_start = target.start as isize;
+    let target_end = if target.end < target.start && template.circular {
+        target.end as isize + len
+    } else {
+        target.end as isize
+    };
+    let sizes = settings.min_size..=settings.max_size.min(len as usize);
+    let max_product = if template.circular {
+        settings.max_product.min(len as usize) as isize
+    } else {
+        settings.max_product as isize
+    };
+
+    // The 5' ends of the primers are kept on the unrolled template so that the product is
+    // always from the 5' end of the left primer to the 5' end of the right primer
+    let mut lefts = vec![];
+    let first = if template.circular {
+        target_end - max_product
+    } else {
+        (target_end - max_product).max(0)
+    };
+    for start in first..target_start {
+        for size in sizes.clone() {
+            let end = start + size as isize;
+            if end > target_start {
+                break;
+            }
+            if let Some(candidate) = candidate(template, start, end, Strand::Forward, settings) {
+                lefts.push(candidate);
+            }
+        }
+    }
+
+    let mut rights = vec![];
+    let last = if template.circular {
+        target_start + max_product
+    } else {
+        (target_start + max_product).min(len)
+    };
+    for end in (target_end + 1)..=last {
+        for size in sizes.clone() {
+            let start = end - size as isize;
+            if start < target_end {
+                break;
+            }
+            if let Some(candidate) = candidate(template, start, end, Strand::Reverse, settings) {
+                rights.push(candidate);
+            }
+        }
+    }
+
+    // The penalty of a pair is at least the penalties of its primers, so once those are sorted
+    // the search can stop as soon as they're worse than the last of the best pairs found
+    let by_penalty =
+        |a: &Candidate<A>, b: &Candidate<A>| a.primer.penalty.total_cmp(&b.primer.penalty);
+    lefts.sort_by(by_penalty);
+    rights.sort_by(by_penalty);
+
+    let mut pairs: Vec<PrimerPair> = vec![];
+    let worst = |pairs: &[PrimerPair]| {
+        if pairs.len() < count { f64::INFINITY } else { pairs[pairs.len() - 1].penalty }
+    };
+
+    for left in &lefts {
+        let best_right = rights.first().map(|right| right.primer.penalty);
+        if best_right.is_none_or(|penalty| left.primer.penalty + penalty >= worst(&pairs)) {
+            break;
+        }
+
+        for right in &rights {
+            let mut penalty = left.primer.penalty + right.primer.penalty;
+            if penalty >= worst(&pairs) {
+                break;
+            }
+
+            let product = right.five_prime - left.five_prime;
+            let tm_difference = (left.primer.tm - right.primer.tm).abs();
+            if product < settings.min_product as isize || product > max_product
+                || tm_difference > settings.max_tm_difference {
+                continue;
+            }
+
+            let size_difference = (product - settings.optimal_product as isize).abs() as f64;
+            penalty += tm_difference + size_difference / 100.0;
+            if penalty >= worst(&pairs) {
+                continue;
+            }
+
+            let dimer = cross_dimer(&left.sequence, &right.sequence);
+            penalty += excess(dimer.as_ref().map(|dimer| dimer.delta_g), settings.dimer_threshold);
+            if penalty >= worst(&pairs) {
+                continue;
+            }
+
+            let amplicon = Sequence::from_indices(
+                template.alphabet(),
+                indices(template, left.five_prime, left.five_prime + product)
+            );
+            let (start, end) = region(left.five_prime, left.five_prime + product, len);
+
+            let pair = PrimerPair {
+                left: left.primer.clone(),
+                right: right.primer.clone(),
+                amplicon: Amplicon { start, end, sequence: symbols(&amplicon) },
+                tm_difference,
+                dimer,
+                penalty,
+            };
+            let position = pairs.partition_point(|other| other.penalty <= penalty);
+            pairs.insert(position, pair);
+            pairs.truncate(count);
+        }
+    }
+
+    pairs
+}
+
+//================================================================================
+// Tests
+//================================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alphabet::UnambiguousDnaAlphabet;
+    use crate::sequence::test_util::{random_dna, reverse_complement};
+
+    /// Tests that every pair keeps to the settings and amplifies the target
+    #[test]
+    fn linear() {
+        let alphabet = UnambiguousDnaAlphabet;
+        let template = random_dna(&alphabet, 12345, 800);
+        let text = symbols(&template);
+        let settings = DesignSettings::default();
+
+        let pairs = design(&template, 300..400, &settings, 10);
+        assert_eq!(pairs.len(), 10);
+        assert!(pairs.windows(2).all(|pair| pair[0].penalty <= pair[1].penalty));
+
+        for pair in &pairs {
+            let (left, right, amplicon) = (&pair.left, &pair.right, &pair.amplicon);
+            assert_eq!(left.sequence, text[left.start..left.end]);
+            assert_eq!(right.sequence, reverse_complement(&text[right.start..right.end]));
+            assert_eq!((left.strand, right.strand), (Strand::Forward, Strand::Reverse));
+            assert!(left.end <= 300 && right.start >= 400);
+
+            assert_eq!((amplicon.start, amplicon.end), (left.start, right.end));
+            assert_eq!(amplicon.sequence, text[amplicon.start..amplicon.end]);
+            let size = amplicon.size();
+            assert!(size >= settings.min_product && size <= settings.max_product);
+
+            for primer in &[left, right] {
+                let size = primer.sequence.len();
+                assert!(size >= settings.min_size && size <= settings.max_size);
+                assert!(primer.tm >= settings.min_tm && primer.tm <= settings.max_tm);
+                assert!(primer.gc >= settings.min_gc && primer.gc <= settings.max_gc);
+            }
+            assert!((pair.tm_difference - (left.tm - right.tm).abs()).abs() < 1e-9);
+            assert!(pair.tm_difference <= settings.max_tm_difference);
+            assert!(pair.penalty >= left.penalty + right.penalty + pair.tm_difference);
+        }
+    }
+
+    /// Tests that tighter settings are kept to and that impossible ones give no pairs
+    #[test]
+    fn settings() {
+        let alphabet = UnambiguousDnaAlphabet;
+        let template = random_dna(&alphabet, 12345, 800);
+
+        let settings = DesignSettings {
+            min_product: 150,
+            max_product: 160,
+            require_gc_clamp: true,
+            ..DesignSettings::default()
+        };
+        for pair in design(&template, 300..340, &settings, 5) {
+            assert!(pair.amplicon.size() >= 150 && pair.amplicon.size() <= 160);
+            assert!(pair.left.sequence.ends_with(['G', 'C']));
+            assert!(pair.right.sequence.ends_with(['G', 'C']));
+        }
+
+        let settings = DesignSettings { max_product: 90, ..DesignSettings::default() };
+        assert!(design(&template, 300..400, &settings, 5).is_empty());
+        assert!(design(&template, 300..400, &DesignSettings::default(), 0).is_empty());
+
+        // There is no room for a primer before the target
+        assert!(design(&template, 10..100, &DesignSettings::default(), 5).is_empty());
+    }
+
+    /// Tests that products of a circular template can span the origin
+    #[test]
+    fn circular() {
+        let alphabet = UnambiguousDnaAlphabet;
+        let template = random_dna(&alphabet, 12345, 800).circular(true);
+        let text = symbols(&template);
+        let unrolled = format!("{}{}", text, text);
+
+        // The target spans the origin
+        let pairs = design(&template, Range { start: 780, end: 20 }, &DesignSettings::default(), 5);
+        assert_eq!(pairs.len(), 5);
+        for pair in &pairs {
+            let amplicon = &pair.amplicon;
+            assert!(amplicon.end <= amplicon.start);
+            assert_eq!(amplicon.sequence, unrolled[amplicon.start..amplicon.end + 800]);
+            assert!(amplicon.sequence.starts_with(&pair.left.sequence));
+            assert!(amplicon.sequence.ends_with(&reverse_complement(&pair.right.sequence)));
+        }
+
+        // The target is close to the origin so the left primer has to be before it
+        let pairs = design(&template, 10..100, &DesignSettings::default(), 5);
+        assert_eq!(pairs.len(), 5);
+        assert!(pairs.iter().all(|pair| {
+            pair.left.start > 100 && pair.amplicon.end <= pair.amplicon.start
+        }));
+
+        let linear = template.clone().circular(false);
+        assert!(design(&linear, 10..100, &DesignSettings::default(), 5).is_empty());
+
+        // An empty target is a position for the primers to flank rather than the whole template
+        for template in &[template.clone(), linear] {
+            let pairs = design(template, 400..400, &DesignSettings::default(), 5);
+            assert_eq!(pairs.len(), 5);
+            assert!(pairs.iter().all(|pair| pair.left.end <= 400 && pair.right.start >= 400));
+        }
+    }
+}
@@ -0,0 +1,372 @@
+//! Analysis of PCR primers: melting temperatures, GC clamps and the secondary structures that
+//! stop a primer binding its template.
+//!
+//! Primers are [Sequence]s of nucleotides and the symbols A, C, G and T (or U) are recognised
+//! by name, so any DNA alphabet can be used. The melting temperature of a primer with IUPAC
+//! ambiguity codes, such as one over the
+//! [AmbiguousDnaAlphabet](crate::alphabet::AmbiguousDnaAlphabet), is given as the range over
+//! every primer it can stand for with [tm_range()].
+//!
+//! The nearest-neighbour thermodynamics use the unified parameters of SantaLucia (1998) with
+//! the entropy correction for monovalent salt from the same paper. Divalent cations are
+//! converted to an equivalent monovalent concentration following von Ahsen et al. (2001), after
+//! taking away the magnesium bound by dNTPs. These are the defaults of Primer3.
+//!
+//! Pairs of primers that amplify a target region of a template can be chosen with
+//! [design()](design::design()) and the products a pair amplifies from a set of templates found
+//! with [pcr()](pcr::pcr()).
+
+use crate::alphabet::Alphabet;
+use crate::alphabet::dna::iupac_bases;
+use crate::sequence::Sequence;
+
+pub use self::design::{design, Amplicon, DesignSettings, Primer, PrimerPair};
+pub use self::pcr::{pcr, Binding, PcrSettings, PrimerRole, Product};
+pub use self::structure::{cross_dimer, hairpin, self_dimer, Dimer, Hairpin};
+
+pub mod design;
+pub mod pcr;
+pub mod structure;
+
+/// The gas constant in cal/(K mol)
+const GAS_CONSTANT: f64 = 1.9872;
+
+/// 0 °C in kelvin
+const ZERO_CELSIUS: f64 = 273.15;
+
+/// 37 °C in kelvin, the temperature free energies are given at
+const BODY_TEMPERATURE: f64 = 310.15;
+
+/// The most primers an ambiguous primer can stand for before [tm_range()] gives up
+pub const MAX_EXPANSIONS: usize = 4096;
+
+/// The enthalpy (kcal/mol) and entropy (cal/(K mol)) of each pair of neighbouring bases read
+/// 5' to 3' along one strand, indexed by the codes A = 0, C = 1, G = 2 and T = 3
+const NEAREST_NEIGHBORS: [[(f64, f64); 4]; 4] = [
+    [(-7.9, -22.2), (-8.4, -22.4), (-7.8, -21.0), (-7.2, -20.4)],
+    [(-8.5, -22.7), (-8.0, -19.9), (-10.6, -27.2), (-7.8, -21.0)],
+    [(-8.2, -22.2), (-9.8, -24.4), (-8.0, -19.9), (-8.4, -22.4)],
+    [(-7.2, -21.3), (-8.2, -22.2), (-8.5, -22.7), (-7.9, -22.2)],
+];
+
+/// The enthalpy and entropy of initiating a duplex at a terminal G·C pair
+const INITIATION_GC: (f64, f64) = (0.1, -2.8);
+
+/// The enthalpy and entropy of initiating a duplex at a terminal A·T pair
+const INITIATION_AT: (f64, f64) = (2.3, 4.1);
+
+/// The entropy penalty of a duplex of a self-complementary primer
+const SYMMETRY_ENTROPY: f64 = -1.4;
+
+/// The reaction conditions that affect how stable a duplex is.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Conditions {
+    /// The concentration of monovalent cations such as Na+ and K+ in mM
+    pub monovalent: f64,
+    /// The concentration of divalent cations such as Mg2+ in mM
+    pub divalent: f64,
+    /// The concentration of dNTPs in mM, which bind divalent cations
+    pub dntp: f64,
+    /// The concentration of each primer in nM
+    pub primer: f64,
+}
+
+impl Conditions {
+    /// The monovalent cation concentration in mM with the same effect as the monovalent and
+    /// divalent cations together.
+    pub fn monovalent_equivalent(&self) -> f64 {
+        self.monovalent + 120.0 * (self.divalent - self.dntp).max(0.0).sqrt()
+    }
+}
+
+impl Default for Conditions {
+    /// 50 mM monovalent cations, 1.5 mM Mg2+, 0.6 mM dNTPs and 50 nM of each primer
+    fn default() -> Self {
+        Conditions { monovalent: 50.0, divalent: 1.5, dntp: 0.6, primer: 50.0 }
+    }
+}
+
+/// The ways of calculating a melting temperature.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TmMethod {
+    /// The Wallace rule 2(A + T) + 4(G + C), only suitable for primers shorter than about 14
+    /// bases
+    Wallace,
+    /// The basic GC formula 64.9 + 41(G + C - 16.4) / N
+    Basic,
+    /// Nearest-neighbour thermodynamics in the given conditions
+    NearestNeighbor(Conditions),
+}
+
+/// The code of each nucleotide of a primer or None if it has any other symbol
+fn primer_codes<A: Alphabet>(primer: &Sequence<A>) -> Option<Vec<u8>> {
+    let symbols = primer.alphabet().symbols();
+
+    primer.as_bytes()
+        .iter()
+        .map(|&byte| nucleotide_code(symbols[byte as usize]))
+        .collect()
+}
+
+/// The code of a nucleotide symbol, A = 0, C = 1, G = 2 and T or U = 3
+pub(crate) fn nucleotide_code(symbol: &str) -> Option<u8> {
+    match symbol {
+        "A" => Some(0),
+        "C" => Some(1),
+        "G" => Some(2),
+        "T" | "U" => Some(3),
+        _ => None,
+    }
+}
+
+/// The enthalpy and entropy of the duplex formed by bases paired with their complements,
+/// including the initiation at both ends but without any salt correction
+pub(crate) fn duplex_thermodynamics(codes: &[u8]) -> (f64, f64) {
+    let (mut enthalpy, mut entropy) = stack_thermodynamics(codes);
+
+    for &end in codes.first().into_iter().chain(codes.last()) {
+        let (h, s) = if end == 1 || end == 2 { INITIATION_GC } else { INITIATION_AT };
+        enthalpy += h;
+        entropy += s;
+    }
+
+    (enthalpy, entropy)
+}
+
+/// The enthalpy and entropy of the stack of nearest-neighbour pairs alone
+pub(crate) fn stack_thermodynamics(codes: &[u8]) -> (f64, f64) {
+    codes.windows(2).fold((0.0, 0.0), |(enthalpy, entropy), pair| {
+        let (h, s) = NEAREST_NEIGHBORS[pair[0] as usize][pair[1] as usize];
+        (enthalpy + h, entropy + s)
+    })
+}
+
+/// The free energy at 37 °C in kcal/mol of an enthalpy and entropy
+pub(crate) fn free_energy((enthalpy, entropy): (f64, f64)) -> f64 {
+    enthalpy - BODY_TEMPERATURE * entropy / 1000.0
+}
+
+/// The melting temperature of a primer made of nucleotide codes
+fn codes_tm(codes: &[u8], method: TmMethod) -> Option<f64> {
+    let len = codes.len();
+    let gc = codes.iter().filter(|&&code| code == 1 || code == 2).count();
+
+    match method {
+        _ if len == 0 => None,
+        TmMethod::Wallace => Some((2 * (len - gc) + 4 * gc) as f64),
+        TmMethod::Basic => Some(64.9 + 41.0 * (gc as f64 - 16.4) / len as f64),
+        TmMethod::NearestNeighbor(_) if len < 2 => None,
+        TmMethod::NearestNeighbor(conditions) => {
+            let (enthalpy, mut entropy) = duplex_thermodynamics(codes);
+
+            // A self-complementary primer binds another copy of itself
+            let self_complementary =
+                codes.iter().copied().eq(codes.iter().rev().map(|code| 3 - code));
+            let strands = if self_complementary {
+                entropy += SYMMETRY_ENTROPY;
+                1.0
+            } else {
+                4.0
+            };
+
+            let salt = conditions.monovalent_equivalent() / 1000.0;
+            entropy += 0.368 * (len - 1) as f64 * salt.ln();
+            let concentration = conditions.primer * 1e-9 / strands;
+
+            Some(enthalpy * 1000.0 / (entropy + GAS_CONSTANT * concentration.ln()) - ZERO_CELSIUS)
+        }
+    }
+}
+
+/// The melting temperature of a primer in °C.
+///
+/// Returns None if the primer is empty, has a symbol other than A, C, G, T or U or is a single
+/// base for the nearest-neighbour method.
+///
+/// # Example
+/// ```
+/// use biors::alphabet::UnambiguousDnaAlphabet;
+/// use biors::primer::{self, Conditions, TmMethod};
+/// use biors::sequence::Sequence;
+///
+/// let dna = UnambiguousDnaAlphabet;
+/// let mut seq = Sequence::new(&dna);
+/// seq.push("AGCGGATAACAATTTCACACAGGA").unwrap();
+///
+/// assert_eq!(primer::tm(&seq, TmMethod::Wallace), Some(68.0));
+/// let tm = primer::tm(&seq, TmMethod::NearestNeighbor(Conditions::default())).unwrap();
+/// assert!((tm - 60.8).abs() < 0.1);
+/// ```
+pub fn tm<A: Alphabet>(primer: &Sequence<A>, method: TmMethod) -> Option<f64> {
+    codes_tm(&primer_codes(primer)?, method)
+}
+
+/// The lowest and highest melting temperatures in °C of the primers an ambiguous primer stands
+/// for.
+///
+/// Returns None if the primer is empty, has a symbol that isn't an IUPAC nucleotide code, stands
+/// for more than [MAX_EXPANSIONS] primers or is a single base for the nearest-neighbour method.
+///
+/// # Example
+/// ```
+/// use biors::alphabet::AmbiguousDnaAlphabet;
+/// use biors::primer::{self, TmMethod};
+/// use biors::sequence::Sequence;
+///
+/// let dna = AmbiguousDnaAlphabet;
+/// let mut seq = Sequence::new(&dna);
+/// seq.push("GAYGGNCAR").unwrap();
+///
+/// assert_eq!(primer::degeneracy(&seq), Some(16));
+/// assert_eq!(primer::tm_range(&seq, TmMethod::Wallace), Some((26.0, 32.0)));
+/// ```
+pub fn tm_range<A: Alphabet>(primer: &Sequence<A>, method: TmMethod) -> Option<(f64, f64)> {
+    if degeneracy(primer)? > MAX_EXPANSIONS {
+        return None;
+    }
+
+    let symbols = primer.alphabet().symbols();
+    let mut expansions: Vec<Vec<u8>> = vec![vec![]];
+    for &byte in primer.as_bytes() {
+        let bases = iupac_bases(symbols[byte as usize])?;
+        expansions = expansions.into_iter()
+            .flat_map(|expansion| {
+                (0..4).filter(move |code| bases & (1 << code) != 0).map(move |code| {
+                    let mut expansion = expansion.clone();
+                    expansion.push(code);
+                    expansion
+                })
+            })
+            .collect();
+    }
+
+    expansions.iter().try_fold((f64::INFINITY, f64::NEG_INFINITY), |(low, high), codes| {
+        let tm = codes_tm(codes, method)?;
+        Some((low.min(tm), high.max(tm)))
+    })
+}
+
+/// The number of unambiguous primers an ambiguous primer stands for, or None if it has a symbol
+/// that isn't an IUPAC nucleotide code. Saturates rather than overflowing.
+pub fn degeneracy<A: Alphabet>(primer: &Sequence<A>) -> Option<usize> {
+    let symbols = primer.alphabet().symbols();
+
+    primer.as_bytes().iter().try_fold(1usize, |degeneracy, &byte| {
+        let bases = iupac_bases(symbols[byte as usize])?;
+        Some(degeneracy.saturating_mul(bases.count_ones() as usize))
+    })
+}
+
+/// The number of G, C or S symbols among the last five bases of the primer.
+pub fn gc_clamp<A: Alphabet>(primer: &Sequence<A>) -> usize {
+    let symbols = primer.alphabet().symbols();
+    let bytes = primer.as_bytes();
+
+    bytes[bytes.len().saturating_sub(5)..]
+        .iter()
+        .filter(|&&byte| matches!(symbols[byte as usize], "G" | "C" | "S"))
+        .count()
+}
+
+/// Returns true if the primer ends in G or C so its 3' end binds firmly, but has no more than
+/// three G or C among its last five bases so it isn't likely to bind in the wrong place.
+pub fn has_gc_clamp<A: Alphabet>(primer: &Sequence<A>) -> bool {
+    let symbols = primer.alphabet().symbols();
+    let ends_in_gc = primer.as_bytes()
+        .last()
+        .is_some_and(|&byte| matches!(symbols[byte as usize], "G" | "C" | "S"));
+
+    ends_in_gc && gc_clamp(primer) <= 3
+}
+
+//================================================================================
+// Tests
+//================================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alphabet::{AmbiguousDnaAlphabet, UnambiguousDnaAlphabet};
+    use crate::sequence::test_util::dna;
+
+    /// Tests the simple formulas
+    #[test]
+    fn simple_formulas() {
+        let alphabet = UnambiguousDnaAlphabet;
+        let primer = dna(&alphabet, "ACGTACGTAC");
+
+        assert_eq!(tm(&primer, TmMethod::Wallace), Some(30.0));
+        let basic = tm(&primer, TmMethod::Basic).unwrap();
+        assert!((basic - (64.9 + 41.0 * (5.0 - 16.4) / 10.0)).abs() < 1e-9);
+        assert_eq!(tm(&dna(&alphabet, ""), TmMethod::Basic), None);
+    }
+
+    /// Tests the nearest-neighbour method against the value of the formula worked by hand and
+    /// how it depends on the conditions
+    #[test]
+    fn nearest_neighbor() {
+        let alphabet = UnambiguousDnaAlphabet;
+        let primer = dna(&alphabet, "CGTTGA");
+
+        // dH = -10.6 - 8.4 - 7.9 - 8.5 - 8.2 + 0.1 + 2.3 = -41.2
+        // dS = -27.2 - 22.4 - 22.2 - 22.7 - 22.2 - 2.8 + 4.1 + 0.368 * 5 * ln(0.05) = -120.9121
+        let conditions = Conditions { monovalent: 50.0, divalent: 0.0, dntp: 0.0, primer: 200.0 };
+        let expected = -41200.0 / (-120.9121 + GAS_CONSTANT * (50e-9f64).ln()) - ZERO_CELSIUS;
+        let actual = tm(&primer, TmMethod::NearestNeighbor(conditions)).unwrap();
+        assert!((actual - expected).abs() < 1e-3);
+
+        let primer = dna(&alphabet, "GTCGATCCAGTTCAGGAAGC");
+        let default = tm(&primer, TmMethod::NearestNeighbor(Conditions::default())).unwrap();
+        assert!(default > 55.0 && default < 65.0);
+
+        let salty = Conditions { monovalent: 100.0, ..Conditions::default() };
+        assert!(tm(&primer, TmMethod::NearestNeighbor(salty)).unwrap() > default);
+        let dilute = Conditions { primer: 5.0, ..Conditions::default() };
+        assert!(tm(&primer, TmMethod::NearestNeighbor(dilute)).unwrap() < default);
+        let magnesium = Conditions { divalent: 3.0, ..Conditions::default() };
+        assert!(tm(&primer, TmMethod::NearestNeighbor(magnesium)).unwrap() > default);
+
+        // The dNTPs take up all of the Mg2+
+        let bound = Conditions { divalent: 0.5, dntp: 0.6, ..Conditions::default() };
+        assert_eq!(bound.monovalent_equivalent(), 50.0);
+
+        let method = TmMethod::NearestNeighbor(Conditions::default());
+        assert_eq!(tm(&dna(&alphabet, "A"), method), None);
+    }
+
+    /// Tests the range of ambiguous primers
+    #[test]
+    fn ambiguous() {
+        let alphabet = AmbiguousDnaAlphabet;
+        let mut primer = Sequence::new(&alphabet);
+        primer.push("ACGTNACGTAGCTAGCTGAS").unwrap();
+
+        assert_eq!(tm(&primer, TmMethod::Wallace), None);
+        let method = TmMethod::NearestNeighbor(Conditions::default());
+        let (low, high) = tm_range(&primer, method).unwrap();
+        assert!(low < high);
+
+        let mut fixed = Sequence::new(&alphabet);
+        fixed.push("ACGTAACGTAGCTAGCTGAC").unwrap();
+        let tm = tm(&fixed, method).unwrap();
+        assert!(low <= tm && tm <= high);
+
+        let mut primer = Sequence::new(&alphabet);
+        primer.push("NNNNNNNNNNNNNNNNNNNNNNNNNNNNNNNNNNNN").unwrap();
+        assert_eq!(degeneracy(&primer), Some(usize::MAX));
+        assert_eq!(tm_range(&primer, TmMethod::Wallace), None);
+    }
+
+    /// Tests the GC clamp checks
+    #[test]
+    fn clamp() {
+        let alphabet = UnambiguousDnaAlphabet;
+
+        assert_eq!(gc_clamp(&dna(&alphabet, "ATATATTAGC")), 2);
+        assert!(has_gc_clamp(&dna(&alphabet, "ATATATTAGC")));
+        assert!(!has_gc_clamp(&dna(&alphabet, "ATATATTAGCA")));
+        assert!(!has_gc_clamp(&dna(&alphabet, "ATATAGCGGC")));
+        assert_eq!(gc_clamp(&dna(&alphabet, "GC")), 2);
+        assert!(!has_gc_clamp(&dna(&alphabet, "")));
+    }
+}
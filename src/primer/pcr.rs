@@ -0,0 +1,434 @@
+//! Finding the products a pair of primers amplifies from a set of templates, which shows whether
+//! the primers are specific to their target.
+//!
+//! A primer binds wherever it matches either strand of a template with at most
+//! [max_mismatches](PcrSettings::max_mismatches) mismatches, none of which can be in the
+//! [protected](PcrSettings::protected) bases at its 3' end where the polymerase starts. The
+//! symbols of the primers and templates match if the sets of bases they stand for overlap, as
+//! in [degenerate](crate::search::degenerate) matching, so degenerate primers can be used.
+//!
+//! A product is made wherever a primer binds the top strand and a primer binds the bottom strand
+//! downstream of it. Either primer can be at either end, so a primer binding in both directions
+//! on its own also gives a product. The product of a
+//! [circular](crate::sequence::Sequence::circular) template can span its origin.
+
+use crate::alphabet::{Alphabet, Complement};
+use crate::alphabet::dna::iupac_bases;
+use crate::search::Match;
+use crate::search::degenerate::TextN;
+use crate::sequence::{Sequence, Strand};
+use super::design::{region, symbols, Amplicon};
+
+/// The limits on where primers bind and the products they give.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PcrSettings {
+    /// The most mismatches between a primer and where it binds
+    pub max_mismatches: usize,
+    /// The number of bases at the 3' end of a primer that must match
+    pub protected: usize,
+    /// The most bases in a product
+    pub max_product: usize,
+    /// What an N in a template matches
+    pub text_n: TextN,
+}
+
+impl Default for PcrSettings {
+    /// Up to 2 mismatches outside the last 5 bases of a primer and products of up to 4000
+    /// bases, where an N in a template matches nothing
+    fn default() -> Self {
+        PcrSettings {
+            max_mismatches: 2,
+            protected: 5,
+            max_product: 4000,
+            text_n: TextN::MatchesNothing,
+        }
+    }
+}
+
+/// Which primer of the pair.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PrimerRole {
+    /// The forward primer
+    Forward,
+    /// The reverse primer
+    Reverse,
+}
+
+/// A place where a primer binds a template.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Binding {
+    /// The primer that binds
+    pub primer: PrimerRole,
+    /// Where the primer matches the template. A forward site is where the primer is on the top
+    /// strand and a reverse site is where its reverse complement is.
+    pub site: Match,
+    /// The number of bases of the primer that don't match the template
+    pub mismatches: usize,
+}
+
+/// A product of a pair of primers.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Product {
+    /// The position of the template in the templates searched
+    pub template: usize,
+    /// The primer at the start of the product, which binds the top strand
+    pub left: Binding,
+    /// The primer at the end of the product, which binds the bottom strand
+    pub right: Binding,
+    /// Where the product is in the template and its top strand, which starts with the left
+    /// primer and ends with the reverse complement of the right primer rather than the bases of
+    /// the template they bind
+    pub amplicon: Amplicon,
+}
+
+impl Product {
+    /// The number of mismatches of both primers
+    pub fn mismatches(&self) -> usize {
+        self.left.mismatches + self.right.mismatches
+    }
+}
+
+/// A primer or its reverse complement as sets of bases, along with the symbols it adds to a
+/// product
+struct Probe {
+    primer: PrimerRole,
+    strand: Strand,
+    bases: Vec<u8>,
+    symbols: String,
+}
+
+impl Probe {
+    /// Both probes of a primer
+    fn both_strands<P: Complement>(primer: &Sequence<P>, role: PrimerRole) -> [Probe; 2] {
+        let mut reverse = primer.clone();
+        reverse.reverse_complement();
+
+        [
+            Probe {
+                primer: role,
+                strand: Strand::Forward,
+                bases: bases(primer),
+                symbols: symbols(primer),
+            },
+            Probe {
+                primer: role,
+                strand: Strand::Reverse,
+                bases: bases(&reverse),
+                symbols: symbols(&reverse),
+            },
+        ]
+    }
+
+    /// The positions of the probe that must match, which are at the 3' end of the primer
+    fn is_protected(&self, position: usize, protected: usize) -> bool {
+        match self.strand {
+            Strand::Forward => position + protected >= self.bases.len(),
+            Strand::Reverse => position < protected,
+        }
+    }
+
+    /// Finds every site where the probe binds the template
+    fn bind(&self, template: &[u8], circular: bool, settings: &PcrSettings) -> Vec<Binding> {
+        let len = template.len();
+        let probe_len = self.bases.len();
+        if probe_len == 0 || probe_len > len {
+            return vec![];
+        }
+
+        let starts = if circular { len } else { len - probe_len + 1 };
+        let mut bindings = vec![];
+
+        'sites: for start in 0..starts {
+            let mut mismatches = 0;
+
+            for (i, &bases) in self.bases.iter().enumerate() {
+                if bases & template[(start + i) % len] == 0 {
+                    mismatches += 1;
+                    if mismatches > settings.max_mismatches
+                        || self.is_protected(i, settings.protected)
+                    {
+                        continue 'sites;
+                    }
+                }
+            }
+
+            let (start, end) = region(start as isize, (start + probe_len) as isize, len as isize);
+            let site = Match { start, end, strand: self.strand };
+            bindings.push(Binding { primer: self.primer, site, mismatches });
+        }
+
+        bindings
+    }
+}
+
+/// The set of bases each symbol of a sequence stands for, or 0 if it isn't an IUPAC code
+fn bases<A: Alphabet>(sequence: &Sequence<A>) -> Vec<u8> {
+    let symbols = sequence.alphabet().symbols();
+
+    sequence.as_bytes()
+        .iter()
+        .map(|&byte| iupac_bases(symbols[byte as usize]).unwrap_or(0))
+        .collect()
+}
+
+/// Finds every product the pair of primers amplifies from the templates, in the order of the
+/// templates and then by start and size.
+///
+/// # Notes
+/// Symbols of the primers or templates that aren't IUPAC nucleotide codes never match. The two
+/// primers can't overlap each other and the product of a circular template can't be longer than
+/// the template.
+///
+/// # Example
+/// ```
+/// use biors::alphabet::{AmbiguousDnaAlphabet, UnambiguousDnaAlphabet};
+/// use biors::primer::pcr::{pcr, PcrSettings, PrimerRole};
+/// use biors::sequence::Sequence;
+///
+/// let primers = AmbiguousDnaAlphabet;
+/// let mut forward = Sequence::new(&primers);
+/// let mut reverse = Sequence::new(&primers);
+/// forward.push("GTRAAGCCTTCG").unwrap();
+/// reverse.push("CTGAGCTTACCA").unwrap();
+///
+/// let dna = UnambiguousDnaAlphabet;
+/// let mut template = Sequence::new(&dna);
+/// // The forward primer and the reverse complement of the reverse primer with one mismatch
+/// template.push("CCCCGTGAAGCCTTCGCCCCCCTGGTAAGCTCAACCCC").unwrap();
+///
+/// let products = pcr(&forward, &reverse, &[template], &PcrSettings::default());
+/// assert_eq!(products.len(), 1);
+///
+/// let product = &products[0];
+/// assert_eq!((product.amplicon.start, product.amplicon.end), (4, 34));
+/// assert_eq!(product.amplicon.sequence, "GTRAAGCCTTCGCCCCCCTGGTAAGCTCAG");
+/// assert_eq!(product.left.primer, PrimerRole::Forward);
+/// assert_eq!(product.right.primer, PrimerRole::Reverse);
+/// assert_eq!(product.mismatches(), 1);
+/// ```
+pub fn pcr<'t, 'a: 't, P, T, I>(
+    forward: &Sequence<P>,
+    reverse: &Sequence<P>,
+    templates: I,
+    settings: &PcrSettings
+) -> Vec<Product>
+where
+    P: Complement,
+    T: Alphabet + 'a,
+    I: IntoIterator<Item = &'t Sequence<'a, T>>
+{
+    let [forward_top, forward_bottom] = Probe::both_strands(forward, PrimerRole::Forward);
+    let [reverse_top, reverse_bottom] = Probe::both_strands(reverse, PrimerRole::Reverse);
+    let probe = |binding: &Binding| match (binding.primer, binding.site.strand) {
+        (PrimerRole::Forward, Strand::Forward) => &forward_top,
+        (PrimerRole::Forward, Strand::Reverse) => &forward_bottom,
+        (PrimerRole::Reverse, Strand::Forward) => &reverse_top,
+        (PrimerRole::Reverse, Strand::Reverse) => &reverse_bottom,
+    };
+
+    let mut products = vec![];
+
+    for (index, template) in templates.into_iter().enumerate() {
+        let symbols = template.alphabet().symbols();
+        let text: Vec<u8> = template.as_bytes()
+            .iter()
+            .map(|&byte| match (symbols[byte as usize], settings.text_n) {
+                ("N", TextN::MatchesNothing) => 0,
+                (symbol, _) => iupac_bases(symbol).unwrap_or(0),
+            })
+            .collect();
+        let circular = template.circular;
+        let len = text.len() as isize;
+
+        let mut lefts = forward_top.bind(&text, circular, settings);
+        lefts.extend(reverse_top.bind(&text, circular, settings));
+        lefts.sort_by_key(|binding| binding.site.start);
+        let mut rights = forward_bottom.bind(&text, circular, settings);
+        rights.extend(reverse_bottom.bind(&text, circular, settings));
+
+        let max_product = if circular {
+            settings.max_product.min(text.len())
+        } else {
+            settings.max_product
+        } as isize;
+        let mut found = vec![];
+
+        // The right primer is placed downstream of the left primer on the unrolled template
+        for left in &lefts {
+            let start = left.site.start as isize;
+            let left_end = start + probe(left).bases.len() as isize;
+
+            for right in &rights {
+                let mut right_start = right.site.start as isize;
+                if circular && right_start < left_end {
+                    right_start += len;
+                }
+                let end = right_start + probe(right).bases.len() as isize;
+                if right_start < left_end || end - start > max_product {
+                    continue;
+                }
+
+                let middle: String = (left_end..right_start)
+                    .map(|position| {
+                        symbols[template.as_bytes()[position.rem_euclid(len) as usize] as usize]
+                    })
+                    .collect();
+                let sequence = format!("{}{}{}", probe(left).symbols, middle, probe(right).symbols);
+                let (start, end) = region(start, end, len);
+
+                found.push(Product {
+                    template: index,
+                    left: *left,
+                    right: *right,
+                    amplicon: Amplicon { start, end, sequence },
+                });
+            }
+        }
+
+        found.sort_by_key(|product| (product.amplicon.start, product.amplicon.size()));
+        products.extend(found);
+    }
+
+    products
+}
+
+//================================================================================
+// Tests
+//================================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alphabet::{AmbiguousDnaAlphabet, UnambiguousDnaAlphabet};
+    use crate::sequence::test_util::{dna, reverse_complement};
+
+    const FORWARD: &str = "GTCGCTGAGTCCTTGCGTCT";
+    const REVERSE: &str = "CGTGCTTCGGACTGTGCTGT";
+    const REVERSE_RC: &str = "ACAGCACAGTCCGAAGCACG";
+
+    /// Tests a product on each strand of a template
+    #[test]
+    fn both_strands() {
+        let alphabet = UnambiguousDnaAlphabet;
+        let forward = dna(&alphabet, FORWARD);
+        let reverse = dna(&alphabet, REVERSE);
+
+        let top = format!(
+            "{}{}{}{}{}", "A".repeat(10), FORWARD, "A".repeat(30), REVERSE_RC, "A".repeat(10));
+        let bottom = reverse_complement(&top);
+        let templates = vec![dna(&alphabet, &top), dna(&alphabet, &bottom)];
+
+        let products = pcr(&forward, &reverse, &templates, &PcrSettings::default());
+        assert_eq!(products.len(), 2);
+
+        let product = &products[0];
+        assert_eq!(product.template, 0);
+        assert_eq!((product.amplicon.start, product.amplicon.end), (10, 80));
+        assert_eq!(product.amplicon.size(), 70);
+        assert_eq!(product.amplicon.sequence, top[10..80]);
+        assert_eq!(product.left, Binding {
+            primer: PrimerRole::Forward,
+            site: Match { start: 10, end: 30, strand: Strand::Forward },
+            mismatches: 0,
+        });
+        assert_eq!(product.right.site, Match { start: 60, end: 80, strand: Strand::Reverse });
+
+        // The reverse primer binds the top strand of the reverse complement
+        let product = &products[1];
+        assert_eq!(product.template, 1);
+        assert_eq!((product.amplicon.start, product.amplicon.end), (10, 80));
+        assert_eq!(product.amplicon.sequence, bottom[10..80]);
+        assert_eq!(product.left.primer, PrimerRole::Reverse);
+        assert_eq!(product.right.primer, PrimerRole::Forward);
+
+        let settings = PcrSettings { max_product: 69, ..PcrSettings::default() };
+        assert!(pcr(&forward, &reverse, &templates, &settings).is_empty());
+    }
+
+    /// Tests that mismatches are allowed up to the limit but not at the 3' end
+    #[test]
+    fn mismatches() {
+        let alphabet = UnambiguousDnaAlphabet;
+        let forward = dna(&alphabet, FORWARD);
+        let reverse = dna(&alphabet, REVERSE);
+        let template = |forward: &str, reverse_rc: &str| {
+            let text = format!(
+                "{}{}{}{}{}", "A".repeat(10), forward, "A".repeat(30), reverse_rc, "A".repeat(10));
+            dna(&alphabet, &text)
+        };
+
+        // Two mismatches near the 5' end of the forward primer and one of the reverse primer
+        let text = template("GAGGCTGAGTCCTTGCGTCT", "ACAGCACAGTCCGAAGCACC");
+        let products = pcr(&forward, &reverse, &[text], &PcrSettings::default());
+        assert_eq!(products.len(), 1);
+        assert_eq!((products[0].left.mismatches, products[0].right.mismatches), (2, 1));
+        assert_eq!(&products[0].amplicon.sequence[..20], FORWARD);
+        assert_eq!(&products[0].amplicon.sequence[50..], REVERSE_RC);
+
+        let text = template("GAGGCTGAGTCCTTGCGTCT", "ACAGCACAGTCCGAAGCACC");
+        let settings = PcrSettings { max_mismatches: 1, ..PcrSettings::default() };
+        assert!(pcr(&forward, &reverse, &[text], &settings).is_empty());
+
+        // One mismatch in the last five bases of the forward primer, then of the reverse primer
+        let settings = PcrSettings { max_mismatches: 1, ..PcrSettings::default() };
+        let text = template("GTCGCTGAGTCCTTGCGACT", REVERSE_RC);
+        assert!(pcr(&forward, &reverse, &[text], &settings).is_empty());
+        let text = template(FORWARD, "ACATCACAGTCCGAAGCACG");
+        assert!(pcr(&forward, &reverse, &[text], &settings).is_empty());
+
+        let settings = PcrSettings { max_mismatches: 1, protected: 0, ..PcrSettings::default() };
+        let text = template(FORWARD, "ACATCACAGTCCGAAGCACG");
+        assert_eq!(pcr(&forward, &reverse, &[text], &settings).len(), 1);
+    }
+
+    /// Tests degenerate primers and N in the template
+    #[test]
+    fn degenerate() {
+        let ambiguous = AmbiguousDnaAlphabet;
+        let alphabet = UnambiguousDnaAlphabet;
+        let mut forward = Sequence::new(&ambiguous);
+        let mut reverse = Sequence::new(&ambiguous);
+        forward.push("GTCGCTGAGTCYTTGCGTCT").unwrap();
+        reverse.push(REVERSE).unwrap();
+
+        let text = format!("{}{}{}", FORWARD, "A".repeat(30), REVERSE_RC);
+        let products = pcr(&forward, &reverse, &[dna(&alphabet, &text)], &PcrSettings::default());
+        assert_eq!(products.len(), 1);
+        assert_eq!(products[0].mismatches(), 0);
+        assert!(products[0].amplicon.sequence.starts_with("GTCGCTGAGTCYTTGCGTCT"));
+
+        let mut template = Sequence::new(&ambiguous);
+        let text = format!("{}{}{}", "GTCGCTGAGTCCTTGCGTCN", "A".repeat(30), REVERSE_RC);
+        template.push(text).unwrap();
+        let settings = PcrSettings { protected: 0, max_mismatches: 0, ..PcrSettings::default() };
+        assert!(pcr(&forward, &reverse, std::slice::from_ref(&template), &settings).is_empty());
+        let settings = PcrSettings { text_n: TextN::MatchesAnything, ..settings };
+        assert_eq!(pcr(&forward, &reverse, std::slice::from_ref(&template), &settings).len(), 1);
+    }
+
+    /// Tests that the products of a circular template can span the origin
+    #[test]
+    fn circular() {
+        let alphabet = UnambiguousDnaAlphabet;
+        let forward = dna(&alphabet, FORWARD);
+        let reverse = dna(&alphabet, REVERSE);
+
+        // The forward primer wraps around the origin
+        let text = format!("{}{}{}{}", &FORWARD[5..], "A".repeat(30), REVERSE_RC, "A".repeat(20));
+        let text = format!("{}{}", text, &FORWARD[..5]);
+        let template = dna(&alphabet, &text).circular(true);
+
+        let settings = PcrSettings::default();
+        let products = pcr(&forward, &reverse, std::slice::from_ref(&template), &settings);
+        assert_eq!(products.len(), 1);
+        let product = &products[0];
+        assert_eq!((product.amplicon.start, product.amplicon.end), (text.len() - 5, 65));
+        let site = Match { start: text.len() - 5, end: 15, strand: Strand::Forward };
+        assert_eq!(product.left.site, site);
+        let amplicon = format!("{}{}{}", FORWARD, "A".repeat(30), REVERSE_RC);
+        assert_eq!(product.amplicon.sequence, amplicon);
+
+        let linear = template.clone().circular(false);
+        assert!(pcr(&forward, &reverse, std::slice::from_ref(&linear), &settings).is_empty());
+    }
+}
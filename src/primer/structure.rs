@@ -0,0 +1,309 @@
+//! Secondary structures formed by primers: dimers between two primers and hairpins within one.
+//!
+//! Bases pair if one is the complement of the other under the alphabet's
+//! [Complement](crate::alphabet::Complement) mapping. Structures are made of a single run of
+//! consecutive base pairs without mismatches or bulges and their free energy is the
+//! nearest-neighbour free energy of the run at 37 °C in kcal/mol, without any salt correction.
+//! Only pairs of A, C, G and T (or U) have nearest-neighbour parameters, so ambiguous symbols
+//! never pair even if they are complements, like N and N.
+
+use crate::alphabet::Complement;
+use crate::sequence::Sequence;
+use super::{duplex_thermodynamics, free_energy, nucleotide_code, stack_thermodynamics};
+use super::{BODY_TEMPERATURE, GAS_CONSTANT};
+
+/// The fewest unpaired bases in the loop of a hairpin
+const MIN_LOOP: usize = 3;
+
+/// The free energy in kcal/mol of closing a hairpin loop of each length from SantaLucia and Hicks
+/// (2004). Lengths in between are interpolated.
+const HAIRPIN_LOOPS: [(usize, f64); 15] = [
+    (3, 3.5), (4, 3.5), (5, 3.3), (6, 4.0), (7, 4.2), (8, 4.3), (9, 4.5), (10, 4.6), (12, 5.0),
+    (14, 5.1), (16, 5.3), (18, 5.5), (20, 5.7), (25, 6.1), (30, 6.3),
+];
+
+/// A duplex formed between two primers or between two copies of the same primer.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Dimer {
+    /// The free energy of the duplex in kcal/mol, which is more negative for a more stable duplex
+    pub delta_g: f64,
+    /// The number of base pairs
+    pub pairs: usize,
+    /// The position in the first primer of its first paired base
+    pub first_start: usize,
+    /// The position in the second primer of its first paired base
+    pub second_start: usize,
+    /// The duplex includes the 3' end of one of the primers, so the polymerase can extend it
+    pub three_prime: bool,
+}
+
+/// A primer folded back on itself with a stem of base pairs closed by a loop.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Hairpin {
+    /// The free energy of the stem and loop in kcal/mol, which is more negative for a more
+    /// stable hairpin
+    pub delta_g: f64,
+    /// The number of base pairs in the stem
+    pub pairs: usize,
+    /// The number of unpaired bases in the loop
+    pub loop_len: usize,
+    /// The position of the first base of the stem
+    pub start: usize,
+    /// The position one past the last base of the stem
+    pub end: usize,
+}
+
+/// The nucleotide code of each symbol of a primer and whether each pair of symbols can pair
+struct Pairing {
+    codes: Vec<Option<u8>>,
+    complements: Vec<usize>,
+}
+
+impl Pairing {
+    fn new<A: Complement>(alphabet: &A) -> Self {
+        Pairing {
+            codes: alphabet.symbols().iter().map(|symbol| nucleotide_code(symbol)).collect(),
+            complements: alphabet.complement_indices(),
+        }
+    }
+
+    /// The nucleotide code of each symbol of the primer
+    fn primer_codes<A: Complement>(&self, primer: &Sequence<A>) -> Vec<Option<u8>> {
+        primer.as_bytes().iter().map(|&byte| self.codes[byte as usize]).collect()
+    }
+
+    fn pairs(&self, a: u8, b: u8) -> bool {
+        self.codes[a as usize].is_some()
+            && self.codes[b as usize].is_some()
+            && self.complements[a as usize] == b as usize
+    }
+}
+
+/// Finds the runs of consecutive pairs where first[i] pairs with second[sum - i], calling found
+/// with the first and last i of each run
+fn runs<P, F>(first_len: usize, second_len: usize, sum: usize, mut pairs: P, mut found: F)
+where
+    P: FnMut(usize, usize) -> bool,
+    F: FnMut(usize, usize)
+{
+    let low = sum.saturating_sub(second_len - 1);
+    let high = sum.min(first_len - 1);
+    let mut start = None;
+
+    for i in low..=high {
+        match (pairs(i, sum - i), start) {
+            (true, None) => start = Some(i),
+            (false, Some(run_start)) => {
+                found(run_start, i - 1);
+                start = None;
+            }
+            _ => {}
+        }
+    }
+
+    if let Some(run_start) = start {
+        found(run_start, high);
+    }
+}
+
+/// Finds the most stable duplex of at least two base pairs between two primers, with the first
+/// primer read 5' to 3' against the second read 3' to 5'.
+///
+/// # Example
+/// ```
+/// use biors::alphabet::UnambiguousDnaAlphabet;
+/// use biors::primer;
+/// use biors::sequence::Sequence;
+///
+/// let dna = UnambiguousDnaAlphabet;
+/// let mut forward = Sequence::new(&dna);
+/// let mut reverse = Sequence::new(&dna);
+/// forward.push("AAAAAAAAGAATTCG").unwrap();
+/// reverse.push("AAAACGAATTCAAAA").unwrap();
+///
+/// // GAATTCG pairs with CGAATTC at the 3' end of the forward primer
+/// let dimer = primer::cross_dimer(&forward, &reverse).unwrap();
+/// assert_eq!((dimer.pairs, dimer.first_start, dimer.second_start), (7, 8, 4));
+/// assert!(dimer.three_prime && dimer.delta_g < -5.0);
+/// ```
+pub fn cross_dimer<A: Complement>(first: &Sequence<A>, second: &Sequence<A>) -> Option<Dimer> {
+    if first.is_empty() || second.is_empty() {
+        return None;
+    }
+
+    let pairing = Pairing::new(first.alphabet());
+    let first_codes = pairing.primer_codes(first);
+    let (first_bytes, second_bytes) = (first.as_bytes(), second.as_bytes());
+    let (first_len, second_len) = (first_bytes.len(), second_bytes.len());
+    let mut best: Option<Dimer> = None;
+
+    for sum in 0..first_len + second_len - 1 {
+        let pairs = |i: usize, j: usize| pairing.pairs(first_bytes[i], second_bytes[j]);
+        runs(first_len, second_len, sum, pairs, |start, end| {
+            if end == start {
+                return;
+            }
+
+            let codes: Vec<u8> = first_codes[start..=end].iter().flatten().copied().collect();
+            let dimer = Dimer {
+                delta_g: free_energy(duplex_thermodynamics(&codes)),
+                pairs: end - start + 1,
+                first_start: start,
+                second_start: sum - end,
+                three_prime: end == first_len - 1 || sum - start == second_len - 1,
+            };
+            if best.is_none_or(|best| dimer.delta_g < best.delta_g) {
+                best = Some(dimer);
+            }
+        });
+    }
+
+    best
+}
+
+/// Finds the most stable duplex of at least two base pairs between two copies of the primer.
+/// See [cross_dimer()].
+pub fn self_dimer<A: Complement>(primer: &Sequence<A>) -> Option<Dimer> {
+    cross_dimer(primer, primer)
+}
+
+/// The free energy of closing a hairpin loop
+fn loop_free_energy(len: usize) -> f64 {
+    let (last_len, last_energy) = HAIRPIN_LOOPS[HAIRPIN_LOOPS.len() - 1];
+    if len >= last_len {
+        // Jacobson-Stockmayer extrapolation 2.44 RT ln(n / 30)
+        let ratio = len as f64 / last_len as f64;
+        return last_energy + 2.44 * GAS_CONSTANT / 1000.0 * BODY_TEMPERATURE * ratio.ln();
+    }
+
+    HAIRPIN_LOOPS.windows(2)
+        .find(|pair| len < pair[1].0)
+        .map(|pair| {
+            let ((low_len, low), (high_len, high)) = (pair[0], pair[1]);
+            low + (high - low) * (len - low_len) as f64 / (high_len - low_len) as f64
+        })
+        .expect("The loop must be at least the shortest in the table")
+}
+
+/// Finds the most stable hairpin with a stem of at least two base pairs and a loop of at least
+/// three bases.
+///
+/// # Example
+/// ```
+/// use biors::alphabet::UnambiguousDnaAlphabet;
+/// use biors::primer;
+/// use biors::sequence::Sequence;
+///
+/// let dna = UnambiguousDnaAlphabet;
+/// let mut seq = Sequence::new(&dna);
+/// seq.push("ATGCCGCTTTTGCGGCATT").unwrap();
+///
+/// let hairpin = primer::hairpin(&seq).unwrap();
+/// assert_eq!((hairpin.pairs, hairpin.loop_len, hairpin.start, hairpin.end), (7, 4, 0, 18));
+/// ```
+pub fn hairpin<A: Complement>(primer: &Sequence<A>) -> Option<Hairpin> {
+    let len = primer.len();
+    if len < MIN_LOOP + 4 {
+        return None;
+    }
+
+    let pairing = Pairing::new(primer.alphabet());
+    let codes = pairing.primer_codes(primer);
+    let bytes = primer.as_bytes();
+    let mut best: Option<Hairpin> = None;
+
+    for sum in MIN_LOOP + 3..2 * len - MIN_LOOP - 4 {
+        // The loop between i and sum - i must have at least MIN_LOOP bases
+        let pairs = |i: usize, j: usize| j > i + MIN_LOOP && pairing.pairs(bytes[i], bytes[j]);
+        runs(len, len, sum, pairs, |start, end| {
+            if end == start {
+                return;
+            }
+
+            let stem: Vec<u8> = codes[start..=end].iter().flatten().copied().collect();
+            let loop_len = sum - 2 * end - 1;
+            let hairpin = Hairpin {
+                delta_g: free_energy(stack_thermodynamics(&stem)) + loop_free_energy(loop_len),
+                pairs: end - start + 1,
+                loop_len,
+                start,
+                end: sum - start + 1,
+            };
+            if best.is_none_or(|best| hairpin.delta_g < best.delta_g) {
+                best = Some(hairpin);
+            }
+        });
+    }
+
+    best
+}
+
+//================================================================================
+// Tests
+//================================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alphabet::{AmbiguousDnaAlphabet, UnambiguousDnaAlphabet};
+    use crate::sequence::test_util::dna;
+
+    /// Tests that self-complementary primers form stable self-dimers
+    #[test]
+    fn self_dimers() {
+        let alphabet = UnambiguousDnaAlphabet;
+
+        let dimer = self_dimer(&dna(&alphabet, "ACGGATCCGT")).unwrap();
+        assert_eq!((dimer.pairs, dimer.first_start, dimer.second_start), (10, 0, 0));
+        assert!(dimer.three_prime);
+        // dG of the whole duplex from the nearest-neighbour parameters
+        let expected = free_energy(duplex_thermodynamics(&[0, 1, 2, 2, 0, 3, 1, 1, 2, 3]));
+        assert!((dimer.delta_g - expected).abs() < 1e-9);
+
+        // Only two bases at the 3' end pair, weakly
+        let dimer = self_dimer(&dna(&alphabet, "AAAAAAAAGC")).unwrap();
+        assert_eq!(dimer.pairs, 2);
+        assert!(dimer.three_prime && dimer.delta_g > -1.0);
+
+        assert!(self_dimer(&dna(&alphabet, "AAAAAAA")).is_none());
+    }
+
+    /// Tests that a dimer away from the 3' ends is reported as such
+    #[test]
+    fn internal_dimer() {
+        let alphabet = UnambiguousDnaAlphabet;
+        let first = dna(&alphabet, "AAGGCGCCAAAA");
+        let second = dna(&alphabet, "AAAAGGCGCCAAAA");
+
+        let dimer = cross_dimer(&first, &second).unwrap();
+        assert_eq!((dimer.pairs, dimer.first_start, dimer.second_start), (6, 2, 4));
+        assert!(!dimer.three_prime);
+    }
+
+    /// Tests that hairpins need a loop of three bases and that ambiguous bases don't pair
+    #[test]
+    fn hairpins() {
+        let alphabet = UnambiguousDnaAlphabet;
+
+        // GGCC could only pair with a loop of two bases
+        assert!(hairpin(&dna(&alphabet, "AAAGGCCAAAA")).is_none());
+
+        let found = hairpin(&dna(&alphabet, "AAGGGCAAAAGCCCTT")).unwrap();
+        assert_eq!((found.pairs, found.loop_len, found.start, found.end), (6, 4, 0, 16));
+        assert!(found.delta_g < 0.0);
+
+        let ambiguous = AmbiguousDnaAlphabet;
+        let mut seq = Sequence::new(&ambiguous);
+        seq.push("NNNNAAAANNNN").unwrap();
+        assert!(hairpin(&seq).is_none());
+    }
+
+    /// Tests the interpolation and extrapolation of loop energies
+    #[test]
+    fn loops() {
+        assert_eq!(loop_free_energy(3), 3.5);
+        assert!((loop_free_energy(11) - 4.8).abs() < 1e-9);
+        assert_eq!(loop_free_energy(30), 6.3);
+        assert!(loop_free_energy(60) > 6.3);
+    }
+}
@@ -0,0 +1,297 @@
+//! Collections of restriction enzymes looked up by name.
+//!
+//! Catalogues can be read from two kinds of REBASE file. The tagged formats, such as
+//! `withrefm` and `allenz`, have a record for each enzyme made up of lines starting with a field
+//! number in angle brackets, where `<1>` is the name and `<3>` is the site:
+//!
+//! ```text
+//! <1>EcoRI
+//! <2>
+//! <3>G^AATTC
+//! <4>
+//! ```
+//!
+//! The simpler form has an enzyme on each line with its name followed by its site. In both the
+//! sites are written in the notation described in the [module](super) documentation.
+
+use std::collections::HashMap;
+use super::{Enzyme, ErrorKind, RestrictionError, Result};
+
+/// A common set of commercially available enzymes in the simple REBASE form
+const COMMON_ENZYMES: &str = "\
+AatII   GACGT^C
+AclI    AA^CGTT
+AflII   C^TTAAG
+AgeI    A^CCGGT
+AluI    AG^CT
+ApaI    GGGCC^C
+AscI    GG^CGCGCC
+AvrII   C^CTAGG
+BamHI   G^GATCC
+BbsI    GAAGAC(2/6)
+BglII   A^GATCT
+BsaI    GGTCTC(1/5)
+BsiWI   C^GTACG
+BsmBI   CGTCTC(1/5)
+BspHI   T^CATGA
+BsrGI   T^GTACA
+ClaI    AT^CGAT
+DraI    TTT^AAA
+EagI    C^GGCCG
+EcoRI   G^AATTC
+EcoRV   GAT^ATC
+FseI    GGCCGG^CC
+HaeIII  GG^CC
+HindIII A^AGCTT
+HinfI   G^ANTC
+HpaI    GTT^AAC
+KpnI    GGTAC^C
+MboI    ^GATC
+MfeI    C^AATTG
+MluI    A^CGCGT
+NcoI    C^CATGG
+NdeI    CA^TATG
+NheI    G^CTAGC
+NotI    GC^GGCCGC
+NruI    TCG^CGA
+NsiI    ATGCA^T
+PacI    TTAAT^TAA
+PciI    A^CATGT
+PmeI    GTTT^AAAC
+PstI    CTGCA^G
+PvuI    CGAT^CG
+PvuII   CAG^CTG
+SacI    GAGCT^C
+SacII   CCGC^GG
+SalI    G^TCGAC
+SapI    GCTCTTC(1/4)
+SbfI    CCTGCA^GG
+ScaI    AGT^ACT
+SfiI    GGCCNNNN^NGGCC
+SmaI    CCC^GGG
+SpeI    A^CTAGT
+SphI    GCATG^C
+SspI    AAT^ATT
+StuI    AGG^CCT
+SwaI    ATTT^AAAT
+XbaI    T^CTAGA
+XhoI    C^TCGAG
+XmaI    C^CCGGG
+XmnI    GAANN^NNTTC
+";
+
+/// A collection of restriction enzymes with unique names.
+///
+/// # Example
+/// ```
+/// use biors::restriction::Catalogue;
+///
+/// let common = Catalogue::common();
+/// let eco_ri = common.get("EcoRI").unwrap();
+/// assert_eq!(eco_ri.site(), "GAATTC");
+///
+/// let text = "<1>EcoRI\n<2>\n<3>G^AATTC\n<4>\n\n<1>HindIII\n<3>A^AGCTT\n";
+/// let rebase = Catalogue::from_rebase(text).unwrap();
+/// assert_eq!(rebase.get("EcoRI"), Some(eco_ri));
+/// assert_eq!(rebase.len(), 2);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Catalogue {
+    enzymes: Vec<Enzyme>,
+    positions: HashMap<String, usize>,
+}
+
+impl Catalogue {
+    /// Constructs an empty catalogue
+    pub fn new() -> Self {
+        Catalogue::default()
+    }
+
+    /// Constructs a catalogue of about 60 common enzymes with sites of 4 to 13 bases, including
+    /// the type IIS enzymes used for Golden Gate cloning and interrupted sites such as SfiI's.
+    pub fn common() -> Self {
+        Catalogue::from_rebase(COMMON_ENZYMES).expect("The common enzymes must be valid")
+    }
+
+    /// Reads the enzymes from a REBASE file in either the tagged or the simple form.
+    ///
+    /// # Notes
+    /// Enzymes whose site is unknown or contains a ? are skipped, as is anything in a tagged
+    /// file outside the `<1>` and `<3>` fields. In the simple form blank lines and lines
+    /// starting with # are skipped.
+    ///
+    /// # Errors
+    /// If a line of the simple form doesn't have a name and a site or a site isn't valid REBASE
+    /// notation.
+    pub fn from_rebase(text: &str) -> Result<Self> {
+        let mut catalogue = Catalogue::new();
+        let tagged = text.lines().any(|line| line.starts_with("<1>"));
+
+        let mut add = |name: &str, site: &str, line: usize| -> Result<()> {
+            if name.is_empty() || site.is_empty() || site.contains('?') {
+                return Ok(());
+            }
+            let enzyme = Enzyme::from_notation(name, site).map_err(|err| {
+                let description = format!("Line {}: {}", line, err.description());
+                RestrictionError::new(err.kind().clone(), description)
+            })?;
+            catalogue.add(enzyme);
+            Ok(())
+        };
+
+        if tagged {
+            let mut name = "";
+            for (line_number, line) in text.lines().enumerate() {
+                let line = line.trim();
+                if let Some(field) = line.strip_prefix("<1>") {
+                    name = field.trim();
+                } else if let Some(site) = line.strip_prefix("<3>") {
+                    add(name, site.trim(), line_number + 1)?;
+                    name = "";
+                }
+            }
+        } else {
+            for (line_number, line) in text.lines().enumerate() {
+                let line = line.trim();
+                if line.is_empty() || line.starts_with('#') {
+                    continue;
+                }
+
+                let mut fields = line.split_whitespace();
+                match (fields.next(), fields.next()) {
+                    (Some(name), Some(site)) => add(name, site, line_number + 1)?,
+                    _ => {
+                        let description = format!(
+                            "Line {}: {:?} must have a name and a site", line_number + 1, line);
+                        return Err(RestrictionError::new(ErrorKind::Parse, description));
+                    }
+                }
+            }
+        }
+
+        Ok(catalogue)
+    }
+
+    /// Writes the catalogue in the simple REBASE form, which can be read back by
+    /// [Catalogue::from_rebase()].
+    pub fn to_rebase(&self) -> String {
+        let width = self.enzymes.iter().map(|enzyme| enzyme.name().len()).max().unwrap_or(0);
+
+        self.enzymes.iter()
+            .map(|enzyme| {
+                format!("{:width$} {}\n", enzyme.name(), enzyme.notation(), width = width)
+            })
+            .collect()
+    }
+
+    /// Adds an enzyme, replacing any enzyme with the same name
+    pub fn add(&mut self, enzyme: Enzyme) {
+        match self.positions.get(enzyme.name()) {
+            Some(&position) => self.enzymes[position] = enzyme,
+            None => {
+                self.positions.insert(enzyme.name().to_owned(), self.enzymes.len());
+                self.enzymes.push(enzyme);
+            }
+        }
+    }
+
+    /// The enzyme with the name if there is one
+    pub fn get(&self, name: &str) -> Option<&Enzyme> {
+        self.positions.get(name).map(|&position| &self.enzymes[position])
+    }
+
+    /// Every enzyme in the order they were added
+    pub fn enzymes(&self) -> &[Enzyme] {
+        &self.enzymes
+    }
+
+    /// The number of enzymes
+    pub fn len(&self) -> usize {
+        self.enzymes.len()
+    }
+
+    /// Returns true if there are no enzymes
+    pub fn is_empty(&self) -> bool {
+        self.enzymes.is_empty()
+    }
+
+    /// The enzymes that recognise the same site as the named enzyme, not including it.
+    ///
+    /// # Notes
+    /// Isoschizomers can still cut the site in different places, like SmaI and XmaI.
+    pub fn isoschizomers(&self, name: &str) -> Vec<&Enzyme> {
+        match self.get(name) {
+            Some(enzyme) => self.enzymes.iter()
+                .filter(|other| other.site() == enzyme.site() && other.name() != name)
+                .collect(),
+            None => vec![],
+        }
+    }
+}
+
+//================================================================================
+// Tests
+//================================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::Overhang;
+
+    /// Tests reading a tagged REBASE file with header lines and unknown sites
+    #[test]
+    fn tagged() {
+        let text = "REBASE version 409                                              withrefm.409
+
+<1>AarI
+<2>BveI
+<3>CACCTGC(4/8)
+<4>
+<5>Arthrobacter aurescens SS2-322
+<7>F
+
+<1>AbaB8342IV
+<2>
+<3>CATTAG(?/?)
+<4>
+
+<1>SmaI
+<3>CCC^GGG
+";
+        let catalogue = Catalogue::from_rebase(text).unwrap();
+        assert_eq!(catalogue.len(), 2);
+        assert_eq!(catalogue.enzymes()[0].cuts(), &[(11, 15)]);
+        assert_eq!(catalogue.get("SmaI").unwrap().overhang(), Overhang::Blunt);
+        assert!(catalogue.get("AbaB8342IV").is_none());
+    }
+
+    /// Tests the simple form, writing it back and replacing enzymes by name
+    #[test]
+    fn simple() {
+        let text = "# Golden Gate\nBsaI GGTCTC(1/5)\n\nEsp3I CGTCTC(1/5)\nBsaI GGTCTC(1/5)\n";
+        let catalogue = Catalogue::from_rebase(text).unwrap();
+        assert_eq!(catalogue.len(), 2);
+        assert_eq!(catalogue.to_rebase(), "BsaI  GGTCTC(1/5)\nEsp3I CGTCTC(1/5)\n");
+
+        let err = Catalogue::from_rebase("BsaI GGTCTC(1/5)\nEcoRI\n").unwrap_err();
+        assert_eq!(err.kind(), &ErrorKind::Parse);
+        assert!(err.description().starts_with("Line 2"));
+
+        let err = Catalogue::from_rebase("EcoRI G^AAXTC\n").unwrap_err();
+        assert_eq!(err.kind(), &ErrorKind::InvalidSite);
+    }
+
+    /// Tests the common set and finding isoschizomers in it
+    #[test]
+    fn common() {
+        let common = Catalogue::common();
+        assert!(common.len() > 50);
+        let rebase = Catalogue::from_rebase(&common.to_rebase()).unwrap();
+        assert_eq!(rebase.enzymes(), common.enzymes());
+
+        let names: Vec<_> =
+            common.isoschizomers("SmaI").iter().map(|enzyme| enzyme.name()).collect();
+        assert_eq!(names, vec!["XmaI"]);
+        assert!(common.isoschizomers("NotAnEnzyme").is_empty());
+    }
+}
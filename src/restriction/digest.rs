@@ -0,0 +1,512 @@
+//! Cutting DNA sequences with restriction enzymes.
+//!
+//! Sites are found on both strands with [degenerate](crate::search::degenerate) matching, where
+//! an N in the sequence is never part of a site. Cuts that fall outside a linear sequence are
+//! ignored but a [circular](crate::sequence::Sequence::circular) sequence is cut across its
+//! origin, so k cuts give k fragments and a single cut linearises it.
+
+use std::fmt;
+use crate::alphabet::{Alphabet, AmbiguousDnaAlphabet};
+use crate::search::Match;
+use crate::search::degenerate::{find_degenerate_both_strands, TextN};
+use crate::sequence::{Sequence, Strand};
+use super::{Enzyme, Overhang};
+
+/// The single stranded bases left at the end of a fragment by a cut.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct StickyEnd {
+    /// Which strand sticks out, or blunt if neither does
+    pub overhang: Overhang,
+    /// The unpaired bases read from the top strand, which is empty for blunt ends
+    pub sequence: String,
+}
+
+/// A place where an enzyme cuts a sequence.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Cut {
+    /// The name of the enzyme
+    pub enzyme: String,
+    /// The recognition site the enzyme bound to. A reverse site is where the reverse complement
+    /// of the site is on the top strand.
+    pub site: Match,
+    /// The position of the cut in the top strand, between bases top - 1 and top
+    pub top: usize,
+    /// The position of the cut in the bottom strand given on the top strand
+    pub bottom: usize,
+    /// The end the cut leaves on both of the fragments either side of it
+    pub end: StickyEnd,
+}
+
+/// A piece of the sequence between two cuts or between a cut and an end of a linear sequence.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Fragment {
+    /// The position of the first base of the top strand
+    pub start: usize,
+    /// The position one past the last base of the top strand. If the fragment wraps around the
+    /// origin of a circular sequence this will be less than or equal to start.
+    pub end: usize,
+    /// The top strand
+    pub sequence: String,
+    /// The end left by the cut at the start or None if it's the start of a linear sequence
+    pub left: Option<StickyEnd>,
+    /// The end left by the cut at the end or None if it's the end of a linear sequence
+    pub right: Option<StickyEnd>,
+}
+
+impl Fragment {
+    /// The number of bases in the top strand
+    pub fn size(&self) -> usize {
+        self.sequence.len()
+    }
+}
+
+/// A band of a simulated gel, made up of fragments too close in size to be told apart.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Band {
+    /// The size of the largest fragment in the band
+    pub size: usize,
+    /// The number of fragments in the band
+    pub fragments: usize,
+    /// The total number of bases in the band, which the brightness of the band is proportional to
+    pub mass: usize,
+}
+
+impl Enzyme {
+    /// Finds every recognition site of the enzyme on either strand of the text.
+    ///
+    /// # Panics
+    /// If the site is longer than [MAX_SHIFT_AND_LEN](crate::search::exact::MAX_SHIFT_AND_LEN).
+    pub fn find_sites<T: Alphabet>(&self, text: &Sequence<T>) -> Vec<Match> {
+        let alphabet = AmbiguousDnaAlphabet;
+        let mut site = Sequence::new(&alphabet);
+        site.push(&self.site).expect("The site must only contain IUPAC codes");
+
+        find_degenerate_both_strands(&site, text, TextN::MatchesNothing)
+    }
+
+    /// Finds every cut the enzyme makes in the text sorted by their top strand position.
+    ///
+    /// # Panics
+    /// If the site is longer than [MAX_SHIFT_AND_LEN](crate::search::exact::MAX_SHIFT_AND_LEN).
+    pub fn find_cuts<T: Alphabet>(&self, text: &Sequence<T>) -> Vec<Cut> {
+        let len = text.len() as isize;
+        let site_len = self.site.len() as isize;
+        let mut cuts = vec![];
+
+        for site in self.find_sites(text) {
+            let start = site.start as isize;
+
+            for &(top, bottom) in &self.cuts {
+                // A reverse site is read along the bottom strand so the enzyme's top strand cut
+                // is in the bottom strand
+                let (top, bottom) = match site.strand {
+                    Strand::Forward => (start + top, start + bottom),
+                    Strand::Reverse => (start + site_len - bottom, start + site_len - top),
+                };
+                let inside = 0 < top && top < len && 0 < bottom && bottom < len;
+                if !(inside || text.circular) {
+                    continue;
+                }
+
+                let overhang = match bottom - top {
+                    0 => Overhang::Blunt,
+                    difference if difference > 0 => Overhang::FivePrime,
+                    _ => Overhang::ThreePrime,
+                };
+                let sequence = symbols(text, top.min(bottom), top.max(bottom));
+
+                cuts.push(Cut {
+                    enzyme: self.name.clone(),
+                    site,
+                    top: top.rem_euclid(len) as usize,
+                    bottom: bottom.rem_euclid(len) as usize,
+                    end: StickyEnd { overhang, sequence },
+                });
+            }
+        }
+
+        cuts.sort_by_key(|cut| (cut.top, cut.bottom));
+        cuts
+    }
+}
+
+/// The symbols of the text from start up to end, wrapping around the origin
+fn symbols<T: Alphabet>(text: &Sequence<T>, start: isize, end: isize) -> String {
+    let symbols = text.alphabet().symbols();
+    let bytes = text.as_bytes();
+    let len = bytes.len() as isize;
+
+    (start..end)
+        .map(|position| symbols[bytes[position.rem_euclid(len) as usize] as usize])
+        .collect()
+}
+
+//================================================================================
+// Digestion
+//================================================================================
+
+/// The result of cutting a sequence with a set of enzymes.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Digest {
+    /// Every cut sorted by top strand position
+    pub cuts: Vec<Cut>,
+    /// The fragments in order along the sequence. The fragments of a circular sequence start at
+    /// the first cut.
+    pub fragments: Vec<Fragment>,
+}
+
+impl Digest {
+    /// The bands the fragments would run as on a gel, from the largest to the smallest.
+    ///
+    /// Fragments are in the same band as the largest fragment of the band if they are smaller
+    /// than it by at most resolution times its size, so a resolution of 0 only puts fragments of
+    /// the same size together.
+    pub fn bands(&self, resolution: f64) -> Vec<Band> {
+        let mut sizes: Vec<usize> = self.fragments.iter().map(Fragment::size).collect();
+        sizes.sort_unstable_by(|a, b| b.cmp(a));
+
+        let mut bands: Vec<Band> = vec![];
+        for size in sizes {
+            match bands.last_mut() {
+                Some(band) if (band.size - size) as f64 <= resolution * band.size as f64 => {
+                    band.fragments += 1;
+                    band.mass += size;
+                }
+                _ => bands.push(Band { size, fragments: 1, mass: size }),
+            }
+        }
+
+        bands
+    }
+}
+
+/// Cuts the text with every enzyme at once.
+///
+/// # Notes
+/// Cuts by different enzymes at the same top strand position are treated as one cut, which
+/// leaves the end of the first. A circular sequence that isn't cut gives a single fragment
+/// without ends that is still circular.
+///
+/// # Panics
+/// If the site of an enzyme is longer than
+/// [MAX_SHIFT_AND_LEN](crate::search::exact::MAX_SHIFT_AND_LEN).
+///
+/// # Example
+/// ```
+/// use biors::alphabet::UnambiguousDnaAlphabet;
+/// use biors::restriction::{self, Catalogue, Overhang};
+/// use biors::sequence::Sequence;
+///
+/// let dna = UnambiguousDnaAlphabet;
+/// let common = Catalogue::common();
+/// let enzymes = vec![common.get("EcoRI").unwrap(), common.get("BamHI").unwrap()];
+///
+/// let mut plasmid = Sequence::new(&dna).circular(true);
+/// plasmid.push("TTGAATTCTTTTTTGGATCCTT").unwrap();
+///
+/// let digest = restriction::digest(&plasmid, enzymes);
+/// let fragments: Vec<_> = digest.fragments.iter().map(|f| f.sequence.as_str()).collect();
+/// assert_eq!(fragments, vec!["AATTCTTTTTTG", "GATCCTTTTG"]);
+///
+/// let left = digest.fragments[0].left.as_ref().unwrap();
+/// assert_eq!((left.overhang, left.sequence.as_str()), (Overhang::FivePrime, "AATT"));
+///
+/// // A single cut linearises the plasmid
+/// let digest = restriction::digest(&plasmid, vec![common.get("EcoRI").unwrap()]);
+/// assert_eq!(digest.fragments.len(), 1);
+/// assert_eq!(digest.fragments[0].sequence, "AATTCTTTTTTGGATCCTTTTG");
+/// ```
+pub fn digest<'e, T, I>(text: &Sequence<T>, enzymes: I) -> Digest
+where
+    T: Alphabet,
+    I: IntoIterator<Item = &'e Enzyme>
+{
+    let mut cuts: Vec<Cut> =
+        enzymes.into_iter().flat_map(|enzyme| enzyme.find_cuts(text)).collect();
+    cuts.sort_by_key(|cut| (cut.top, cut.bottom));
+
+    let mut boundaries: Vec<&Cut> = cuts.iter().collect();
+    boundaries.dedup_by_key(|cut| cut.top);
+
+    let len = text.len();
+    let fragment = |left: Option<&Cut>, right: Option<&Cut>| {
+        let start = left.map_or(0, |cut| cut.top);
+        let end = right.map_or(len, |cut| cut.top);
+        let size = if end > start { end - start } else { end + len - start };
+
+        Fragment {
+            start,
+            end,
+            sequence: symbols(text, start as isize, (start + size) as isize),
+            left: left.map(|cut| cut.end.clone()),
+            right: right.map(|cut| cut.end.clone()),
+        }
+    };
+
+    let fragments = if boundaries.is_empty() {
+        vec![fragment(None, None)]
+    } else if text.circular {
+        boundaries.iter()
+            .zip(boundaries.iter().cycle().skip(1))
+            .map(|(&left, &right)| fragment(Some(left), Some(right)))
+            .collect()
+    } else {
+        let mut ends: Vec<Option<&Cut>> = vec![None];
+        ends.extend(boundaries.iter().map(|&cut| Some(cut)));
+        ends.push(None);
+        ends.windows(2).map(|pair| fragment(pair[0], pair[1])).collect()
+    };
+
+    Digest { cuts, fragments }
+}
+
+//================================================================================
+// Restriction Maps
+//================================================================================
+
+/// Where a single enzyme of a [RestrictionMap] recognises and cuts the sequence.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct MapEntry {
+    /// The name of the enzyme
+    pub enzyme: String,
+    /// Every recognition site in order of position
+    pub sites: Vec<Match>,
+    /// The top strand position of every cut in increasing order
+    pub cuts: Vec<usize>,
+}
+
+/// A summary of where every enzyme of a set recognises and cuts a sequence.
+///
+/// # Example
+/// ```
+/// use biors::alphabet::UnambiguousDnaAlphabet;
+/// use biors::restriction::{Catalogue, RestrictionMap};
+/// use biors::sequence::Sequence;
+///
+/// let dna = UnambiguousDnaAlphabet;
+/// let mut seq = Sequence::new(&dna);
+/// seq.push("GAATTCTTGGATCCTTGAATTC").unwrap();
+///
+/// let map = RestrictionMap::new(&seq, Catalogue::common().enzymes());
+/// let single: Vec<_> = map.single_cutters().iter().map(|entry| entry.enzyme.as_str()).collect();
+/// assert_eq!(single, vec!["BamHI", "MboI"]);
+/// assert_eq!(map.get("EcoRI").unwrap().cuts, vec![1, 17]);
+/// assert!(map.to_string().contains("EcoRI   2  1, 17\n"));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct RestrictionMap {
+    entries: Vec<MapEntry>,
+}
+
+impl RestrictionMap {
+    /// Finds the sites and cuts of every enzyme in the text.
+    ///
+    /// # Panics
+    /// If the site of an enzyme is longer than
+    /// [MAX_SHIFT_AND_LEN](crate::search::exact::MAX_SHIFT_AND_LEN).
+    pub fn new<'e, T, I>(text: &Sequence<T>, enzymes: I) -> Self
+    where
+        T: Alphabet,
+        I: IntoIterator<Item = &'e Enzyme>
+    {
+        let entries = enzymes.into_iter()
+            .map(|enzyme| {
+                let mut cuts: Vec<usize> =
+                    enzyme.find_cuts(text).iter().map(|cut| cut.top).collect();
+                cuts.dedup();
+                MapEntry { enzyme: enzyme.name().to_owned(), sites: enzyme.find_sites(text), cuts }
+            })
+            .collect();
+
+        RestrictionMap { entries }
+    }
+
+    /// The entry of every enzyme in the order they were given
+    pub fn entries(&self) -> &[MapEntry] {
+        &self.entries
+    }
+
+    /// The entry of the named enzyme if it was in the map
+    pub fn get(&self, name: &str) -> Option<&MapEntry> {
+        self.entries.iter().find(|entry| entry.enzyme == name)
+    }
+
+    /// The enzymes with exactly n recognition sites
+    pub fn cutters(&self, n: usize) -> Vec<&MapEntry> {
+        self.entries.iter().filter(|entry| entry.sites.len() == n).collect()
+    }
+
+    /// The enzymes without any recognition sites
+    pub fn non_cutters(&self) -> Vec<&MapEntry> {
+        self.cutters(0)
+    }
+
+    /// The enzymes with one recognition site
+    pub fn single_cutters(&self) -> Vec<&MapEntry> {
+        self.cutters(1)
+    }
+
+    /// The enzymes with two recognition sites
+    pub fn double_cutters(&self) -> Vec<&MapEntry> {
+        self.cutters(2)
+    }
+}
+
+impl fmt::Display for RestrictionMap {
+    /// Lists each enzyme that has a site with its number of sites and its cuts
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let cutters: Vec<&MapEntry> =
+            self.entries.iter().filter(|entry| !entry.sites.is_empty()).collect();
+        let width = cutters.iter().map(|entry| entry.enzyme.len()).max().unwrap_or(0);
+
+        for entry in cutters {
+            let cuts: Vec<String> = entry.cuts.iter().map(|cut| cut.to_string()).collect();
+            writeln!(
+                f,
+                "{:width$} {:>3}  {}",
+                entry.enzyme,
+                entry.sites.len(),
+                cuts.join(", "),
+                width = width
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+//================================================================================
+// Tests
+//================================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alphabet::{AmbiguousDnaAlphabet, UnambiguousDnaAlphabet};
+    use super::super::Catalogue;
+    use crate::sequence::test_util::dna;
+
+    /// Tests the fragments and ends of a linear digest
+    #[test]
+    fn linear() {
+        let alphabet = UnambiguousDnaAlphabet;
+        let text = dna(&alphabet, "AAGAATTCAAAAGAATTCAA");
+        let eco_ri = Enzyme::from_notation("EcoRI", "G^AATTC").unwrap();
+
+        let digest = digest(&text, vec![&eco_ri]);
+        let cuts: Vec<_> = digest.cuts.iter().map(|cut| (cut.top, cut.bottom)).collect();
+        assert_eq!(cuts, vec![(3, 7), (13, 17)]);
+
+        let fragments: Vec<_> =
+            digest.fragments.iter().map(|f| (f.start, f.end, f.sequence.as_str())).collect();
+        assert_eq!(fragments, vec![(0, 3, "AAG"), (3, 13, "AATTCAAAAG"), (13, 20, "AATTCAA")]);
+        assert_eq!(digest.fragments[0].left, None);
+        assert_eq!(digest.fragments[2].right, None);
+        let end = StickyEnd { overhang: Overhang::FivePrime, sequence: "AATT".to_owned() };
+        assert_eq!(digest.fragments[1].left, Some(end.clone()));
+        assert_eq!(digest.fragments[1].right, Some(end));
+    }
+
+    /// Tests that a site across the origin of a circular sequence is cut and linearises it
+    #[test]
+    fn circular() {
+        let alphabet = UnambiguousDnaAlphabet;
+        let mut text = dna(&alphabet, "ATTCTTTTTTGA");
+        let eco_ri = Enzyme::from_notation("EcoRI", "G^AATTC").unwrap();
+
+        assert!(digest(&text, vec![&eco_ri]).cuts.is_empty());
+
+        text.circular = true;
+        let digest = digest(&text, vec![&eco_ri]);
+        assert_eq!((digest.cuts[0].top, digest.cuts[0].bottom), (11, 3));
+        assert_eq!(digest.cuts[0].end.sequence, "AATT");
+        assert_eq!(digest.fragments.len(), 1);
+        assert_eq!((digest.fragments[0].start, digest.fragments[0].end), (11, 11));
+        assert_eq!(digest.fragments[0].sequence, "AATTCTTTTTTG");
+
+        // Without a cut the plasmid stays whole
+        let not_i = Enzyme::from_notation("NotI", "GC^GGCCGC").unwrap();
+        let uncut = super::digest(&text, vec![&not_i]);
+        assert_eq!(uncut.fragments.len(), 1);
+        assert_eq!((uncut.fragments[0].start, uncut.fragments[0].end), (0, 12));
+    }
+
+    /// Tests cuts outside the site on either strand
+    #[test]
+    fn type_iis() {
+        let alphabet = UnambiguousDnaAlphabet;
+        let bsa_i = Enzyme::from_notation("BsaI", "GGTCTC(1/5)").unwrap();
+
+        let text = dna(&alphabet, "TTTTTCCCCCGAGACCTTT");
+        let cuts = bsa_i.find_cuts(&text);
+        assert_eq!(cuts[0].site.strand, Strand::Reverse);
+        assert_eq!((cuts[0].top, cuts[0].bottom), (5, 9));
+        assert_eq!(cuts[0].end.sequence, "CCCC");
+
+        let text = dna(&alphabet, "AAGGTCTCAGCTTAAA");
+        let cuts = bsa_i.find_cuts(&text);
+        assert_eq!((cuts[0].top, cuts[0].bottom), (9, 13));
+        assert_eq!(cuts[0].end.sequence, "GCTT");
+
+        // The site is found but the cuts would be past the end of the sequence
+        let text = dna(&alphabet, "AAGGTCTCAG");
+        assert_eq!(bsa_i.find_sites(&text).len(), 1);
+        assert!(bsa_i.find_cuts(&text).is_empty());
+    }
+
+    /// Tests that ambiguous sites match and Ns in the text don't
+    #[test]
+    fn ambiguous() {
+        let alphabet = AmbiguousDnaAlphabet;
+        let hinf_i = Enzyme::from_notation("HinfI", "G^ANTC").unwrap();
+        let mut text = Sequence::new(&alphabet);
+        text.push("GACTCTTGAGTCTTGANTC").unwrap();
+
+        let starts: Vec<_> = hinf_i.find_sites(&text).iter().map(|site| site.start).collect();
+        assert_eq!(starts, vec![0, 7]);
+    }
+
+    /// Tests the cutter reports of a restriction map
+    #[test]
+    fn map() {
+        let alphabet = UnambiguousDnaAlphabet;
+        let text = dna(&alphabet, "GAATTCTTGGATCCTTGAATTC");
+        let common = Catalogue::common();
+        let map = RestrictionMap::new(&text, common.enzymes());
+
+        assert_eq!(map.entries().len(), common.len());
+        let double: Vec<_> =
+            map.double_cutters().iter().map(|entry| entry.enzyme.as_str()).collect();
+        assert_eq!(double, vec!["EcoRI"]);
+        assert!(map.non_cutters().iter().any(|entry| entry.enzyme == "XhoI"));
+        assert_eq!(map.get("MboI").unwrap().cuts, vec![9]);
+        assert_eq!(map.to_string(), "BamHI   1  9\nEcoRI   2  1, 17\nMboI    1  9\n");
+    }
+
+    /// Tests that fragments of similar size run together on a gel
+    #[test]
+    fn bands() {
+        let alphabet = UnambiguousDnaAlphabet;
+        let text = format!(
+            "{}GAATTC{}GAATTC{}GAATTC{}",
+            "A".repeat(99),
+            "C".repeat(94),
+            "A".repeat(89),
+            "T".repeat(50)
+        );
+        let text = dna(&alphabet, &text);
+        let eco_ri = Enzyme::from_notation("EcoRI", "G^AATTC").unwrap();
+        let digest = digest(&text, vec![&eco_ri]);
+
+        let sizes: Vec<_> = digest.fragments.iter().map(Fragment::size).collect();
+        assert_eq!(sizes, vec![100, 100, 95, 55]);
+
+        let bands = digest.bands(0.0);
+        assert_eq!(bands[0], Band { size: 100, fragments: 2, mass: 200 });
+        assert_eq!(bands.len(), 3);
+
+        let bands = digest.bands(0.05);
+        assert_eq!(bands[0], Band { size: 100, fragments: 3, mass: 295 });
+        assert_eq!(bands[1], Band { size: 55, fragments: 1, mass: 55 });
+    }
+}
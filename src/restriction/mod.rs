@@ -0,0 +1,312 @@
+//! Restriction enzymes and in-silico digestion of DNA sequences.
+//!
+//! An [Enzyme] has a recognition site written with IUPAC codes and the positions where it cuts
+//! each strand. Enzymes can be written in the notation used by REBASE:
+//!
+//! - `G^AATTC` cuts the top strand at the caret and the bottom strand at the mirror position,
+//!   which is how sites that are their own reverse complement are written
+//! - `GGTCTC(1/5)` cuts the top strand 1 base and the bottom strand 5 bases after the site
+//! - `(8/13)GACNNNNNNTGG` cuts the top strand 8 bases and the bottom strand 13 bases before it
+//!
+//! A [Catalogue] holds a set of enzymes by name and can be read from REBASE files or built from a
+//! common set of commercially available enzymes. The [digest](mod@digest) module cuts a sequence
+//! with a set of enzymes into fragments with sticky ends and summarises where every enzyme of a
+//! catalogue cuts.
+
+use std::error::Error;
+use std::fmt;
+use crate::alphabet::{AmbiguousDnaAlphabet, Complement};
+use crate::alphabet::dna::iupac_bases;
+
+pub use self::catalogue::Catalogue;
+pub use self::digest::{digest, Digest, Fragment, RestrictionMap};
+
+pub mod catalogue;
+pub mod digest;
+
+/// Result type for building and reading restriction enzymes
+pub type Result<T> = std::result::Result<T, RestrictionError>;
+
+/// The single stranded end left by a cut.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Overhang {
+    /// Both strands are cut at the same place
+    Blunt,
+    /// The top strand is cut before the bottom strand so the 5' end of each strand sticks out
+    FivePrime,
+    /// The top strand is cut after the bottom strand so the 3' end of each strand sticks out
+    ThreePrime,
+}
+
+/// A restriction enzyme with its recognition site and where it cuts.
+///
+/// Cuts are given as a pair of positions on the top strand relative to the start of the site,
+/// one where the top strand is cut and one where the bottom strand is cut. A position of i is
+/// between bases i - 1 and i of the site so cuts can be before the site (negative) or after it
+/// (larger than the site).
+///
+/// # Example
+/// ```
+/// use biors::restriction::{Enzyme, Overhang};
+///
+/// let eco_ri = Enzyme::from_notation("EcoRI", "G^AATTC").unwrap();
+/// assert_eq!(eco_ri.site(), "GAATTC");
+/// assert_eq!(eco_ri.cuts(), &[(1, 5)]);
+/// assert_eq!((eco_ri.overhang(), eco_ri.overhang_len()), (Overhang::FivePrime, 4));
+/// assert!(eco_ri.is_palindromic());
+///
+/// let bsa_i = Enzyme::from_notation("BsaI", "GGTCTC(1/5)").unwrap();
+/// assert_eq!(bsa_i.cuts(), &[(7, 11)]);
+/// assert_eq!(bsa_i.to_string(), "BsaI GGTCTC(1/5)");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Enzyme {
+    name: String,
+    site: String,
+    cuts: Vec<(isize, isize)>,
+}
+
+impl Enzyme {
+    /// Constructs an enzyme from its recognition site and the top and bottom strand position of
+    /// each of its cuts. An enzyme without any cuts can still find its sites but never cuts.
+    ///
+    /// # Errors
+    /// If the site is empty or has a symbol that isn't an IUPAC nucleotide code.
+    pub fn new(name: &str, site: &str, cuts: Vec<(isize, isize)>) -> Result<Self> {
+        if site.is_empty() {
+            let description = format!("The site of {} is empty", name);
+            return Err(RestrictionError::new(ErrorKind::InvalidSite, description));
+        }
+        let invalid = site.chars().find(|&c| c == 'U' || iupac_bases(&c.to_string()).is_none());
+        if let Some(symbol) = invalid {
+            let description = format!(
+                "The site {} of {} has {:?} which isn't an IUPAC code", site, name, symbol);
+            return Err(RestrictionError::new(ErrorKind::InvalidSite, description));
+        }
+
+        Ok(Enzyme { name: name.to_owned(), site: site.to_owned(), cuts })
+    }
+
+    /// Constructs an enzyme from its site written in REBASE notation, such as `G^AATTC`,
+    /// `GGTCTC(1/5)` or `(8/13)GACNNNNNNTGG(12/7)`. A site without a caret or parentheses has no
+    /// known cuts.
+    ///
+    /// # Errors
+    /// If the notation can't be parsed or the site has a symbol that isn't an IUPAC nucleotide
+    /// code.
+    pub fn from_notation(name: &str, notation: &str) -> Result<Self> {
+        let invalid = || {
+            let description =
+                format!("The site {:?} of {} isn't in REBASE notation", notation, name);
+            RestrictionError::new(ErrorKind::InvalidSite, description)
+        };
+        let parse_cut = |cut: &str| -> Result<(isize, isize)> {
+            let (top, bottom) = cut.split_once('/').ok_or_else(invalid)?;
+            let top = top.trim().parse().map_err(|_| invalid())?;
+            let bottom = bottom.trim().parse().map_err(|_| invalid())?;
+            Ok((top, bottom))
+        };
+
+        let mut site = notation.trim();
+        let mut before = None;
+        let mut after = None;
+        if let Some(rest) = site.strip_prefix('(') {
+            let (cut, rest) = rest.split_once(')').ok_or_else(invalid)?;
+            before = Some(parse_cut(cut)?);
+            site = rest;
+        }
+        if let Some(rest) = site.strip_suffix(')') {
+            let (rest, cut) = rest.rsplit_once('(').ok_or_else(invalid)?;
+            after = Some(parse_cut(cut)?);
+            site = rest;
+        }
+
+        let caret = site.find('^');
+        let site = site.replacen('^', "", 1);
+        let len = site.len() as isize;
+
+        let mut cuts = vec![];
+        if let Some((top, bottom)) = before {
+            cuts.push((-top, -bottom));
+        }
+        if let Some(caret) = caret {
+            if before.is_some() || after.is_some() || site.contains('^') {
+                return Err(invalid());
+            }
+            cuts.push((caret as isize, len - caret as isize));
+        }
+        if let Some((top, bottom)) = after {
+            cuts.push((len + top, len + bottom));
+        }
+
+        Enzyme::new(name, &site, cuts)
+    }
+
+    /// The name of the enzyme
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The recognition site as IUPAC codes
+    pub fn site(&self) -> &str {
+        &self.site
+    }
+
+    /// The top and bottom strand positions of each cut relative to the start of the site
+    pub fn cuts(&self) -> &[(isize, isize)] {
+        &self.cuts
+    }
+
+    /// Returns true if the site is its own reverse complement, so the enzyme cuts the same way
+    /// whichever strand the site is read on.
+    pub fn is_palindromic(&self) -> bool {
+        reverse_complement(&self.site) == self.site
+    }
+
+    /// The kind of end left by the first cut, which is blunt if the enzyme doesn't cut.
+    pub fn overhang(&self) -> Overhang {
+        match self.cuts.first() {
+            Some((top, bottom)) if top < bottom => Overhang::FivePrime,
+            Some((top, bottom)) if top > bottom => Overhang::ThreePrime,
+            _ => Overhang::Blunt,
+        }
+    }
+
+    /// The number of unpaired bases left by the first cut
+    pub fn overhang_len(&self) -> usize {
+        self.cuts.first().map_or(0, |(top, bottom)| (top - bottom).unsigned_abs())
+    }
+
+    /// Writes the site in REBASE notation, which can be read back by
+    /// [Enzyme::from_notation()].
+    pub fn notation(&self) -> String {
+        let len = self.site.len() as isize;
+        let mut before = String::new();
+        let mut site = self.site.clone();
+        let mut after = String::new();
+
+        for &(top, bottom) in &self.cuts {
+            if top < 0 && bottom < 0 {
+                before = format!("({}/{})", -top, -bottom);
+            } else if (0..=len).contains(&top)
+                && bottom == len - top
+                && site.len() == self.site.len()
+            {
+                site.insert(top as usize, '^');
+            } else {
+                after = format!("({}/{})", top - len, bottom - len);
+            }
+        }
+
+        format!("{}{}{}", before, site, after)
+    }
+}
+
+impl fmt::Display for Enzyme {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {}", self.name, self.notation())
+    }
+}
+
+/// The reverse complement of a string of IUPAC codes
+fn reverse_complement(site: &str) -> String {
+    let alphabet = AmbiguousDnaAlphabet;
+    let symbols: Vec<String> = site.chars().rev().map(|c| c.to_string()).collect();
+    alphabet.complement(&symbols).concat()
+}
+
+//================================================================================
+// Errors
+//================================================================================
+
+/// Represents the kind of error that occurred while building or reading restriction enzymes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ErrorKind {
+    /// A recognition site isn't made of IUPAC codes or isn't in REBASE notation
+    InvalidSite,
+
+    /// An enzyme file could not be parsed
+    Parse,
+}
+
+/// The type of error returned when a restriction enzyme can't be built or read
+#[derive(Debug, Clone)]
+pub struct RestrictionError {
+    kind: ErrorKind,
+    description: String,
+}
+
+impl RestrictionError {
+    /// Construct a new RestrictionError from the given ErrorKind and description
+    pub fn new(kind: ErrorKind, description: String) -> RestrictionError {
+        RestrictionError { kind, description }
+    }
+
+    /// Get the associated ErrorKind for this error
+    pub fn kind(&self) -> &ErrorKind {
+        &self.kind
+    }
+
+    /// Get the associated description for this error
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+}
+
+impl Error for RestrictionError {}
+
+impl fmt::Display for RestrictionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Restriction error: {:?}:\n\t{}", self.kind, self.description)
+    }
+}
+
+//================================================================================
+// Tests
+//================================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tests reading each form of REBASE notation and writing it back
+    #[test]
+    fn notation() {
+        let pst_i = Enzyme::from_notation("PstI", "CTGCA^G").unwrap();
+        assert_eq!(pst_i.cuts(), &[(5, 1)]);
+        assert_eq!((pst_i.overhang(), pst_i.overhang_len()), (Overhang::ThreePrime, 4));
+
+        let eco_rv = Enzyme::from_notation("EcoRV", "GAT^ATC").unwrap();
+        assert_eq!((eco_rv.overhang(), eco_rv.overhang_len()), (Overhang::Blunt, 0));
+
+        let mbo_i = Enzyme::from_notation("MboI", "^GATC").unwrap();
+        assert_eq!(mbo_i.cuts(), &[(0, 4)]);
+
+        let bae_i = Enzyme::from_notation("BaeI", "(10/15)ACNNNNGTAYC(12/7)").unwrap();
+        assert_eq!(bae_i.cuts(), &[(-10, -15), (23, 18)]);
+        assert_eq!(bae_i.overhang(), Overhang::ThreePrime);
+        assert!(!bae_i.is_palindromic());
+
+        let gsu_i = Enzyme::from_notation("GsuI", "CTGGAG(16/14)").unwrap();
+        assert_eq!(gsu_i.overhang(), Overhang::ThreePrime);
+
+        for enzyme in &[pst_i, eco_rv, mbo_i, bae_i, gsu_i] {
+            assert_eq!(&Enzyme::from_notation(enzyme.name(), &enzyme.notation()).unwrap(), enzyme);
+        }
+
+        let unknown = Enzyme::from_notation("Unknown", "GATC").unwrap();
+        assert!(unknown.cuts().is_empty());
+        assert_eq!(unknown.notation(), "GATC");
+    }
+
+    /// Tests that invalid sites are rejected
+    #[test]
+    fn invalid_sites() {
+        let notations =
+            ["", "GA^AT^TC", "GAEGTC", "GAATTC(1/x)", "(1/5GAATTC", "G^AATTC(1/5)", "gaattc"];
+        for notation in &notations {
+            let err = Enzyme::from_notation("Bad", notation).unwrap_err();
+            assert_eq!(err.kind(), &ErrorKind::InvalidSite, "{}", notation);
+        }
+    }
+}
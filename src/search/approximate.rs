@@ -0,0 +1,526 @@
+//! Finding occurrences of a pattern that differ from it by at most k edits.
+//!
+//! Edit distance searches use Myers' bit-vector algorithm which computes a column of the dynamic
+//! programming matrix 64 rows at a time. Patterns longer than 64 symbols are split into blocks
+//! of 64 rows. When only substitutions need to be allowed the Hamming distance search, which
+//! counts mismatches bit-parallel with Shift-Add, is faster.
+//!
+//! Both searches compare encoded bytes directly so they work on the symbol indices produced by
+//! the [AsciiIndexEncoder](crate::alphabet::encoding::index_encoder::AsciiIndexEncoder). The text
+//! is always treated as linear.
+
+use crate::alphabet::Alphabet;
+use crate::sequence::Sequence;
+
+/// The number of rows of the dynamic programming matrix handled by each block
+const BLOCK_SIZE: usize = 64;
+
+/// A single step of an alignment between a pattern and the text it was found in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EditOperation {
+    /// The symbol of the pattern is the same as the symbol of the text
+    Match,
+    /// The symbol of the pattern was replaced by a different symbol in the text
+    Substitution,
+    /// The text contains a symbol that is not in the pattern
+    Insertion,
+    /// A symbol of the pattern is missing from the text
+    Deletion,
+}
+
+/// The end of an approximate occurrence found by [Myers::find_ends()] or [find_hamming()].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Hit {
+    /// Position in the text one past the last symbol of the occurrence
+    pub end: usize,
+    /// The smallest number of edits needed to turn the pattern into text ending at end
+    pub distance: usize,
+}
+
+/// An approximate occurrence of a pattern along with how it aligns to the text.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ApproximateMatch {
+    /// Position in the text of the first symbol of the occurrence
+    pub start: usize,
+    /// Position in the text one past the last symbol of the occurrence
+    pub end: usize,
+    /// The number of edits in the alignment
+    pub distance: usize,
+    /// The steps of the alignment from the start of the pattern to its end
+    pub operations: Vec<EditOperation>,
+}
+
+//================================================================================
+// Myers
+//================================================================================
+
+/// Myers' bit-vector algorithm for finding every end position in a text where some substring
+/// is within k edits of the pattern.
+///
+/// Runs in O(n⌈m / 64⌉) time for a text of length n and a pattern of length m.
+///
+/// # Example
+/// ```
+/// use biors::search::approximate::{Myers, Hit};
+///
+/// // Encoded bytes rather than symbols
+/// let myers = Myers::new(&[0, 1, 2, 3]);
+/// let text = [3, 3, 0, 1, 3, 3, 3];
+///
+/// // 0 1 3 needs one deletion and 0 1 3 3 needs one substitution
+/// let ends: Vec<_> = myers.find_ends(&text, 1).iter().map(|hit| hit.end).collect();
+/// assert_eq!(ends, vec![5, 6]);
+/// ```
+#[derive(Debug, Clone)]
+pub struct Myers {
+    pattern: Vec<u8>,
+    /// Bit i of peq\[b\]\[c\] is set if row 64b + i of the pattern is the byte c
+    peq: Vec<Vec<u64>>,
+}
+
+/// The state of a block of rows between columns
+#[derive(Debug, Clone, Copy)]
+struct Block {
+    /// Vertical positive delta bits
+    pv: u64,
+    /// Vertical negative delta bits
+    mv: u64,
+}
+
+impl Myers {
+    /// Precomputes the bit vectors for each block of the pattern.
+    pub fn new(pattern: &[u8]) -> Self {
+        let blocks = pattern.len().div_ceil(BLOCK_SIZE);
+        let mut peq = vec![vec![0u64; 256]; blocks];
+
+        for (i, &byte) in pattern.iter().enumerate() {
+            peq[i / BLOCK_SIZE][byte as usize] |= 1 << (i % BLOCK_SIZE);
+        }
+
+        Myers { pattern: pattern.to_vec(), peq }
+    }
+
+    /// The number of bytes in the pattern
+    pub fn pattern_len(&self) -> usize {
+        self.pattern.len()
+    }
+
+    /// Finds every position in the text where an occurrence of the pattern with at most k
+    /// edits ends, in increasing order.
+    ///
+    /// # Notes
+    /// An occurrence usually ends at several neighbouring positions with different distances.
+    /// Every one of them is reported.
+    pub fn find_ends(&self, text: &[u8], k: usize) -> Vec<Hit> {
+        let m = self.pattern.len();
+        let mut hits = vec![];
+
+        if m == 0 {
+            return hits;
+        }
+
+        let mut blocks = vec![Block { pv: !0, mv: 0 }; self.peq.len()];
+        let last = blocks.len() - 1;
+        let last_bit = 1u64 << ((m - 1) % BLOCK_SIZE);
+        let mut score = m;
+
+        for (j, &byte) in text.iter().enumerate() {
+            // The top row of the matrix is always 0 so there is no carry into the first block
+            let mut carry = 0;
+
+            for (b, block) in blocks.iter_mut().enumerate() {
+                let high_bit = if b == last { last_bit } else { 1 << (BLOCK_SIZE - 1) };
+                carry = advance_block(block, self.peq[b][byte as usize], carry, high_bit);
+            }
+
+            score = (score as isize + carry as isize) as usize;
+            if score <= k {
+                hits.push(Hit { end: j + 1, distance: score });
+            }
+        }
+
+        hits
+    }
+
+    /// Finds every occurrence of the pattern with at most k edits along with its start and
+    /// alignment. Each end found by [find_ends()](Myers::find_ends) is traced back to the start
+    /// of the best alignment that finishes there.
+    pub fn find_matches(&self, text: &[u8], k: usize) -> Vec<ApproximateMatch> {
+        self.find_ends(text, k)
+            .into_iter()
+            .map(|hit| self.traceback(text, hit, k))
+            .collect()
+    }
+
+    /// Recovers the alignment of an occurrence by filling in the full matrix for the part of the
+    /// text it could start in
+    fn traceback(&self, text: &[u8], hit: Hit, k: usize) -> ApproximateMatch {
+        let m = self.pattern.len();
+        let offset = hit.end.saturating_sub(m + k);
+        let window = &text[offset..hit.end];
+        let w = window.len();
+
+        // The first row is 0 as the occurrence may start anywhere in the window
+        let mut matrix = vec![vec![0usize; w + 1]; m + 1];
+        for (i, row) in matrix.iter_mut().enumerate() {
+            row[0] = i;
+        }
+
+        for i in 1..=m {
+            for j in 1..=w {
+                let cost = if self.pattern[i - 1] == window[j - 1] { 0 } else { 1 };
+                matrix[i][j] = (matrix[i - 1][j - 1] + cost)
+                    .min(matrix[i - 1][j] + 1)
+                    .min(matrix[i][j - 1] + 1);
+            }
+        }
+
+        let (mut i, mut j) = (m, w);
+        let mut operations = Vec::with_capacity(m + k);
+
+        while i > 0 {
+            let current = matrix[i][j];
+
+            if j > 0 && self.pattern[i - 1] == window[j - 1] && current == matrix[i - 1][j - 1] {
+                operations.push(EditOperation::Match);
+                i -= 1;
+                j -= 1;
+            } else if j > 0 && current == matrix[i - 1][j - 1] + 1 {
+                operations.push(EditOperation::Substitution);
+                i -= 1;
+                j -= 1;
+            } else if current == matrix[i - 1][j] + 1 {
+                operations.push(EditOperation::Deletion);
+                i -= 1;
+            } else {
+                operations.push(EditOperation::Insertion);
+                j -= 1;
+            }
+        }
+
+        operations.reverse();
+
+        ApproximateMatch { start: offset + j, end: hit.end, distance: matrix[m][w], operations }
+    }
+}
+
+/// Advances a block of rows by one column of the text given the bits of the rows that match the
+/// text symbol and the change in score along the top of the block. Returns the change in score
+/// along the row marked by high_bit.
+#[inline]
+fn advance_block(block: &mut Block, eq: u64, carry_in: i8, high_bit: u64) -> i8 {
+    let Block { pv, mv } = *block;
+
+    let xv = eq | mv;
+    let eq = if carry_in < 0 { eq | 1 } else { eq };
+    let xh = ((eq & pv).wrapping_add(pv) ^ pv) | eq;
+
+    let mut ph = mv | !(xh | pv);
+    let mut mh = pv & xh;
+
+    let carry_out = if ph & high_bit != 0 {
+        1
+    } else if mh & high_bit != 0 {
+        -1
+    } else {
+        0
+    };
+
+    ph <<= 1;
+    mh <<= 1;
+    if carry_in < 0 {
+        mh |= 1;
+    } else if carry_in > 0 {
+        ph |= 1;
+    }
+
+    block.pv = mh | !(xv | ph);
+    block.mv = ph & xv;
+
+    carry_out
+}
+
+//================================================================================
+// Hamming
+//================================================================================
+
+/// Finds every position where the pattern occurs in the text with at most k substitutions and
+/// no insertions or deletions. Each occurrence starts at end - m.
+///
+/// Uses the bit-parallel Shift-Add algorithm, which keeps a counter of mismatches for every
+/// prefix of the pattern. The counters are sliced across words so that bit i of the b-th word
+/// is bit b of the counter of the prefix of length i + 1, and counters that go past k are
+/// marked as overflowed. Runs in O(n⌈m / 64⌉⌈log2(k + 1)⌉) time for a text of length n and a
+/// pattern of length m.
+///
+/// # Example
+/// ```
+/// use biors::search::approximate::find_hamming;
+///
+/// let ends: Vec<_> = find_hamming(&[0, 1, 2], &[0, 1, 1, 0, 2, 2], 1)
+///     .iter()
+///     .map(|hit| hit.end)
+///     .collect();
+///
+/// assert_eq!(ends, vec![3, 6]);
+/// ```
+pub fn find_hamming(pattern: &[u8], text: &[u8], k: usize) -> Vec<Hit> {
+    let m = pattern.len();
+    let mut hits = vec![];
+
+    if m == 0 || m > text.len() {
+        return hits;
+    }
+
+    let blocks = m.div_ceil(BLOCK_SIZE);
+    let bits = (usize::BITS - k.leading_zeros()) as usize;
+
+    // The prefixes of the pattern whose last symbol isn't each byte
+    let mut mismatches = vec![0u64; 256 * blocks];
+    for byte in 0..256 {
+        for (i, &p) in pattern.iter().enumerate() {
+            if p as usize != byte {
+                mismatches[byte * blocks + i / BLOCK_SIZE] |= 1 << (i % BLOCK_SIZE);
+            }
+        }
+    }
+
+    let mut counters = vec![0u64; bits * blocks];
+    let mut overflow = vec![0u64; blocks];
+    let last_block = (m - 1) / BLOCK_SIZE;
+    let last_bit = 1 << ((m - 1) % BLOCK_SIZE);
+
+    for (j, &t) in text.iter().enumerate() {
+        // Extending each prefix by one symbol moves its counter to the next bit and starts a new
+        // empty prefix
+        for slice in counters.chunks_mut(blocks).chain(std::iter::once(&mut overflow[..])) {
+            let mut carry = 0;
+            for word in slice.iter_mut() {
+                let shifted_out = *word >> (BLOCK_SIZE - 1);
+                *word = (*word << 1) | carry;
+                carry = shifted_out;
+            }
+        }
+
+        // Add one to the counter of every prefix whose last symbol doesn't match
+        for block in 0..blocks {
+            let mut carry = mismatches[t as usize * blocks + block];
+            for b in 0..bits {
+                let word = &mut counters[b * blocks + block];
+                let sum = *word ^ carry;
+                carry &= *word;
+                *word = sum;
+            }
+            overflow[block] |= carry;
+        }
+
+        if j + 1 >= m && overflow[last_block] & last_bit == 0 {
+            let distance = (0..bits)
+                .filter(|&b| counters[b * blocks + last_block] & last_bit != 0)
+                .map(|b| 1 << b)
+                .sum();
+            if distance <= k {
+                hits.push(Hit { end: j + 1, distance });
+            }
+        }
+    }
+
+    hits
+}
+
+//================================================================================
+// Sequences
+//================================================================================
+
+/// Finds every occurrence of the pattern in the text within k edits along with its alignment.
+/// See [Myers::find_matches()].
+pub fn find_approximate<A: Alphabet>(
+    pattern: &Sequence<A>,
+    text: &Sequence<A>,
+    k: usize
+) -> Vec<ApproximateMatch> {
+    Myers::new(pattern.as_bytes()).find_matches(text.as_bytes(), k)
+}
+
+/// Finds every occurrence of the pattern in the text with at most k substitutions along with
+/// its alignment. See [find_hamming()].
+pub fn find_approximate_hamming<A: Alphabet>(
+    pattern: &Sequence<A>,
+    text: &Sequence<A>,
+    k: usize
+) -> Vec<ApproximateMatch> {
+    let pattern = pattern.as_bytes();
+    let text = text.as_bytes();
+    let m = pattern.len();
+
+    find_hamming(pattern, text, k)
+        .into_iter()
+        .map(|hit| {
+            let start = hit.end - m;
+            let operations = pattern.iter()
+                .zip(&text[start..hit.end])
+                .map(|(p, t)| {
+                    if p == t { EditOperation::Match } else { EditOperation::Substitution }
+                })
+                .collect();
+
+            ApproximateMatch { start, end: hit.end, distance: hit.distance, operations }
+        })
+        .collect()
+}
+
+//================================================================================
+// Tests
+//================================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alphabet::UnambiguousDnaAlphabet;
+    use crate::sequence::test_util::{dna, random_bytes};
+
+    /// Computes the last row of the semi-global edit distance matrix directly
+    fn naive_distances(pattern: &[u8], text: &[u8]) -> Vec<usize> {
+        let mut previous: Vec<usize> = vec![0; text.len() + 1];
+
+        for (i, &p) in pattern.iter().enumerate() {
+            let mut current = vec![i + 1; text.len() + 1];
+            for (j, &t) in text.iter().enumerate() {
+                let cost = if p == t { 0 } else { 1 };
+                current[j + 1] = (previous[j] + cost).min(previous[j + 1] + 1).min(current[j] + 1);
+            }
+            previous = current;
+        }
+
+        previous[1..].to_vec()
+    }
+
+    /// Returns every end position within k of the pattern using the naive distances
+    fn naive_ends(pattern: &[u8], text: &[u8], k: usize) -> Vec<Hit> {
+        naive_distances(pattern, text)
+            .into_iter()
+            .enumerate()
+            .filter(|&(_, d)| d <= k)
+            .map(|(j, d)| Hit { end: j + 1, distance: d })
+            .collect()
+    }
+
+    /// Mutates a copy of some bytes with substitutions, insertions and deletions
+    fn mutate(bytes: &[u8]) -> Vec<u8> {
+        let mut mutated = bytes.to_vec();
+        mutated[bytes.len() / 4] = (mutated[bytes.len() / 4] + 1) % 4;
+        mutated.remove(bytes.len() / 2);
+        mutated.insert(3 * bytes.len() / 4, 2);
+        mutated
+    }
+
+    /// Tests single block patterns against the naive algorithm
+    #[test]
+    fn single_block_matches_naive() {
+        let text = random_bytes(17, 1000, 4);
+
+        for &(start, len, k) in &[(10, 1, 0), (100, 12, 2), (300, 40, 5), (500, 64, 8)] {
+            let pattern = mutate(&text[start..start + len.max(4)]);
+            let myers = Myers::new(&pattern);
+
+            assert_eq!(myers.find_ends(&text, k), naive_ends(&pattern, &text, k));
+        }
+    }
+
+    /// Tests patterns that need several blocks against the naive algorithm
+    #[test]
+    fn multiple_blocks_match_naive() {
+        let text = random_bytes(99, 1500, 4);
+
+        for &(start, len, k) in &[(200, 65, 3), (400, 128, 10), (700, 200, 30), (1000, 300, 60)] {
+            let pattern = mutate(&text[start..start + len]);
+            let myers = Myers::new(&pattern);
+
+            assert_eq!(myers.find_ends(&text, k), naive_ends(&pattern, &text, k));
+        }
+    }
+
+    /// Tests that every traced back alignment has the reported distance and spells the text
+    #[test]
+    fn traceback() {
+        let text = random_bytes(5, 600, 4);
+        let pattern = mutate(&text[150..250]);
+        let myers = Myers::new(&pattern);
+
+        let matches = myers.find_matches(&text, 4);
+        assert!(!matches.is_empty());
+
+        for found in matches {
+            let (mut i, mut j) = (0, found.start);
+            let mut edits = 0;
+
+            for op in &found.operations {
+                match op {
+                    EditOperation::Match => {
+                        assert_eq!(pattern[i], text[j]);
+                        i += 1;
+                        j += 1;
+                    }
+                    EditOperation::Substitution => {
+                        assert_ne!(pattern[i], text[j]);
+                        edits += 1;
+                        i += 1;
+                        j += 1;
+                    }
+                    EditOperation::Insertion => {
+                        edits += 1;
+                        j += 1;
+                    }
+                    EditOperation::Deletion => {
+                        edits += 1;
+                        i += 1;
+                    }
+                }
+            }
+
+            assert_eq!((i, j), (pattern.len(), found.end));
+            assert_eq!(edits, found.distance);
+        }
+    }
+
+    /// Tests the bit-parallel Hamming search against comparing every position directly
+    #[test]
+    fn hamming_matches_naive() {
+        let text = random_bytes(23, 1200, 4);
+
+        let cases = [(10, 1, 0), (100, 20, 0), (300, 64, 3), (500, 65, 7), (800, 150, 40)];
+        for &(start, len, k) in &cases {
+            let mut pattern = text[start..start + len].to_vec();
+            pattern[len / 2] = (pattern[len / 2] + 1) % 4;
+
+            let naive: Vec<Hit> = text.windows(len)
+                .enumerate()
+                .map(|(j, window)| {
+                    let distance = window.iter().zip(&pattern).filter(|(t, p)| t != p).count();
+                    Hit { end: j + len, distance }
+                })
+                .filter(|hit| hit.distance <= k)
+                .collect();
+
+            assert_eq!(find_hamming(&pattern, &text, k), naive);
+        }
+    }
+
+    /// Tests the Hamming search allows substitutions but not insertions or deletions
+    #[test]
+    fn hamming() {
+        let a = UnambiguousDnaAlphabet;
+        let text = dna(&a, "ACGTACCTACGGTTACGT");
+        let pattern = dna(&a, "ACGT");
+
+        let found: Vec<_> = find_approximate_hamming(&pattern, &text, 1)
+            .iter()
+            .map(|m| (m.start, m.distance))
+            .collect();
+        assert_eq!(found, vec![(0, 0), (4, 1), (8, 1), (14, 0)]);
+
+        // ACGGT only matches ACGT with a deletion
+        let edits = find_approximate(&pattern, &text, 1);
+        assert!(edits.iter().any(|m| m.start == 8 && m.end == 13 && m.distance == 1));
+    }
+}
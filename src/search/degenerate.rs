@@ -0,0 +1,227 @@
+//! Matching degenerate nucleotide patterns that contain IUPAC ambiguity codes.
+//!
+//! A symbol of the pattern matches a symbol of the text if the sets of bases they stand for
+//! overlap. For example R (A or G) matches A, G, R, D and N but not C or Y. This lets a pattern
+//! over the [AmbiguousDnaAlphabet](crate::alphabet::AmbiguousDnaAlphabet) be searched for in text
+//! over either DNA alphabet.
+
+use crate::alphabet::{Alphabet, Complement};
+use crate::alphabet::dna::iupac_bases;
+use crate::sequence::{Sequence, Strand};
+use super::{search_sequence, sort_matches, Match, Matcher, ShiftAnd};
+
+/// Decides what an N in the text matches.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextN {
+    /// N stands for any base so it matches every symbol of the pattern
+    MatchesAnything,
+    /// N is an unknown base so it never matches, not even an N in the pattern
+    MatchesNothing,
+}
+
+/// Matches a pattern of IUPAC codes against encoded text using bit-parallel Shift-And over the
+/// sets of bases each symbol stands for.
+///
+/// # Notes
+/// Symbols of the pattern or text that are not IUPAC nucleotide codes never match.
+///
+/// # Example
+/// ```
+/// use biors::alphabet::{AmbiguousDnaAlphabet, UnambiguousDnaAlphabet};
+/// use biors::sequence::Sequence;
+/// use biors::search::degenerate::{DegenerateMatcher, TextN};
+/// use biors::search::Matcher;
+///
+/// let ambiguous = AmbiguousDnaAlphabet;
+/// let unambiguous = UnambiguousDnaAlphabet;
+///
+/// let mut pattern = Sequence::new(&ambiguous);
+/// let mut text = Sequence::new(&unambiguous);
+/// pattern.push("RGATCY").unwrap();
+/// text.push("GGATCCAGATCT").unwrap();
+///
+/// let matcher = DegenerateMatcher::new(&pattern, &unambiguous, TextN::MatchesAnything);
+/// assert_eq!(matcher.find_all(text.as_bytes()), vec![0, 6]);
+/// ```
+#[derive(Debug, Clone)]
+pub struct DegenerateMatcher {
+    shift_and: ShiftAnd,
+}
+
+impl DegenerateMatcher {
+    /// Constructs a matcher for the pattern against text made up of symbols from text_alphabet.
+    ///
+    /// # Panics
+    /// If the pattern is longer than [MAX_SHIFT_AND_LEN](super::exact::MAX_SHIFT_AND_LEN).
+    pub fn new<P, T>(pattern: &Sequence<P>, text_alphabet: &T, text_n: TextN) -> Self
+    where
+        P: Alphabet,
+        T: Alphabet
+    {
+        let pattern_bases: Vec<u8> = pattern_bases(pattern);
+        let mut masks = vec![0u64; 256];
+
+        for (byte, symbol) in text_alphabet.symbols().iter().enumerate().take(256) {
+            let text_bases = match (*symbol, text_n) {
+                ("N", TextN::MatchesNothing) => 0,
+                _ => iupac_bases(symbol).unwrap_or(0),
+            };
+
+            for (i, bases) in pattern_bases.iter().enumerate().take(64) {
+                if bases & text_bases != 0 {
+                    masks[byte] |= 1 << i;
+                }
+            }
+        }
+
+        DegenerateMatcher { shift_and: ShiftAnd::from_masks(pattern_bases.len(), masks) }
+    }
+}
+
+impl Matcher for DegenerateMatcher {
+    fn pattern_len(&self) -> usize {
+        self.shift_and.pattern_len()
+    }
+
+    fn find_all(&self, text: &[u8]) -> Vec<usize> {
+        self.shift_and.find_all(text)
+    }
+}
+
+/// Finds every position where the degenerate pattern matches the text on the forward strand.
+///
+/// Overlapping matches are all reported and if the text is
+/// [circular](crate::sequence::Sequence::circular) so are matches that wrap around its origin.
+///
+/// # Panics
+/// If the pattern is longer than [MAX_SHIFT_AND_LEN](super::exact::MAX_SHIFT_AND_LEN).
+pub fn find_degenerate<P, T>(pattern: &Sequence<P>, text: &Sequence<T>, text_n: TextN) -> Vec<Match>
+where
+    P: Alphabet,
+    T: Alphabet
+{
+    let matcher = DegenerateMatcher::new(pattern, text.alphabet(), text_n);
+    search_sequence(&matcher, text, Strand::Forward)
+}
+
+/// Finds every position where the degenerate pattern or its reverse complement matches the text.
+///
+/// Matches are sorted by position with forward matches before reverse matches at the same
+/// position. If the pattern is its own reverse complement it is only searched for once and every
+/// match is reported on the forward strand.
+///
+/// # Panics
+/// If the pattern is longer than [MAX_SHIFT_AND_LEN](super::exact::MAX_SHIFT_AND_LEN).
+pub fn find_degenerate_both_strands<P, T>(
+    pattern: &Sequence<P>,
+    text: &Sequence<T>,
+    text_n: TextN
+) -> Vec<Match>
+where
+    P: Complement,
+    T: Alphabet
+{
+    let mut reverse = pattern.clone();
+    reverse.reverse_complement();
+
+    let mut matches = find_degenerate(pattern, text, text_n);
+    if reverse.as_bytes() != pattern.as_bytes() {
+        let matcher = DegenerateMatcher::new(&reverse, text.alphabet(), text_n);
+        matches.extend(search_sequence(&matcher, text, Strand::Reverse));
+        sort_matches(&mut matches);
+    }
+
+    matches
+}
+
+/// The set of bases that each symbol of the pattern stands for
+fn pattern_bases<P: Alphabet>(pattern: &Sequence<P>) -> Vec<u8> {
+    let symbols = pattern.alphabet().symbols();
+
+    pattern.as_bytes()
+        .iter()
+        .map(|&byte| iupac_bases(symbols[byte as usize]).unwrap_or(0))
+        .collect()
+}
+
+//================================================================================
+// Tests
+//================================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alphabet::{AmbiguousDnaAlphabet, UnambiguousDnaAlphabet};
+    use crate::sequence::test_util::{dna, sequence};
+
+    /// Returns the start of each match
+    fn starts(matches: &[Match]) -> Vec<usize> {
+        matches.iter().map(|m| m.start).collect()
+    }
+
+    /// Tests that an unambiguous pattern only matches itself
+    #[test]
+    fn unambiguous_pattern() {
+        let a = AmbiguousDnaAlphabet;
+        let u = UnambiguousDnaAlphabet;
+        let pattern = sequence(&a, "GAATTC");
+        let text = dna(&u, "GAATTCGAATTGGAATTC");
+
+        let matches = find_degenerate(&pattern, &text, TextN::MatchesAnything);
+        assert_eq!(starts(&matches), vec![0, 12]);
+    }
+
+    /// Tests that ambiguous symbols in the pattern match any of their bases
+    #[test]
+    fn ambiguous_pattern() {
+        let a = AmbiguousDnaAlphabet;
+        let u = UnambiguousDnaAlphabet;
+        let pattern = sequence(&a, "RGATCY");
+        let text = dna(&u, "AGATCTCGATCTGGATCCAGATCA");
+
+        let matches = find_degenerate(&pattern, &text, TextN::MatchesAnything);
+        assert_eq!(starts(&matches), vec![0, 12]);
+    }
+
+    /// Tests that ambiguous symbols in the text match when their bases overlap the pattern's
+    #[test]
+    fn ambiguous_text() {
+        let a = AmbiguousDnaAlphabet;
+        let pattern = sequence(&a, "RGATCY");
+        let text = sequence(&a, "KGATCBMGATCA");
+
+        // K overlaps R at G and B overlaps Y at C and T but the final A doesn't overlap Y
+        let matches = find_degenerate(&pattern, &text, TextN::MatchesAnything);
+        assert_eq!(starts(&matches), vec![0]);
+    }
+
+    /// Tests both treatments of N in the text
+    #[test]
+    fn text_n() {
+        let a = AmbiguousDnaAlphabet;
+        let pattern = sequence(&a, "GANTC");
+        let text = sequence(&a, "GANTCGNCTC");
+
+        let anything = find_degenerate(&pattern, &text, TextN::MatchesAnything);
+        assert_eq!(starts(&anything), vec![0, 5]);
+
+        // Not even the N in the pattern matches the N in the text
+        let nothing = find_degenerate(&pattern, &text, TextN::MatchesNothing);
+        assert!(nothing.is_empty());
+    }
+
+    /// Tests that degenerate patterns are found on the reverse strand and across the origin
+    #[test]
+    fn both_strands_circular() {
+        let a = AmbiguousDnaAlphabet;
+        let u = UnambiguousDnaAlphabet;
+        let pattern = sequence(&a, "ACNNG");
+        let mut text = Sequence::new(&u).circular(true);
+        text.push("TTGCAAGTTAC").unwrap();
+
+        // CNNGT (the reverse complement) at 3 and ACNNG wraps around the origin at 9
+        let matches = find_degenerate_both_strands(&pattern, &text, TextN::MatchesAnything);
+        let found: Vec<_> = matches.iter().map(|m| (m.start, m.end, m.strand)).collect();
+        assert_eq!(found, vec![(3, 8, Strand::Reverse), (9, 3, Strand::Forward)]);
+    }
+}
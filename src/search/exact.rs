@@ -0,0 +1,341 @@
+//! Algorithms that find exact occurrences of a pattern in encoded bytes.
+
+use super::Matcher;
+
+/// The longest pattern that [ShiftAnd] can search for
+pub const MAX_SHIFT_AND_LEN: usize = 64;
+
+/// Alphabets with more symbols than this are searched with [Horspool] by [ExactMatcher]
+const HORSPOOL_ALPHABET_SIZE: usize = 8;
+
+//================================================================================
+// Exact Matcher
+//================================================================================
+
+/// Chooses the most suitable exact matching algorithm for a pattern.
+///
+/// # Algorithm Choice
+/// * Alphabets of more than 8 symbols (such as proteins) use [Horspool] as mismatches let it
+///   skip large parts of the text.
+/// * Smaller alphabets (such as DNA) use the bit-parallel [ShiftAnd] for patterns of up to 64
+///   symbols as Horspool's skips are short when there are few symbols.
+/// * Longer patterns over small alphabets use [Kmp] which is linear in the length of the text
+///   no matter the pattern.
+#[derive(Debug, Clone)]
+pub enum ExactMatcher {
+    /// Search using Boyer-Moore-Horspool
+    Horspool(Horspool),
+    /// Search using Knuth-Morris-Pratt
+    Kmp(Kmp),
+    /// Search using bit-parallel Shift-And
+    ShiftAnd(ShiftAnd),
+}
+
+impl ExactMatcher {
+    /// Constructs a matcher for the pattern choosing the algorithm based on the length of the
+    /// pattern and the number of symbols in its alphabet.
+    pub fn new(pattern: &[u8], alphabet_size: usize) -> Self {
+        if alphabet_size > HORSPOOL_ALPHABET_SIZE {
+            ExactMatcher::Horspool(Horspool::new(pattern))
+        } else if pattern.len() <= MAX_SHIFT_AND_LEN {
+            ExactMatcher::ShiftAnd(ShiftAnd::new(pattern))
+        } else {
+            ExactMatcher::Kmp(Kmp::new(pattern))
+        }
+    }
+}
+
+impl Matcher for ExactMatcher {
+    fn pattern_len(&self) -> usize {
+        match self {
+            ExactMatcher::Horspool(m) => m.pattern_len(),
+            ExactMatcher::Kmp(m) => m.pattern_len(),
+            ExactMatcher::ShiftAnd(m) => m.pattern_len(),
+        }
+    }
+
+    fn find_all(&self, text: &[u8]) -> Vec<usize> {
+        match self {
+            ExactMatcher::Horspool(m) => m.find_all(text),
+            ExactMatcher::Kmp(m) => m.find_all(text),
+            ExactMatcher::ShiftAnd(m) => m.find_all(text),
+        }
+    }
+}
+
+//================================================================================
+// Horspool
+//================================================================================
+
+/// The Boyer-Moore-Horspool algorithm.
+///
+/// Compares the pattern to the text from right to left and on a mismatch shifts the pattern
+/// along based on the last byte of the current window. Sublinear on average for large alphabets
+/// but O(nm) in the worst case.
+#[derive(Debug, Clone)]
+pub struct Horspool {
+    pattern: Vec<u8>,
+    shifts: Vec<usize>,
+}
+
+impl Horspool {
+    /// Precomputes the shift table for the pattern
+    pub fn new(pattern: &[u8]) -> Self {
+        let m = pattern.len();
+        let mut shifts = vec![m.max(1); 256];
+
+        for (i, &byte) in pattern.iter().enumerate().take(m.saturating_sub(1)) {
+            shifts[byte as usize] = m - 1 - i;
+        }
+
+        Horspool { pattern: pattern.to_vec(), shifts }
+    }
+}
+
+impl Matcher for Horspool {
+    fn pattern_len(&self) -> usize {
+        self.pattern.len()
+    }
+
+    fn find_all(&self, text: &[u8]) -> Vec<usize> {
+        let m = self.pattern.len();
+        let mut found = vec![];
+
+        if m == 0 || m > text.len() {
+            return found;
+        }
+
+        let mut start = 0;
+        while start + m <= text.len() {
+            let window = &text[start..start + m];
+            if window.iter().rev().eq(self.pattern.iter().rev()) {
+                found.push(start);
+            }
+
+            start += self.shifts[window[m - 1] as usize];
+        }
+
+        found
+    }
+}
+
+//================================================================================
+// KMP
+//================================================================================
+
+/// The Knuth-Morris-Pratt algorithm.
+///
+/// Uses the longest border of each prefix of the pattern to avoid ever comparing a byte of the
+/// text twice. Always O(n + m).
+#[derive(Debug, Clone)]
+pub struct Kmp {
+    pattern: Vec<u8>,
+    /// borders[i] is the length of the longest proper border of pattern[..i]
+    borders: Vec<usize>,
+}
+
+impl Kmp {
+    /// Precomputes the border table for the pattern
+    pub fn new(pattern: &[u8]) -> Self {
+        let m = pattern.len();
+        let mut borders = vec![0; m + 1];
+
+        let mut border = 0;
+        for i in 1..m {
+            while border > 0 && pattern[i] != pattern[border] {
+                border = borders[border];
+            }
+            if pattern[i] == pattern[border] {
+                border += 1;
+            }
+            borders[i + 1] = border;
+        }
+
+        Kmp { pattern: pattern.to_vec(), borders }
+    }
+}
+
+impl Matcher for Kmp {
+    fn pattern_len(&self) -> usize {
+        self.pattern.len()
+    }
+
+    fn find_all(&self, text: &[u8]) -> Vec<usize> {
+        let m = self.pattern.len();
+        let mut found = vec![];
+
+        if m == 0 {
+            return found;
+        }
+
+        let mut matched = 0;
+        for (i, &byte) in text.iter().enumerate() {
+            while matched > 0 && byte != self.pattern[matched] {
+                matched = self.borders[matched];
+            }
+            if byte == self.pattern[matched] {
+                matched += 1;
+            }
+            if matched == m {
+                found.push(i + 1 - m);
+                // Continue from the longest border so overlapping matches are found
+                matched = self.borders[m];
+            }
+        }
+
+        found
+    }
+}
+
+//================================================================================
+// Shift-And
+//================================================================================
+
+/// The bit-parallel Shift-And algorithm.
+///
+/// Tracks which prefixes of the pattern match the text ending at the current position as the
+/// bits of a single u64 so each byte of the text costs a shift, an or and an and. Always O(n)
+/// but limited to patterns of at most [MAX_SHIFT_AND_LEN] bytes.
+#[derive(Debug, Clone)]
+pub struct ShiftAnd {
+    len: usize,
+    /// Bit i of masks\[b\] is set if byte i of the pattern is b
+    masks: Vec<u64>,
+}
+
+impl ShiftAnd {
+    /// Precomputes the byte masks for the pattern.
+    ///
+    /// # Panics
+    /// If the pattern is longer than [MAX_SHIFT_AND_LEN].
+    pub fn new(pattern: &[u8]) -> Self {
+        let mut masks = vec![0; 256];
+        for (i, &byte) in pattern.iter().enumerate().take(MAX_SHIFT_AND_LEN) {
+            masks[byte as usize] |= 1 << i;
+        }
+
+        ShiftAnd::from_masks(pattern.len(), masks)
+    }
+
+    /// Constructs a Shift-And matcher from precomputed masks. Bit i of masks\[b\] must be set if
+    /// byte b matches position i of the pattern. This allows a single position of the pattern to
+    /// match several different bytes.
+    ///
+    /// # Panics
+    /// If len is greater than [MAX_SHIFT_AND_LEN] or there are not 256 masks.
+    pub fn from_masks(len: usize, masks: Vec<u64>) -> Self {
+        assert!(
+            len <= MAX_SHIFT_AND_LEN,
+            "Shift-And can only search for patterns of up to {} symbols but the pattern has {}",
+            MAX_SHIFT_AND_LEN, len
+        );
+        assert_eq!(masks.len(), 256, "Shift-And needs a mask for every byte");
+
+        ShiftAnd { len, masks }
+    }
+}
+
+impl Matcher for ShiftAnd {
+    fn pattern_len(&self) -> usize {
+        self.len
+    }
+
+    fn find_all(&self, text: &[u8]) -> Vec<usize> {
+        let mut found = vec![];
+
+        if self.len == 0 {
+            return found;
+        }
+
+        let accept = 1u64 << (self.len - 1);
+        let mut state = 0u64;
+
+        for (i, &byte) in text.iter().enumerate() {
+            state = ((state << 1) | 1) & self.masks[byte as usize];
+            if state & accept != 0 {
+                found.push(i + 1 - self.len);
+            }
+        }
+
+        found
+    }
+}
+
+//================================================================================
+// Tests
+//================================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Finds every occurrence by comparing at every position
+    fn naive(pattern: &[u8], text: &[u8]) -> Vec<usize> {
+        if pattern.is_empty() || pattern.len() > text.len() {
+            return vec![];
+        }
+
+        text.windows(pattern.len())
+            .enumerate()
+            .filter(|(_, window)| *window == pattern)
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    /// A repetitive text over 3 symbols so that there are many partial and overlapping matches
+    fn text() -> Vec<u8> {
+        let mut state = 7u32;
+        (0..2000)
+            .map(|_| {
+                state = state.wrapping_mul(1_103_515_245).wrapping_add(12345);
+                if (state >> 16) & 3 == 0 { 2 } else { ((state >> 20) % 2) as u8 }
+            })
+            .collect()
+    }
+
+    /// Tests all three algorithms against the naive search for many patterns
+    #[test]
+    fn algorithms_agree() {
+        let text = text();
+
+        for &(start, len) in &[(0, 1), (10, 2), (5, 4), (100, 9), (300, 30), (900, 64), (50, 70)] {
+            let pattern = &text[start..start + len];
+            let expected = naive(pattern, &text);
+
+            assert_eq!(Horspool::new(pattern).find_all(&text), expected);
+            assert_eq!(Kmp::new(pattern).find_all(&text), expected);
+            if len <= MAX_SHIFT_AND_LEN {
+                assert_eq!(ShiftAnd::new(pattern).find_all(&text), expected);
+            }
+        }
+    }
+
+    /// Tests that periodic patterns report every overlapping match
+    #[test]
+    fn overlapping() {
+        let text = [0, 0, 0, 0, 0];
+        let expected = vec![0, 1, 2];
+
+        assert_eq!(Horspool::new(&[0, 0, 0]).find_all(&text), expected);
+        assert_eq!(Kmp::new(&[0, 0, 0]).find_all(&text), expected);
+        assert_eq!(ShiftAnd::new(&[0, 0, 0]).find_all(&text), expected);
+    }
+
+    /// Tests the algorithm chosen for different patterns and alphabets
+    #[test]
+    fn algorithm_choice() {
+        let short = [0; 20];
+        let long = [0; 100];
+
+        assert!(matches!(ExactMatcher::new(&short, 20), ExactMatcher::Horspool(_)));
+        assert!(matches!(ExactMatcher::new(&short, 4), ExactMatcher::ShiftAnd(_)));
+        assert!(matches!(ExactMatcher::new(&long, 4), ExactMatcher::Kmp(_)));
+    }
+
+    /// Tests that Shift-And rejects patterns that don't fit in a u64
+    #[test]
+    #[should_panic]
+    fn shift_and_too_long() {
+        ShiftAnd::new(&[0; MAX_SHIFT_AND_LEN + 1]);
+    }
+}
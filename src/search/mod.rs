@@ -0,0 +1,217 @@
+//! Searching for occurrences of a pattern [Sequence](crate::sequence::Sequence) in a text
+//! Sequence.
+//!
+//! Searches compare the encoded bytes of the pattern and the text directly which is possible
+//! because [AlphabetEncoder](crate::alphabet::encoding::AlphabetEncoder)s map every symbol to the
+//! same bytes no matter where it appears. The pattern and the text must therefore use the same
+//! alphabet.
+
+pub use self::exact::{ExactMatcher, Horspool, Kmp, ShiftAnd};
+
+use crate::alphabet::{Alphabet, Complement};
+use crate::sequence::{Sequence, Strand};
+
+pub mod approximate;
+pub mod degenerate;
+pub mod exact;
+pub mod pattern;
+
+/// An occurrence of a pattern in a text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Match {
+    /// Position in the text of the first symbol of the occurrence
+    pub start: usize,
+    /// Position one past the last symbol of the occurrence. If the occurrence wraps around the
+    /// origin of a circular text this will be less than or equal to start.
+    pub end: usize,
+    /// The strand of the text the pattern was found on. Positions are always given on the
+    /// forward strand so a reverse match is where the reverse complement of the pattern occurs.
+    pub strand: Strand,
+}
+
+/// Finds every occurrence of a fixed pattern in a slice of encoded bytes.
+pub trait Matcher {
+    /// The number of bytes in the pattern
+    fn pattern_len(&self) -> usize;
+
+    /// Returns the start position of every occurrence of the pattern in the text in increasing
+    /// order including occurrences that overlap each other.
+    fn find_all(&self, text: &[u8]) -> Vec<usize>;
+}
+
+/// Finds every occurrence of the pattern in the text on the forward strand.
+///
+/// The algorithm is chosen by [ExactMatcher::new()]. Overlapping occurrences are all reported
+/// and if the text is [circular](crate::sequence::Sequence::circular) so are occurrences that
+/// wrap around its origin. An empty pattern never matches.
+///
+/// # Example
+/// ```
+/// use biors::alphabet::UnambiguousDnaAlphabet;
+/// use biors::sequence::Sequence;
+/// use biors::search;
+///
+/// let alphabet = UnambiguousDnaAlphabet;
+/// let mut text = Sequence::new(&alphabet);
+/// let mut pattern = Sequence::new(&alphabet);
+/// text.push("AAAAT").unwrap();
+/// pattern.push("AA").unwrap();
+///
+/// let starts: Vec<_> = search::find_all(&pattern, &text).iter().map(|m| m.start).collect();
+/// assert_eq!(starts, vec![0, 1, 2]);
+/// ```
+pub fn find_all<A: Alphabet>(pattern: &Sequence<A>, text: &Sequence<A>) -> Vec<Match> {
+    let alphabet_size = pattern.alphabet().symbols().len();
+    let matcher = ExactMatcher::new(pattern.as_bytes(), alphabet_size);
+
+    search_sequence(&matcher, text, Strand::Forward)
+}
+
+/// Finds every occurrence of the pattern and of its reverse complement in the text.
+///
+/// Matches are sorted by position with forward matches before reverse matches at the same
+/// position. If the pattern is its own reverse complement, like many restriction sites, it is
+/// only searched for once and every match is reported on the forward strand.
+///
+/// # Example
+/// ```
+/// use biors::alphabet::UnambiguousDnaAlphabet;
+/// use biors::sequence::{Sequence, Strand};
+/// use biors::search;
+///
+/// let alphabet = UnambiguousDnaAlphabet;
+/// let mut text = Sequence::new(&alphabet).circular(true);
+/// let mut pattern = Sequence::new(&alphabet);
+/// text.push("CGTTTTAA").unwrap();
+/// pattern.push("AAC").unwrap();
+///
+/// let matches = search::find_all_both_strands(&pattern, &text);
+///
+/// // AAC wraps around the origin and its reverse complement GTT is at 1
+/// assert_eq!(matches.len(), 2);
+/// assert_eq!((matches[0].start, matches[0].strand), (1, Strand::Reverse));
+/// assert_eq!((matches[1].start, matches[1].end), (6, 1));
+/// ```
+pub fn find_all_both_strands<A>(pattern: &Sequence<A>, text: &Sequence<A>) -> Vec<Match>
+where
+    A: Complement
+{
+    let mut reverse = pattern.clone();
+    reverse.reverse_complement();
+
+    let mut matches = find_all(pattern, text);
+    if reverse.as_bytes() != pattern.as_bytes() {
+        let alphabet_size = pattern.alphabet().symbols().len();
+        let matcher = ExactMatcher::new(reverse.as_bytes(), alphabet_size);
+        matches.extend(search_sequence(&matcher, text, Strand::Reverse));
+        sort_matches(&mut matches);
+    }
+
+    matches
+}
+
+/// Searches the encoded bytes of a sequence with any matcher including the occurrences that wrap
+/// around the origin of circular sequences. The strand is simply recorded in each match.
+pub fn search_sequence<M, A>(matcher: &M, text: &Sequence<A>, strand: Strand) -> Vec<Match>
+where
+    M: Matcher + ?Sized,
+    A: Alphabet
+{
+    let bytes = text.as_bytes();
+    let len = bytes.len();
+    let m = matcher.pattern_len();
+
+    if m == 0 || m > len {
+        return vec![];
+    }
+
+    let mut matches: Vec<Match> = matcher.find_all(bytes)
+        .into_iter()
+        .map(|start| Match { start, end: start + m, strand })
+        .collect();
+
+    if text.circular && m > 1 {
+        // Only the last and first m - 1 symbols can be part of a match that wraps the origin
+        let offset = len - (m - 1);
+        let mut junction = Vec::with_capacity(2 * (m - 1));
+        junction.extend_from_slice(&bytes[offset..]);
+        junction.extend_from_slice(&bytes[..m - 1]);
+
+        for start in matcher.find_all(&junction) {
+            matches.push(Match { start: offset + start, end: start + 1, strand });
+        }
+    }
+
+    matches
+}
+
+/// Sorts matches by position and then strand
+pub(crate) fn sort_matches(matches: &mut [Match]) {
+    matches.sort_by_key(|m| (m.start, m.strand == Strand::Reverse));
+}
+
+//================================================================================
+// Tests
+//================================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alphabet::UnambiguousDnaAlphabet;
+    use crate::sequence::test_util::dna;
+
+    /// Tests that overlapping matches are all found
+    #[test]
+    fn overlapping() {
+        let a = UnambiguousDnaAlphabet;
+        let text = dna(&a, "ATATATA");
+        let pattern = dna(&a, "ATA");
+
+        let starts: Vec<_> = find_all(&pattern, &text).iter().map(|m| m.start).collect();
+        assert_eq!(starts, vec![0, 2, 4]);
+    }
+
+    /// Tests that matches wrapping around the origin are only found in circular texts
+    #[test]
+    fn circular() {
+        let a = UnambiguousDnaAlphabet;
+        let mut text = dna(&a, "CGATTTTG");
+        let pattern = dna(&a, "TGCG");
+
+        assert!(find_all(&pattern, &text).is_empty());
+
+        text.circular = true;
+        let matches = find_all(&pattern, &text);
+        assert_eq!(matches, vec![Match { start: 6, end: 2, strand: Strand::Forward }]);
+
+        // Matches that don't wrap aren't found twice
+        let pattern = dna(&a, "TTTT");
+        assert_eq!(find_all(&pattern, &text).len(), 1);
+    }
+
+    /// Tests that the reverse complement of the pattern is found
+    #[test]
+    fn both_strands() {
+        let a = UnambiguousDnaAlphabet;
+        let text = dna(&a, "GGATCCTTTGACGG");
+        let pattern = dna(&a, "CGTC");
+
+        let matches = find_all_both_strands(&pattern, &text);
+        assert_eq!(matches, vec![Match { start: 9, end: 13, strand: Strand::Reverse }]);
+
+        // GGATCC is its own reverse complement
+        let pattern = dna(&a, "GGATCC");
+        let matches = find_all_both_strands(&pattern, &text);
+        assert_eq!(matches, vec![Match { start: 0, end: 6, strand: Strand::Forward }]);
+    }
+
+    /// Tests that patterns that are empty or longer than the text never match
+    #[test]
+    fn degenerate_patterns() {
+        let a = UnambiguousDnaAlphabet;
+        let text = dna(&a, "ACGT").circular(true);
+
+        assert!(find_all(&dna(&a, ""), &text).is_empty());
+        assert!(find_all(&dna(&a, "ACGTA"), &text).is_empty());
+    }
+}
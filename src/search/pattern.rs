@@ -0,0 +1,978 @@
+//! Searching for patterns written in PROSITE syntax or a small subset of regular expressions.
+//!
+//! PROSITE patterns are elements separated by dashes, such as `[AC]-x(2,4)-{P}-C`:
+//!
+//! - A residue such as `C`, or `x` for any residue
+//! - `[ACD]` for any of the residues inside the brackets and `{P}` for any residue except those
+//!   inside the braces
+//! - `(n)` or `(n,m)` after an element to repeat it exactly n or from n to m times
+//! - `<` at the start and `>` at the end to anchor the pattern to the ends of the sequence
+//!
+//! Regular expressions are written without separators, such as `GA[AT]{2,3}TC`:
+//!
+//! - A residue, or `.` for any residue
+//! - `[AC]` and `[^AC]` for any residue inside or outside the brackets
+//! - `?`, `*`, `+`, `{n}`, `{n,}` and `{n,m}` after an element to repeat it
+//! - `^` at the start and `$` at the end to anchor the pattern to the ends of the sequence
+//!
+//! Groups and alternation are not supported.
+//!
+//! Residues are single characters and are resolved against [Alphabet::symbols()] when the
+//! pattern is compiled, so a residue that the alphabet can't match is an error then rather than
+//! a pattern that silently never matches. If every symbol of the alphabet is an IUPAC nucleotide
+//! code a residue can be any IUPAC code and matches every symbol whose bases overlap it, so R
+//! matches A, G, R and N just like in [degenerate](super::degenerate) searches. Otherwise a
+//! residue must be a symbol of the alphabet or one of the ambiguous amino acid codes B (D or N),
+//! Z (E or Q), J (I or L) and X (anything).
+//!
+//! The compiled pattern is an automaton with one state for each residue that can be matched,
+//! which is run over the encoded bytes of a sequence. Each state keeps the earliest start of the
+//! partial matches that reach it so a single pass finds the leftmost longest match, taking
+//! O(ns) time for a text of length n and a pattern with s states. A pattern can have at most
+//! [MAX_STATES] states, so a repeat such as `x(1,100000)` is an error.
+
+use std::error::Error;
+use std::fmt;
+use crate::alphabet::Alphabet;
+use crate::alphabet::dna::iupac_bases;
+use crate::sequence::{Sequence, Strand};
+use super::Match;
+
+/// Result type for compiling patterns
+pub type Result<T> = std::result::Result<T, PatternError>;
+
+/// The most states a compiled pattern can have. An element has a state for each symbol up to its
+/// maximum number of repeats, or up to its minimum if it has no maximum.
+pub const MAX_STATES: usize = 10_000;
+
+/// An element of a pattern before it is compiled
+#[derive(Debug, Clone)]
+struct Element {
+    /// Whether each symbol of the alphabet matches the element
+    symbols: Vec<bool>,
+    min: usize,
+    /// The most repeats or None if there is no limit
+    max: Option<usize>,
+}
+
+/// A state of the automaton which matches one symbol of an element
+#[derive(Debug, Clone, Copy)]
+struct State {
+    element: usize,
+    /// The state can be skipped
+    optional: bool,
+    /// The state can match any number of symbols in a row
+    repeat: bool,
+}
+
+/// A pattern compiled against an alphabet, ready to search sequences over that alphabet.
+///
+/// Searches find the leftmost match and the longest match at its start, then carry on after its
+/// end, so matches don't overlap.
+///
+/// # Example
+/// ```
+/// use biors::alphabet::ProteinAlphabet;
+/// use biors::search::pattern::Pattern;
+/// use biors::sequence::Sequence;
+///
+/// let protein = ProteinAlphabet;
+/// let pattern = Pattern::prosite("C-x(2,4)-C-x(3)-[LIVMFYWC]", &protein).unwrap();
+///
+/// let mut seq = Sequence::new(&protein);
+/// seq.push("MKCAACGGSLAKCPRCRRRFE").unwrap();
+///
+/// let found: Vec<_> = pattern.find_iter(&seq).map(|m| (m.start, m.end)).collect();
+/// assert_eq!(found, vec![(2, 10), (12, 20)]);
+/// ```
+pub struct Pattern<'a, A: Alphabet> {
+    alphabet: &'a A,
+    source: String,
+    automaton: Automaton,
+}
+
+impl<'a, A: Alphabet> Pattern<'a, A> {
+    /// Compiles a pattern written in PROSITE syntax. A final full stop is allowed.
+    ///
+    /// # Errors
+    /// If the pattern isn't valid PROSITE syntax, has a residue the alphabet can't match, has
+    /// a repeat with a larger minimum than maximum, needs more than [MAX_STATES] states or can
+    /// match an empty sequence.
+    pub fn prosite(pattern: &str, alphabet: &'a A) -> Result<Self> {
+        let (elements, anchors) = Parser::new(pattern, alphabet).prosite()?;
+        Pattern::compile(pattern, alphabet, elements, anchors)
+    }
+
+    /// Compiles a pattern written as a regular expression.
+    ///
+    /// # Errors
+    /// If the pattern uses syntax that isn't supported, has a residue the alphabet can't match,
+    /// has a repeat with a larger minimum than maximum, needs more than [MAX_STATES] states or
+    /// can match an empty sequence.
+    ///
+    /// # Example
+    /// ```
+    /// use biors::alphabet::UnambiguousDnaAlphabet;
+    /// use biors::search::pattern::Pattern;
+    /// use biors::sequence::Sequence;
+    ///
+    /// let dna = UnambiguousDnaAlphabet;
+    /// // Y is C or T and N is any base
+    /// let pattern = Pattern::regex("TATAWAW[^C]?N{2}Y", &dna).unwrap();
+    /// assert_eq!((pattern.min_len(), pattern.max_len()), (10, Some(11)));
+    ///
+    /// let mut seq = Sequence::new(&dna);
+    /// seq.push("GCTATAAATGGCTTATATAAGGT").unwrap();
+    ///
+    /// let starts: Vec<_> = pattern.find_iter(&seq).map(|m| m.start).collect();
+    /// assert_eq!(starts, vec![2, 13]);
+    /// ```
+    pub fn regex(pattern: &str, alphabet: &'a A) -> Result<Self> {
+        let (elements, anchors) = Parser::new(pattern, alphabet).regex()?;
+        Pattern::compile(pattern, alphabet, elements, anchors)
+    }
+
+    fn compile(
+        source: &str,
+        alphabet: &'a A,
+        elements: Vec<Element>,
+        anchors: (bool, bool)
+    ) -> Result<Self> {
+        let automaton = Automaton::new(elements, anchors);
+        if automaton.matches_empty() {
+            let description = format!("The pattern {:?} can match an empty sequence", source);
+            let position = source.chars().count();
+            return Err(PatternError::new(ErrorKind::EmptyMatch, position, description));
+        }
+
+        Ok(Pattern { alphabet, source: source.to_owned(), automaton })
+    }
+
+    /// The alphabet the pattern was compiled against
+    pub fn alphabet(&self) -> &'a A {
+        self.alphabet
+    }
+
+    /// The pattern as it was written
+    pub fn as_str(&self) -> &str {
+        &self.source
+    }
+
+    /// The length of the shortest sequence the pattern can match
+    pub fn min_len(&self) -> usize {
+        self.automaton.elements.iter()
+            .try_fold(0usize, |len, element| len.checked_add(element.min))
+            .expect("MAX_STATES limits the length of a pattern")
+    }
+
+    /// The length of the longest sequence the pattern can match or None if there is no limit
+    pub fn max_len(&self) -> Option<usize> {
+        let mut len: usize = 0;
+        for element in &self.automaton.elements {
+            len = len.checked_add(element.max?).expect("MAX_STATES limits the length of a pattern");
+        }
+        Some(len)
+    }
+
+    /// Iterates over the matches of the pattern in the text in order of their start.
+    ///
+    /// The leftmost match is found, taking the longest one if several start there, and the
+    /// search continues after its end so matches never overlap. If the text is
+    /// [circular](Sequence::circular) matches can wrap around its origin but are never longer
+    /// than the text and stop before the first match.
+    pub fn find_iter<'p, 't>(&'p self, text: &'t Sequence<A>) -> Matches<'p, 't> {
+        Matches {
+            automaton: &self.automaton,
+            text: text.as_bytes(),
+            circular: text.circular,
+            next_start: 0,
+            first_start: None,
+        }
+    }
+
+    /// Collects every match found by [Pattern::find_iter()].
+    pub fn find_all(&self, text: &Sequence<A>) -> Vec<Match> {
+        self.find_iter(text).collect()
+    }
+
+    /// Returns true if the pattern matches anywhere in the text.
+    pub fn is_match(&self, text: &Sequence<A>) -> bool {
+        self.find_iter(text).next().is_some()
+    }
+}
+
+impl<'a, A: Alphabet> Clone for Pattern<'a, A> {
+    fn clone(&self) -> Self {
+        Pattern {
+            alphabet: self.alphabet,
+            source: self.source.clone(),
+            automaton: self.automaton.clone(),
+        }
+    }
+}
+
+impl<'a, A: Alphabet> fmt::Debug for Pattern<'a, A> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Pattern").field("source", &self.source).finish()
+    }
+}
+
+impl<'a, A: Alphabet> fmt::Display for Pattern<'a, A> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.source)
+    }
+}
+
+/// An iterator over the matches of a [Pattern] in a sequence, created by [Pattern::find_iter()].
+#[derive(Debug, Clone)]
+pub struct Matches<'p, 't> {
+    automaton: &'p Automaton,
+    text: &'t [u8],
+    circular: bool,
+    next_start: usize,
+    /// The start of the first match, which a match wrapping around the origin must stop before
+    first_start: Option<usize>,
+}
+
+impl<'p, 't> Iterator for Matches<'p, 't> {
+    type Item = Match;
+
+    fn next(&mut self) -> Option<Match> {
+        let len = self.text.len();
+        if self.next_start >= len {
+            return None;
+        }
+
+        let limit = if self.circular { len + self.first_start.unwrap_or(len) } else { len };
+        match self.automaton.find(self.text, self.next_start, limit) {
+            Some((start, end)) => {
+                self.next_start = end;
+                self.first_start.get_or_insert(start);
+                let end = if end > len { end - len } else { end };
+                Some(Match { start, end, strand: Strand::Forward })
+            }
+            None => {
+                self.next_start = len;
+                None
+            }
+        }
+    }
+}
+
+//================================================================================
+// Automaton
+//================================================================================
+
+/// A nondeterministic automaton with a state for each symbol an element can match. Elements
+/// that repeat between n and m times have n states followed by m - n optional states and
+/// elements without a limit repeat their last state.
+#[derive(Debug, Clone)]
+struct Automaton {
+    elements: Vec<Element>,
+    states: Vec<State>,
+    anchored_start: bool,
+    anchored_end: bool,
+}
+
+impl Automaton {
+    fn new(elements: Vec<Element>, (anchored_start, anchored_end): (bool, bool)) -> Self {
+        let mut states = vec![];
+
+        for (index, element) in elements.iter().enumerate() {
+            for _ in 0..element.min {
+                states.push(State { element: index, optional: false, repeat: false });
+            }
+            match element.max {
+                Some(max) => {
+                    for _ in element.min..max {
+                        states.push(State { element: index, optional: true, repeat: false });
+                    }
+                }
+                None if element.min == 0 => {
+                    states.push(State { element: index, optional: true, repeat: true });
+                }
+                None => {
+                    let last = states.len() - 1;
+                    states[last].repeat = true;
+                }
+            }
+        }
+
+        Automaton { elements, states, anchored_start, anchored_end }
+    }
+
+    /// Whether the end of the pattern can be reached without matching any symbols
+    fn matches_empty(&self) -> bool {
+        self.states.iter().all(|state| state.optional)
+    }
+
+    /// Finds the states that can match the next symbol after the states that just matched, each
+    /// with the earliest start of a partial match that reaches it. A match can also begin at
+    /// start. Returns the earliest start of a match that can end here, if there is one.
+    fn follow(
+        &self,
+        matched: &[Option<usize>],
+        start: Option<usize>,
+        next: &mut [Option<usize>]
+    ) -> Option<usize> {
+        // The earliest start that reaches the gap before the current state
+        let mut gap = start;
+
+        for (index, state) in self.states.iter().enumerate() {
+            let repeat = if state.repeat { matched[index] } else { None };
+            next[index] = earliest(gap, repeat);
+            gap = earliest(matched[index], if state.optional { gap } else { None });
+        }
+
+        gap
+    }
+
+    /// Finds the leftmost match starting between from and the end of the text and the longest
+    /// match at that start, returning its start and end. The text is read up to limit, which is
+    /// past its end if it's circular, and matches are never longer than the text.
+    fn find(&self, text: &[u8], from: usize, limit: usize) -> Option<(usize, usize)> {
+        let len = text.len();
+        // Matches can begin anywhere before the end of the text until one has been found
+        let begins = |position: usize, found: bool| {
+            let allowed = position < len && !found && (!self.anchored_start || position == 0);
+            if allowed { Some(position) } else { None }
+        };
+
+        let mut matched = vec![None; self.states.len()];
+        let mut candidates = vec![None; self.states.len()];
+        self.follow(&matched, begins(from, false), &mut candidates);
+
+        let mut best: Option<(usize, usize)> = None;
+        for position in from..limit {
+            let symbol = text[position % len] as usize;
+            let end = position + 1;
+
+            let states = matched.iter_mut().zip(&candidates).zip(&self.states);
+            for ((matched, &candidate), state) in states {
+                // Partial matches that start after the best match can't replace it
+                *matched = candidate.filter(|&start| {
+                    end - start <= len
+                        && best.is_none_or(|(best_start, _)| start <= best_start)
+                        && self.elements[state.element].symbols.get(symbol) == Some(&true)
+                });
+            }
+
+            let accepted = self.follow(&matched, begins(end, best.is_some()), &mut candidates);
+            if let Some(start) = accepted.filter(|_| !self.anchored_end || end == len) {
+                if best.is_none_or(|(best_start, _)| start <= best_start) {
+                    best = Some((start, end));
+                }
+            }
+
+            if candidates.iter().all(Option::is_none) {
+                break;
+            }
+        }
+
+        best
+    }
+}
+
+/// The earlier of two starts
+fn earliest(a: Option<usize>, b: Option<usize>) -> Option<usize> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(a.min(b)),
+        _ => a.or(b),
+    }
+}
+
+//================================================================================
+// Parsing
+//================================================================================
+
+/// The ambiguous amino acid codes and the residues they stand for. X stands for anything.
+const AMINO_ACID_CODES: [(char, &[&str]); 3] =
+    [('B', &["D", "N"]), ('Z', &["E", "Q"]), ('J', &["I", "L"])];
+
+/// Reads the elements of a pattern one character at a time
+struct Parser<'a, A: Alphabet> {
+    alphabet: &'a A,
+    chars: Vec<char>,
+    position: usize,
+    /// The number of states the elements so far will compile to
+    states: usize,
+}
+
+impl<'a, A: Alphabet> Parser<'a, A> {
+    fn new(pattern: &str, alphabet: &'a A) -> Self {
+        Parser { alphabet, chars: pattern.trim().chars().collect(), position: 0, states: 0 }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.position).copied()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let next = self.peek();
+        if next.is_some() {
+            self.position += 1;
+        }
+        next
+    }
+
+    /// Moves past the next character if it's c
+    fn eat(&mut self, c: char) -> bool {
+        let found = self.peek() == Some(c);
+        if found {
+            self.position += 1;
+        }
+        found
+    }
+
+    fn syntax_error(&self, position: usize, description: String) -> PatternError {
+        PatternError::new(ErrorKind::Syntax, position, description)
+    }
+
+    /// A description of the character at a position for error messages
+    fn describe(&self, position: usize) -> String {
+        match self.chars.get(position) {
+            Some(c) => format!("{:?}", c),
+            None => "the end of the pattern".to_owned(),
+        }
+    }
+
+    fn expect(&mut self, c: char) -> Result<()> {
+        if self.eat(c) {
+            Ok(())
+        } else {
+            let description =
+                format!("Expected {:?} but found {}", c, self.describe(self.position));
+            Err(self.syntax_error(self.position, description))
+        }
+    }
+
+    fn number(&mut self) -> Result<usize> {
+        let start = self.position;
+        while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+            self.position += 1;
+        }
+
+        let digits: String = self.chars[start..self.position].iter().collect();
+        digits.parse().map_err(|_| {
+            let description = format!("Expected a number but found {}", self.describe(start));
+            self.syntax_error(start, description)
+        })
+    }
+
+    /// Checks a repeat starting at a position and creates its element
+    fn element(
+        &self,
+        symbols: Vec<bool>,
+        min: usize,
+        max: Option<usize>,
+        position: usize
+    ) -> Result<Element> {
+        match max {
+            Some(max) if min > max => {
+                let description =
+                    format!("The repeat has a minimum of {} but a maximum of {}", min, max);
+                Err(PatternError::new(ErrorKind::InvalidRepeat, position, description))
+            }
+            _ => Ok(Element { symbols, min, max }),
+        }
+    }
+
+    /// Adds an element starting at a position, checking the pattern doesn't need too many states
+    fn push(&mut self, elements: &mut Vec<Element>, element: Element, start: usize) -> Result<()> {
+        let states = element.max.unwrap_or_else(|| element.min.max(1));
+        self.states = self.states.saturating_add(states);
+        if self.states > MAX_STATES {
+            let description = format!("The pattern needs more than {} states", MAX_STATES);
+            return Err(PatternError::new(ErrorKind::InvalidRepeat, start, description));
+        }
+
+        elements.push(element);
+        Ok(())
+    }
+
+    /// Every symbol of the alphabet
+    fn any(&self) -> Vec<bool> {
+        vec![true; self.alphabet.symbols().len()]
+    }
+
+    /// The symbols of the alphabet that a residue of the pattern matches
+    fn residue(&self, residue: char, position: usize) -> Result<Vec<bool>> {
+        let symbols = self.alphabet.symbols();
+        let residue_str = residue.to_string();
+
+        let matches: Vec<bool> = if symbols.iter().all(|symbol| iupac_bases(symbol).is_some()) {
+            let bases = iupac_bases(&residue_str).unwrap_or(0);
+            symbols.iter().map(|symbol| iupac_bases(symbol).unwrap_or(0) & bases != 0).collect()
+        } else if residue == 'X' {
+            self.any()
+        } else {
+            let members = AMINO_ACID_CODES.iter()
+                .find(|(code, _)| *code == residue)
+                .map_or(&[][..], |(_, members)| members);
+            symbols.iter()
+                .map(|symbol| *symbol == residue_str || members.contains(symbol))
+                .collect()
+        };
+
+        if matches.contains(&true) {
+            Ok(matches)
+        } else {
+            let description =
+                format!("The residue {} doesn't match any symbol of the alphabet", residue);
+            Err(PatternError::new(ErrorKind::UnknownResidue(residue_str), position, description))
+        }
+    }
+
+    /// The symbols matched by any of the residues up to the closing character
+    fn residues_until(&mut self, close: char) -> Result<Vec<bool>> {
+        let open = self.position - 1;
+        let mut matches = vec![false; self.alphabet.symbols().len()];
+        let mut empty = true;
+
+        loop {
+            let position = self.position;
+            match self.next() {
+                Some(c) if c == close => break,
+                Some(c) if c.is_alphabetic() => {
+                    for (matches, residue) in matches.iter_mut().zip(self.residue(c, position)?) {
+                        *matches |= residue;
+                    }
+                    empty = false;
+                }
+                _ => {
+                    let description = format!(
+                        "Expected a residue or {:?} but found {}", close, self.describe(position));
+                    return Err(self.syntax_error(position, description));
+                }
+            }
+        }
+
+        if empty {
+            let description = "The brackets don't contain any residues".to_owned();
+            return Err(self.syntax_error(open, description));
+        }
+
+        Ok(matches)
+    }
+
+    /// Parses PROSITE syntax, returning the elements and whether the start and end are anchored
+    fn prosite(mut self) -> Result<(Vec<Element>, (bool, bool))> {
+        let anchored_start = self.eat('<');
+        let mut elements = vec![];
+
+        let anchored_end = loop {
+            let start = self.position;
+            let symbols = match self.next() {
+                Some('x') => self.any(),
+                Some('[') => self.residues_until(']')?,
+                Some('{') => {
+                    self.residues_until('}')?.into_iter().map(|matches| !matches).collect()
+                }
+                Some(c) if c.is_alphabetic() => self.residue(c, start)?,
+                _ => {
+                    let description =
+                        format!("Expected an element but found {}", self.describe(start));
+                    return Err(self.syntax_error(start, description));
+                }
+            };
+
+            let element = if self.eat('(') {
+                let min = self.number()?;
+                let max = if self.eat(',') { self.number()? } else { min };
+                self.expect(')')?;
+                self.element(symbols, min, Some(max), start)?
+            } else {
+                Element { symbols, min: 1, max: Some(1) }
+            };
+            self.push(&mut elements, element, start)?;
+
+            if self.eat('-') {
+                continue;
+            }
+            let anchored_end = self.eat('>');
+            self.eat('.');
+            if self.peek().is_some() {
+                let description = format!(
+                    "Expected - between elements but found {}", self.describe(self.position));
+                return Err(self.syntax_error(self.position, description));
+            }
+            break anchored_end;
+        };
+
+        Ok((elements, (anchored_start, anchored_end)))
+    }
+
+    /// Parses a regular expression, returning the elements and whether the start and end are
+    /// anchored
+    fn regex(mut self) -> Result<(Vec<Element>, (bool, bool))> {
+        let anchored_start = self.eat('^');
+        let mut anchored_end = false;
+        let mut elements = vec![];
+
+        while let Some(c) = self.next() {
+            let start = self.position - 1;
+            let symbols = match c {
+                '$' if self.peek().is_none() => {
+                    anchored_end = true;
+                    break;
+                }
+                '.' => self.any(),
+                '[' => {
+                    let negated = self.eat('^');
+                    let matches = self.residues_until(']')?;
+                    if negated {
+                        matches.into_iter().map(|matches| !matches).collect()
+                    } else {
+                        matches
+                    }
+                }
+                c if c.is_alphabetic() => self.residue(c, start)?,
+                '(' | ')' | '|' => {
+                    let description = "Groups and alternation are not supported".to_owned();
+                    return Err(self.syntax_error(start, description));
+                }
+                _ => {
+                    let description =
+                        format!("Expected an element but found {}", self.describe(start));
+                    return Err(self.syntax_error(start, description));
+                }
+            };
+
+            let element = if self.eat('?') {
+                Element { symbols, min: 0, max: Some(1) }
+            } else if self.eat('*') {
+                Element { symbols, min: 0, max: None }
+            } else if self.eat('+') {
+                Element { symbols, min: 1, max: None }
+            } else if self.eat('{') {
+                let min = self.number()?;
+                let max = if !self.eat(',') {
+                    Some(min)
+                } else if self.peek() == Some('}') {
+                    None
+                } else {
+                    Some(self.number()?)
+                };
+                self.expect('}')?;
+                self.element(symbols, min, max, start)?
+            } else {
+                Element { symbols, min: 1, max: Some(1) }
+            };
+            self.push(&mut elements, element, start)?;
+        }
+
+        Ok((elements, (anchored_start, anchored_end)))
+    }
+}
+
+//================================================================================
+// Errors
+//================================================================================
+
+/// Represents the kind of error that occurred while compiling a pattern.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ErrorKind {
+    /// The pattern isn't written in the expected syntax
+    Syntax,
+
+    /// A residue of the pattern doesn't match any symbol of the alphabet
+    UnknownResidue(String),
+
+    /// A repeat has a larger minimum than maximum or the pattern needs more than [MAX_STATES]
+    /// states
+    InvalidRepeat,
+
+    /// The pattern can match an empty sequence
+    EmptyMatch,
+}
+
+/// The type of error returned when a pattern can't be compiled
+#[derive(Debug, Clone)]
+pub struct PatternError {
+    kind: ErrorKind,
+    position: usize,
+    description: String,
+}
+
+impl PatternError {
+    /// Construct a new PatternError from the given ErrorKind, position and description
+    pub fn new(kind: ErrorKind, position: usize, description: String) -> PatternError {
+        PatternError { kind, position, description }
+    }
+
+    /// Get the associated ErrorKind for this error
+    pub fn kind(&self) -> &ErrorKind {
+        &self.kind
+    }
+
+    /// Get the position of the character of the pattern the error was found at, counted from 0
+    /// after leading whitespace
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// Get the associated description for this error
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+}
+
+impl Error for PatternError {}
+
+impl fmt::Display for PatternError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Pattern error: {:?} at character {}:\n\t{}",
+            self.kind, self.position, self.description
+        )
+    }
+}
+
+//================================================================================
+// Tests
+//================================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alphabet::{
+        AmbiguousDnaAlphabet, ExtendedProteinAlphabet, ProteinAlphabet, UnambiguousDnaAlphabet
+    };
+
+    /// Returns the start and end of each match
+    fn found<A: Alphabet>(pattern: &Pattern<A>, text: &Sequence<A>) -> Vec<(usize, usize)> {
+        pattern.find_iter(text).map(|m| (m.start, m.end)).collect()
+    }
+
+    /// Tests each kind of PROSITE element
+    #[test]
+    fn prosite_elements() {
+        let protein = ProteinAlphabet;
+        let mut text = Sequence::new(&protein);
+        text.push("GACPGCAACPWCKC").unwrap();
+
+        let pattern = Pattern::prosite("[AC]-x(2,4)-{P}-C.", &protein).unwrap();
+        assert_eq!((pattern.min_len(), pattern.max_len()), (5, Some(7)));
+        // The longest match is taken at each start and matches don't overlap
+        assert_eq!(found(&pattern, &text), vec![(1, 6), (6, 12)]);
+
+        let pattern = Pattern::prosite("C-P-x-C", &protein).unwrap();
+        assert_eq!(found(&pattern, &text), vec![(2, 6), (8, 12)]);
+
+        let pattern = Pattern::prosite("<G-A", &protein).unwrap();
+        assert_eq!(found(&pattern, &text), vec![(0, 2)]);
+        let pattern = Pattern::prosite("C-A>", &protein).unwrap();
+        assert!(found(&pattern, &text).is_empty());
+        let pattern = Pattern::prosite("K-C>.", &protein).unwrap();
+        assert_eq!(found(&pattern, &text), vec![(12, 14)]);
+        assert!(pattern.is_match(&text));
+    }
+
+    /// Tests the regular expression repeats and IUPAC codes in a DNA pattern
+    #[test]
+    fn regex_repeats() {
+        let dna = UnambiguousDnaAlphabet;
+        let mut text = Sequence::new(&dna);
+        text.push("GAATTCGAAATTTCGATC").unwrap();
+
+        let pattern = Pattern::regex("GA+T+C", &dna).unwrap();
+        assert_eq!(pattern.max_len(), None);
+        assert_eq!(found(&pattern, &text), vec![(0, 6), (6, 14), (14, 18)]);
+
+        let pattern = Pattern::regex("GA{2,}T*C", &dna).unwrap();
+        assert_eq!(found(&pattern, &text), vec![(0, 6), (6, 14)]);
+
+        let pattern = Pattern::regex("^GRA?WT", &dna).unwrap();
+        assert_eq!(found(&pattern, &text), vec![(0, 5)]);
+
+        let pattern = Pattern::regex("[^A]TC$", &dna).unwrap();
+        assert!(found(&pattern, &text).is_empty());
+        let pattern = Pattern::regex("S.TC$", &dna).unwrap();
+        assert_eq!(found(&pattern, &text), vec![(14, 18)]);
+    }
+
+    /// Tests that ambiguous symbols in the text match residues whose bases overlap
+    #[test]
+    fn ambiguous_text() {
+        let dna = AmbiguousDnaAlphabet;
+        let mut text = Sequence::new(&dna);
+        text.push("GANTCGRTTY").unwrap();
+
+        let pattern = Pattern::regex("GATY", &dna).unwrap();
+        assert_eq!(found(&pattern, &text), vec![(0, 4), (5, 9)]);
+    }
+
+    /// Tests the ambiguous amino acid codes
+    #[test]
+    fn amino_acid_codes() {
+        let protein = ProteinAlphabet;
+        let mut text = Sequence::new(&protein);
+        text.push("MDKNLEKQI").unwrap();
+
+        let pattern = Pattern::prosite("B-x-Z", &protein).unwrap();
+        assert_eq!(found(&pattern, &text), vec![(3, 6)]);
+        let pattern = Pattern::prosite("B-K", &protein).unwrap();
+        assert_eq!(found(&pattern, &text), vec![(1, 3)]);
+        let pattern = Pattern::regex("ZKZJ", &protein).unwrap();
+        assert_eq!(found(&pattern, &text), vec![(5, 9)]);
+
+        // B and X are symbols of the extended alphabet as well as codes
+        let extended = ExtendedProteinAlphabet;
+        let mut text = Sequence::new(&extended);
+        text.push("BDNB").unwrap();
+        let pattern = Pattern::regex("B{3}X", &extended).unwrap();
+        assert_eq!(found(&pattern, &text), vec![(0, 4)]);
+    }
+
+    /// Tests that matches wrap around the origin of circular sequences
+    #[test]
+    fn circular() {
+        let dna = UnambiguousDnaAlphabet;
+        let mut text = Sequence::new(&dna);
+        text.push("TCCAAAAGGA").unwrap();
+
+        let pattern = Pattern::regex("GGAT+C", &dna).unwrap();
+        assert!(found(&pattern, &text).is_empty());
+
+        let text = text.circular(true);
+        assert_eq!(found(&pattern, &text), vec![(7, 2)]);
+
+        // A match can't be longer than the text
+        let pattern = Pattern::regex("[ACGT]+", &dna).unwrap();
+        assert_eq!(found(&pattern, &text), vec![(0, 10)]);
+
+        // A match that wraps around the origin stops before the first match
+        let mut text = Sequence::new(&dna);
+        text.push("TATCCTT").unwrap();
+        let pattern = Pattern::regex("T+A", &dna).unwrap();
+        assert_eq!(found(&pattern, &text.clone().circular(true)), vec![(0, 2)]);
+        let pattern = Pattern::regex("T+C*", &dna).unwrap();
+        assert_eq!(found(&pattern, &text.circular(true)), vec![(0, 1), (2, 5), (5, 7)]);
+    }
+
+    /// Whether the elements match all of the text, by trying every number of repeats
+    fn matches_whole(elements: &[Element], text: &[usize]) -> bool {
+        let element = match elements.first() {
+            Some(element) => element,
+            None => return text.is_empty(),
+        };
+        let most = element.max.unwrap_or(text.len()).min(text.len());
+
+        (element.min..=most).any(|count| {
+            text[..count].iter().all(|&symbol| element.symbols[symbol])
+                && matches_whole(&elements[1..], &text[count..])
+        })
+    }
+
+    /// Finds matches by trying the longest match at each start in turn
+    fn naive<A: Alphabet>(pattern: &Pattern<A>, text: &Sequence<A>) -> Vec<(usize, usize)> {
+        let automaton = &pattern.automaton;
+        let symbols: Vec<usize> = text.as_bytes().iter().map(|&symbol| symbol as usize).collect();
+        let len = symbols.len();
+        let mut found: Vec<(usize, usize)> = vec![];
+        let mut start = 0;
+
+        while start < len {
+            let longest = match (text.circular, found.first()) {
+                (true, Some(&(first, _))) => len + first - start,
+                (true, None) => len,
+                (false, _) => len - start,
+            };
+            let length = (1..=longest.min(len)).rev().find(|&length| {
+                let window: Vec<usize> =
+                    (start..start + length).map(|i| symbols[i % len]).collect();
+                (!automaton.anchored_start || start == 0)
+                    && (!automaton.anchored_end || start + length == len)
+                    && matches_whole(&automaton.elements, &window)
+            });
+
+            match length {
+                Some(length) => {
+                    let end = start + length;
+                    found.push((start, if end > len { end - len } else { end }));
+                    start = end;
+                }
+                None => start += 1,
+            }
+        }
+
+        found
+    }
+
+    /// Tests the single pass search against trying every start on random texts
+    #[test]
+    fn matches_naive() {
+        let dna = UnambiguousDnaAlphabet;
+        let patterns = [
+            "GA+T*C", "[AG]T{1,3}A?", "A.{0,2}T+", "T+A", "^A[CG]*", "[ACGT]+", "C[AT]*G$", "AT*"
+        ];
+        let mut state = 12345u64;
+
+        for length in 0..40 {
+            let text: String = (0..length)
+                .map(|_| {
+                    state = state.wrapping_mul(6_364_136_223_846_793_005).wrapping_add(1);
+                    ['A', 'C', 'G', 'T'][(state >> 33) as usize % 4]
+                })
+                .collect();
+            let mut sequence = Sequence::new(&dna);
+            sequence.push(&text).unwrap();
+
+            for &source in &patterns {
+                let pattern = Pattern::regex(source, &dna).unwrap();
+                let expected = naive(&pattern, &sequence);
+                assert_eq!(found(&pattern, &sequence), expected, "{} in {}", source, text);
+
+                let circular = sequence.clone().circular(true);
+                let expected = naive(&pattern, &circular);
+                assert_eq!(found(&pattern, &circular), expected, "{} in circular {}", source, text);
+            }
+        }
+    }
+
+    /// Tests the errors and the positions they are reported at
+    #[test]
+    fn errors() {
+        let protein = ProteinAlphabet;
+        let dna = UnambiguousDnaAlphabet;
+
+        let err = Pattern::prosite("C-x(2,4-C", &protein).unwrap_err();
+        assert_eq!((err.kind(), err.position()), (&ErrorKind::Syntax, 7));
+
+        let err = Pattern::prosite("C-x(4,2)-C", &protein).unwrap_err();
+        assert_eq!((err.kind(), err.position()), (&ErrorKind::InvalidRepeat, 2));
+
+        let err = Pattern::prosite("C-x(1,400000000)", &protein).unwrap_err();
+        assert_eq!((err.kind(), err.position()), (&ErrorKind::InvalidRepeat, 2));
+        let err = Pattern::prosite("x(18446744073709551615)-x(1)", &protein).unwrap_err();
+        assert_eq!((err.kind(), err.position()), (&ErrorKind::InvalidRepeat, 0));
+        let err = Pattern::regex("A{6000}C{4001}", &dna).unwrap_err();
+        assert_eq!((err.kind(), err.position()), (&ErrorKind::InvalidRepeat, 7));
+        assert_eq!(Pattern::regex("A{6000}C{4000}", &dna).unwrap().max_len(), Some(MAX_STATES));
+
+        let err = Pattern::prosite("C-[AOC]", &protein).unwrap_err();
+        assert_eq!((err.kind(), err.position()), (&ErrorKind::UnknownResidue("O".to_owned()), 4));
+
+        let err = Pattern::prosite("CC", &protein).unwrap_err();
+        assert_eq!((err.kind(), err.position()), (&ErrorKind::Syntax, 1));
+
+        let err = Pattern::prosite("C-[]", &protein).unwrap_err();
+        assert_eq!((err.kind(), err.position()), (&ErrorKind::Syntax, 2));
+
+        let err = Pattern::prosite("x(0,2)", &protein).unwrap_err();
+        assert_eq!(err.kind(), &ErrorKind::EmptyMatch);
+
+        let err = Pattern::regex("GA(TC)+", &dna).unwrap_err();
+        assert_eq!((err.kind(), err.position()), (&ErrorKind::Syntax, 2));
+
+        let err = Pattern::regex("GAE", &dna).unwrap_err();
+        assert_eq!((err.kind(), err.position()), (&ErrorKind::UnknownResidue("E".to_owned()), 2));
+
+        let err = Pattern::regex("A*C?", &dna).unwrap_err();
+        assert_eq!(err.kind(), &ErrorKind::EmptyMatch);
+
+        let err = Pattern::regex("AC{x}", &dna).unwrap_err();
+        assert_eq!((err.kind(), err.position()), (&ErrorKind::Syntax, 3));
+    }
+}
@@ -6,6 +6,7 @@ pub use crate::alphabet::{Alphabet, Complement};
 use std::marker::PhantomData;
 use crate::alphabet::encoding::{AlphabetEncoder, index_encoder::AsciiIndexEncoder};
 use crate::alphabet::encoding::{self, EncodingError};
+use crate::kmer::{Kmers, PackedKmers};
 use std::fmt;
 
 /// A Sequence contains a string constructed from the symbols of the specified
@@ -126,6 +127,34 @@ where
     pub fn clear(&mut self) {
         self.string.clear();
     }
+
+    /// Returns the encoded bytes that make up the sequence.
+    ///
+    /// # Notes
+    /// How these bytes relate to the symbols of the alphabet depends entirely on the encoder.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.string
+    }
+
+    /// Decodes the sequence into the index of each of its symbols in
+    /// [Alphabet::symbols()](crate::alphabet::Alphabet::symbols).
+    ///
+    /// # Notes
+    /// For the default AsciiIndexEncoder these are the same as the encoded bytes.
+    pub fn symbol_indices(&self) -> encoding::Result<Vec<usize>> {
+        let symbols = self.alphabet().symbols();
+
+        self.encoder.decode_all(&self.string)?
+            .into_iter()
+            .map(|symbol| {
+                symbols.iter().position(|s| *s == symbol).ok_or_else(|| {
+                    let kind = encoding::ErrorKind::InvalidSymbol(symbol.to_owned());
+                    let description = format!("Decoded symbol {} is not in the alphabet", symbol);
+                    EncodingError::new(kind, description)
+                })
+            })
+            .collect()
+    }
 }
 
 //================================================================================
@@ -143,6 +172,59 @@ impl<'a, A: Alphabet> Sequence<'a, A, AsciiIndexEncoder<'a, A>> {
             phantom: PhantomData
         }
     }
+
+    /// The number of symbols in the sequence.
+    ///
+    /// # Notes
+    /// The AsciiIndexEncoder always uses exactly one byte per symbol so this is also the number of
+    /// encoded bytes.
+    pub fn len(&self) -> usize {
+        self.string.len()
+    }
+
+    /// Returns true if the sequence contains no symbols.
+    pub fn is_empty(&self) -> bool {
+        self.string.is_empty()
+    }
+
+    /// Reverses the order of the symbols in the sequence.
+    pub fn reverse(&mut self) {
+        self.string.reverse();
+    }
+
+    /// Constructs a sequence from bytes that are already indices of symbols in the alphabet
+    pub(crate) fn from_indices(alphabet: &'a A, indices: Vec<u8>) -> Self {
+        Sequence {
+            encoder: AsciiIndexEncoder::new(alphabet),
+            string: indices,
+            circular: false,
+            phantom: PhantomData
+        }
+    }
+
+    /// Mutable access to the symbol indices, which must remain valid for the alphabet
+    pub(crate) fn indices_mut(&mut self) -> &mut Vec<u8> {
+        &mut self.string
+    }
+}
+
+//================================================================================
+// Clone
+//================================================================================
+
+impl<'a, A, E> Clone for Sequence<'a, A, E>
+where
+    A: Alphabet,
+    E: AlphabetEncoder<A> + Clone
+{
+    fn clone(&self) -> Self {
+        Sequence {
+            encoder: self.encoder.clone(),
+            circular: self.circular,
+            string: self.string.clone(),
+            phantom: PhantomData
+        }
+    }
 }
 
 //================================================================================
@@ -193,10 +275,78 @@ where
 // Complement
 //================================================================================
 
+/// The strand of a double stranded sequence that something was found on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Strand {
+    /// The sequence as it was given
+    Forward,
+    /// The reverse complement of the sequence
+    Reverse,
+}
+
 // Give Sequences that use an alphabet with complement symbols the ability to complement themselves
 impl<'a, A: Complement> Sequence<'a, A> {
+    /// Replaces each symbol in the sequence with its complement as defined by
+    /// [Complement::complement_mapping()](crate::alphabet::Complement::complement_mapping).
     pub fn complement(&mut self) {
-        unimplemented!()
+        let mapping = self.alphabet().complement_indices();
+
+        for byte in self.string.iter_mut() {
+            *byte = mapping[*byte as usize] as u8;
+        }
+    }
+
+    /// Turns the sequence into its reverse complement, the sequence of the opposite strand read
+    /// in its own 5' to 3' direction.
+    ///
+    /// # Example
+    /// ```
+    /// use biors::alphabet::UnambiguousDnaAlphabet;
+    /// use biors::sequence::Sequence;
+    ///
+    /// let alphabet = UnambiguousDnaAlphabet;
+    /// let mut seq = Sequence::new(&alphabet);
+    /// seq.push("AACG").unwrap();
+    ///
+    /// seq.reverse_complement();
+    /// assert_eq!(format!("{}", seq), "Sequence: CGTT");
+    /// ```
+    pub fn reverse_complement(&mut self) {
+        self.reverse();
+        self.complement();
+    }
+
+    /// Returns an iterator over every k-mer of the sequence packed into a u64 using 2 bits per
+    /// nucleotide. See [PackedKmers](crate::kmer::PackedKmers).
+    ///
+    /// # Panics
+    /// If k is 0 or greater than 32.
+    pub fn packed_kmers(&self, k: usize) -> PackedKmers<'_> {
+        PackedKmers::new(self, k)
+    }
+
+    /// Returns an iterator over the position and canonical form of every k-mer of the sequence.
+    /// The canonical form of a k-mer is the smaller of its packed forward and reverse complement.
+    ///
+    /// # Panics
+    /// If k is 0 or greater than 32.
+    pub fn canonical_kmers(&self, k: usize) -> impl Iterator<Item=(usize, u64)> + '_ {
+        self.packed_kmers(k).map(|kmer| (kmer.position, kmer.canonical()))
+    }
+}
+
+//================================================================================
+// K-mers
+//================================================================================
+
+impl<'a, A: Alphabet> Sequence<'a, A> {
+    /// Returns an iterator over the encoded bytes of every k-mer (substring of length k) in the
+    /// sequence. See [Kmers](crate::kmer::Kmers).
+    ///
+    /// # Panics
+    /// If k is 0.
+    pub fn kmers(&self, k: usize) -> Kmers<'_> {
+        Kmers::new(self, k)
     }
 }
 
@@ -212,14 +362,74 @@ fn string_chunks(src: &str, chunk_size: usize) -> impl Iterator<Item=&str> {
         .step_by(chunk_size)
         .flat_map(move |(from, _)| {
             src[from..].char_indices()
-                .skip(chunk_size - 1)
-                .next()
+                .nth(chunk_size - 1)
                 .map(|(to, c)| {
                     &src[from .. from + to + c.len_utf8()]
                 })
         })
 }
 
+/// Helpers for the tests of the modules that work on sequences
+#[cfg(test)]
+pub(crate) mod test_util {
+    use crate::alphabet::{Alphabet, UnambiguousDnaAlphabet};
+    use super::Sequence;
+
+    /// Constructs a sequence from a string of symbols
+    pub(crate) fn sequence<'a, A: Alphabet>(alphabet: &'a A, s: &str) -> Sequence<'a, A> {
+        let mut seq = Sequence::new(alphabet);
+        seq.push(s).unwrap();
+        seq
+    }
+
+    /// Constructs a DNA sequence from a string of bases
+    pub(crate) fn dna<'a>(
+        alphabet: &'a UnambiguousDnaAlphabet,
+        s: &str
+    ) -> Sequence<'a, UnambiguousDnaAlphabet> {
+        sequence(alphabet, s)
+    }
+
+    /// Generates pseudo-random symbol indices below the alphabet size with xorshift. The seed
+    /// must not be 0.
+    pub(crate) fn random_indices(seed: u64, len: usize, alphabet: usize) -> Vec<usize> {
+        let mut state = seed;
+        (0..len)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                (state % alphabet as u64) as usize
+            })
+            .collect()
+    }
+
+    /// Generates pseudo-random encoded bytes below the alphabet size. See [random_indices].
+    pub(crate) fn random_bytes(seed: u64, len: usize, alphabet: u8) -> Vec<u8> {
+        random_indices(seed, len, alphabet as usize).into_iter().map(|i| i as u8).collect()
+    }
+
+    /// Generates a pseudo-random DNA sequence. See [random_indices].
+    pub(crate) fn random_dna(
+        alphabet: &UnambiguousDnaAlphabet,
+        seed: u64,
+        len: usize
+    ) -> Sequence<'_, UnambiguousDnaAlphabet> {
+        let bases: String = random_indices(seed, len, 4).into_iter()
+            .map(|i| ['A', 'C', 'G', 'T'][i])
+            .collect();
+        dna(alphabet, &bases)
+    }
+
+    /// The reverse complement of a string of bases
+    pub(crate) fn reverse_complement(s: &str) -> String {
+        let alphabet = UnambiguousDnaAlphabet;
+        let mut seq = dna(&alphabet, s);
+        seq.reverse_complement();
+        seq.as_bytes().iter().map(|&index| alphabet.symbols()[index as usize]).collect()
+    }
+}
+
 //================================================================================
 // Tests
 //================================================================================
@@ -357,7 +567,7 @@ mod tests {
             .iter()
             .cycle()
             .take(60)
-            .map(|s| *s);
+            .copied();
 
         s.extend(seq);
 
@@ -369,4 +579,19 @@ mod tests {
         assert_eq!(display_res, expected);
     }
 
+    /// Tests that complementing a sequence complements each of its symbols
+    #[test]
+    fn complement() {
+        let a = crate::alphabet::AmbiguousDnaAlphabet;
+        let mut s = Sequence::new(&a);
+        s.push("ACGTRN").unwrap();
+
+        let mut c = s.clone();
+        c.complement();
+        assert_eq!(format!("{}", c), "Sequence: TGCAYN");
+
+        s.reverse_complement();
+        assert_eq!(format!("{}", s), "Sequence: NYACGT");
+    }
+
 }
\ No newline at end of file
@@ -0,0 +1,571 @@
+//! MinHash sketches of the canonical k-mers of one or more [Sequences](crate::sequence::Sequence).
+//!
+//! A sketch keeps a small sample of the hashes of a sequence's k-mers chosen so that comparing
+//! two sketches estimates how similar the full sets of k-mers are. This makes it possible to
+//! compare thousands of genomes without comparing every k-mer.
+//!
+//! Two kinds of sketch are supported:
+//! * Bottom-k sketches keep the s smallest hashes. They are a fixed size no matter how long the
+//!   sequence is.
+//! * Scaled (FracMinHash) sketches keep every hash below u64::MAX / scale. They grow with the
+//!   sequence but estimate containment between sequences of very different sizes much better.
+
+use std::collections::BTreeSet;
+use std::error::Error;
+use std::fmt;
+use std::io::{self, Read, Write};
+
+use crate::alphabet::Complement;
+use crate::kmer::{mix64, MAX_PACKED_K};
+use crate::sequence::Sequence;
+
+/// The type of Results returned when comparing sketches.
+pub type Result<T> = std::result::Result<T, SketchError>;
+
+/// Identifies the binary format written by [Sketch::write_binary()]
+const MAGIC: &[u8; 4] = b"BMSH";
+
+/// The version of the binary format
+const VERSION: u8 = 1;
+
+/// Decides which hashes are kept in a [Sketch].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SketchKind {
+    /// Keep the given number of smallest hashes
+    BottomK(usize),
+    /// Keep every hash less than u64::MAX divided by the given scale
+    Scaled(u64),
+}
+
+/// A MinHash sketch of the canonical k-mers of some sequences.
+///
+/// K-mers are hashed with [mix64()](crate::kmer::mix64) so sketches are only comparable with
+/// other biors sketches.
+///
+/// # Example
+/// ```
+/// use biors::alphabet::UnambiguousDnaAlphabet;
+/// use biors::sequence::Sequence;
+/// use biors::sketch::Sketch;
+///
+/// let alphabet = UnambiguousDnaAlphabet;
+/// let mut seq = Sequence::new(&alphabet);
+/// seq.push("GATTACAGGCATTACCAGTAGGACTTATGCA").unwrap();
+///
+/// let mut rc = seq.clone();
+/// rc.reverse_complement();
+///
+/// let mut a = Sketch::bottom_k(11, 100);
+/// let mut b = Sketch::bottom_k(11, 100);
+/// a.add_sequence(&seq);
+/// b.add_sequence(&rc);
+///
+/// // Canonical k-mers are the same on both strands
+/// assert_eq!(a.jaccard(&b).unwrap(), 1.0);
+/// assert_eq!(a.mash_distance(&b).unwrap(), 0.0);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Sketch {
+    k: usize,
+    kind: SketchKind,
+    hashes: BTreeSet<u64>,
+}
+
+impl Sketch {
+    /// Constructs an empty bottom-k sketch that keeps the size smallest hashes.
+    ///
+    /// # Panics
+    /// If size is 0 or k is 0 or greater than [MAX_PACKED_K](crate::kmer::MAX_PACKED_K).
+    pub fn bottom_k(k: usize, size: usize) -> Self {
+        assert!(size > 0, "Sketch size must be greater than 0");
+        Sketch::new(k, SketchKind::BottomK(size))
+    }
+
+    /// Constructs an empty scaled sketch that keeps roughly one in every scale hashes.
+    ///
+    /// # Panics
+    /// If scale is 0 or k is 0 or greater than [MAX_PACKED_K](crate::kmer::MAX_PACKED_K).
+    pub fn scaled(k: usize, scale: u64) -> Self {
+        assert!(scale > 0, "Sketch scale must be greater than 0");
+        Sketch::new(k, SketchKind::Scaled(scale))
+    }
+
+    /// Constructs an empty sketch
+    fn new(k: usize, kind: SketchKind) -> Self {
+        assert!(
+            k > 0 && k <= MAX_PACKED_K,
+            "k must be between 1 and {} to sketch packed k-mers but was {}", MAX_PACKED_K, k
+        );
+
+        Sketch { k, kind, hashes: BTreeSet::new() }
+    }
+
+    /// The length of the sketched k-mers.
+    pub fn k(&self) -> usize {
+        self.k
+    }
+
+    /// How the sketch decides which hashes to keep.
+    pub fn kind(&self) -> SketchKind {
+        self.kind
+    }
+
+    /// The number of hashes in the sketch.
+    pub fn len(&self) -> usize {
+        self.hashes.len()
+    }
+
+    /// Returns true if the sketch contains no hashes.
+    pub fn is_empty(&self) -> bool {
+        self.hashes.is_empty()
+    }
+
+    /// An iterator over the hashes in the sketch in increasing order.
+    pub fn hashes(&self) -> impl Iterator<Item=u64> + '_ {
+        self.hashes.iter().cloned()
+    }
+
+    /// Adds the canonical k-mers of the sequence to the sketch. K-mers that can't be packed are
+    /// skipped as described in [PackedKmers](crate::kmer::PackedKmers).
+    pub fn add_sequence<A: Complement>(&mut self, seq: &Sequence<A>) {
+        for kmer in seq.packed_kmers(self.k) {
+            self.add_hash(mix64(kmer.canonical()));
+        }
+    }
+
+    /// Adds a hash to the sketch if it should be kept.
+    pub fn add_hash(&mut self, hash: u64) {
+        match self.kind {
+            SketchKind::BottomK(size) => {
+                if self.hashes.len() < size {
+                    self.hashes.insert(hash);
+                } else if hash < *self.hashes.iter().next_back().unwrap()
+                    && self.hashes.insert(hash)
+                {
+                    let largest = *self.hashes.iter().next_back().unwrap();
+                    self.hashes.remove(&largest);
+                }
+            }
+            SketchKind::Scaled(scale) => {
+                if hash < u64::MAX / scale {
+                    self.hashes.insert(hash);
+                }
+            }
+        }
+    }
+
+    /// Adds every hash from another sketch to this one making it a sketch of both.
+    ///
+    /// # Errors
+    /// If the sketches are not [compatible](Sketch::is_compatible).
+    pub fn merge(&mut self, other: &Sketch) -> Result<()> {
+        self.check_compatible(other)?;
+
+        for &hash in other.hashes.iter() {
+            self.add_hash(hash);
+        }
+
+        Ok(())
+    }
+
+    /// Returns true if the sketches have the same k and kind so they can be compared.
+    pub fn is_compatible(&self, other: &Sketch) -> bool {
+        self.check_compatible(other).is_ok()
+    }
+
+    /// Estimates the Jaccard similarity |A ∩ B| / |A ∪ B| of the k-mers of both sketches.
+    ///
+    /// For bottom-k sketches this looks at the s smallest hashes of the union of both sketches
+    /// as in Mash.
+    ///
+    /// # Errors
+    /// If the sketches are not [compatible](Sketch::is_compatible).
+    pub fn jaccard(&self, other: &Sketch) -> Result<f64> {
+        self.check_compatible(other)?;
+
+        let limit = match self.kind {
+            SketchKind::BottomK(size) => size,
+            SketchKind::Scaled(_) => usize::MAX,
+        };
+
+        let mut union_size = 0;
+        let mut shared = 0;
+
+        for hash in self.hashes.union(&other.hashes).take(limit) {
+            union_size += 1;
+            if self.hashes.contains(hash) && other.hashes.contains(hash) {
+                shared += 1;
+            }
+        }
+
+        if union_size == 0 {
+            Ok(0.0)
+        } else {
+            Ok(shared as f64 / union_size as f64)
+        }
+    }
+
+    /// Estimates the containment |A ∩ B| / |A| of the k-mers of this sketch (A) in the other
+    /// sketch (B).
+    ///
+    /// For bottom-k sketches only the hashes below the largest hash of both sketches are
+    /// compared so the estimate is poor if the sequences are very different in size. Use scaled
+    /// sketches for that.
+    ///
+    /// # Errors
+    /// If the sketches are not [compatible](Sketch::is_compatible).
+    pub fn containment(&self, other: &Sketch) -> Result<f64> {
+        self.check_compatible(other)?;
+
+        let threshold = match self.kind {
+            SketchKind::BottomK(_) => {
+                let largest = |s: &Sketch| s.hashes.iter().next_back().cloned().unwrap_or(0);
+                largest(self).min(largest(other))
+            }
+            SketchKind::Scaled(_) => u64::MAX,
+        };
+
+        let mut total = 0;
+        let mut shared = 0;
+        for hash in self.hashes.range(..=threshold) {
+            total += 1;
+            if other.hashes.contains(hash) {
+                shared += 1;
+            }
+        }
+
+        if total == 0 {
+            Ok(0.0)
+        } else {
+            Ok(shared as f64 / total as f64)
+        }
+    }
+
+    /// Estimates the Mash distance between the sketches, an estimate of the mutation rate
+    /// between the sequences calculated as -ln(2j / (1 + j)) / k where j is the
+    /// [Jaccard similarity](Sketch::jaccard). Sketches sharing no hashes have a distance of 1.
+    ///
+    /// # Errors
+    /// If the sketches are not [compatible](Sketch::is_compatible).
+    pub fn mash_distance(&self, other: &Sketch) -> Result<f64> {
+        let j = self.jaccard(other)?;
+
+        if j == 0.0 {
+            Ok(1.0)
+        } else {
+            Ok((-(2.0 * j / (1.0 + j)).ln() / self.k as f64).max(0.0))
+        }
+    }
+
+    /// Writes the sketch in a stable binary form that can be read back with
+    /// [read_binary()](Sketch::read_binary).
+    ///
+    /// # Format
+    /// A header of the bytes `BMSH`, a version byte, the kind (0 for bottom-k, 1 for scaled), k
+    /// and a reserved 0 byte. This is followed by the size or scale of the sketch and the number
+    /// of hashes, each as a little endian u64, and then the hashes themselves in increasing order
+    /// as little endian u64s.
+    pub fn write_binary<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        let (kind, parameter) = match self.kind {
+            SketchKind::BottomK(size) => (0, size as u64),
+            SketchKind::Scaled(scale) => (1, scale),
+        };
+
+        writer.write_all(MAGIC)?;
+        writer.write_all(&[VERSION, kind, self.k as u8, 0])?;
+        writer.write_all(&parameter.to_le_bytes())?;
+        writer.write_all(&(self.hashes.len() as u64).to_le_bytes())?;
+
+        for hash in self.hashes.iter() {
+            writer.write_all(&hash.to_le_bytes())?;
+        }
+
+        Ok(())
+    }
+
+    /// Reads a sketch written by [write_binary()](Sketch::write_binary).
+    ///
+    /// # Errors
+    /// An error of kind [InvalidData](std::io::ErrorKind::InvalidData) is returned if the data
+    /// is not a valid sketch, including when a scaled sketch has a hash it would never keep.
+    pub fn read_binary<R: Read>(mut reader: R) -> io::Result<Sketch> {
+        let mut header = [0u8; 8];
+        reader.read_exact(&mut header)?;
+
+        if &header[0..4] != MAGIC {
+            return Err(invalid_data("Not a binary sketch"));
+        }
+        if header[4] != VERSION {
+            return Err(invalid_data("Unsupported binary sketch version"));
+        }
+
+        let k = header[6] as usize;
+        if k == 0 || k > MAX_PACKED_K {
+            return Err(invalid_data("Binary sketch has an invalid k"));
+        }
+        if header[7] != 0 {
+            return Err(invalid_data("Binary sketch has a non-zero reserved byte"));
+        }
+
+        let parameter = read_u64(&mut reader)?;
+        let kind = match (header[5], parameter) {
+            (_, 0) => return Err(invalid_data("Binary sketch has a size or scale of 0")),
+            (0, size) => SketchKind::BottomK(size as usize),
+            (1, scale) => SketchKind::Scaled(scale),
+            _ => return Err(invalid_data("Binary sketch has an invalid kind")),
+        };
+
+        let len = read_u64(&mut reader)?;
+        let mut sketch = Sketch::new(k, kind);
+        let mut previous = None;
+
+        for _ in 0..len {
+            let hash = read_u64(&mut reader)?;
+            if previous.is_some_and(|p| hash <= p) {
+                return Err(invalid_data("Binary sketch hashes are not in increasing order"));
+            }
+            if let SketchKind::Scaled(scale) = kind {
+                if hash >= u64::MAX / scale {
+                    return Err(invalid_data("Binary sketch contains a hash above its scale"));
+                }
+            }
+
+            sketch.hashes.insert(hash);
+            previous = Some(hash);
+        }
+
+        if let SketchKind::BottomK(size) = kind {
+            if sketch.hashes.len() > size {
+                return Err(invalid_data("Binary sketch contains more hashes than its size"));
+            }
+        }
+
+        Ok(sketch)
+    }
+
+    /// Returns an error describing why two sketches can't be compared if they can't
+    fn check_compatible(&self, other: &Sketch) -> Result<()> {
+        if self.k != other.k {
+            let description = format!(
+                "Tried to compare a sketch with k = {} to one with k = {}", self.k, other.k
+            );
+            return Err(SketchError::new(ErrorKind::DifferentK, description));
+        }
+
+        if self.kind != other.kind {
+            let description = format!(
+                "Tried to compare a {:?} sketch to a {:?} sketch", self.kind, other.kind
+            );
+            return Err(SketchError::new(ErrorKind::DifferentKind, description));
+        }
+
+        Ok(())
+    }
+}
+
+//================================================================================
+// Errors
+//================================================================================
+
+/// Represents the reason two sketches could not be compared.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ErrorKind {
+    /// The sketches were made from k-mers of different lengths
+    DifferentK,
+
+    /// The sketches keep their hashes in different ways
+    DifferentKind,
+}
+
+/// The type of error returned when sketches can't be compared or combined.
+#[derive(Debug, Clone)]
+pub struct SketchError {
+    kind: ErrorKind,
+    description: String,
+}
+
+impl SketchError {
+    /// Construct a new SketchError from the given ErrorKind and description
+    pub fn new(kind: ErrorKind, description: String) -> SketchError {
+        SketchError { kind, description }
+    }
+
+    /// Get the associated ErrorKind for this error
+    pub fn kind(&self) -> &ErrorKind {
+        &self.kind
+    }
+
+    /// Get the associated description for this error
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+}
+
+impl Error for SketchError {}
+
+impl fmt::Display for SketchError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Sketch error: {:?}:\n\t{}", self.kind, self.description)
+    }
+}
+
+//================================================================================
+// Utility Functions
+//================================================================================
+
+/// Reads a little endian u64
+fn read_u64<R: Read>(reader: &mut R) -> io::Result<u64> {
+    let mut bytes = [0u8; 8];
+    reader.read_exact(&mut bytes)?;
+    Ok(u64::from_le_bytes(bytes))
+}
+
+/// Constructs an io::Error for malformed input
+fn invalid_data(description: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, description)
+}
+
+//================================================================================
+// Tests
+//================================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alphabet::UnambiguousDnaAlphabet;
+
+    const GENOME: &str = "GATTACAGGCATTACCAGTAGGACTTATGCATCCAGGATTACAACGTTGCAGTCAGTTACGATCGGA";
+
+    /// Sketches the given string
+    fn sketch(string: &str, mut sketch: Sketch) -> Sketch {
+        let a = UnambiguousDnaAlphabet;
+        let mut s = Sequence::new(&a);
+        s.push(string).unwrap();
+        sketch.add_sequence(&s);
+        sketch
+    }
+
+    /// Tests that a bottom-k sketch only keeps the smallest hashes
+    #[test]
+    fn bottom_k_keeps_smallest() {
+        let full = sketch(GENOME, Sketch::scaled(9, 1));
+        let bottom = sketch(GENOME, Sketch::bottom_k(9, 10));
+
+        let smallest: Vec<_> = full.hashes().take(10).collect();
+        assert_eq!(bottom.hashes().collect::<Vec<_>>(), smallest);
+    }
+
+    /// Tests that a scale of 1 keeps every k-mer so the estimates are exact
+    #[test]
+    fn exact_jaccard_and_containment() {
+        // Shares the first half of the genome
+        let half = &GENOME[..GENOME.len() / 2];
+        let a = sketch(GENOME, Sketch::scaled(7, 1));
+        let b = sketch(half, Sketch::scaled(7, 1));
+
+        let shared = a.hashes.intersection(&b.hashes).count() as f64;
+        let union = a.hashes.union(&b.hashes).count() as f64;
+
+        assert_eq!(a.jaccard(&b).unwrap(), shared / union);
+        assert_eq!(b.containment(&a).unwrap(), 1.0);
+        assert_eq!(a.containment(&b).unwrap(), shared / a.len() as f64);
+    }
+
+    /// Tests the Mash distance of identical and unrelated sequences
+    #[test]
+    fn mash_distance() {
+        let a = sketch(GENOME, Sketch::bottom_k(15, 50));
+        let b = sketch(GENOME, Sketch::bottom_k(15, 50));
+        let c = sketch("AAAAAAAAAAAAAAAAAAAAAAAAA", Sketch::bottom_k(15, 50));
+
+        assert_eq!(a.mash_distance(&b).unwrap(), 0.0);
+        assert_eq!(a.mash_distance(&c).unwrap(), 1.0);
+
+        let j: f64 = 0.5;
+        let expected = -(2.0 * j / (1.0 + j)).ln() / 15.0;
+        let mut d = Sketch::bottom_k(15, 4);
+        let mut e = Sketch::bottom_k(15, 4);
+        for hash in &[1, 2, 3, 4] {
+            d.add_hash(*hash);
+        }
+        for hash in &[1, 2, 5, 6] {
+            e.add_hash(*hash);
+        }
+        // The 4 smallest of the union are 1, 2, 3 and 4 of which 1 and 2 are shared
+        assert_eq!(d.mash_distance(&e).unwrap(), expected);
+    }
+
+    /// Tests that sketches with different parameters can't be compared
+    #[test]
+    fn incompatible() {
+        let a = Sketch::bottom_k(15, 50);
+
+        let err = a.jaccard(&Sketch::bottom_k(16, 50)).unwrap_err();
+        assert_eq!(err.kind(), &ErrorKind::DifferentK);
+
+        let err = a.containment(&Sketch::scaled(15, 50)).unwrap_err();
+        assert_eq!(err.kind(), &ErrorKind::DifferentKind);
+    }
+
+    /// Tests that merging sketches is the same as sketching everything together
+    #[test]
+    fn merge() {
+        let (left, right) = GENOME.split_at(30);
+        let mut a = sketch(left, Sketch::bottom_k(11, 20));
+        let b = sketch(right, Sketch::bottom_k(11, 20));
+
+        let mut both = Sketch::bottom_k(11, 20);
+        for hash in a.hashes().chain(b.hashes()) {
+            both.add_hash(hash);
+        }
+
+        a.merge(&b).unwrap();
+        assert_eq!(a, both);
+    }
+
+    /// Tests that both kinds of sketch read back identically from their binary form
+    #[test]
+    fn binary_round_trip() {
+        for empty in [Sketch::bottom_k(21, 100), Sketch::scaled(31, 3)] {
+            let original = sketch(GENOME, empty);
+
+            let mut bytes = vec![];
+            original.write_binary(&mut bytes).unwrap();
+            assert_eq!(bytes.len(), 24 + 8 * original.len());
+
+            let read = Sketch::read_binary(bytes.as_slice()).unwrap();
+            assert_eq!(read, original);
+        }
+    }
+
+    /// Tests that malformed binary data is rejected
+    #[test]
+    fn binary_invalid() {
+        let original = sketch(GENOME, Sketch::bottom_k(21, 100));
+        let mut bytes = vec![];
+        original.write_binary(&mut bytes).unwrap();
+
+        assert!(Sketch::read_binary(&bytes[..bytes.len() - 3]).is_err());
+
+        // Swap the first two hashes so they are out of order
+        let mut swapped = bytes.clone();
+        swapped[24..40].rotate_left(8);
+        let err = Sketch::read_binary(swapped.as_slice()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+        let mut reserved = bytes.clone();
+        reserved[7] = 1;
+        let err = Sketch::read_binary(reserved.as_slice()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+        // A scale of 4 keeps hashes below u64::MAX / 4, so the last allowed hash is one less
+        let mut scaled = Sketch::scaled(21, 4);
+        let mut bytes = vec![];
+        scaled.hashes.insert(u64::MAX / 4 - 1);
+        scaled.write_binary(&mut bytes).unwrap();
+        assert!(Sketch::read_binary(bytes.as_slice()).is_ok());
+        bytes[24..32].copy_from_slice(&(u64::MAX / 4).to_le_bytes());
+        let err = Sketch::read_binary(bytes.as_slice()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+}
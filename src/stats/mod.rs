@@ -0,0 +1,451 @@
+//! Statistics describing the composition of a [Sequence](crate::sequence::Sequence).
+//!
+//! Everything in this module works straight from the encoded bytes of a sequence that uses the
+//! default [AsciiIndexEncoder](crate::alphabet::encoding::index_encoder::AsciiIndexEncoder) so no
+//! symbols ever need to be decoded.
+//!
+//! The nucleotide statistics recognise the symbols A, C, G, T and S (strong, G or C) by name so
+//! they work with any alphabet that uses those symbols, such as the
+//! [DNA alphabets](crate::alphabet::dna).
+
+use crate::alphabet::Alphabet;
+use crate::sequence::Sequence;
+
+/// Counts how many times each symbol of the alphabet occurs in the sequence.
+///
+/// The count at position i corresponds to the symbol at position i of
+/// [Alphabet::symbols()](crate::alphabet::Alphabet::symbols).
+///
+/// # Example
+/// ```
+/// use biors::alphabet::UnambiguousDnaAlphabet;
+/// use biors::sequence::Sequence;
+/// use biors::stats;
+///
+/// let alphabet = UnambiguousDnaAlphabet;
+/// let mut seq = Sequence::new(&alphabet);
+/// seq.push("AACTG").unwrap();
+///
+/// // Symbols are ordered A, C, T, G
+/// assert_eq!(stats::symbol_counts(&seq), vec![2, 1, 1, 1]);
+/// ```
+pub fn symbol_counts<A: Alphabet>(seq: &Sequence<A>) -> Vec<usize> {
+    let mut counts = [0usize; 256];
+
+    for &byte in seq.as_bytes() {
+        counts[byte as usize] += 1;
+    }
+
+    counts[..seq.alphabet().symbols().len()].to_vec()
+}
+
+//================================================================================
+// Nucleotide Counts
+//================================================================================
+
+/// The number of each kind of nucleotide within some region of a sequence.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct NucleotideCounts {
+    /// Number of A symbols
+    pub a: usize,
+    /// Number of C symbols
+    pub c: usize,
+    /// Number of G symbols
+    pub g: usize,
+    /// Number of T symbols
+    pub t: usize,
+    /// Number of S symbols (G or C but unknown which)
+    pub s: usize,
+    /// Total number of symbols in the region including any not counted above
+    pub total: usize,
+}
+
+impl NucleotideCounts {
+    /// The fraction of symbols in the region that are G, C or S.
+    /// Returns 0 for an empty region.
+    ///
+    /// # Notes
+    /// Every symbol counts towards the total so ambiguous symbols other than S (such as N)
+    /// lower the GC content.
+    pub fn gc_content(&self) -> f64 {
+        if self.total == 0 {
+            0.0
+        } else {
+            (self.g + self.c + self.s) as f64 / self.total as f64
+        }
+    }
+
+    /// The GC skew (G - C) / (G + C) of the region. Returns 0 if there are no G or C symbols.
+    ///
+    /// # Notes
+    /// S symbols are not included because it is unknown which strand they favour.
+    pub fn gc_skew(&self) -> f64 {
+        skew(self.g, self.c)
+    }
+
+    /// The AT skew (A - T) / (A + T) of the region. Returns 0 if there are no A or T symbols.
+    pub fn at_skew(&self) -> f64 {
+        skew(self.a, self.t)
+    }
+
+    /// Adds a single symbol of the given class to the counts
+    #[inline]
+    fn add(&mut self, class: Nucleotide) {
+        self.total += 1;
+        match class {
+            Nucleotide::A => self.a += 1,
+            Nucleotide::C => self.c += 1,
+            Nucleotide::G => self.g += 1,
+            Nucleotide::T => self.t += 1,
+            Nucleotide::S => self.s += 1,
+            Nucleotide::Other => {}
+        }
+    }
+
+    /// Removes a single symbol of the given class from the counts
+    #[inline]
+    fn remove(&mut self, class: Nucleotide) {
+        self.total -= 1;
+        match class {
+            Nucleotide::A => self.a -= 1,
+            Nucleotide::C => self.c -= 1,
+            Nucleotide::G => self.g -= 1,
+            Nucleotide::T => self.t -= 1,
+            Nucleotide::S => self.s -= 1,
+            Nucleotide::Other => {}
+        }
+    }
+}
+
+/// Counts the nucleotides across the whole sequence.
+pub fn nucleotide_counts<A: Alphabet>(seq: &Sequence<A>) -> NucleotideCounts {
+    let classes = nucleotide_classes(seq.alphabet());
+    let mut counts = NucleotideCounts::default();
+
+    for &byte in seq.as_bytes() {
+        counts.add(classes[byte as usize]);
+    }
+
+    counts
+}
+
+/// The fraction of the sequence made up of G, C or S symbols.
+/// See [NucleotideCounts::gc_content()].
+pub fn gc_content<A: Alphabet>(seq: &Sequence<A>) -> f64 {
+    nucleotide_counts(seq).gc_content()
+}
+
+/// The GC skew (G - C) / (G + C) of the whole sequence. See [NucleotideCounts::gc_skew()].
+pub fn gc_skew<A: Alphabet>(seq: &Sequence<A>) -> f64 {
+    nucleotide_counts(seq).gc_skew()
+}
+
+/// The AT skew (A - T) / (A + T) of the whole sequence. See [NucleotideCounts::at_skew()].
+pub fn at_skew<A: Alphabet>(seq: &Sequence<A>) -> f64 {
+    nucleotide_counts(seq).at_skew()
+}
+
+//================================================================================
+// Sliding Windows
+//================================================================================
+
+/// The nucleotide counts of a single window from [sliding_windows()].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Window {
+    /// Position of the first symbol in the window
+    pub start: usize,
+    /// Position one past the last symbol in the window. If the window wraps around the origin of
+    /// a circular sequence this will be less than or equal to start.
+    pub end: usize,
+    /// The nucleotide counts within the window
+    pub counts: NucleotideCounts,
+}
+
+/// Calculates the nucleotide counts of windows of `window` symbols starting every `step` symbols.
+///
+/// For linear sequences only windows that fit entirely within the sequence are returned.
+/// If the sequence is [circular](crate::sequence::Sequence::circular) a window starts at every
+/// multiple of step before the end of the sequence and the windows wrap around the origin.
+/// No windows are returned if the window is longer than the sequence.
+///
+/// # Panics
+/// If either window or step is 0.
+///
+/// # Example
+/// ```
+/// use biors::alphabet::UnambiguousDnaAlphabet;
+/// use biors::sequence::Sequence;
+/// use biors::stats;
+///
+/// let alphabet = UnambiguousDnaAlphabet;
+/// let mut seq = Sequence::new(&alphabet);
+/// seq.push("GGGGAAAA").unwrap();
+///
+/// let profile: Vec<f64> = stats::sliding_windows(&seq, 4, 2)
+///     .iter()
+///     .map(|w| w.counts.gc_content())
+///     .collect();
+///
+/// assert_eq!(profile, vec![1.0, 0.5, 0.0]);
+/// ```
+pub fn sliding_windows<A: Alphabet>(seq: &Sequence<A>, window: usize, step: usize) -> Vec<Window> {
+    assert!(window > 0, "Window size must be greater than 0");
+    assert!(step > 0, "Step size must be greater than 0");
+
+    let bytes = seq.as_bytes();
+    let len = bytes.len();
+    if window > len {
+        return vec![];
+    }
+
+    let classes = nucleotide_classes(seq.alphabet());
+    let class_at = |i: usize| classes[bytes[i % len] as usize];
+
+    let last_start = if seq.circular { len - 1 } else { len - window };
+    let mut windows = Vec::with_capacity(last_start / step + 1);
+    let mut counts = NucleotideCounts::default();
+
+    for i in 0..window {
+        counts.add(class_at(i));
+    }
+
+    let mut start = 0;
+    loop {
+        // Windows that wrap around the origin of a circular sequence end before they start
+        let end = if start + window > len { start + window - len } else { start + window };
+        windows.push(Window { start, end, counts });
+
+        let next = start + step;
+        if next > last_start {
+            break;
+        }
+
+        if step < window {
+            // Slide the window along removing the symbols that left it and adding the new ones
+            for i in start..next {
+                counts.remove(class_at(i));
+                counts.add(class_at(i + window));
+            }
+        } else {
+            counts = NucleotideCounts::default();
+            for i in next..next + window {
+                counts.add(class_at(i));
+            }
+        }
+
+        start = next;
+    }
+
+    windows
+}
+
+/// The cumulative GC skew of the sequence calculated over sliding windows.
+///
+/// Each element is the start of a window from [sliding_windows()] paired with the sum of the GC
+/// skew of that window and every window before it.
+///
+/// # Panics
+/// If either window or step is 0.
+pub fn cumulative_gc_skew<A>(seq: &Sequence<A>, window: usize, step: usize) -> Vec<(usize, f64)>
+where
+    A: Alphabet
+{
+    let mut total = 0.0;
+
+    sliding_windows(seq, window, step)
+        .iter()
+        .map(|w| {
+            total += w.counts.gc_skew();
+            (w.start, total)
+        })
+        .collect()
+}
+
+/// The predicted origin and terminus of replication of a circular genome.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SkewExtrema {
+    /// Start of the window at which the cumulative GC skew is at its minimum
+    pub origin: usize,
+    /// Start of the window at which the cumulative GC skew is at its maximum
+    pub terminus: usize,
+}
+
+/// Predicts the origin and terminus of replication using the cumulative GC skew.
+///
+/// The leading strand of a bacterial chromosome is usually rich in G compared to C so the
+/// cumulative skew falls to its minimum at the origin of replication and rises to its maximum
+/// at the terminus. Returns None if no windows fit in the sequence.
+///
+/// # Notes
+/// This is only meaningful for [circular](crate::sequence::Sequence::circular) genomes.
+///
+/// # Panics
+/// If either window or step is 0.
+pub fn skew_extrema<A: Alphabet>(
+    seq: &Sequence<A>,
+    window: usize,
+    step: usize
+) -> Option<SkewExtrema> {
+    let cumulative = cumulative_gc_skew(seq, window, step);
+
+    let origin = cumulative.iter()
+        .fold(None, |min: Option<&(usize, f64)>, x| match min {
+            Some(m) if m.1 <= x.1 => Some(m),
+            _ => Some(x),
+        })?;
+    let terminus = cumulative.iter()
+        .fold(None, |max: Option<&(usize, f64)>, x| match max {
+            Some(m) if m.1 >= x.1 => Some(m),
+            _ => Some(x),
+        })?;
+
+    Some(SkewExtrema { origin: origin.0, terminus: terminus.0 })
+}
+
+//================================================================================
+// Utility Functions
+//================================================================================
+
+/// The kinds of symbol that the nucleotide statistics care about
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Nucleotide {
+    A,
+    C,
+    G,
+    T,
+    S,
+    Other
+}
+
+/// Constructs a table mapping each possible encoded byte to its nucleotide class
+fn nucleotide_classes<A: Alphabet>(alphabet: &A) -> [Nucleotide; 256] {
+    let mut classes = [Nucleotide::Other; 256];
+
+    for (index, symbol) in alphabet.symbols().iter().enumerate().take(256) {
+        classes[index] = match *symbol {
+            "A" => Nucleotide::A,
+            "C" => Nucleotide::C,
+            "G" => Nucleotide::G,
+            "T" => Nucleotide::T,
+            "S" => Nucleotide::S,
+            _ => Nucleotide::Other,
+        };
+    }
+
+    classes
+}
+
+/// Calculates (x - y) / (x + y) or 0 if both are 0
+fn skew(x: usize, y: usize) -> f64 {
+    if x + y == 0 {
+        0.0
+    } else {
+        (x as f64 - y as f64) / (x + y) as f64
+    }
+}
+
+//================================================================================
+// Tests
+//================================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alphabet::{AmbiguousDnaAlphabet, UnambiguousDnaAlphabet};
+
+    /// Tests that the symbol counts are in the same order as the alphabet's symbols
+    #[test]
+    fn counts_symbols() {
+        let a = AmbiguousDnaAlphabet;
+        let mut s = Sequence::new(&a);
+        s.push("AANGGCS").unwrap();
+
+        let counts = symbol_counts(&s);
+
+        assert_eq!(counts.len(), a.symbols().len());
+        assert_eq!(counts[0], 2); // A
+        assert_eq!(counts[1], 2); // G
+        assert_eq!(counts[2], 1); // C
+        assert_eq!(counts[7], 1); // S
+        assert_eq!(counts[14], 1); // N
+        assert_eq!(counts.iter().sum::<usize>(), 7);
+    }
+
+    /// Tests GC content, GC skew and AT skew over a whole sequence
+    #[test]
+    fn whole_sequence_statistics() {
+        let a = UnambiguousDnaAlphabet;
+        let mut s = Sequence::new(&a);
+        s.push("GGGCAAAT").unwrap();
+
+        assert_eq!(gc_content(&s), 0.5);
+        assert_eq!(gc_skew(&s), 0.5);
+        assert_eq!(at_skew(&s), 0.5);
+
+        s.clear();
+        assert_eq!(gc_content(&s), 0.0);
+        assert_eq!(gc_skew(&s), 0.0);
+    }
+
+    /// Tests that S counts towards the GC content but not the skew while N counts towards neither
+    #[test]
+    fn ambiguous_gc() {
+        let a = AmbiguousDnaAlphabet;
+        let mut s = Sequence::new(&a);
+        s.push("GSSN").unwrap();
+
+        assert_eq!(gc_content(&s), 0.75);
+        assert_eq!(gc_skew(&s), 1.0);
+    }
+
+    /// Tests that linear sequences only produce windows that fit in the sequence
+    #[test]
+    fn linear_windows() {
+        let a = UnambiguousDnaAlphabet;
+        let mut s = Sequence::new(&a);
+        s.push("GGCCAATTG").unwrap();
+
+        let windows = sliding_windows(&s, 4, 3);
+        let starts: Vec<_> = windows.iter().map(|w| (w.start, w.end)).collect();
+        let gc: Vec<_> = windows.iter().map(|w| w.counts.gc_content()).collect();
+
+        assert_eq!(starts, vec![(0, 4), (3, 7)]);
+        assert_eq!(gc, vec![1.0, 0.25]);
+
+        // Steps larger than the window are recounted from scratch
+        let windows = sliding_windows(&s, 2, 3);
+        let gc: Vec<_> = windows.iter().map(|w| w.counts.gc_content()).collect();
+        assert_eq!(gc, vec![1.0, 0.5, 0.0]);
+
+        assert!(sliding_windows(&s, 10, 1).is_empty());
+    }
+
+    /// Tests that circular sequences produce windows that wrap around the origin
+    #[test]
+    fn circular_windows() {
+        let a = UnambiguousDnaAlphabet;
+        let mut s = Sequence::new(&a).circular(true);
+        s.push("GAAAAG").unwrap();
+
+        let windows = sliding_windows(&s, 2, 1);
+        let gc: Vec<_> = windows.iter().map(|w| w.counts.gc_content()).collect();
+
+        assert_eq!(windows.len(), 6);
+        assert_eq!((windows[5].start, windows[5].end), (5, 1));
+        assert_eq!(gc, vec![0.5, 0.0, 0.0, 0.0, 0.5, 1.0]);
+    }
+
+    /// Tests the cumulative skew finds the origin and terminus of a toy genome
+    #[test]
+    fn cumulative_skew() {
+        let a = UnambiguousDnaAlphabet;
+        let mut s = Sequence::new(&a).circular(true);
+        s.push("CCCCGGGGGGGGCCCC").unwrap();
+
+        let cumulative: Vec<_> = cumulative_gc_skew(&s, 4, 4).iter().map(|x| x.1).collect();
+        assert_eq!(cumulative, vec![-1.0, 0.0, 1.0, 0.0]);
+
+        let extrema = skew_extrema(&s, 4, 4).unwrap();
+        assert_eq!(extrema, SkewExtrema { origin: 0, terminus: 8 });
+    }
+}